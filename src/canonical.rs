@@ -0,0 +1,1321 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Canonical byte layout of the payloads signed by this crate.
+//!
+//! Signatures must remain valid across upgrades of `serde` and `bincode`, so signed
+//! payloads are not serialized with them but written with the following stable layout:
+//!
+//! - Integers are written big-endian with their fixed width; `usize` is written as a `u64`.
+//! - `bool` is a single byte, `0` or `1`.
+//! - `Option` is a one byte tag, `0` for `None` or `1` for `Some`, followed by the value.
+//! - Strings, slices, `Vec`, `BTreeSet` and `BTreeMap` are a `u32` length followed by their
+//!   items, in their natural order. Map entries are written as their key then their value.
+//! - Tuples are their items, in order, without any separator.
+//! - `XorName`, `MessageId`, `CreditId`, `QuoteId` and Register entry hashes are their 32
+//!   raw bytes.
+//! - BLS public keys are their 48 compressed bytes.
+//! - `PublicKey` is a one byte tag, `0` for Ed25519, `1` for BLS or `2` for BLS share,
+//!   followed by the raw bytes of the key.
+//! - `Token` is its amount of nanos, as a `u64`.
+//! - `Prefix` is its bit count, as a `u16`, followed by its name.
+//! - `SocketAddr` is a one byte tag, `4` or `6`, followed by the octets of the IP and the port.
+//! - Data addresses are a one byte tag for the data type (`0` Blob, `1` Map, `2` Sequence,
+//!   `3` Register), a one byte tag for the kind (`0` public or unsequenced, `1` private or
+//!   sequenced), the name, and the type tag for all but Blobs.
+//! - `CreditAgreementProof` is the id, amount, recipient and message of its credit.
+//!   The signatures of the proof are not part of it.
+//! - `TransferAgreementProof` is the sender and counter of its debit id, the debited
+//!   amount, then its credit as for `CreditAgreementProof`.
+//! - `SignedTransfer` is its debit and its credit as for `TransferAgreementProof`, then the
+//!   actor signatures of both.
+//! - BLS signatures are their 96 compressed bytes. `Signature` is a one byte tag, `0` for
+//!   Ed25519, `1` for BLS or `2` for BLS share followed by the index of the share, then the
+//!   raw bytes of the signature. `Signed` is its public key then its signature.
+//! - BLS public key sets are their threshold, as a `u64`, then their public key and their
+//!   first `threshold + 1` key shares, which determine all the others.
+//! - `SectionElders` is its prefix, the names of the elders, then its key set.
+//! - `SectionAuthorityProvider` is its prefix, its elders, its generation, then its key set.
+//! - `EndUser` is its name then its socket id. `DstLocation` is a one byte tag, `0` for an
+//!   end user, `1` for a node, `2` for a section or `3` for direct and unrouted, followed by
+//!   the end user or the name, if any.
+//! - Register and Sequence policies, and permissions, are a one byte tag, `0` public or
+//!   `1` private, followed by the owner and the permissions of each user for policies.
+//!   A user is a one byte tag, `0` for anyone or `1` for a key, followed by the key.
+//!   Public permissions are whether the user may write, or append, as an `Option<bool>`;
+//!   private ones are whether the user may read, then write or append, as `bool`s.
+//!
+//! Client commands are laid out as follows:
+//!
+//! - `Cmd`, and the data and transfer commands it carries, are a one byte tag for their
+//!   variant, numbered from `0` in declaration order, followed by the fields of the variant
+//!   in their declaration order.
+//! - `Blob` is its address, its owner, if any, then its value.
+//! - `Map` is its address, its version, its owner, the permissions of each user, then its
+//!   entries. Map permissions are whether the user may read, insert, update, delete and
+//!   manage permissions, as `bool`s. Sequenced values are their data then their version.
+//! - Map entry actions are a one byte tag, `0` unsequenced or `1` sequenced, followed by the
+//!   action for each key: a one byte tag, `0` insert, `1` update or `2` delete, followed by
+//!   the value, or the version of a sequenced deletion.
+//! - `Sequence` and `Register` are their address, their owner and their replica authority,
+//!   then their whole CRDT state. That state, e.g. the identifiers and the clock of the
+//!   entries, can't be read but through serde, and two values differing only by it, or by
+//!   parts their owner can't read, must not share a signature. It is written from the serde
+//!   data model with the rules above: structs and tuples are their fields in order, sequences
+//!   and maps are a `u32` count then their items, enums are their variant index, as a `u32`,
+//!   then their fields, byte strings are a `u32` length then their bytes, units write nothing,
+//!   floats are their bits and chars their code point, as a `u32`. That is deterministic as
+//!   all their collections are ordered, but any change to the fields of the state in
+//!   `sn_data_types` changes this layout.
+//! - Sequence operations are their address, a one byte tag, `0` insert or `1` delete, the
+//!   actor and counter of the entry, the inserted value or the actor and counter of the
+//!   deletion, then their source and their signature. The position of an insertion isn't
+//!   part of it, as it is covered by the signature of the operation.
+//! - Register operations are their address, the hashes of their parents, their value, then
+//!   their source and their signature.
+//! - `OwnerTransfer` is its new owner, the owner index, then its signature.
+//! - `SessionToken` is its client, its capabilities, as the protocol version then whether
+//!   events are enabled, when it was created, when it expires, then its proof.
+//!
+//! The following node types are only part of it without the `client-only` feature:
+//!
+//! - `Peer` is its name, its address and whether it is reachable.
+//! - `NodeState` is its peer, its membership state, its penalty, when it joined, its
+//!   relocation count, then when it was last seen. The membership state is a one byte tag,
+//!   `0` joined, `1` left, `2` relocated followed by the destination, or `3` penalized.
+//!   A penalty is a one byte tag for its reason, `0` unresponsive, `1` invalid message or
+//!   `2` missing data, followed by its proof as a byte string.
+//! - A section key chain is its keys, from the root to the last one, as a slice.
+//! - `SectionSigned` is its value, its version, then the public key and the signature.
+//! - `Proposal` is a one byte tag, `0` online, `1` offline, `2` section info, `3` our
+//!   elders, `4` accumulate at source, `5` joins allowed or `6` section split, followed by
+//!   the fields of the variant in their declaration order. It isn't a `CanonicalBytes`
+//!   itself, as the message to accumulate at the source is written as its source name,
+//!   destination and destination key, then the wire encoding of its variant as a byte
+//!   string, which may fail; see `Proposal::as_signable_bytes`.
+//!
+//! Writing a payload fails, rather than truncating it, if a length doesn't fit in a `u32`.
+//!
+//! Any change to this layout invalidates previously issued signatures.
+
+use crate::{DstLocation, EndUser, Error, MessageId, Result, SectionAuthorityProvider, Signed};
+use serde::{
+    ser::{
+        self, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize, Serializer,
+};
+use sn_data_types::{
+    register::{self, Address as RegisterAddress},
+    BlobAddress, Credit, CreditAgreementProof, DataAddress, Debit, MapAddress, PublicKey,
+    SectionElders, SequenceAction, SequenceAddress, SequencePermissions, SequencePolicy,
+    SequencePrivatePermissions, SequencePublicPermissions, SequenceUser, Signature, SignedTransfer,
+    Token, TransferAgreementProof,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt::Display,
+    net::SocketAddr,
+};
+use threshold_crypto::{PublicKey as BlsPublicKey, PublicKeySet, Signature as BlsSignature};
+use xor_name::{Prefix, XorName};
+
+/// A type with a canonical byte layout, which can be signed.
+pub trait CanonicalBytes {
+    /// Appends the canonical bytes of `self` to `out`.
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()>;
+
+    /// Returns the canonical bytes of `self`.
+    fn canonical_bytes(&self) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        self.write_canonical(&mut out)?;
+        Ok(out)
+    }
+}
+
+/// Returns the bytes to sign for `payload`, prefixed with `domain`, so that
+/// a signature over a payload can't be replayed as a signature over another
+/// payload with the same layout.
+pub fn signing_bytes<T: CanonicalBytes + ?Sized>(domain: &str, payload: &T) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    domain.write_canonical(&mut out)?;
+    payload.write_canonical(&mut out)?;
+    Ok(out)
+}
+
+fn write_len(len: usize, out: &mut Vec<u8>) -> Result<()> {
+    // Payloads are bound by the message size limits far below `u32::MAX`, so a longer one
+    // must not be signed with a truncated length.
+    let len = u32::try_from(len).map_err(|_| {
+        Error::Serialisation(format!(
+            "length {} doesn't fit the canonical layout of signed payloads",
+            len
+        ))
+    })?;
+    len.write_canonical(out)
+}
+
+// Appends raw bytes, whose length is fixed by their type.
+fn write_raw(bytes: &[u8], out: &mut Vec<u8>) -> Result<()> {
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {
+        $(impl CanonicalBytes for $t {
+            fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+                out.extend_from_slice(&self.to_be_bytes());
+                Ok(())
+            }
+        })*
+    };
+}
+
+impl_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl CanonicalBytes for usize {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (*self as u64).write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for bool {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        out.push(*self as u8);
+        Ok(())
+    }
+}
+
+impl CanonicalBytes for str {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        write_len(self.len(), out)?;
+        out.extend_from_slice(self.as_bytes());
+        Ok(())
+    }
+}
+
+impl CanonicalBytes for String {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.as_str().write_canonical(out)
+    }
+}
+
+impl<T: CanonicalBytes + ?Sized> CanonicalBytes for &T {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (**self).write_canonical(out)
+    }
+}
+
+impl<T: CanonicalBytes> CanonicalBytes for Option<T> {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            None => 0u8.write_canonical(out),
+            Some(value) => (1u8, value).write_canonical(out),
+        }
+    }
+}
+
+impl<T: CanonicalBytes> CanonicalBytes for [T] {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        write_len(self.len(), out)?;
+        self.iter().try_for_each(|item| item.write_canonical(out))
+    }
+}
+
+impl<T: CanonicalBytes> CanonicalBytes for Vec<T> {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.as_slice().write_canonical(out)
+    }
+}
+
+impl<T: CanonicalBytes> CanonicalBytes for BTreeSet<T> {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        write_len(self.len(), out)?;
+        self.iter().try_for_each(|item| item.write_canonical(out))
+    }
+}
+
+impl<K: CanonicalBytes, V: CanonicalBytes> CanonicalBytes for BTreeMap<K, V> {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        write_len(self.len(), out)?;
+        for (key, value) in self {
+            key.write_canonical(out)?;
+            value.write_canonical(out)?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! impl_tuple {
+    ($(($($name:ident),+)),*) => {
+        $(impl<$($name: CanonicalBytes),+> CanonicalBytes for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+                let ($($name,)+) = self;
+                $($name.write_canonical(out)?;)+
+                Ok(())
+            }
+        })*
+    };
+}
+
+impl_tuple!((A, B), (A, B, C), (A, B, C, D), (A, B, C, D, E));
+
+impl CanonicalBytes for [u8; 32] {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        write_raw(self, out)
+    }
+}
+
+impl CanonicalBytes for XorName {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.0.write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for MessageId {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        write_raw(self.as_ref(), out)
+    }
+}
+
+impl CanonicalBytes for BlsPublicKey {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        write_raw(&self.to_bytes(), out)
+    }
+}
+
+impl CanonicalBytes for PublicKey {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::Ed25519(key) => {
+                0u8.write_canonical(out)?;
+                write_raw(key.as_bytes(), out)
+            }
+            Self::Bls(key) => (1u8, key).write_canonical(out),
+            Self::BlsShare(key) => {
+                2u8.write_canonical(out)?;
+                write_raw(&key.to_bytes(), out)
+            }
+        }
+    }
+}
+
+impl CanonicalBytes for Token {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.as_nano().write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for Prefix {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (self.bit_count() as u16).write_canonical(out)?;
+        self.name().write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for SocketAddr {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::V4(addr) => {
+                out.push(4);
+                out.extend_from_slice(&addr.ip().octets());
+            }
+            Self::V6(addr) => {
+                out.push(6);
+                out.extend_from_slice(&addr.ip().octets());
+            }
+        }
+        self.port().write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for BlobAddress {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::Public(name) => (0u8, 0u8, name).write_canonical(out),
+            Self::Private(name) => (0u8, 1u8, name).write_canonical(out),
+        }
+    }
+}
+
+impl CanonicalBytes for MapAddress {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::Unseq { name, tag } => (1u8, 0u8, name, tag).write_canonical(out),
+            Self::Seq { name, tag } => (1u8, 1u8, name, tag).write_canonical(out),
+        }
+    }
+}
+
+impl CanonicalBytes for SequenceAddress {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::Public { name, tag } => (2u8, 0u8, name, tag).write_canonical(out),
+            Self::Private { name, tag } => (2u8, 1u8, name, tag).write_canonical(out),
+        }
+    }
+}
+
+impl CanonicalBytes for RegisterAddress {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::Public { name, tag } => (3u8, 0u8, name, tag).write_canonical(out),
+            Self::Private { name, tag } => (3u8, 1u8, name, tag).write_canonical(out),
+        }
+    }
+}
+
+impl CanonicalBytes for DataAddress {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::Blob(address) => address.write_canonical(out),
+            Self::Map(address) => address.write_canonical(out),
+            Self::Sequence(address) => address.write_canonical(out),
+            Self::Register(address) => address.write_canonical(out),
+        }
+    }
+}
+
+impl CanonicalBytes for Debit {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (self.id.actor, self.id.counter, self.amount).write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for Credit {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (self.id, self.amount, self.recipient, &self.msg).write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for CreditAgreementProof {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        self.signed_credit.credit.write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for TransferAgreementProof {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (&self.signed_debit.debit, &self.signed_credit.credit).write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for SignedTransfer {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (&self.debit.debit, &self.credit.credit).write_canonical(out)?;
+        (&self.debit.actor_signature, &self.credit.actor_signature).write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for BlsSignature {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        write_raw(&self.to_bytes(), out)
+    }
+}
+
+impl CanonicalBytes for Signature {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::Ed25519(signature) => {
+                0u8.write_canonical(out)?;
+                write_raw(&signature.to_bytes(), out)
+            }
+            Self::Bls(signature) => (1u8, signature).write_canonical(out),
+            Self::BlsShare(share) => {
+                (2u8, share.index).write_canonical(out)?;
+                write_raw(&share.share.to_bytes(), out)
+            }
+        }
+    }
+}
+
+impl CanonicalBytes for Signed {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (self.public_key, &self.signature).write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for PublicKeySet {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (self.threshold(), self.public_key()).write_canonical(out)?;
+        (0..=self.threshold())
+            .try_for_each(|index| write_raw(&self.public_key_share(index).to_bytes(), out))
+    }
+}
+
+impl CanonicalBytes for SectionElders {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (&self.prefix, &self.names, &self.key_set).write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for SectionAuthorityProvider {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (
+            &self.prefix,
+            &self.elders,
+            self.generation,
+            &self.public_key_set,
+        )
+            .write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for EndUser {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        (self.xorname, self.socket_id).write_canonical(out)
+    }
+}
+
+impl CanonicalBytes for DstLocation {
+    fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Self::EndUser(user) => (0u8, user).write_canonical(out),
+            Self::Node(name) => (1u8, name).write_canonical(out),
+            Self::Section(name) => (2u8, name).write_canonical(out),
+            Self::DirectAndUnrouted => 3u8.write_canonical(out),
+        }
+    }
+}
+
+macro_rules! impl_policy {
+    ($user:ty, $public:ty, $private:ty, $permissions:ty, $policy:ty, $read:expr, $write:expr) => {
+        impl CanonicalBytes for $user {
+            fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+                match self {
+                    Self::Anyone => 0u8.write_canonical(out),
+                    Self::Key(key) => (1u8, key).write_canonical(out),
+                }
+            }
+        }
+
+        impl CanonicalBytes for $public {
+            fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+                self.is_allowed($write).write_canonical(out)
+            }
+        }
+
+        impl CanonicalBytes for $private {
+            fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+                (self.is_allowed($read), self.is_allowed($write)).write_canonical(out)
+            }
+        }
+
+        impl CanonicalBytes for $permissions {
+            fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+                match self {
+                    Self::Public(permissions) => (0u8, permissions).write_canonical(out),
+                    Self::Private(permissions) => (1u8, permissions).write_canonical(out),
+                }
+            }
+        }
+
+        impl CanonicalBytes for $policy {
+            fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+                match self {
+                    Self::Public(policy) => {
+                        (0u8, policy.owner, &policy.permissions).write_canonical(out)
+                    }
+                    Self::Private(policy) => {
+                        (1u8, policy.owner, &policy.permissions).write_canonical(out)
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_policy!(
+    register::User,
+    register::PublicPermissions,
+    register::PrivatePermissions,
+    register::Permissions,
+    register::Policy,
+    register::Action::Read,
+    register::Action::Write
+);
+impl_policy!(
+    SequenceUser,
+    SequencePublicPermissions,
+    SequencePrivatePermissions,
+    SequencePermissions,
+    SequencePolicy,
+    SequenceAction::Read,
+    SequenceAction::Append
+);
+
+// Writes the whole state of a value through its serde representation, with the layout of
+// the serde data model described at the top of this module.
+fn write_state<T: Serialize + ?Sized>(value: &T, out: &mut Vec<u8>) -> Result<()> {
+    value
+        .serialize(StateSerializer { out })
+        .map_err(|StateError(error)| error)
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+struct StateError(#[from] Error);
+
+impl ser::Error for StateError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(Error::Serialisation(format!(
+            "state couldn't be written with the canonical layout: {}",
+            msg
+        )))
+    }
+}
+
+struct StateSerializer<'a> {
+    out: &'a mut Vec<u8>,
+}
+
+impl<'a> StateSerializer<'a> {
+    fn write<T: CanonicalBytes + ?Sized>(self, value: &T) -> Result<(), StateError> {
+        Ok(value.write_canonical(self.out)?)
+    }
+}
+
+// Items of a sequence or a map, buffered as their count is written before them.
+struct StateItems<'a> {
+    out: &'a mut Vec<u8>,
+    items: Vec<u8>,
+    len: usize,
+}
+
+impl<'a> StateItems<'a> {
+    fn add<T: Serialize + ?Sized>(&mut self, item: &T) -> Result<(), StateError> {
+        self.len += 1;
+        item.serialize(StateSerializer {
+            out: &mut self.items,
+        })
+    }
+
+    fn end(self) -> Result<(), StateError> {
+        write_len(self.len, self.out)?;
+        Ok(write_raw(&self.items, self.out)?)
+    }
+}
+
+// Fields of a tuple, a struct or a variant, written in their declaration order.
+struct StateFields<'a> {
+    out: &'a mut Vec<u8>,
+}
+
+impl<'a> StateFields<'a> {
+    fn add<T: Serialize + ?Sized>(&mut self, field: &T) -> Result<(), StateError> {
+        field.serialize(StateSerializer { out: self.out })
+    }
+}
+
+impl<'a> Serializer for StateSerializer<'a> {
+    type Ok = ();
+    type Error = StateError;
+    type SerializeSeq = StateItems<'a>;
+    type SerializeTuple = StateFields<'a>;
+    type SerializeTupleStruct = StateFields<'a>;
+    type SerializeTupleVariant = StateFields<'a>;
+    type SerializeMap = StateItems<'a>;
+    type SerializeStruct = StateFields<'a>;
+    type SerializeStructVariant = StateFields<'a>;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), StateError> {
+        self.write(&v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), StateError> {
+        self.write(&v.to_bits())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), StateError> {
+        self.write(&v.to_bits())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), StateError> {
+        self.write(&u32::from(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), StateError> {
+        self.write(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), StateError> {
+        write_len(v.len(), self.out)?;
+        Ok(write_raw(v, self.out)?)
+    }
+
+    fn serialize_none(self) -> Result<(), StateError> {
+        self.write(&0u8)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), StateError> {
+        1u8.write_canonical(self.out)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), StateError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), StateError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), StateError> {
+        self.write(&variant_index)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), StateError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), StateError> {
+        variant_index.write_canonical(self.out)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<StateItems<'a>, StateError> {
+        Ok(StateItems {
+            out: self.out,
+            items: Vec::new(),
+            len: 0,
+        })
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<StateFields<'a>, StateError> {
+        Ok(StateFields { out: self.out })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<StateFields<'a>, StateError> {
+        Ok(StateFields { out: self.out })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<StateFields<'a>, StateError> {
+        variant_index.write_canonical(self.out)?;
+        Ok(StateFields { out: self.out })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<StateItems<'a>, StateError> {
+        self.serialize_seq(len)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<StateFields<'a>, StateError> {
+        Ok(StateFields { out: self.out })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<StateFields<'a>, StateError> {
+        variant_index.write_canonical(self.out)?;
+        Ok(StateFields { out: self.out })
+    }
+}
+
+impl<'a> SerializeSeq for StateItems<'a> {
+    type Ok = ();
+    type Error = StateError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), StateError> {
+        self.add(value)
+    }
+
+    fn end(self) -> Result<(), StateError> {
+        StateItems::end(self)
+    }
+}
+
+impl<'a> SerializeMap for StateItems<'a> {
+    type Ok = ();
+    type Error = StateError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), StateError> {
+        self.add(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), StateError> {
+        // The entry was counted with its key.
+        value.serialize(StateSerializer {
+            out: &mut self.items,
+        })
+    }
+
+    fn end(self) -> Result<(), StateError> {
+        StateItems::end(self)
+    }
+}
+
+macro_rules! impl_state_fields {
+    ($($trait:ident::$method:ident($($key:ident: $key_type:ty),*)),*) => {
+        $(impl<'a> $trait for StateFields<'a> {
+            type Ok = ();
+            type Error = StateError;
+
+            fn $method<T: Serialize + ?Sized>(
+                &mut self,
+                $($key: $key_type,)*
+                value: &T,
+            ) -> Result<(), StateError> {
+                self.add(value)
+            }
+
+            fn end(self) -> Result<(), StateError> {
+                Ok(())
+            }
+        })*
+    };
+}
+
+impl_state_fields!(
+    SerializeTuple::serialize_element(),
+    SerializeTupleStruct::serialize_field(),
+    SerializeTupleVariant::serialize_field(),
+    SerializeStruct::serialize_field(_key: &'static str),
+    SerializeStructVariant::serialize_field(_key: &'static str)
+);
+
+mod client {
+    use super::{write_state, CanonicalBytes};
+    use crate::{
+        client::{
+            BlobWrite, Capabilities, Cmd, DataCmd, MapWrite, OwnerTransfer, QuoteId, RegisterWrite,
+            SequenceWrite, SessionToken, TransferCmd,
+        },
+        Result,
+    };
+    use sn_data_types::{
+        register::{Entry as RegisterEntry, Register, RegisterOp},
+        Blob, Map, MapAction, MapEntryActions, MapPermissionSet, MapSeqEntryAction, MapSeqValue,
+        MapUnseqEntryAction, Sequence, SequenceEntry, SequenceOp,
+    };
+
+    impl CanonicalBytes for Blob {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (self.address(), self.owner(), self.value()).write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for MapPermissionSet {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (
+                self.is_allowed(MapAction::Read),
+                self.is_allowed(MapAction::Insert),
+                self.is_allowed(MapAction::Update),
+                self.is_allowed(MapAction::Delete),
+                self.is_allowed(MapAction::ManagePermissions),
+            )
+                .write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for MapSeqValue {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (&self.data, self.version).write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for Map {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (self.address(), self.version(), self.owner()).write_canonical(out)?;
+            self.permissions().write_canonical(out)?;
+            match self {
+                Self::Seq(map) => map.entries().write_canonical(out),
+                Self::Unseq(map) => map.entries().write_canonical(out),
+            }
+        }
+    }
+
+    impl CanonicalBytes for MapSeqEntryAction {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::Ins(value) => (0u8, value).write_canonical(out),
+                Self::Update(value) => (1u8, value).write_canonical(out),
+                Self::Del(version) => (2u8, version).write_canonical(out),
+            }
+        }
+    }
+
+    impl CanonicalBytes for MapUnseqEntryAction {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::Ins(value) => (0u8, value).write_canonical(out),
+                Self::Update(value) => (1u8, value).write_canonical(out),
+                Self::Del => 2u8.write_canonical(out),
+            }
+        }
+    }
+
+    impl CanonicalBytes for MapEntryActions {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::Unseq(actions) => (0u8, actions.actions()).write_canonical(out),
+                Self::Seq(actions) => (1u8, actions.actions()).write_canonical(out),
+            }
+        }
+    }
+
+    impl CanonicalBytes for Sequence {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (self.address(), self.owner(), self.replica_authority()).write_canonical(out)?;
+            write_state(self, out)
+        }
+    }
+
+    impl CanonicalBytes for SequenceOp<SequenceEntry> {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            use crdts::list::Op;
+
+            self.address.write_canonical(out)?;
+            match &self.crdt_op {
+                Op::Insert { id, val } => {
+                    let marker = id.value();
+                    (0u8, &marker.actor, marker.counter, val).write_canonical(out)?
+                }
+                Op::Delete { id, dot } => {
+                    let marker = id.value();
+                    (1u8, &marker.actor, marker.counter).write_canonical(out)?;
+                    (&dot.actor, dot.counter).write_canonical(out)?
+                }
+            }
+            (self.source, &self.signature).write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for Register {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (self.address(), self.owner(), self.replica_authority()).write_canonical(out)?;
+            write_state(self, out)
+        }
+    }
+
+    impl CanonicalBytes for RegisterOp<RegisterEntry> {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (self.address, &self.crdt_op.parents, &self.crdt_op.value).write_canonical(out)?;
+            (self.source, &self.signature).write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for OwnerTransfer {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (self.new_owner, self.owner_index, &self.signature).write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for BlobWrite {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::New(blob) => (0u8, blob).write_canonical(out),
+                Self::DeletePrivate(address) => (1u8, address).write_canonical(out),
+                Self::Batch(blobs) => (2u8, blobs).write_canonical(out),
+            }
+        }
+    }
+
+    impl CanonicalBytes for MapWrite {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::New(map) => (0u8, map).write_canonical(out),
+                Self::Delete(address) => (1u8, address).write_canonical(out),
+                Self::Edit { address, changes } => (2u8, address, changes).write_canonical(out),
+                Self::DelUserPermissions {
+                    address,
+                    user,
+                    version,
+                } => (3u8, address, user, version).write_canonical(out),
+                Self::SetUserPermissions {
+                    address,
+                    user,
+                    permissions,
+                    version,
+                } => (4u8, address, user, permissions, version).write_canonical(out),
+                Self::SetOwner { address, transfer } => {
+                    (5u8, address, transfer).write_canonical(out)
+                }
+            }
+        }
+    }
+
+    impl CanonicalBytes for SequenceWrite {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::New(sequence) => (0u8, sequence).write_canonical(out),
+                Self::Edit(op) => (1u8, op).write_canonical(out),
+                Self::Delete(address) => (2u8, address).write_canonical(out),
+                Self::SetOwner { address, transfer } => {
+                    (3u8, address, transfer).write_canonical(out)
+                }
+                Self::RelayOps {
+                    address,
+                    ops,
+                    base_len,
+                } => (4u8, address, ops, base_len).write_canonical(out),
+            }
+        }
+    }
+
+    impl CanonicalBytes for RegisterWrite {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::New(register) => (0u8, register).write_canonical(out),
+                Self::Edit(op) => (1u8, op).write_canonical(out),
+                Self::Delete(address) => (2u8, address).write_canonical(out),
+                Self::SetPolicy { address, policy } => (3u8, address, policy).write_canonical(out),
+                Self::SetUserPermissions {
+                    address,
+                    user,
+                    permissions,
+                } => (4u8, address, user, permissions).write_canonical(out),
+                Self::DelUserPermissions { address, user } => {
+                    (5u8, address, user).write_canonical(out)
+                }
+                Self::SetOwner { address, transfer } => {
+                    (6u8, address, transfer).write_canonical(out)
+                }
+                Self::RelayOps {
+                    address,
+                    ops,
+                    frontier,
+                } => (7u8, address, ops, frontier).write_canonical(out),
+            }
+        }
+    }
+
+    impl CanonicalBytes for DataCmd {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::Blob(write) => (0u8, write).write_canonical(out),
+                Self::Map(write) => (1u8, write).write_canonical(out),
+                Self::Sequence(write) => (2u8, write).write_canonical(out),
+                Self::Register(write) => (3u8, write).write_canonical(out),
+            }
+        }
+    }
+
+    impl CanonicalBytes for TransferCmd {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::ValidateTransfer(transfer) => (0u8, transfer).write_canonical(out),
+                Self::RegisterTransfer(proof) => (1u8, proof).write_canonical(out),
+                #[cfg(feature = "testnet")]
+                Self::SimulatePayout { to, amount } => (2u8, to, amount).write_canonical(out),
+            }
+        }
+    }
+
+    impl CanonicalBytes for Capabilities {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (self.protocol_version, self.events).write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for SessionToken {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (self.client, self.capabilities, self.created, self.expiry).write_canonical(out)?;
+            self.proof.write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for QuoteId {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            self.0.write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for Cmd {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::Data { cmd, payment } => (0u8, cmd, payment).write_canonical(out),
+                Self::Transfer(cmd) => (1u8, cmd).write_canonical(out),
+                Self::RegisterEndUser {
+                    socket_addr,
+                    socketaddr_sig,
+                } => (2u8, socket_addr, socketaddr_sig).write_canonical(out),
+                Self::CreateSession {
+                    client,
+                    capabilities,
+                    last_seen_key,
+                } => (3u8, client, capabilities, last_seen_key).write_canonical(out),
+                Self::ResumeSession {
+                    token,
+                    last_seen_key,
+                } => (4u8, token, last_seen_key).write_canonical(out),
+                Self::KeepAlive { socket_id } => (5u8, socket_id).write_canonical(out),
+                Self::QuotedData {
+                    cmd,
+                    quote,
+                    payment,
+                } => (6u8, cmd, quote, payment).write_canonical(out),
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "client-only"))]
+mod node {
+    use super::CanonicalBytes;
+    use crate::node::{MembershipState, NodeState, Peer, Penalty, PenaltyReason, SectionSigned};
+    use crate::Result;
+    use secured_linked_list::SecuredLinkedList;
+    use serde::Serialize;
+
+    impl CanonicalBytes for Peer {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (self.name, self.addr, self.reachable).write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for MembershipState {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            match self {
+                Self::Joined => 0u8.write_canonical(out),
+                Self::Left => 1u8.write_canonical(out),
+                Self::Relocated(destination) => (2u8, destination).write_canonical(out),
+                Self::Penalized => 3u8.write_canonical(out),
+            }
+        }
+    }
+
+    impl CanonicalBytes for Penalty {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            let reason: u8 = match self.reason {
+                PenaltyReason::Unresponsive => 0,
+                PenaltyReason::InvalidMessage => 1,
+                PenaltyReason::MissingData => 2,
+            };
+            (reason, &self.proof).write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for NodeState {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (self.peer, self.state, &self.penalty, self.joined_at).write_canonical(out)?;
+            (self.relocation_count, self.last_seen).write_canonical(out)
+        }
+    }
+
+    impl CanonicalBytes for SecuredLinkedList {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            self.keys().collect::<Vec<_>>().write_canonical(out)
+        }
+    }
+
+    impl<T: Serialize + CanonicalBytes> CanonicalBytes for SectionSigned<T> {
+        fn write_canonical(&self, out: &mut Vec<u8>) -> Result<()> {
+            (&self.value, self.version, &self.signed).write_canonical(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    #[test]
+    fn stable_layout() -> Result<()> {
+        let name = XorName([7; 32]);
+        let prefix = Prefix::default().pushed(true);
+        let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 258));
+        let elders: BTreeMap<_, _> = vec![(name, addr)].into_iter().collect();
+
+        let mut expected = vec![0, 1];
+        expected.extend_from_slice(&prefix.name().0);
+        expected.extend_from_slice(&[0, 0, 0, 1]);
+        expected.extend_from_slice(&name.0);
+        expected.extend_from_slice(&[4, 10, 0, 0, 1, 1, 2]);
+        assert_eq!((prefix, &elders).canonical_bytes()?, expected);
+
+        let address = DataAddress::Map(MapAddress::Seq { name, tag: 3 });
+        let mut expected = vec![1, 1];
+        expected.extend_from_slice(&name.0);
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 3]);
+        assert_eq!(address.canonical_bytes()?, expected);
+
+        assert_eq!(
+            (Some(1u16), None::<u8>, vec![true]).canonical_bytes()?,
+            vec![1, 0, 1, 0, 0, 0, 0, 1, 1]
+        );
+
+        let owner = PublicKey::from(threshold_crypto::SecretKey::random().public_key());
+        let policy = register::Policy::Private(register::PrivatePolicy {
+            owner,
+            permissions: vec![(owner, register::PrivatePermissions::new(true, false))]
+                .into_iter()
+                .collect(),
+        });
+        let mut expected = vec![1];
+        expected.extend(owner.canonical_bytes()?);
+        expected.extend_from_slice(&[0, 0, 0, 1]);
+        expected.extend(owner.canonical_bytes()?);
+        expected.extend_from_slice(&[1, 0]);
+        assert_eq!(policy.canonical_bytes()?, expected);
+
+        let cmd = crate::client::Cmd::KeepAlive { socket_id: name };
+        let mut expected = vec![5];
+        expected.extend_from_slice(&name.0);
+        assert_eq!(cmd.canonical_bytes()?, expected);
+
+        let blob = sn_data_types::Blob::Public(sn_data_types::PublicBlob::new(vec![1, 2]));
+        let write = crate::client::DataCmd::Blob(crate::client::BlobWrite::New(blob.clone()));
+        let mut expected = vec![0, 0, 0, 0];
+        expected.extend_from_slice(&blob.name().0);
+        expected.extend_from_slice(&[0, 0, 0, 0, 2, 1, 2]);
+        assert_eq!(write.canonical_bytes()?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn crdt_state_is_covered() -> Result<()> {
+        use sn_data_types::Sequence;
+
+        let owner = PublicKey::from(threshold_crypto::SecretKey::random().public_key());
+        let name = XorName([7; 32]);
+        let sequence = |actor: &str| -> Result<Sequence> {
+            let mut sequence = Sequence::new_public(owner, actor.to_string(), name, 3, None);
+            let _ = sequence.create_unsigned_append_op(b"entry".to_vec())?;
+            Ok(sequence)
+        };
+
+        // Same owner, policy and entries, but the entries were appended by different actors.
+        let (actor, other_actor) = ("actor", "other actor");
+        assert_eq!(
+            sequence(actor)?.canonical_bytes()?,
+            sequence(actor)?.canonical_bytes()?
+        );
+        assert_ne!(
+            sequence(actor)?.canonical_bytes()?,
+            sequence(other_actor)?.canonical_bytes()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn state_layout() -> Result<()> {
+        #[derive(serde::Serialize)]
+        enum Entry {
+            Deleted,
+            Value(Vec<u8>, Option<char>),
+        }
+
+        #[derive(serde::Serialize)]
+        struct State {
+            clock: BTreeMap<String, u64>,
+            entries: Vec<Entry>,
+        }
+
+        let state = State {
+            clock: vec![("a".to_string(), 2)].into_iter().collect(),
+            entries: vec![Entry::Deleted, Entry::Value(vec![9], Some('x'))],
+        };
+        let mut bytes = Vec::new();
+        write_state(&state, &mut bytes)?;
+
+        let mut expected = vec![0, 0, 0, 1, 0, 0, 0, 1, b'a', 0, 0, 0, 0, 0, 0, 0, 2];
+        expected.extend_from_slice(&[0, 0, 0, 2, 0, 0, 0, 0]);
+        expected.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 1, 9, 1, 0, 0, 0, b'x']);
+        assert_eq!(bytes, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn domain_separation() -> Result<()> {
+        let payload = (XorName([1; 32]), 5u64);
+        let bytes = signing_bytes("wallet", &payload)?;
+
+        assert_eq!(&bytes[..4], &[0, 0, 0, 6]);
+        assert_eq!(&bytes[4..10], b"wallet");
+        assert_eq!(&bytes[10..], payload.canonical_bytes()?.as_slice());
+        assert_ne!(bytes, signing_bytes("redirect", &payload)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{Error, Result};
+use crate::{
+    canonical::{signing_bytes, CanonicalBytes},
+    MessageId, Signed,
+};
+use serde::{Deserialize, Serialize};
+use sn_data_types::PublicKey;
+use threshold_crypto::PublicKey as BlsPublicKey;
+use tiny_keccak::{Hasher, Sha3};
+
+/// Proof attached to a query that the client isn't flooding the section with queries,
+/// which elders may require from anonymous clients, rejecting queries without a valid
+/// one with `Error::QueryThrottled`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum AntiSpam {
+    /// Nonce such that the hash of the query id, the client key and the nonce has
+    /// at least as many leading zero bits as the difficulty required by the section.
+    /// As query ids are deduplicated, a proof can't be used for more than one query.
+    ProofOfWork(u64),
+    /// Token issued by the section to the client, e.g. once it paid for its queries.
+    Token(QueryToken),
+}
+
+/// Token issued by a section, admitting the queries of a client until it expires.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct QueryToken {
+    /// Client the token was issued to.
+    pub client: PublicKey,
+    /// Time, in milliseconds since the Unix epoch, at which the token expires.
+    pub expiry: u64,
+    /// Signature of the section over the client and expiry.
+    pub proof: Signed,
+}
+
+impl QueryToken {
+    /// Returns the bytes a section signs to issue a token.
+    pub fn bytes(client: &PublicKey, expiry: u64) -> crate::Result<Vec<u8>> {
+        signing_bytes("query-token", &(client, expiry))
+    }
+
+    /// Verifies the token was issued by the given section to the given client,
+    /// and hasn't expired at the given time, in milliseconds since the Unix epoch.
+    pub fn verify(&self, client: &PublicKey, section_key: &BlsPublicKey, now_millis: u64) -> bool {
+        self.client == *client
+            && now_millis < self.expiry
+            && self.proof.public_key == *section_key
+            && Self::bytes(&self.client, self.expiry)
+                .map(|bytes| self.proof.verify(&bytes))
+                .unwrap_or(false)
+    }
+}
+
+impl AntiSpam {
+    /// Solves the proof of work for the query with the given id, from the given client.
+    /// The expected number of hashes computed doubles with each bit of difficulty.
+    pub fn solve(id: &MessageId, client: &PublicKey, difficulty: u8) -> crate::Result<Self> {
+        let client = client.canonical_bytes()?;
+        let mut nonce = 0;
+        while leading_zeros(&work_hash(id, &client, nonce)) < difficulty.into() {
+            nonce += 1;
+        }
+        Ok(Self::ProofOfWork(nonce))
+    }
+
+    /// Validates the proof for the query with the given id, from the given client.
+    /// A proof of work must meet the difficulty, while a token must have been issued
+    /// to the client by the given section and be unexpired at the given time,
+    /// in milliseconds since the Unix epoch.
+    pub fn validate(
+        &self,
+        id: &MessageId,
+        client: &PublicKey,
+        difficulty: u8,
+        section_key: &BlsPublicKey,
+        now_millis: u64,
+    ) -> Result<()> {
+        let valid = match self {
+            Self::ProofOfWork(nonce) => client
+                .canonical_bytes()
+                .map(|client| leading_zeros(&work_hash(id, &client, *nonce)) >= difficulty.into())
+                .unwrap_or(false),
+            Self::Token(token) => token.verify(client, section_key, now_millis),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::QueryThrottled)
+        }
+    }
+}
+
+// Hashes the query id, the canonical bytes of the client key and the nonce.
+fn work_hash(id: &MessageId, client: &[u8], nonce: u64) -> [u8; 32] {
+    let mut hasher = Sha3::v256();
+    hasher.update(b"query-pow");
+    hasher.update(id.as_ref());
+    hasher.update(client);
+    hasher.update(&nonce.to_be_bytes());
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+fn leading_zeros(hash: &[u8; 32]) -> u32 {
+    let mut zeros = 0;
+    for byte in hash {
+        zeros += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    zeros
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_data_types::Keypair;
+
+    #[test]
+    fn proof_of_work() -> anyhow::Result<()> {
+        let client = PublicKey::from(&Keypair::new_ed25519(&mut rand::thread_rng()));
+        let section_key = threshold_crypto::SecretKey::random().public_key();
+        let id = MessageId::new();
+        let proof = AntiSpam::solve(&id, &client, 8)?;
+
+        assert_eq!(proof.validate(&id, &client, 8, &section_key, 0), Ok(()));
+        assert_eq!(
+            proof.validate(&MessageId::new(), &client, 8, &section_key, 0),
+            Err(Error::QueryThrottled)
+        );
+        assert_eq!(
+            proof.validate(&id, &client, 64, &section_key, 0),
+            Err(Error::QueryThrottled)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn section_token() -> anyhow::Result<()> {
+        let client = PublicKey::from(&Keypair::new_ed25519(&mut rand::thread_rng()));
+        let section_key = threshold_crypto::SecretKey::random();
+        let token = AntiSpam::Token(QueryToken {
+            client,
+            expiry: 1_000,
+            proof: Signed {
+                public_key: section_key.public_key(),
+                signature: section_key.sign(QueryToken::bytes(&client, 1_000)?),
+            },
+        });
+        let id = MessageId::new();
+
+        assert_eq!(
+            token.validate(&id, &client, 255, &section_key.public_key(), 999),
+            Ok(())
+        );
+        assert_eq!(
+            token.validate(&id, &client, 255, &section_key.public_key(), 1_000),
+            Err(Error::QueryThrottled)
+        );
+        let other_section = threshold_crypto::SecretKey::random().public_key();
+        assert_eq!(
+            token.validate(&id, &client, 255, &other_section, 999),
+            Err(Error::QueryThrottled)
+        );
+        let other_client = PublicKey::from(&Keypair::new_ed25519(&mut rand::thread_rng()));
+        assert_eq!(
+            token.validate(&id, &other_client, 255, &section_key.public_key(), 999),
+            Err(Error::QueryThrottled)
+        );
+        Ok(())
+    }
+}
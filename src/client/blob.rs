@@ -7,16 +7,18 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::{CmdError, Error, QueryResponse};
+use super::{CmdError, DataCmdKind, Error, QueryResponse};
 use serde::{Deserialize, Serialize};
-use sn_data_types::{Blob, BlobAddress, PublicKey};
-use xor_name::XorName;
+use sn_data_types::{Blob, BlobAddress, DataAddress, PublicKey};
+use xor_name::{Prefix, XorName};
 
 /// TODO: docs
 #[derive(Hash, Eq, PartialEq, PartialOrd, Clone, Serialize, Deserialize, Debug)]
 pub enum BlobRead {
     /// TODO: docs
     Get(BlobAddress),
+    /// Check whether a Blob is already stored, so its upload (and payment) can be skipped.
+    Exists(BlobAddress),
 }
 
 /// TODO: docs
@@ -27,20 +29,30 @@ pub enum BlobWrite {
     New(Blob),
     /// TODO: docs
     DeletePrivate(BlobAddress),
+    /// Store several Blobs with a single command and payment, e.g. the chunks of a
+    /// self-encrypted file. The outcome of each Blob is reported in `Event::BlobBatchStored`.
+    ///
+    /// A batch is routed to the section of its first Blob, so all its Blobs must belong to
+    /// that section: sections reject batches which aren't `within` their prefix, see
+    /// `ProcessMsg::validate_in_section`. Empty batches fail validation.
+    Batch(Vec<Blob>),
 }
 
 impl BlobRead {
     /// Creates a Response containing an error, with the Response variant corresponding to the
     /// Request variant.
     pub fn error(&self, error: Error) -> QueryResponse {
-        QueryResponse::GetBlob(Err(error))
+        match self {
+            Self::Get(_) => QueryResponse::GetBlob(Err(error)),
+            Self::Exists(_) => QueryResponse::BlobExists(Err(error)),
+        }
     }
 
     /// Returns the address of the destination for `request`.
     pub fn dst_address(&self) -> XorName {
         use BlobRead::*;
         match self {
-            Get(address) => *address.name(),
+            Get(address) | Exists(address) => *address.name(),
         }
     }
 }
@@ -49,7 +61,34 @@ impl BlobWrite {
     /// Creates a Response containing an error, with the Response variant corresponding to the
     /// Request variant.
     pub fn error(&self, error: Error) -> CmdError {
-        CmdError::Data(error)
+        CmdError::Data {
+            error,
+            address: DataAddress::Blob(self.address()),
+            op: self.kind(),
+        }
+    }
+
+    /// Returns the address of the data targeted by the request.
+    /// For a batch, that's the address of its first Blob. An empty batch, which
+    /// fails validation, has none and is given the address of the default name.
+    pub fn address(&self) -> BlobAddress {
+        match self {
+            Self::New(data) => *data.address(),
+            Self::DeletePrivate(address) => *address,
+            Self::Batch(blobs) => blobs
+                .first()
+                .map_or(BlobAddress::Public(XorName::default()), |data| {
+                    *data.address()
+                }),
+        }
+    }
+
+    /// Returns the kind of operation of the request.
+    pub fn kind(&self) -> DataCmdKind {
+        match self {
+            Self::New(_) | Self::Batch(_) => DataCmdKind::New,
+            Self::DeletePrivate(_) => DataCmdKind::Delete,
+        }
     }
 
     /// Returns the address of the destination for `request`.
@@ -58,14 +97,27 @@ impl BlobWrite {
         match self {
             New(ref data) => *data.name(),
             DeletePrivate(ref address) => *address.name(),
+            Batch(_) => *self.address().name(),
+        }
+    }
+
+    /// Returns true if all the Blobs written by the request are within the section
+    /// of the given prefix.
+    pub fn within(&self, prefix: &Prefix) -> bool {
+        match self {
+            Self::New(data) => prefix.matches(data.name()),
+            Self::DeletePrivate(address) => prefix.matches(address.name()),
+            Self::Batch(blobs) => blobs.iter().all(|data| prefix.matches(data.name())),
         }
     }
 
     /// Returns the owner of the data on a New Blob write.
+    /// All Blobs of a batch are required to share the owner of the first one.
     pub fn owner(&self) -> Option<PublicKey> {
         match self {
             Self::New(data) => data.owner().cloned(),
             Self::DeletePrivate(_) => None,
+            Self::Batch(blobs) => blobs.first().and_then(|data| data.owner().cloned()),
         }
     }
 }
@@ -0,0 +1,124 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{DataError, Error, QueryResponse, Result};
+use crate::MessageId;
+use std::collections::BTreeMap;
+
+/// Reassembles the content of a chunk returned in parts with `QueryResponse::GetChunkStream`,
+/// handing out the content in order as soon as it's contiguous, so clients can start
+/// decrypting it before all the parts arrived.
+#[derive(Clone, Debug)]
+pub struct ChunkStream {
+    correlation: MessageId,
+    total: Option<u32>,
+    next: u32,
+    pending: BTreeMap<u32, Vec<u8>>,
+}
+
+impl ChunkStream {
+    /// Splits the content of a chunk into the responses of the stream with the given id,
+    /// each carrying up to `part_size` bytes. Empty content is returned in a single empty part.
+    pub fn split(content: &[u8], part_size: usize, correlation: MessageId) -> Vec<QueryResponse> {
+        let parts: Vec<_> = if content.is_empty() {
+            vec![&[][..]]
+        } else {
+            content.chunks(part_size.max(1)).collect()
+        };
+        let total = parts.len() as u32;
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(index, part)| QueryResponse::GetChunkStream {
+                part: Ok(part.to_vec()),
+                index: index as u32,
+                total,
+                correlation,
+            })
+            .collect()
+    }
+
+    /// Creates an empty stream, accepting the parts with the given stream id.
+    pub fn new(correlation: MessageId) -> Self {
+        Self {
+            correlation,
+            total: None,
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a part of the stream, returning the content which became readable
+    /// in order, which is empty if preceding parts are still missing.
+    /// Duplicated parts are ignored, while parts of another stream, or inconsistent
+    /// with the previous ones, are rejected with `DataError::InvalidOperation`.
+    pub fn push(&mut self, response: QueryResponse) -> Result<Vec<u8>> {
+        let (part, index, total) = match response {
+            QueryResponse::GetChunkStream {
+                part,
+                index,
+                total,
+                correlation,
+            } if correlation == self.correlation => (part?, index, total),
+            _ => {
+                return Err(Error::Data(DataError::InvalidOperation(
+                    "Not a part of this chunk stream".to_string(),
+                )))
+            }
+        };
+        if index >= total || matches!(self.total, Some(known) if known != total) {
+            return Err(Error::Data(DataError::InvalidOperation(format!(
+                "Chunk stream part {} of {} is inconsistent",
+                index, total
+            ))));
+        }
+        self.total = Some(total);
+        if index >= self.next {
+            let _ = self.pending.entry(index).or_insert(part);
+        }
+
+        let mut content = Vec::new();
+        while let Some(part) = self.pending.remove(&self.next) {
+            content.extend(part);
+            self.next += 1;
+        }
+        Ok(content)
+    }
+
+    /// Returns true once all the content of the chunk has been handed out.
+    pub fn is_complete(&self) -> bool {
+        self.total == Some(self.next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reassemble_out_of_order() -> Result<()> {
+        let correlation = MessageId::new();
+        let content: Vec<u8> = (0..10).collect();
+        let mut parts = ChunkStream::split(&content, 4, correlation);
+        assert_eq!(parts.len(), 3);
+
+        let mut stream = ChunkStream::new(correlation);
+        let last = parts.pop().expect("three parts");
+        assert_eq!(stream.push(last.clone())?, Vec::<u8>::new());
+        assert_eq!(stream.push(parts.remove(1))?, Vec::<u8>::new());
+        assert_eq!(stream.push(last)?, Vec::<u8>::new());
+        assert!(!stream.is_complete());
+        assert_eq!(stream.push(parts.remove(0))?, content);
+        assert!(stream.is_complete());
+
+        let other = ChunkStream::split(&content, 4, MessageId::new());
+        assert!(stream.push(other[0].clone()).is_err());
+        Ok(())
+    }
+}
@@ -7,9 +7,18 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::{data::DataCmd, transfer::TransferCmd};
+use super::{
+    data::DataCmd,
+    session::{Capabilities, SessionToken},
+    store_cost::QuoteId,
+    transfer::TransferCmd,
+    Error, Result,
+};
+use crate::{canonical::signing_bytes, EndUser, SocketId};
 use serde::{Deserialize, Serialize};
-use sn_data_types::TransferAgreementProof;
+use sn_data_types::{PublicKey, Signature, TransferAgreementProof};
+use std::{collections::BTreeMap, fmt, net::SocketAddr};
+use threshold_crypto::PublicKey as BlsPublicKey;
 use xor_name::XorName;
 
 /// Command messages for data or transfer operations
@@ -25,6 +34,51 @@ pub enum Cmd {
     },
     /// Command for transfering safe network tokens
     Transfer(TransferCmd),
+    /// Command for registering the client as an EndUser proxied through the recipient Elders,
+    /// mapping its socket address to an EndUser name.
+    RegisterEndUser {
+        /// The socket address of the client, as seen by the client itself.
+        socket_addr: SocketAddr,
+        /// The client's signature over `Cmd::register_end_user_bytes` of the socket address.
+        socketaddr_sig: Signature,
+    },
+    /// Command for establishing a session with the recipient section, which responds
+    /// with `Event::SessionCreated`. The client signing the cmd must be `client`.
+    CreateSession {
+        /// The client.
+        client: PublicKey,
+        /// Features the client supports.
+        capabilities: Capabilities,
+        /// Last key of the section known to the client, if any, so the section can
+        /// bring the client up to date.
+        last_seen_key: Option<BlsPublicKey>,
+    },
+    /// Command for resuming a session established earlier, e.g. after reconnecting,
+    /// which the section confirms with `Event::SessionCreated`.
+    /// The client signing the cmd must be the one the session belongs to.
+    ResumeSession {
+        /// Token issued by the section when the session was created.
+        token: SessionToken,
+        /// Last key of the section known to the client, if any.
+        last_seen_key: Option<BlsPublicKey>,
+    },
+    /// Command keeping the EndUser registration of the given socket alive, and the NAT
+    /// mapping of the client warm. Clients are expected to send it at the interval of the
+    /// `RegistrationExpiry` of the Elders, which drop registrations idle past its timeout.
+    KeepAlive {
+        /// Socket id of the registered EndUser.
+        socket_id: SocketId,
+    },
+    /// Commands for manipulating data, paid as per a `StoreCostQuote` obtained beforehand,
+    /// which the recipient section charges instead of its current store cost.
+    QuotedData {
+        /// The data command struct itself
+        cmd: DataCmd,
+        /// Id of the quote the payment was made for.
+        quote: QuoteId,
+        /// Proof of payment of the quoted cost
+        payment: TransferAgreementProof,
+    },
 }
 
 impl Cmd {
@@ -32,8 +86,171 @@ impl Cmd {
     pub fn dst_address(&self) -> XorName {
         use Cmd::*;
         match self {
-            Data { cmd, .. } => cmd.dst_address(),
+            Data { cmd, .. } | QuotedData { cmd, .. } => cmd.dst_address(),
             Transfer(c) => c.dst_address(),
+            RegisterEndUser { socket_addr, .. } => EndUser::socket_id_for(socket_addr),
+            CreateSession { client, .. } => XorName::from(*client),
+            ResumeSession { token, .. } => XorName::from(token.client),
+            KeepAlive { socket_id } => *socket_id,
+        }
+    }
+
+    /// Returns the bytes a client signs to register as an end user with the given address.
+    pub fn register_end_user_bytes(socket_addr: &SocketAddr) -> crate::Result<Vec<u8>> {
+        signing_bytes("register-end-user", socket_addr)
+    }
+
+    /// Returns true if this is an end user registration whose socket address was signed by
+    /// `client`, or any other command.
+    pub fn verify_socket_addr_sig(&self, client: &PublicKey) -> bool {
+        match self {
+            Self::RegisterEndUser {
+                socket_addr,
+                socketaddr_sig,
+            } => Self::register_end_user_bytes(socket_addr)
+                .map(|bytes| client.verify(socketaddr_sig, bytes).is_ok())
+                .unwrap_or(false),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Cmd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Data { cmd, .. } => write!(f, "Cmd::Data({}, dst={})", cmd, self.dst_address()),
+            Self::Transfer(c) => write!(f, "Cmd::Transfer({}, dst={})", c, self.dst_address()),
+            Self::RegisterEndUser { .. } => {
+                write!(f, "Cmd::RegisterEndUser(dst={})", self.dst_address())
+            }
+            Self::CreateSession { .. } => {
+                write!(f, "Cmd::CreateSession(dst={})", self.dst_address())
+            }
+            Self::ResumeSession { token, .. } => match token.id() {
+                Ok(id) => write!(
+                    f,
+                    "Cmd::ResumeSession({:?}, dst={})",
+                    id,
+                    self.dst_address()
+                ),
+                Err(_) => write!(f, "Cmd::ResumeSession(dst={})", self.dst_address()),
+            },
+            Self::KeepAlive { .. } => write!(f, "Cmd::KeepAlive(dst={})", self.dst_address()),
+            Self::QuotedData { cmd, quote, .. } => write!(
+                f,
+                "Cmd::QuotedData({}, {:?}, dst={})",
+                cmd,
+                quote,
+                self.dst_address()
+            ),
+        }
+    }
+}
+
+/// Policy for expiring the EndUser registrations of clients which stopped sending
+/// `Cmd::KeepAlive`, e.g. because they went away without unregistering.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct RegistrationExpiry {
+    /// Time, in milliseconds, after the last keepalive past which a registration expires.
+    pub idle_timeout: u64,
+}
+
+impl Default for RegistrationExpiry {
+    fn default() -> Self {
+        Self {
+            idle_timeout: 90_000,
         }
     }
 }
+
+impl RegistrationExpiry {
+    /// Returns the interval, in milliseconds, clients should send keepalives at,
+    /// allowing for a couple of them to be lost before the registration expires.
+    pub fn keep_alive_interval(&self) -> u64 {
+        self.idle_timeout / 3
+    }
+
+    /// Returns whether a registration last kept alive at `last_seen` has expired at `now`,
+    /// both in milliseconds since the Unix epoch.
+    pub fn is_expired(&self, last_seen: u64, now: u64) -> bool {
+        now.saturating_sub(last_seen) > self.idle_timeout
+    }
+}
+
+/// Tracks the last command nonce seen from each client, rejecting
+/// commands which don't carry a strictly greater one.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayGuard {
+    last_nonces: BTreeMap<PublicKey, u64>,
+}
+
+impl ReplayGuard {
+    /// Create an empty guard.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the last nonce accepted from the given client, if any.
+    pub fn last_nonce(&self, client: &PublicKey) -> Option<u64> {
+        self.last_nonces.get(client).copied()
+    }
+
+    /// Checks the nonce of a command from the given client, without recording it.
+    pub fn check(&self, client: &PublicKey, nonce: u64) -> Result<()> {
+        match self.last_nonce(client) {
+            Some(last) if nonce <= last => Err(Error::ReplayDetected(nonce)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the nonce of a command from the given client, and records it if valid.
+    /// This should only be called once the client signature over the command has been verified.
+    pub fn check_and_record(&mut self, client: PublicKey, nonce: u64) -> Result<()> {
+        self.check(&client, nonce)?;
+        let _ = self.last_nonces.insert(client, nonce);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use sn_data_types::Keypair;
+
+    #[test]
+    fn register_end_user_signature() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let client = Keypair::new_ed25519(&mut rng);
+        let socket_addr = ([127, 0, 0, 1], 12000).into();
+        let cmd = |socketaddr_sig| Cmd::RegisterEndUser {
+            socket_addr,
+            socketaddr_sig,
+        };
+
+        let signed = cmd(client.sign(&Cmd::register_end_user_bytes(&socket_addr)?));
+        assert!(signed.verify_socket_addr_sig(&client.public_key()));
+        assert!(!signed.verify_socket_addr_sig(&Keypair::new_ed25519(&mut rng).public_key()));
+        let raw = cmd(client.sign(b"127.0.0.1:12000"));
+        assert!(!raw.verify_socket_addr_sig(&client.public_key()));
+        Ok(())
+    }
+
+    #[test]
+    fn replayed_nonce_is_rejected() {
+        let client = PublicKey::from(&Keypair::new_ed25519(&mut rand::thread_rng()));
+        let mut guard = ReplayGuard::new();
+
+        assert_eq!(guard.check_and_record(client, 1), Ok(()));
+        assert_eq!(guard.check_and_record(client, 3), Ok(()));
+        assert_eq!(
+            guard.check_and_record(client, 3),
+            Err(Error::ReplayDetected(3))
+        );
+        assert_eq!(
+            guard.check_and_record(client, 2),
+            Err(Error::ReplayDetected(2))
+        );
+        assert_eq!(guard.last_nonce(&client), Some(3));
+    }
+}
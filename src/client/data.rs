@@ -12,12 +12,86 @@ use super::{
     map::{MapRead, MapWrite},
     register::{RegisterRead, RegisterWrite},
     sequence::{SequenceRead, SequenceWrite},
-    CmdError, Error, QueryResponse,
+    CmdError, DataError, Error, QueryResponse,
 };
-use sn_data_types::PublicKey;
+use crate::canonical::signing_bytes;
+use sn_data_types::{DataAddress, PublicKey, Signature, Token};
 use xor_name::XorName;
 
 use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, fmt};
+
+/// Kind of operation of a data command, reported in its errors.
+#[derive(Copy, Hash, Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize, Debug)]
+pub enum DataCmdKind {
+    /// Creation of new data.
+    New,
+    /// Edition of existing data.
+    Edit,
+    /// Removal of data.
+    Delete,
+    /// Setting permissions of a user.
+    SetUserPermissions,
+    /// Removal of permissions of a user.
+    DelUserPermissions,
+    /// Replacement of the whole policy.
+    SetPolicy,
+    /// Transfer of the ownership to a new owner.
+    SetOwner,
+}
+
+/// Transfer of the ownership of a piece of data to a new owner,
+/// signed by its current owner.
+#[derive(Hash, Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct OwnerTransfer {
+    /// The new owner of the data.
+    pub new_owner: PublicKey,
+    /// Index of the current owner in the history of owners of the data, `0` being its
+    /// creator. The transfer is only valid while the data is at that index, so it can't
+    /// be replayed to take the ownership back after the data was transferred again.
+    pub owner_index: u64,
+    /// Signature of the current owner over the address of the data, the owner index
+    /// and the new owner.
+    pub signature: Signature,
+}
+
+// Signatures have no ordering, so transfers to the same
+// owner with different signatures aren't comparable.
+impl PartialOrd for OwnerTransfer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.new_owner, self.owner_index).partial_cmp(&(other.new_owner, other.owner_index))
+        {
+            Some(Ordering::Equal) if self.signature != other.signature => None,
+            ordering => ordering,
+        }
+    }
+}
+
+impl OwnerTransfer {
+    /// Returns the bytes the current owner, at `owner_index` in the history of
+    /// owners of the data at `address`, signs to transfer its ownership to `new_owner`.
+    pub fn bytes_to_sign(
+        address: &DataAddress,
+        owner_index: u64,
+        new_owner: &PublicKey,
+    ) -> crate::Result<Vec<u8>> {
+        signing_bytes("owner-transfer", &(address, owner_index, new_owner))
+    }
+
+    /// Verifies the transfer of the data at `address` was signed by `current_owner`,
+    /// which is at `current_owner_index` in the history of owners of the data.
+    pub fn verify(
+        &self,
+        address: &DataAddress,
+        current_owner: &PublicKey,
+        current_owner_index: u64,
+    ) -> bool {
+        self.owner_index == current_owner_index
+            && Self::bytes_to_sign(address, self.owner_index, &self.new_owner)
+                .map(|bytes| current_owner.verify(&self.signature, bytes).is_ok())
+                .unwrap_or(false)
+    }
+}
 
 /// Data command operations. Creating, updating or removing data
 #[allow(clippy::large_enum_variant)]
@@ -46,6 +120,39 @@ impl DataCmd {
         }
     }
 
+    /// Creates the error reporting the command came without sufficient payment,
+    /// quoting its cost until the given time, in milliseconds since the Unix epoch.
+    pub fn payment_required(&self, cost: Token, payee: PublicKey, quote_expiry: u64) -> CmdError {
+        CmdError::PaymentRequired {
+            address: self.address(),
+            cost,
+            payee,
+            quote_expiry,
+        }
+    }
+
+    /// Returns the address of the data targeted by the command.
+    pub fn address(&self) -> DataAddress {
+        use DataCmd::*;
+        match self {
+            Blob(c) => DataAddress::Blob(c.address()),
+            Map(c) => DataAddress::Map(c.address()),
+            Sequence(c) => DataAddress::Sequence(c.address()),
+            Register(c) => DataAddress::Register(c.address()),
+        }
+    }
+
+    /// Returns the kind of operation of the command.
+    pub fn kind(&self) -> DataCmdKind {
+        use DataCmd::*;
+        match self {
+            Blob(c) => c.kind(),
+            Map(c) => c.kind(),
+            Sequence(c) => c.kind(),
+            Register(c) => c.kind(),
+        }
+    }
+
     /// Returns the address of the destination for `cuest`.
     pub fn dst_address(&self) -> XorName {
         use DataCmd::*;
@@ -57,6 +164,34 @@ impl DataCmd {
         }
     }
 
+    /// Returns the ownership transfer carried by the command, if any.
+    pub fn owner_transfer(&self) -> Option<&OwnerTransfer> {
+        match self {
+            Self::Map(MapWrite::SetOwner { transfer, .. })
+            | Self::Sequence(SequenceWrite::SetOwner { transfer, .. })
+            | Self::Register(RegisterWrite::SetOwner { transfer, .. }) => Some(transfer),
+            _ => None,
+        }
+    }
+
+    /// Verifies an ownership transfer was signed by the given current owner of the data,
+    /// at the given index in its history of owners. Other commands don't transfer the
+    /// ownership, so they have nothing to check here.
+    pub fn verify_owner_transfer(
+        &self,
+        current_owner: &PublicKey,
+        current_owner_index: u64,
+    ) -> Result<(), Error> {
+        match self.owner_transfer() {
+            Some(transfer)
+                if !transfer.verify(&self.address(), current_owner, current_owner_index) =>
+            {
+                Err(Error::Data(DataError::UnauthorizedOwnerTransfer))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Returns the owner of the data.
     pub fn owner(&self) -> Option<PublicKey> {
         match self {
@@ -68,6 +203,18 @@ impl DataCmd {
     }
 }
 
+impl fmt::Display for DataCmd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let data_type = match self {
+            Self::Blob(_) => "Blob",
+            Self::Map(_) => "Map",
+            Self::Sequence(_) => "Sequence",
+            Self::Register(_) => "Register",
+        };
+        write!(f, "{}::{:?}", data_type, self.kind())
+    }
+}
+
 /// TODO: docs
 #[allow(clippy::large_enum_variant)]
 #[derive(Hash, Eq, PartialEq, PartialOrd, Clone, Serialize, Deserialize, Debug)]
@@ -80,6 +227,25 @@ pub enum DataQuery {
     Sequence(SequenceRead),
     /// TODO: docs
     Register(RegisterRead),
+    /// Get the metadata of any data, without its content.
+    GetMetadata(DataAddress),
+}
+
+/// Metadata of a piece of data, e.g. for listings of data which don't need its content.
+/// The kind of data is given by its address.
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct DataMetadata {
+    /// Address of the data.
+    pub address: DataAddress,
+    /// Serialised size of the data in bytes.
+    pub size: u64,
+    /// Owner of the data, if it's private or mutable.
+    pub owner: Option<PublicKey>,
+    /// Current version of the data, if it's mutable.
+    pub version: Option<u64>,
+    /// Digest of the current policy or permissions of the data, if it has any,
+    /// see `Event::policy_digest`.
+    pub policy_digest: Option<XorName>,
 }
 
 impl DataQuery {
@@ -92,6 +258,7 @@ impl DataQuery {
             Map(q) => q.error(error),
             Sequence(q) => q.error(error),
             Register(q) => q.error(error),
+            GetMetadata(_) => QueryResponse::GetDataMetadata(Err(error)),
         }
     }
 
@@ -103,6 +270,122 @@ impl DataQuery {
             Map(q) => q.dst_address(),
             Sequence(q) => q.dst_address(),
             Register(q) => q.dst_address(),
+            GetMetadata(address) => match address {
+                DataAddress::Blob(address) => *address.name(),
+                DataAddress::Map(address) => *address.name(),
+                DataAddress::Sequence(address) => *address.name(),
+                DataAddress::Register(address) => *address.name(),
+            },
         }
     }
 }
+
+impl fmt::Display for DataQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (data_type, operation) = match self {
+            Self::Blob(read) => (
+                "Blob",
+                match read {
+                    BlobRead::Get(_) => "Get",
+                    BlobRead::Exists(_) => "Exists",
+                },
+            ),
+            Self::Map(read) => (
+                "Map",
+                match read {
+                    MapRead::Get(_) => "Get",
+                    MapRead::GetValue { .. } => "GetValue",
+                    MapRead::GetShell(_) => "GetShell",
+                    MapRead::GetVersion(_) => "GetVersion",
+                    MapRead::ListEntries(_) => "ListEntries",
+                    MapRead::ListKeys(_) => "ListKeys",
+                    MapRead::ListValues(_) => "ListValues",
+                    MapRead::ListPermissions(_) => "ListPermissions",
+                    MapRead::ListUserPermissions { .. } => "ListUserPermissions",
+                },
+            ),
+            Self::Sequence(read) => (
+                "Sequence",
+                match read {
+                    SequenceRead::Get(_) => "Get",
+                    SequenceRead::GetRange { .. } => "GetRange",
+                    SequenceRead::GetLastEntry(_) => "GetLastEntry",
+                    SequenceRead::GetPublicPolicy(_) => "GetPublicPolicy",
+                    SequenceRead::GetPrivatePolicy(_) => "GetPrivatePolicy",
+                    SequenceRead::GetUserPermissions { .. } => "GetUserPermissions",
+                    SequenceRead::GetPage { .. } => "GetPage",
+                },
+            ),
+            Self::Register(read) => (
+                "Register",
+                match read {
+                    RegisterRead::Get(_) => "Get",
+                    RegisterRead::Read(_) => "Read",
+                    RegisterRead::GetPolicy(_) => "GetPolicy",
+                    RegisterRead::GetUserPermissions { .. } => "GetUserPermissions",
+                    RegisterRead::GetOwner(_) => "GetOwner",
+                    RegisterRead::GetEntries { .. } => "GetEntries",
+                    RegisterRead::GetEntriesSince { .. } => "GetEntriesSince",
+                },
+            ),
+            Self::GetMetadata(_) => return write!(f, "GetMetadata"),
+        };
+        write!(f, "{}::{}", data_type, operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use sn_data_types::{Keypair, SequenceAddress};
+
+    #[test]
+    fn owner_transfer_signed_by_current_owner() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let owner = Keypair::new_ed25519(&mut rng);
+        let new_owner = Keypair::new_ed25519(&mut rng).public_key();
+        let address = SequenceAddress::Public {
+            name: XorName::random(),
+            tag: 0,
+        };
+
+        let bytes = OwnerTransfer::bytes_to_sign(&DataAddress::Sequence(address), 2, &new_owner)?;
+        let cmd = DataCmd::Sequence(SequenceWrite::SetOwner {
+            address,
+            transfer: OwnerTransfer {
+                new_owner,
+                owner_index: 2,
+                signature: owner.sign(&bytes),
+            },
+        });
+
+        assert_eq!(cmd.kind(), DataCmdKind::SetOwner);
+        assert_eq!(cmd.verify_owner_transfer(&owner.public_key(), 2), Ok(()));
+        assert_eq!(
+            cmd.verify_owner_transfer(&new_owner, 2),
+            Err(Error::Data(DataError::UnauthorizedOwnerTransfer))
+        );
+
+        // Once the data changed owners again, the transfer can't be replayed.
+        assert_eq!(
+            cmd.verify_owner_transfer(&owner.public_key(), 4),
+            Err(Error::Data(DataError::UnauthorizedOwnerTransfer))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn metadata_query() {
+        let name = XorName::random();
+        let query = DataQuery::GetMetadata(DataAddress::Sequence(SequenceAddress::Private {
+            name,
+            tag: 1,
+        }));
+        assert_eq!(query.dst_address(), name);
+        assert!(matches!(
+            query.error(Error::Data(DataError::NoSuchEntry)),
+            QueryResponse::GetDataMetadata(Err(Error::Data(DataError::NoSuchEntry)))
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::ProcessMsg;
+use crate::{DeserialisationLimits, Error, Result};
+use std::collections::BTreeMap;
+use threshold_crypto::{
+    DecryptionShare, PublicKey as BlsPublicKey, PublicKeySet, SecretKey as BlsSecretKey,
+    SecretKeyShare,
+};
+
+impl ProcessMsg {
+    /// Returns the hint identifying the given recipient key in `ProcessMsg::Encrypted`.
+    pub fn recipient_pk_hint(recipient: &BlsPublicKey) -> [u8; 4] {
+        let mut hint = [0; 4];
+        hint.copy_from_slice(&recipient.to_bytes()[..4]);
+        hint
+    }
+
+    /// Encrypts the message to the given key, e.g. of the section holding private data
+    /// queried by the client, or of the EndUser a response is sent to.
+    /// Its destination, if any, is left in clear for it to be routed.
+    pub fn encrypt(&self, recipient: &BlsPublicKey) -> Result<Self> {
+        let bytes = rmp_serde::to_vec_named(self).map_err(|err| {
+            Error::Serialisation(format!(
+                "could not serialize message (id: {}) to encrypt: {}",
+                self.id(),
+                err
+            ))
+        })?;
+        Ok(Self::Encrypted {
+            id: self.id(),
+            recipient_pk_hint: Self::recipient_pk_hint(recipient),
+            dst_address: self.dst_address(),
+            ciphertext: recipient.encrypt(bytes),
+        })
+    }
+
+    /// Returns true if this is an encrypted message whose hint matches the given key.
+    pub fn is_encrypted_to(&self, key: &BlsPublicKey) -> bool {
+        match self {
+            Self::Encrypted {
+                recipient_pk_hint, ..
+            } => *recipient_pk_hint == Self::recipient_pk_hint(key),
+            _ => false,
+        }
+    }
+
+    /// Decrypts an encrypted message with the secret key it was encrypted to.
+    /// Fails if the decrypted message doesn't match the id and destination left in clear.
+    pub fn decrypt(&self, secret_key: &BlsSecretKey) -> Result<Self> {
+        match self {
+            Self::Encrypted { ciphertext, .. } => self.decrypted(
+                &secret_key
+                    .decrypt(ciphertext)
+                    .ok_or(Error::FailedToDecrypt)?,
+            ),
+            _ => Err(Error::FailedToDecrypt),
+        }
+    }
+
+    /// Returns the share of the decryption of a message encrypted to a section,
+    /// for the elder with the given key share.
+    pub fn decryption_share(&self, secret_key_share: &SecretKeyShare) -> Option<DecryptionShare> {
+        match self {
+            Self::Encrypted { ciphertext, .. } => secret_key_share.decrypt_share(ciphertext),
+            _ => None,
+        }
+    }
+
+    /// Decrypts a message encrypted to a section, from enough decryption shares of its elders,
+    /// keyed by their index in the key set.
+    pub fn decrypt_with_shares(
+        &self,
+        public_key_set: &PublicKeySet,
+        shares: &BTreeMap<usize, DecryptionShare>,
+    ) -> Result<Self> {
+        match self {
+            Self::Encrypted { ciphertext, .. } => self.decrypted(
+                &public_key_set
+                    .decrypt(shares, ciphertext)
+                    .map_err(|_| Error::FailedToDecrypt)?,
+            ),
+            _ => Err(Error::FailedToDecrypt),
+        }
+    }
+
+    // Decodes the decrypted bytes of this message, within the default limits as anyone can
+    // encrypt to a key, and checks the fields left in clear, which the ciphertext doesn't
+    // cover, match the decrypted message.
+    fn decrypted(&self, bytes: &[u8]) -> Result<Self> {
+        let msg: Self = DeserialisationLimits::default().decode(bytes, "decrypted message")?;
+        if msg.id() != self.id() {
+            return Err(Error::EncryptedMismatch("id"));
+        }
+        if msg.dst_address() != self.dst_address() {
+            return Err(Error::EncryptedMismatch("destination"));
+        }
+        Ok(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::{CmdError, DataError, TransferCmdError},
+        MessageId,
+    };
+    use anyhow::{anyhow, Result};
+    use threshold_crypto::SecretKeySet;
+
+    fn msg() -> ProcessMsg {
+        ProcessMsg::CmdError {
+            id: MessageId::new(),
+            error: CmdError::Transfer(TransferCmdError::TransferValidation(
+                crate::client::Error::Data(DataError::NoSuchEntry),
+            )),
+            correlation_id: MessageId::new(),
+            origin: None,
+        }
+    }
+
+    #[test]
+    fn encrypt_to_key() -> Result<()> {
+        let secret_key = BlsSecretKey::random();
+        let msg = msg();
+        let encrypted = msg.encrypt(&secret_key.public_key())?;
+
+        assert!(encrypted.is_encrypted_to(&secret_key.public_key()));
+        assert_eq!(encrypted.id(), msg.id());
+        assert_eq!(encrypted.decrypt(&secret_key)?, msg);
+        assert!(encrypted.decrypt(&BlsSecretKey::random()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn encrypted_query_keeps_dst_in_clear() -> Result<()> {
+        use crate::client::{BlobRead, ClientSigned, DataQuery, Query};
+        use sn_data_types::{BlobAddress, Keypair};
+        use xor_name::XorName;
+
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let name = XorName::random();
+        let query = ProcessMsg::Query {
+            id: MessageId::new(),
+            query: Query::Data(DataQuery::Blob(BlobRead::Get(BlobAddress::Private(name)))),
+            client_signed: ClientSigned {
+                public_key: keypair.public_key(),
+                signature: keypair.sign(b"query"),
+            },
+            deadline: None,
+            anti_spam: None,
+            session: None,
+        };
+        let secret_key = BlsSecretKey::random();
+
+        let encrypted = query.encrypt(&secret_key.public_key())?;
+        assert_eq!(encrypted.dst_address(), Some(name));
+        assert_eq!(encrypted.decrypt(&secret_key)?, query);
+
+        let encrypted = msg().encrypt(&secret_key.public_key())?;
+        assert_eq!(encrypted.dst_address(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn clear_text_rewrite_is_detected() -> Result<()> {
+        let secret_key = BlsSecretKey::random();
+        let encrypted = msg().encrypt(&secret_key.public_key())?;
+
+        let mut rewritten = encrypted.clone();
+        if let ProcessMsg::Encrypted { id, .. } = &mut rewritten {
+            *id = MessageId::new();
+        }
+        assert!(matches!(
+            rewritten.decrypt(&secret_key),
+            Err(Error::EncryptedMismatch("id"))
+        ));
+
+        let mut rewritten = encrypted;
+        if let ProcessMsg::Encrypted { dst_address, .. } = &mut rewritten {
+            *dst_address = Some(xor_name::XorName::random());
+        }
+        assert!(matches!(
+            rewritten.decrypt(&secret_key),
+            Err(Error::EncryptedMismatch("destination"))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_plaintext_is_rejected() -> Result<()> {
+        let secret_key = BlsSecretKey::random();
+        let bytes = vec![0; crate::limits::MAX_MSG_SIZE + 1];
+        let encrypted = ProcessMsg::Encrypted {
+            id: MessageId::new(),
+            recipient_pk_hint: ProcessMsg::recipient_pk_hint(&secret_key.public_key()),
+            dst_address: None,
+            ciphertext: secret_key.public_key().encrypt(bytes),
+        };
+        assert!(matches!(
+            encrypted.decrypt(&secret_key),
+            Err(Error::MessageTooLarge { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn encrypt_to_section() -> Result<()> {
+        let secret_key_set = SecretKeySet::random(1, &mut rand::thread_rng());
+        let public_key_set = secret_key_set.public_keys();
+        let msg = msg();
+        let encrypted = msg.encrypt(&public_key_set.public_key())?;
+
+        let shares = (0..2)
+            .map(|index| {
+                encrypted
+                    .decryption_share(&secret_key_set.secret_key_share(index))
+                    .map(|share| (index, share))
+                    .ok_or_else(|| anyhow!("Could not decrypt share"))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        assert_eq!(
+            encrypted.decrypt_with_shares(&public_key_set, &shares)?,
+            msg
+        );
+        Ok(())
+    }
+}
@@ -7,45 +7,98 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
+use super::data::DataCmdKind;
 use crate::section_info::Error as TargetSectionError;
+use crate::MessageId;
 use serde::{Deserialize, Serialize};
-use sn_data_types::DataAddress;
-use sn_data_types::PublicKey;
-use std::result;
+use sn_data_types::{
+    register::Action as RegisterAction, DataAddress, MapAction, PublicKey, SequenceAction,
+};
+use std::{fmt::Write, result};
 use thiserror::Error;
 
 /// A specialised `Result` type.
 pub type Result<T, E = Error> = result::Result<T, E>;
 
 /// Main error type for the crate.
+///
+/// Errors of operations on data, of transfers and of the nodes processing a request are
+/// nested in their own enums, the remaining variants being about the message itself,
+/// e.g. its version, signature or id, or of the section it was sent to.
 #[derive(Error, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(from = "WireError", into = "WireError")]
 #[non_exhaustive]
-#[allow(clippy::large_enum_variant)]
 pub enum Error {
+    /// Error of an operation on data.
+    #[error(transparent)]
+    Data(#[from] DataError),
+    /// Error of a transfer or payment.
+    #[error(transparent)]
+    Transfer(#[from] TransferError),
+    /// Error of the nodes processing the request.
+    #[error(transparent)]
+    NodeOp(#[from] NodeOpError),
     /// Message read was built with an unsupported version.
     #[error("Unsupported messaging protocol version: {0}")]
     UnsupportedVersion(u16),
     /// Message read contains a payload with an unsupported serialization type.
     #[error("Unsupported payload serialization: {0}")]
     UnsupportedSerialization(u16),
-    /// Access denied for supplied PublicKey
-    #[error("Access denied for PublicKey: {0}")]
-    AccessDenied(PublicKey),
     /// Error occurred when atempting to verify signature
     #[error("Signature verification error: {0}")]
     SignatureVerification(String),
     /// Serialization error
     #[error("Serialization error: {0}")]
     Serialization(String),
+    /// Mismatch between key type and signature type.
+    #[error("Sign key and signature type do not match")]
+    SigningKeyTypeMismatch,
+    /// Failed signature validation.
+    #[error("Invalid signature")]
+    InvalidSignature,
+    /// Received a request with a duplicate MessageId
+    #[error("Duplicate message id received")]
+    DuplicateMessageId,
+    // /// Network error occurring at Node level which has no bearing on clients, e.g. serialisation
+    // /// failure or database failure
+    // #[error("Network error: {0}")]
+    // NetworkOther(String),
+    /// There was an error in the target section of a message. Probably related to section keys.
+    #[error("Target section error")]
+    TargetSection(#[from] TargetSectionError),
+    /// Received a command with a nonce not greater than the last one seen from the client.
+    #[error("Replayed command detected, with nonce: {0}")]
+    ReplayDetected(u64),
+    /// The query was dropped since its deadline had passed.
+    #[error("Query aborted as its deadline has passed")]
+    QueryAborted,
+    /// A query response of a different type than the one expected was received.
+    #[error("Unexpected type of query response")]
+    UnexpectedQueryResponse,
+    /// The query came without a valid anti-spam proof, while the section requires one.
+    #[error("Query throttled for lack of a valid anti-spam proof")]
+    QueryThrottled,
+}
+
+/// Errors of operations on data: missing data or entries, permissions,
+/// concurrent or invalid mutations.
+#[derive(Error, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum DataError {
+    /// Access denied for supplied PublicKey
+    #[error("Access denied for PublicKey: {0}")]
+    AccessDenied(PublicKey),
+    /// Access denied for supplied PublicKey, along with the operation it attempted,
+    /// `None` for reads, and the permission that operation requires, if known.
+    #[error("Access denied for PublicKey: {key}{}", denied_access(.op, .required))]
+    AccessDeniedDetailed {
+        key: PublicKey,
+        op: Option<DataCmdKind>,
+        required: Option<RequiredPermission>,
+    },
     /// Requested data not found
     #[error("Requested data not found: {0:?}")]
     DataNotFound(DataAddress),
-    /// No history found for PublicKey
-    #[error("No history found for PublicKey: {0}")]
-    NoHistoryForPublicKey(sn_data_types::PublicKey),
-    /// Failed to write file, likely due to a system Io error
-    #[error("Failed to write file")]
-    FailedToWriteFile,
     /// Provided data already exists on the network
     #[error("Data provided already exists")]
     DataExists,
@@ -58,9 +111,6 @@ pub enum Error {
     /// Key does not exist
     #[error("Key does not exist")]
     NoSuchKey,
-    /// Node NotEnoughSpace error
-    #[error("Node does not have sufficient space to store chunk")]
-    NotEnoughSpace,
     /// Duplicate Entries in this push
     #[error("Duplicate entries provided")]
     DuplicateEntryKeys,
@@ -88,19 +138,36 @@ pub enum Error {
     /// Invalid Operation such as a POST on ImmutableData
     #[error("Invalid operation: {0}")]
     InvalidOperation(String),
-    /// Mismatch between key type and signature type.
-    #[error("Sign key and signature type do not match")]
-    SigningKeyTypeMismatch,
-    /// Failed signature validation.
-    #[error("Invalid signature")]
-    InvalidSignature,
-    /// Received a request with a duplicate MessageId
-    #[error("Duplicate message id received")]
-    DuplicateMessageId,
-    // /// Network error occurring at Node level which has no bearing on clients, e.g. serialisation
-    // /// failure or database failure
-    // #[error("Network error: {0}")]
-    // NetworkOther(String),
+    /// Expected data size exceeded.
+    #[error("Size of the structure exceeds the limit")]
+    ExceededSize,
+    /// The operation has not been signed by an actor PK and so cannot be validated.
+    #[error("CRDT operation missing actor signature")]
+    CrdtMissingOpSignature,
+    /// The data for a given policy could not be located, so CRDT operations cannot be applied.
+    #[error("CRDT data is in an unexpected and/or inconsistent state. No data found for current policy.")]
+    CrdtUnexpectedState,
+    /// Entry already exists. Contains the current entry Key.
+    #[error("Entry already exists {0}")]
+    EntryExists(u8),
+    /// Public data can't be deleted.
+    #[error("Public data can't be deleted")]
+    CannotDeletePublicData,
+    /// The kind (public or private) of a policy or permissions doesn't match the one of the data.
+    #[error("Policy kind doesn't match the kind of the data")]
+    PolicyKindMismatch,
+    /// The ownership transfer wasn't signed by the current owner of the data.
+    #[error("Ownership transfer not signed by the current owner")]
+    UnauthorizedOwnerTransfer,
+}
+
+/// Errors of transfers and payments, and of the balances they apply to.
+#[derive(Error, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum TransferError {
+    /// No history found for PublicKey
+    #[error("No history found for PublicKey: {0}")]
+    NoHistoryForPublicKey(sn_data_types::PublicKey),
     /// While parsing, precision would be lost.
     #[error("Lost precision on the number of coins during parsing")]
     LossOfPrecision,
@@ -127,21 +194,22 @@ pub enum Error {
     /// Coin balance already exists.
     #[error("Key already exists")]
     BalanceExists,
-    /// Expected data size exceeded.
-    #[error("Size of the structure exceeds the limit")]
-    ExceededSize,
-    /// The operation has not been signed by an actor PK and so cannot be validated.
-    #[error("CRDT operation missing actor signature")]
-    CrdtMissingOpSignature,
-    /// The data for a given policy could not be located, so CRDT operations cannot be applied.
-    #[error("CRDT data is in an unexpected and/or inconsistent state. No data found for current policy.")]
-    CrdtUnexpectedState,
-    /// Entry already exists. Contains the current entry Key.
-    #[error("Entry already exists {0}")]
-    EntryExists(u8),
     /// Problem registering the payment at a node
     #[error("Payment registration failed")]
     PaymentFailed,
+}
+
+/// Errors of the nodes processing a request, unrelated to the request itself,
+/// e.g. storage or churn failures.
+#[derive(Error, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum NodeOpError {
+    /// Failed to write file, likely due to a system Io error
+    #[error("Failed to write file")]
+    FailedToWriteFile,
+    /// Node NotEnoughSpace error
+    #[error("Node does not have sufficient space to store chunk")]
+    NotEnoughSpace,
     /// Node failed to delete the requested data for some reason.
     #[error("Failed to delete requested data")]
     FailedToDelete,
@@ -160,7 +228,239 @@ pub enum Error {
     /// The node hasn't left the section, and was not marked for relocation during reward operations
     #[error("Node is not being relocated")]
     NodeWasNotRelocated,
-    /// There was an error in the target section of a message. Probably related to section keys.
-    #[error("Target section error")]
-    TargetSection(#[from] TargetSectionError),
+    /// A reward payout was accumulated without a matching outstanding proposal,
+    /// e.g. because it was already applied.
+    #[error("No outstanding reward proposal with id: {0}")]
+    UnknownRewardProposal(MessageId),
+    /// A reward payout was proposed for an epoch preceding the current one.
+    #[error("Reward proposal for past epoch: {0}")]
+    StaleRewardEpoch(u64),
+}
+
+// Path of a variant of `Error`, nested in its category unless it's about the message.
+macro_rules! categorised {
+    (Data, $($variant:tt)*) => { Error::Data(DataError::$($variant)*) };
+    (Transfer, $($variant:tt)*) => { Error::Transfer(TransferError::$($variant)*) };
+    (NodeOp, $($variant:tt)*) => { Error::NodeOp(NodeOpError::$($variant)*) };
+    (Msg, $($variant:tt)*) => { Error::$($variant)* };
+}
+
+// Declares `WireError`, the flat layout `Error` is sent over the wire with, and the
+// conversions between the two. The variants are listed in their wire order, which must
+// never change: new ones are appended at the end.
+macro_rules! wire_error {
+    ($(
+        $category:ident: $variant:ident
+        $(($value:ident: $ty:ty))?
+        $({ $($field:ident: $field_ty:ty),* })?,
+    )*) => {
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename = "Error")]
+        enum WireError {
+            $($variant $(($ty))? $({ $($field: $field_ty),* })?,)*
+        }
+
+        impl From<Error> for WireError {
+            fn from(error: Error) -> Self {
+                match error {
+                    $(categorised!($category, $variant $(($value))? $({ $($field),* })?)
+                        => Self::$variant $(($value))? $({ $($field),* })?,)*
+                }
+            }
+        }
+
+        impl From<WireError> for Error {
+            fn from(error: WireError) -> Self {
+                match error {
+                    $(WireError::$variant $(($value))? $({ $($field),* })?
+                        => categorised!($category, $variant $(($value))? $({ $($field),* })?),)*
+                }
+            }
+        }
+    };
+}
+
+wire_error! {
+    Msg: UnsupportedVersion(version: u16),
+    Msg: UnsupportedSerialization(serialization: u16),
+    Data: AccessDenied(key: PublicKey),
+    Msg: SignatureVerification(message: String),
+    Msg: Serialization(message: String),
+    Data: DataNotFound(address: DataAddress),
+    Transfer: NoHistoryForPublicKey(key: PublicKey),
+    NodeOp: FailedToWriteFile,
+    Data: DataExists,
+    Data: NoSuchEntry,
+    Data: TooManyEntries,
+    Data: NoSuchKey,
+    NodeOp: NotEnoughSpace,
+    Data: DuplicateEntryKeys,
+    Data: InvalidOwners(key: PublicKey),
+    Data: PolicyNotSet,
+    Data: InvalidSuccessor(version: u64),
+    Data: InvalidOwnersSuccessor(version: u64),
+    Data: OpNotCausallyReady,
+    Data: InvalidPermissionsSuccessor(version: u64),
+    Data: InvalidOperation(message: String),
+    Msg: SigningKeyTypeMismatch,
+    Msg: InvalidSignature,
+    Msg: DuplicateMessageId,
+    Transfer: LossOfPrecision,
+    Transfer: ExcessiveValue,
+    Transfer: TransactionIdExists,
+    Transfer: InsufficientPayment,
+    Transfer: NoSuchBalance,
+    Transfer: NoSuchSender,
+    Transfer: NoSuchRecipient,
+    Transfer: BalanceExists,
+    Data: ExceededSize,
+    Data: CrdtMissingOpSignature,
+    Data: CrdtUnexpectedState,
+    Data: EntryExists(key: u8),
+    Transfer: PaymentFailed,
+    NodeOp: FailedToDelete,
+    NodeOp: NoSectionFunds,
+    NodeOp: NoSectionMetaData,
+    NodeOp: NoImmutableChunks,
+    NodeOp: NodeChurningFunds,
+    NodeOp: NodeWasNotRelocated,
+    Msg: TargetSection(error: TargetSectionError),
+    Msg: ReplayDetected(nonce: u64),
+    Msg: QueryAborted,
+    Msg: UnexpectedQueryResponse,
+    Data: CannotDeletePublicData,
+    Data: PolicyKindMismatch,
+    Data: UnauthorizedOwnerTransfer,
+    NodeOp: UnknownRewardProposal(id: MessageId),
+    NodeOp: StaleRewardEpoch(epoch: u64),
+    Msg: QueryThrottled,
+    Data: AccessDeniedDetailed {
+        key: PublicKey,
+        op: Option<DataCmdKind>,
+        required: Option<RequiredPermission>
+    },
+}
+
+/// Permission, or policy entry, required for an operation on data.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum RequiredPermission {
+    /// Reading the data.
+    Read,
+    /// Inserting entries in a Map.
+    Insert,
+    /// Updating entries of a Map.
+    Update,
+    /// Deleting entries of a Map.
+    Delete,
+    /// Appending to a Sequence.
+    Append,
+    /// Writing to a Register.
+    Write,
+    /// Managing the permissions of a Map.
+    ManagePermissions,
+    /// Being the owner of the data.
+    Owner,
+}
+
+impl From<MapAction> for RequiredPermission {
+    fn from(action: MapAction) -> Self {
+        match action {
+            MapAction::Read => Self::Read,
+            MapAction::Insert => Self::Insert,
+            MapAction::Update => Self::Update,
+            MapAction::Delete => Self::Delete,
+            MapAction::ManagePermissions => Self::ManagePermissions,
+        }
+    }
+}
+
+impl From<SequenceAction> for RequiredPermission {
+    fn from(action: SequenceAction) -> Self {
+        match action {
+            SequenceAction::Read => Self::Read,
+            SequenceAction::Append => Self::Append,
+        }
+    }
+}
+
+impl From<RegisterAction> for RequiredPermission {
+    fn from(action: RegisterAction) -> Self {
+        match action {
+            RegisterAction::Read => Self::Read,
+            RegisterAction::Write => Self::Write,
+        }
+    }
+}
+
+// Details of an `AccessDeniedDetailed`, appended to its message.
+fn denied_access(op: &Option<DataCmdKind>, required: &Option<RequiredPermission>) -> String {
+    let mut details = String::new();
+    if let Some(op) = op {
+        let _ = write!(details, ", attempting {:?}", op);
+    }
+    if let Some(required) = required {
+        let _ = write!(details, ", which requires {:?}", required);
+    }
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_data_types::BlobAddress;
+    use xor_name::XorName;
+
+    #[test]
+    fn errors_are_nested_in_their_category() {
+        let address = DataAddress::Blob(BlobAddress::Public(XorName::random()));
+        let not_found = Error::from(DataError::DataNotFound(address.clone()));
+        assert_eq!(
+            not_found.to_string(),
+            format!("Requested data not found: {:?}", address)
+        );
+        assert!(matches!(
+            Error::from(TransferError::InsufficientPayment),
+            Error::Transfer(TransferError::InsufficientPayment)
+        ));
+
+        let key = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let denied = Error::from(DataError::AccessDeniedDetailed {
+            key,
+            op: Some(DataCmdKind::Edit),
+            required: Some(MapAction::Update.into()),
+        });
+        assert_eq!(
+            denied.to_string(),
+            format!(
+                "Access denied for PublicKey: {}, attempting Edit, which requires Update",
+                key
+            )
+        );
+        assert_eq!(
+            Error::from(DataError::AccessDenied(key)).to_string(),
+            format!("Access denied for PublicKey: {}", key)
+        );
+    }
+
+    #[test]
+    fn errors_keep_their_wire_layout() -> anyhow::Result<()> {
+        // Variants are sent as a map from their index in the flat layout to their value.
+        let errors = [
+            (Error::from(DataError::NoSuchEntry), 9),
+            (Error::from(TransferError::InsufficientPayment), 27),
+            (Error::QueryAborted, 45),
+            (Error::QueryThrottled, 52),
+        ];
+        for (error, index) in errors.iter() {
+            let bytes = rmp_serde::to_vec_named(error)?;
+            assert_eq!(bytes, [0x81, *index, 0xc0]);
+            assert_eq!(&rmp_serde::from_slice::<Error>(&bytes)?, error);
+        }
+
+        let error = Error::from(NodeOpError::StaleRewardEpoch(3));
+        let bytes = rmp_serde::to_vec_named(&error)?;
+        assert_eq!(bytes, [0x81, 51, 3]);
+        assert_eq!(rmp_serde::from_slice::<Error>(&bytes)?, error);
+        Ok(())
+    }
 }
@@ -7,10 +7,10 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::{CmdError, Error, QueryResponse};
+use super::{CmdError, DataCmdKind, Error, OwnerTransfer, QueryResponse};
 use sn_data_types::{
-    Map, MapAddress as Address, MapEntryActions as Changes, MapPermissionSet as PermissionSet,
-    PublicKey,
+    DataAddress, Map, MapAddress as Address, MapEntryActions as Changes,
+    MapPermissionSet as PermissionSet, PublicKey,
 };
 use xor_name::XorName;
 
@@ -85,6 +85,13 @@ pub enum MapWrite {
         /// Version to set.
         version: u64,
     },
+    /// Transfer the ownership of the Map to a new owner.
+    SetOwner {
+        /// Map address.
+        address: Address,
+        /// The transfer, signed by the current owner.
+        transfer: OwnerTransfer,
+    },
 }
 
 impl MapRead {
@@ -126,7 +133,37 @@ impl MapWrite {
     /// Creates a Response containing an error, with the Response variant corresponding to the
     /// Request variant.
     pub fn error(&self, error: Error) -> CmdError {
-        CmdError::Data(error)
+        CmdError::Data {
+            error,
+            address: DataAddress::Map(self.address()),
+            op: self.kind(),
+        }
+    }
+
+    /// Returns the address of the data targeted by the request.
+    pub fn address(&self) -> Address {
+        use MapWrite::*;
+        match self {
+            New(ref data) => *data.address(),
+            Delete(ref address)
+            | SetUserPermissions { ref address, .. }
+            | DelUserPermissions { ref address, .. }
+            | SetOwner { ref address, .. }
+            | Edit { ref address, .. } => *address,
+        }
+    }
+
+    /// Returns the kind of operation of the request.
+    pub fn kind(&self) -> DataCmdKind {
+        use MapWrite::*;
+        match self {
+            New(_) => DataCmdKind::New,
+            Delete(_) => DataCmdKind::Delete,
+            Edit { .. } => DataCmdKind::Edit,
+            SetUserPermissions { .. } => DataCmdKind::SetUserPermissions,
+            DelUserPermissions { .. } => DataCmdKind::DelUserPermissions,
+            SetOwner { .. } => DataCmdKind::SetOwner,
+        }
     }
 
     /// Returns the address of the destination for request.
@@ -137,6 +174,7 @@ impl MapWrite {
             Delete(ref address)
             | SetUserPermissions { ref address, .. }
             | DelUserPermissions { ref address, .. }
+            | SetOwner { ref address, .. }
             | Edit { ref address, .. } => *address.name(),
         }
     }
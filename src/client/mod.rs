@@ -7,50 +7,77 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
+mod anti_spam;
 mod blob;
+mod chunk_stream;
 mod cmd;
 mod data;
 mod data_exchange;
 mod duty;
+mod encryption;
 mod errors;
 mod map;
 mod query;
 mod register;
+mod response_accumulator;
 mod sequence;
+mod session;
+mod store_cost;
 mod transfer;
+mod transfer_validation;
+mod validation;
 
 pub use self::{
+    anti_spam::{AntiSpam, QueryToken},
     blob::{BlobRead, BlobWrite},
-    cmd::Cmd,
-    data::{DataCmd, DataQuery},
+    chunk_stream::ChunkStream,
+    cmd::{Cmd, RegistrationExpiry, ReplayGuard},
+    data::{DataCmd, DataCmdKind, DataMetadata, DataQuery, OwnerTransfer},
     data_exchange::{
         BlobDataExchange, ChunkMetadata, DataExchange, HolderMetadata, MapDataExchange,
         SequenceDataExchange,
     },
     duty::{AdultDuties, Duty, ElderDuties, NodeDuties},
-    errors::{Error, Result},
+    errors::{DataError, Error, NodeOpError, RequiredPermission, Result, TransferError},
     map::{MapRead, MapWrite},
     query::Query,
     register::{RegisterRead, RegisterWrite},
-    sequence::{SequenceRead, SequenceWrite},
-    transfer::{TransferCmd, TransferQuery},
+    response_accumulator::{Accumulated, ResponseAccumulator, ResponseTally},
+    sequence::{SequencePage, SequenceRead, SequenceWrite},
+    session::{Capabilities, SessionId, SessionToken},
+    store_cost::{QuoteId, StoreCostQuote},
+    transfer::{TransferCmd, TransferProof, TransferQuery},
+    transfer_validation::{
+        TransferValidationAccumulator, TransferValidationError, DEFAULT_VALIDATION_TTL,
+    },
+    validation::ValidationError,
 };
 
-use crate::{MessageId, MessageType, WireMsg};
+use self::transfer_validation::{verify_agreement, verify_validation};
+use crate::{
+    canonical::{signing_bytes, CanonicalBytes},
+    section_info::SectionKeyProof,
+    serialisation::unknown::{self, ForwardCompatible},
+    DstLocation, MessageId, MessageType, Signed, WireMsg,
+};
 use bytes::Bytes;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sn_data_types::{
-    register::{Entry, EntryHash, Permissions, Policy, Register},
-    ActorHistory, Blob, Map, MapEntries, MapPermissionSet, MapValue, MapValues, PublicKey,
-    Sequence, SequenceEntries, SequenceEntry, SequencePermissions, SequencePrivatePolicy,
-    SequencePublicPolicy, Signature, Token, TransferAgreementProof, TransferValidated,
+    register::{Entry, EntryHash, Permissions, Policy, Register, RegisterOp},
+    ActorHistory, Blob, BlobAddress, DataAddress, Map, MapEntries, MapPermissionSet, MapValue,
+    MapValues, PublicKey, Sequence, SequenceEntries, SequenceEntry, SequencePermissions,
+    SequencePrivatePolicy, SequencePublicPolicy, Signature, Token, TransferAgreementProof,
+    TransferValidated,
 };
 use std::{
     collections::{BTreeMap, BTreeSet},
     convert::TryFrom,
+    fmt,
+    net::SocketAddr,
+    time::Duration,
 };
-use threshold_crypto::PublicKey as BlsPublicKey;
-use xor_name::XorName;
+use threshold_crypto::{Ciphertext, PublicKey as BlsPublicKey};
+use xor_name::{Prefix, XorName};
 
 /// Public key and signature provided by the client
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
@@ -177,19 +204,19 @@ impl ClientMsg {
     }
 
     /// Serialize this Message into bytes ready to be sent over the wire.
-    pub fn serialize(&self, dest: XorName, dest_section_pk: BlsPublicKey) -> crate::Result<Bytes> {
+    pub fn serialize(
+        &self,
+        dest: DstLocation,
+        dest_section_pk: BlsPublicKey,
+    ) -> crate::Result<Bytes> {
         WireMsg::serialize_client_msg(self, dest, dest_section_pk)
     }
 
     /// Gets the message ID.
     pub fn id(&self) -> MessageId {
         match self {
-            Self::Process(ProcessMsg::Cmd { id, .. })
-            | Self::Process(ProcessMsg::Query { id, .. })
-            | Self::Process(ProcessMsg::Event { id, .. })
-            | Self::Process(ProcessMsg::QueryResponse { id, .. })
-            | Self::Process(ProcessMsg::CmdError { id, .. })
-            | Self::ProcessingError(ProcessingError { id, .. }) => *id,
+            Self::Process(msg) => msg.id(),
+            Self::ProcessingError(ProcessingError { id, .. }) => *id,
             Self::SupportingInfo(SupportingInfo { id, .. }) => *id,
         }
     }
@@ -211,11 +238,51 @@ impl ClientMsg {
             Self::ProcessingError(error) => Some(error),
         }
     }
+
+    /// Returns the id of the message this one responds to, if any.
+    /// For a processing error, this is the id of the message which failed to be processed.
+    pub fn correlation_id(&self) -> Option<MessageId> {
+        match self {
+            Self::Process(msg) => msg.correlation_id(),
+            Self::ProcessingError(error) => error.source_message().as_ref().map(ProcessMsg::id),
+            Self::SupportingInfo(info) => Some(info.correlation_id),
+        }
+    }
+
+    /// Returns the address of the destination of the message, or of the message
+    /// which an error or supporting info refers to.
+    pub fn dst_address(&self) -> Option<XorName> {
+        match self {
+            Self::Process(msg) => msg.dst_address(),
+            Self::ProcessingError(error) => error
+                .source_message()
+                .as_ref()
+                .and_then(ProcessMsg::dst_address),
+            Self::SupportingInfo(info) => info.source_message().dst_address(),
+        }
+    }
 }
 
-///
+impl fmt::Display for ClientMsg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Process(msg) => write!(f, "{}", msg),
+            Self::ProcessingError(error) => write!(f, "ProcessingError(id={})", error.id),
+            Self::SupportingInfo(info) => write!(
+                f,
+                "SupportingInfo(id={}, correlation_id={})",
+                info.id, info.correlation_id
+            ),
+        }
+    }
+}
+
+/// Messages exchanged by clients and the network to be processed by their recipient: the
+/// commands and queries of clients, and the events, responses and errors they cause.
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+#[non_exhaustive]
 pub enum ProcessMsg {
     /// A Cmd is leads to a write / change of state.
     /// We expect them to be successful, and only return a msg
@@ -225,8 +292,16 @@ pub enum ProcessMsg {
         id: MessageId,
         /// Cmd.
         cmd: Cmd,
-        /// Public key and corresponding signature over the command
+        /// Client-monotonic nonce, which along with the command is covered by the
+        /// client signature, so that the command can't be replayed.
+        nonce: u64,
+        /// Public key and corresponding signature over the command and nonce, see
+        /// `ProcessMsg::cmd_bytes`.
         client_signed: ClientSigned,
+        /// Id of the session the command is sent within, if any.
+        /// Not covered by the client signature.
+        #[serde(default)]
+        session: Option<SessionId>,
     },
     /// Queries is a read-only operation.
     Query {
@@ -236,6 +311,18 @@ pub enum ProcessMsg {
         query: Query,
         /// Public key and corresponding signature over the query
         client_signed: ClientSigned,
+        /// Time, in milliseconds since the Unix epoch, past which the client no longer
+        /// expects a response. It's only a hint, thus not covered by the client signature.
+        #[serde(default)]
+        deadline: Option<u64>,
+        /// Proof that the client isn't flooding the section with queries, which elders
+        /// may require before processing the query. Not covered by the client signature.
+        #[serde(default)]
+        anti_spam: Option<AntiSpam>,
+        /// Id of the session the query is sent within, if any.
+        /// Not covered by the client signature.
+        #[serde(default)]
+        session: Option<SessionId>,
     },
     /// An Event is a fact about something that happened.
     Event {
@@ -254,6 +341,9 @@ pub enum ProcessMsg {
         response: QueryResponse,
         /// ID of causing query.
         correlation_id: MessageId,
+        /// Section which handled the query, set at least on errors.
+        #[serde(default)]
+        origin: Option<ResponseOrigin>,
     },
     /// Cmd error.
     CmdError {
@@ -263,9 +353,84 @@ pub enum ProcessMsg {
         error: CmdError,
         /// ID of causing cmd.
         correlation_id: MessageId,
+        /// Section which handled the cmd.
+        #[serde(default)]
+        origin: Option<ResponseOrigin>,
+    },
+    /// Tells the elders the client no longer expects a response to a query,
+    /// e.g. because it timed out, so they can abandon processing it.
+    CancelQuery {
+        /// Message ID.
+        id: MessageId,
+        /// ID of the query to cancel.
+        correlation_id: MessageId,
+        /// Public key and corresponding signature over the query id, see
+        /// `ProcessMsg::cancel_query_bytes`. It must be the client which sent the query.
+        client_signed: ClientSigned,
+    },
+    /// Another message encrypted to the key of its recipient, a section or an EndUser,
+    /// so that it can't be read by the elders relaying it. See `ProcessMsg::encrypt`.
+    Encrypted {
+        /// ID of the encrypted message.
+        id: MessageId,
+        /// First bytes of the recipient key, for the recipient to pick the key to decrypt with.
+        recipient_pk_hint: [u8; 4],
+        /// Destination of the encrypted message, if it has one, left in clear so the
+        /// message can be routed without being decrypted.
+        #[serde(default)]
+        dst_address: Option<XorName>,
+        /// The encrypted message.
+        ciphertext: Ciphertext,
+    },
+    /// A variant introduced by a newer version, which this version can't process.
+    /// It is kept as received, Msgpack encoded, so it can still be forwarded.
+    #[serde(skip)]
+    Unknown {
+        /// Index of the variant.
+        tag: u32,
+        /// Content of the variant.
+        bytes: Vec<u8>,
     },
 }
 
+impl ForwardCompatible for ProcessMsg {
+    const NAME: &'static str = "ProcessMsg";
+    const VARIANTS: &'static [&'static str] = &[
+        "Cmd",
+        "Query",
+        "Event",
+        "QueryResponse",
+        "CmdError",
+        "CancelQuery",
+        "Encrypted",
+    ];
+
+    fn deserialize_known<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::deserialize(deserializer)
+    }
+
+    fn unknown(tag: u32, bytes: Vec<u8>) -> Self {
+        Self::Unknown { tag, bytes }
+    }
+}
+
+impl Serialize for ProcessMsg {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Unknown { tag, bytes } => {
+                unknown::serialize_unknown(Self::NAME, *tag, bytes, serializer)
+            }
+            _ => Self::serialize(self, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ProcessMsg {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        unknown::deserialize(deserializer)
+    }
+}
+
 impl ProcessMsg {
     pub fn create_processing_error(&self, reason: Option<Error>) -> ProcessingError {
         ProcessingError {
@@ -276,33 +441,340 @@ impl ProcessMsg {
     }
 
     /// Gets the message ID.
+    /// Messages unknown to this version are identified by the hash of their content.
     pub fn id(&self) -> MessageId {
         match self {
             Self::Cmd { id, .. }
             | Self::Query { id, .. }
             | Self::Event { id, .. }
             | Self::QueryResponse { id, .. }
-            | Self::CmdError { id, .. } => *id,
+            | Self::CmdError { id, .. }
+            | Self::CancelQuery { id, .. }
+            | Self::Encrypted { id, .. } => *id,
+            Self::Unknown { bytes, .. } => MessageId::with(XorName::from_content(&[bytes]).0),
+        }
+    }
+
+    /// Returns true if this is a query whose deadline is before the given time,
+    /// in milliseconds since the Unix epoch.
+    pub fn is_past_deadline(&self, now_millis: u64) -> bool {
+        matches!(self, Self::Query { deadline: Some(deadline), .. } if *deadline < now_millis)
+    }
+
+    /// Checks the anti-spam proof of a query, as required by a section demanding
+    /// proofs of work of the given difficulty, or tokens issued with the given key.
+    /// No proof is required with a difficulty of zero, and other messages always pass.
+    pub fn check_anti_spam(
+        &self,
+        difficulty: u8,
+        section_key: &BlsPublicKey,
+        now_millis: u64,
+    ) -> Result<()> {
+        match self {
+            Self::Query {
+                id,
+                client_signed,
+                anti_spam: Some(anti_spam),
+                ..
+            } => anti_spam.validate(
+                id,
+                &client_signed.public_key,
+                difficulty,
+                section_key,
+                now_millis,
+            ),
+            Self::Query {
+                anti_spam: None, ..
+            } if difficulty > 0 => Err(Error::QueryThrottled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the response to send back for a query which is being dropped,
+    /// e.g. because it's past its deadline.
+    pub fn aborted_response(&self) -> Option<ProcessMsg> {
+        if let Self::Query { id, query, .. } = self {
+            Some(ProcessMsg::QueryResponse {
+                id: MessageId::in_response_to(id),
+                response: query.error(Error::QueryAborted),
+                correlation_id: *id,
+                origin: None,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the id of the message this one responds to, if any.
+    pub fn correlation_id(&self) -> Option<MessageId> {
+        match self {
+            Self::Event { correlation_id, .. }
+            | Self::QueryResponse { correlation_id, .. }
+            | Self::CmdError { correlation_id, .. } => Some(*correlation_id),
+            Self::Cmd { .. }
+            | Self::Query { .. }
+            | Self::CancelQuery { .. }
+            | Self::Encrypted { .. }
+            | Self::Unknown { .. } => None,
+        }
+    }
+
+    /// Returns the section which handled the request this message responds to, if known.
+    pub fn origin(&self) -> Option<&ResponseOrigin> {
+        match self {
+            Self::QueryResponse { origin, .. } | Self::CmdError { origin, .. } => origin.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the epoch of the section which handled the request this message responds to,
+    /// if known.
+    pub fn epoch(&self) -> Option<u64> {
+        self.origin()
+            .map(|origin| origin.epoch)
+            .filter(|epoch| *epoch > 0)
+    }
+
+    /// Returns true if this response was handled by the section at a later epoch than the
+    /// other one, so it is to be preferred when elders disagree during churn. Responses of
+    /// unknown epoch are never considered fresher.
+    pub fn is_fresher_than(&self, other: &ProcessMsg) -> bool {
+        self.epoch().unwrap_or(0) > other.epoch().unwrap_or(0)
+    }
+
+    /// Returns true if this is a response from a section whose key has since been superseded,
+    /// e.g. from elders which were demoted by churn, given the keys known for that section,
+    /// oldest first. Responses from keys which aren't known yet may come from a newer section
+    /// and are not considered stale.
+    pub fn is_stale_response(&self, known_keys: &[BlsPublicKey]) -> bool {
+        match (self.origin(), known_keys.split_last()) {
+            (Some(origin), Some((_, previous_keys))) => previous_keys.contains(&origin.section_key),
+            _ => false,
+        }
+    }
+
+    /// Returns the bytes a client signs to send the given command with the given nonce.
+    pub fn cmd_bytes(cmd: &Cmd, nonce: u64) -> crate::Result<Vec<u8>> {
+        signing_bytes("cmd", &(cmd, nonce))
+    }
+
+    /// Returns true if this is a command signed by the client over the command and its nonce,
+    /// or any other message. Commands whose nonce was tampered with thus fail to verify.
+    pub fn verify_cmd_signature(&self) -> bool {
+        match self {
+            Self::Cmd {
+                cmd,
+                nonce,
+                client_signed,
+                ..
+            } => Self::cmd_bytes(cmd, *nonce)
+                .map(|bytes| {
+                    client_signed
+                        .public_key
+                        .verify(&client_signed.signature, bytes)
+                        .is_ok()
+                })
+                .unwrap_or(false),
+            _ => true,
+        }
+    }
+
+    /// Returns the bytes a client signs to cancel its query with the given id.
+    pub fn cancel_query_bytes(query_id: &MessageId) -> crate::Result<Vec<u8>> {
+        signing_bytes("cancel-query", query_id)
+    }
+
+    /// Returns true if this is a cancellation of the given query,
+    /// signed by the client which sent it.
+    pub fn cancels(&self, query: &ProcessMsg) -> bool {
+        match (self, query) {
+            (
+                Self::CancelQuery {
+                    correlation_id,
+                    client_signed,
+                    ..
+                },
+                Self::Query {
+                    id,
+                    client_signed: query_signed,
+                    ..
+                },
+            ) => {
+                correlation_id == id
+                    && client_signed.public_key == query_signed.public_key
+                    && Self::cancel_query_bytes(correlation_id)
+                        .map(|bytes| {
+                            client_signed
+                                .public_key
+                                .verify(&client_signed.signature, bytes)
+                                .is_ok()
+                        })
+                        .unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the address of the destination of the message, derived from the
+    /// inner cmd or query, or carried in clear by encrypted messages.
+    /// Responses to the client have no data destination.
+    pub fn dst_address(&self) -> Option<XorName> {
+        match self {
+            Self::Cmd { cmd, .. } => Some(cmd.dst_address()),
+            Self::Query { query, .. } => Some(query.dst_address()),
+            Self::Encrypted { dst_address, .. } => *dst_address,
+            Self::Event { .. }
+            | Self::QueryResponse { .. }
+            | Self::CmdError { .. }
+            | Self::CancelQuery { .. }
+            | Self::Unknown { .. } => None,
         }
     }
 }
 
+impl fmt::Display for ProcessMsg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cmd { id, cmd, .. } => write!(f, "ProcessMsg::Cmd({}, id={})", cmd, id),
+            Self::Query { id, query, .. } => {
+                write!(f, "ProcessMsg::Query({}, id={})", query, id)
+            }
+            Self::Event {
+                id,
+                event,
+                correlation_id,
+            } => write!(
+                f,
+                "ProcessMsg::Event({}, id={}, correlation_id={})",
+                event, id, correlation_id
+            ),
+            Self::QueryResponse {
+                id,
+                response,
+                correlation_id,
+                ..
+            } => write!(
+                f,
+                "ProcessMsg::QueryResponse({}, id={}, correlation_id={})",
+                response, id, correlation_id
+            ),
+            Self::CmdError {
+                id,
+                error,
+                correlation_id,
+                ..
+            } => write!(
+                f,
+                "ProcessMsg::CmdError({}, id={}, correlation_id={})",
+                error, id, correlation_id
+            ),
+            Self::CancelQuery {
+                id, correlation_id, ..
+            } => write!(
+                f,
+                "ProcessMsg::CancelQuery(id={}, correlation_id={})",
+                id, correlation_id
+            ),
+            Self::Encrypted { id, .. } => write!(f, "ProcessMsg::Encrypted(id={})", id),
+            Self::Unknown { tag, .. } => write!(f, "ProcessMsg::Unknown(tag={})", tag),
+        }
+    }
+}
+
+/// The section which handled a request, so clients can tell responses
+/// from elders which have since been superseded by churn.
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ResponseOrigin {
+    /// Key of the section when it handled the request.
+    pub section_key: BlsPublicKey,
+    /// Id of the message with which the request was handled within the section,
+    /// e.g. the one forwarded to the adults holding the data.
+    pub handler_msg_id: MessageId,
+    /// Epoch of the section when it handled the request, i.e. the length of its chain of keys.
+    /// Zero if unknown, e.g. for responses from older elders.
+    #[serde(default)]
+    pub epoch: u64,
+}
+
+impl ResponseOrigin {
+    /// Returns true if this origin is from a later epoch than the other one.
+    /// Origins with an unknown epoch are never fresher than any other.
+    pub fn is_fresher_than(&self, other: &Self) -> bool {
+        self.epoch > other.epoch
+    }
+}
+
 ///
 #[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum CmdError {
+    /// The error of a data command, along with the address
+    /// of the data and the kind of operation it targeted.
+    Data {
+        /// The error.
+        error: Error,
+        /// Address of the data the command targeted.
+        address: DataAddress,
+        /// Kind of operation of the command.
+        op: DataCmdKind,
+    },
     ///
-    Data(Error), // DataError enum for better differentiation?
-    ///
-    Transfer(TransferError),
+    Transfer(TransferCmdError),
+    /// The data command came without sufficient payment. Carries a quote of the
+    /// cost of the command, so the client can pay it and retry right away.
+    PaymentRequired {
+        /// Address of the data the command targeted.
+        address: DataAddress,
+        /// Cost of the command.
+        cost: Token,
+        /// Key of the section wallet the payment is to be made to.
+        payee: PublicKey,
+        /// Time, in milliseconds since the Unix epoch, past which the quoted cost
+        /// may no longer be accepted.
+        quote_expiry: u64,
+    },
 }
 
-///
+impl CmdError {
+    /// Returns true if this is a payment request whose quote is still valid
+    /// at the given time, in milliseconds since the Unix epoch.
+    pub fn is_payable(&self, now_millis: u64) -> bool {
+        matches!(self, Self::PaymentRequired { quote_expiry, .. } if now_millis <= *quote_expiry)
+    }
+
+    /// Returns true if the cmd failed because the recipient lacks ops preceding those
+    /// relayed with `RelayOps`, which the client should then relay along with them.
+    pub fn is_not_causally_ready(&self) -> bool {
+        matches!(
+            self,
+            Self::Data {
+                error: Error::Data(DataError::OpNotCausallyReady),
+                ..
+            }
+        )
+    }
+}
+
+impl fmt::Display for CmdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Data { error, op, .. } => write!(f, "CmdError::Data({:?}, {})", op, error),
+            Self::Transfer(_) => write!(f, "CmdError::Transfer"),
+            Self::PaymentRequired { cost, .. } => write!(f, "CmdError::PaymentRequired({})", cost),
+        }
+    }
+}
+
+/// Error of a `TransferCmd`, along with the kind of cmd.
 #[derive(Debug, Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
-pub enum TransferError {
+pub enum TransferCmdError {
     /// The error of a ValidateTransfer cmd.
     TransferValidation(Error),
     /// The error of a RegisterTransfer cmd.
     TransferRegistration(Error),
+    /// The error of a SimulatePayout cmd, e.g. `DataError::InvalidOperation` when the
+    /// network doesn't simulate payouts.
+    #[cfg(feature = "testnet")]
+    SimulatedPayout(Error),
 }
 
 /// Events from the network that
@@ -317,19 +789,130 @@ pub enum Event {
         event: TransferValidated,
     },
     /// An aggregate event created client side
-    /// (for upper Client layers) out of a quorum of TransferValidated events.
-    /// This is a temporary variant, until
-    /// SignatureAccumulation has been broken out
-    /// to its own crate, and can be used at client.
+    /// (for upper Client layers) out of a quorum of TransferValidated events,
+    /// e.g. with a `TransferValidationAccumulator`.
     TransferAgreementReached {
         /// The accumulated proof.
         proof: TransferAgreementProof,
     },
+    /// The outcome of storing each Blob of a `BlobWrite::Batch`, in the order of the batch.
+    BlobBatchStored {
+        /// Address of each Blob along with the result of storing it.
+        outcomes: Vec<(BlobAddress, Result<()>)>,
+    },
+    /// The elders of the client's section changed, pushed to the connected EndUsers
+    /// so they can target the new elders right away.
+    SectionChanged {
+        /// Prefix of the section.
+        prefix: Prefix,
+        /// The new section key.
+        new_key: BlsPublicKey,
+        /// The new elders, with their socket addresses.
+        elders: BTreeMap<XorName, SocketAddr>,
+        /// Signature of the section, with the key preceding the change,
+        /// over the serialized change.
+        proof: Signed,
+    },
+    /// A session was created, or resumed, with `Cmd::CreateSession` or `Cmd::ResumeSession`.
+    SessionCreated {
+        /// Token of the session, for the client to resume it with.
+        token: SessionToken,
+    },
+    /// The policy or permissions of a Register or Sequence changed, pushed to the clients
+    /// watching it so they can react, e.g. stop offering edits, without polling its policy.
+    PolicyChanged {
+        /// Address of the data.
+        address: DataAddress,
+        /// Digest of the new policy, see `Event::policy_digest`.
+        new_policy_digest: XorName,
+    },
+}
+
+impl Event {
+    /// Returns the bytes the section signs for a change of its elders.
+    pub fn section_changed_bytes(
+        prefix: &Prefix,
+        new_key: &BlsPublicKey,
+        elders: &BTreeMap<XorName, SocketAddr>,
+    ) -> crate::Result<Vec<u8>> {
+        signing_bytes("section-changed", &(prefix, new_key, elders))
+    }
+
+    /// Verifies the proof carried by the event: the signatures of the replicas over a
+    /// transfer, or the signature of a section change with the last section key known by
+    /// the client.
+    pub fn verify(&self, known_key: &BlsPublicKey) -> bool {
+        match self {
+            Self::SectionChanged {
+                prefix,
+                new_key,
+                elders,
+                proof,
+            } => {
+                proof.public_key == *known_key
+                    && Self::section_changed_bytes(prefix, new_key, elders)
+                        .map(|bytes| proof.verify(&bytes))
+                        .unwrap_or(false)
+            }
+            Self::TransferValidated { event } => verify_validation(event),
+            Self::TransferAgreementReached { proof } => verify_agreement(proof),
+            // The outcomes are only claims of the elders storing the batch, which the client
+            // confirms by reading the Blobs back.
+            Self::BlobBatchStored { .. } => true,
+            // The token is opaque to the client, and only honoured by the elders issuing it.
+            Self::SessionCreated { .. } => true,
+            // The digest is merely a hint to fetch the new policy, which it can be compared to.
+            Self::PolicyChanged { .. } => true,
+        }
+    }
+
+    /// Returns the digest of a policy, or permissions, of some data, which changes
+    /// with any change of it. It's computed over the canonical bytes of the policy,
+    /// so that it doesn't depend on the version of the serialiser.
+    pub fn policy_digest<T: CanonicalBytes + ?Sized>(policy: &T) -> crate::Result<XorName> {
+        Ok(XorName::from_content(&[&policy.canonical_bytes()?]))
+    }
+
+    /// Returns the addresses of the Blobs of a batch which failed to be stored.
+    pub fn failed_blobs(&self) -> Vec<BlobAddress> {
+        match self {
+            Self::BlobBatchStored { outcomes } => outcomes
+                .iter()
+                .filter(|(_, result)| result.is_err())
+                .map(|(address, _)| *address)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TransferValidated { .. } => write!(f, "Event::TransferValidated"),
+            Self::TransferAgreementReached { .. } => write!(f, "Event::TransferAgreementReached"),
+            Self::BlobBatchStored { outcomes } => {
+                write!(f, "Event::BlobBatchStored({} blobs)", outcomes.len())
+            }
+            Self::SectionChanged { prefix, .. } => {
+                write!(f, "Event::SectionChanged({:?})", prefix)
+            }
+            Self::SessionCreated { token } => match token.id() {
+                Ok(id) => write!(f, "Event::SessionCreated({:?})", id),
+                Err(_) => write!(f, "Event::SessionCreated"),
+            },
+            Self::PolicyChanged { address, .. } => {
+                write!(f, "Event::PolicyChanged({:?})", address)
+            }
+        }
+    }
 }
 
 /// Query responses from the network.
 #[allow(clippy::large_enum_variant, clippy::type_complexity)]
 #[derive(Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+#[serde(remote = "Self")]
+#[non_exhaustive]
 pub enum QueryResponse {
     //
     // ===== Blob =====
@@ -388,58 +971,424 @@ pub enum QueryResponse {
     //
     // ===== Tokens =====
     //
-    /// Get key balance.
-    GetBalance(Result<Token>),
-    /// Get key transfer history.
-    GetHistory(Result<ActorHistory>),
+    /// Get key balance, along with the index of the
+    /// transfer history it was evaluated at.
+    GetBalance(Result<(Token, usize)>),
+    /// Get key transfer history, along with the index of the next page
+    /// of transfers to query, if there are any more.
+    GetHistory(Result<(ActorHistory, Option<usize>)>),
     /// Get Store Cost.
     GetStoreCost(Result<(u64, Token, PublicKey)>),
+    /// Get the section wallet key and balance.
+    GetSectionWallet(Result<(PublicKey, Token)>),
+    //
+    // ===== Routing =====
+    //
+    /// The query was sent to a section not responsible for its destination,
+    /// which redirects the client to the one that is.
+    Redirect {
+        /// Prefix of the section responsible for the destination.
+        prefix: Prefix,
+        /// Key of that section.
+        key: BlsPublicKey,
+        /// Elders of that section, with their socket addresses.
+        elders: BTreeMap<XorName, SocketAddr>,
+        /// Signature of that section over the serialized prefix and elders.
+        proof: Signed,
+        /// Proof of the key of that section, from a key the client trusts,
+        /// e.g. the genesis key or the key of the section which redirects it.
+        key_proof: SectionKeyProof,
+    },
+    //
+    // ===== Streaming =====
+    //
+    /// A part of the content of a Blob too large to be returned in a single message.
+    /// See `ChunkStream` for reassembling the content.
+    GetChunkStream {
+        /// Content of the part.
+        part: Result<Vec<u8>>,
+        /// Index of the part, from zero.
+        index: u32,
+        /// Number of parts of the stream.
+        total: u32,
+        /// Id of the stream, identical in all of its parts, so that parts of
+        /// concurrent streams answering the same query can be told apart.
+        correlation: MessageId,
+    },
+    //
+    // ===== Register Data =====
+    //
+    /// Get the ops of a Register applied since a given frontier, in causal order.
+    GetRegisterEntriesSince(Result<Vec<RegisterOp<Entry>>>),
+    //
+    // ===== Tokens =====
+    //
+    /// Get the proof that a transfer was registered.
+    GetTransferProof(Result<TransferProof>),
+    /// Get a signed quote of the store cost.
+    GetStoreCostQuote(Result<StoreCostQuote>),
+    //
+    // ===== Sequence Data =====
+    //
+    /// Get a page of Sequence entries.
+    GetSequencePage(Result<SequencePage>),
+    //
+    // ===== Register Data =====
+    //
+    /// Get Register entries by their hash. Entries which weren't found are omitted.
+    GetRegisterEntries(Result<BTreeMap<EntryHash, Entry>>),
+    //
+    // ===== Any Data =====
+    //
+    /// Get the metadata of data.
+    GetDataMetadata(Result<DataMetadata>),
+    //
+    // ===== Blob =====
+    //
+    /// Whether a Blob is already stored.
+    BlobExists(Result<bool>),
+    /// A variant introduced by a newer version, which this version can't process.
+    /// It is kept as received, Msgpack encoded, so it can still be forwarded.
+    #[serde(skip)]
+    Unknown {
+        /// Index of the variant.
+        tag: u32,
+        /// Content of the variant.
+        bytes: Vec<u8>,
+    },
+}
+
+impl ForwardCompatible for QueryResponse {
+    const NAME: &'static str = "QueryResponse";
+    const VARIANTS: &'static [&'static str] = &[
+        "GetBlob",
+        "GetMap",
+        "GetMapShell",
+        "GetMapVersion",
+        "ListMapEntries",
+        "ListMapKeys",
+        "ListMapValues",
+        "ListMapUserPermissions",
+        "ListMapPermissions",
+        "GetMapValue",
+        "GetSequence",
+        "GetSequenceRange",
+        "GetSequenceLastEntry",
+        "GetSequencePublicPolicy",
+        "GetSequencePrivatePolicy",
+        "GetSequenceUserPermissions",
+        "GetRegister",
+        "GetRegisterOwner",
+        "ReadRegister",
+        "GetRegisterPolicy",
+        "GetRegisterUserPermissions",
+        "GetBalance",
+        "GetHistory",
+        "GetStoreCost",
+        "GetSectionWallet",
+        "Redirect",
+        "GetChunkStream",
+        "GetRegisterEntriesSince",
+        "GetTransferProof",
+        "GetStoreCostQuote",
+        "GetSequencePage",
+        "GetRegisterEntries",
+        "GetDataMetadata",
+        "BlobExists",
+    ];
+
+    fn deserialize_known<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::deserialize(deserializer)
+    }
+
+    fn unknown(tag: u32, bytes: Vec<u8>) -> Self {
+        Self::Unknown { tag, bytes }
+    }
+}
+
+impl Serialize for QueryResponse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Unknown { tag, bytes } => {
+                unknown::serialize_unknown(Self::NAME, *tag, bytes, serializer)
+            }
+            _ => Self::serialize(self, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for QueryResponse {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        unknown::deserialize(deserializer)
+    }
 }
 
 impl QueryResponse {
     /// Returns true if the result returned is a success or not
     pub fn is_success(&self) -> bool {
+        self.error().is_none() && !matches!(self, Self::Redirect { .. } | Self::Unknown { .. })
+    }
+
+    /// Returns the bytes a section signs to redirect a client to itself.
+    pub fn redirect_bytes(
+        prefix: &Prefix,
+        elders: &BTreeMap<XorName, SocketAddr>,
+    ) -> crate::Result<Vec<u8>> {
+        signing_bytes("redirect", &(prefix, elders))
+    }
+
+    /// Verifies a redirect is signed with the key of the section it redirects to, that key
+    /// is proven from one of the `trusted_keys`, and all of its elders match its prefix.
+    /// Other responses don't send the client elsewhere, so there's nothing to check.
+    pub fn verify_redirect(&self, trusted_keys: &[BlsPublicKey]) -> bool {
+        match self {
+            Self::Redirect {
+                prefix,
+                key,
+                elders,
+                proof,
+                key_proof,
+            } => {
+                proof.public_key == *key
+                    && key_proof.key() == key
+                    && key_proof.verify(trusted_keys)
+                    && elders.keys().all(|name| prefix.matches(name))
+                    && Self::redirect_bytes(prefix, elders)
+                        .map(|bytes| proof.verify(&bytes))
+                        .unwrap_or(false)
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the error carried by the response, if any.
+    pub fn error(&self) -> Option<&Error> {
         use QueryResponse::*;
         match self {
-            GetBlob(result) => result.is_ok(),
-            GetMap(result) => result.is_ok(),
-            GetMapShell(result) => result.is_ok(),
-            GetMapVersion(result) => result.is_ok(),
-            ListMapEntries(result) => result.is_ok(),
-            ListMapKeys(result) => result.is_ok(),
-            ListMapValues(result) => result.is_ok(),
-            ListMapUserPermissions(result) => result.is_ok(),
-            ListMapPermissions(result) => result.is_ok(),
-            GetMapValue(result) => result.is_ok(),
-            GetSequence(result) => result.is_ok(),
-            GetSequenceRange(result) => result.is_ok(),
-            GetSequenceLastEntry(result) => result.is_ok(),
-            GetSequencePublicPolicy(result) => result.is_ok(),
-            GetSequencePrivatePolicy(result) => result.is_ok(),
-            GetSequenceUserPermissions(result) => result.is_ok(),
-            GetRegister(result) => result.is_ok(),
-            GetRegisterOwner(result) => result.is_ok(),
-            ReadRegister(result) => result.is_ok(),
-            GetRegisterPolicy(result) => result.is_ok(),
-            GetRegisterUserPermissions(result) => result.is_ok(),
-            GetBalance(result) => result.is_ok(),
-            GetHistory(result) => result.is_ok(),
-            GetStoreCost(result) => result.is_ok(),
+            GetBlob(result) => result.as_ref().err(),
+            BlobExists(result) => result.as_ref().err(),
+            GetChunkStream { part, .. } => part.as_ref().err(),
+            GetMap(result) => result.as_ref().err(),
+            GetMapShell(result) => result.as_ref().err(),
+            GetMapVersion(result) => result.as_ref().err(),
+            ListMapEntries(result) => result.as_ref().err(),
+            ListMapKeys(result) => result.as_ref().err(),
+            ListMapValues(result) => result.as_ref().err(),
+            ListMapUserPermissions(result) => result.as_ref().err(),
+            ListMapPermissions(result) => result.as_ref().err(),
+            GetMapValue(result) => result.as_ref().err(),
+            GetSequence(result) => result.as_ref().err(),
+            GetSequenceRange(result) => result.as_ref().err(),
+            GetSequenceLastEntry(result) => result.as_ref().err(),
+            GetSequencePublicPolicy(result) => result.as_ref().err(),
+            GetSequencePrivatePolicy(result) => result.as_ref().err(),
+            GetSequenceUserPermissions(result) => result.as_ref().err(),
+            GetSequencePage(result) => result.as_ref().err(),
+            GetRegister(result) => result.as_ref().err(),
+            GetRegisterOwner(result) => result.as_ref().err(),
+            ReadRegister(result) => result.as_ref().err(),
+            GetRegisterPolicy(result) => result.as_ref().err(),
+            GetRegisterUserPermissions(result) => result.as_ref().err(),
+            GetRegisterEntries(result) => result.as_ref().err(),
+            GetRegisterEntriesSince(result) => result.as_ref().err(),
+            GetDataMetadata(result) => result.as_ref().err(),
+            GetBalance(result) => result.as_ref().err(),
+            GetHistory(result) => result.as_ref().err(),
+            GetStoreCost(result) => result.as_ref().err(),
+            GetSectionWallet(result) => result.as_ref().err(),
+            GetTransferProof(result) => result.as_ref().err(),
+            GetStoreCostQuote(result) => result.as_ref().err(),
+            Redirect { .. } | Unknown { .. } => None,
         }
     }
+
+    /// Returns true if the response reports that the requested data,
+    /// or an entry or key of it, doesn't exist.
+    pub fn is_data_not_found(&self) -> bool {
+        matches!(
+            self.error(),
+            Some(Error::Data(DataError::DataNotFound(_)))
+                | Some(Error::Data(DataError::NoSuchEntry))
+                | Some(Error::Data(DataError::NoSuchKey))
+        )
+    }
+
+    /// Returns how long the response can be cached for. Errors, balances and other
+    /// frequently changing values are never cached, while chunks are immutable.
+    pub fn cache_hint(&self) -> CacheHint {
+        use QueryResponse::*;
+        if !self.is_success() {
+            return CacheHint::NO_CACHE;
+        }
+        match self {
+            GetBlob(_) => CacheHint::IMMUTABLE,
+            // A missing Blob is likely to be uploaded right after the check.
+            BlobExists(Ok(false)) => CacheHint::NO_CACHE,
+            // A part is only meaningful along with the rest of its stream.
+            GetChunkStream { .. } => CacheHint::NO_CACHE,
+            GetMapVersion(_)
+            | GetSequenceLastEntry(_)
+            | GetBalance(_)
+            | GetHistory(_)
+            | GetStoreCost(_)
+            | GetStoreCostQuote(_)
+            | GetSectionWallet(_)
+            | Redirect { .. }
+            | Unknown { .. } => CacheHint::NO_CACHE,
+            GetMap(_)
+            | GetMapShell(_)
+            | ListMapEntries(_)
+            | ListMapKeys(_)
+            | ListMapValues(_)
+            | ListMapUserPermissions(_)
+            | ListMapPermissions(_)
+            | GetMapValue(_)
+            | GetSequence(_)
+            | GetSequenceRange(_)
+            | GetSequencePublicPolicy(_)
+            | GetSequencePrivatePolicy(_)
+            | GetSequenceUserPermissions(_)
+            | GetSequencePage(_)
+            | GetRegister(_)
+            | GetRegisterOwner(_)
+            | ReadRegister(_)
+            | GetRegisterPolicy(_)
+            | GetRegisterUserPermissions(_)
+            | GetRegisterEntries(_)
+            | GetRegisterEntriesSince(_)
+            | GetDataMetadata(_)
+            | BlobExists(_) => CacheHint::mutable_data(),
+            // A registered transfer can't be reverted.
+            GetTransferProof(_) => CacheHint::IMMUTABLE,
+        }
+    }
+
+    /// Returns the name of the response variant, for logging.
+    pub fn name(&self) -> &'static str {
+        use QueryResponse::*;
+        match self {
+            GetBlob(_) => "GetBlob",
+            BlobExists(_) => "BlobExists",
+            GetChunkStream { .. } => "GetChunkStream",
+            GetMap(_) => "GetMap",
+            GetMapShell(_) => "GetMapShell",
+            GetMapVersion(_) => "GetMapVersion",
+            ListMapEntries(_) => "ListMapEntries",
+            ListMapKeys(_) => "ListMapKeys",
+            ListMapValues(_) => "ListMapValues",
+            ListMapUserPermissions(_) => "ListMapUserPermissions",
+            ListMapPermissions(_) => "ListMapPermissions",
+            GetMapValue(_) => "GetMapValue",
+            GetSequence(_) => "GetSequence",
+            GetSequenceRange(_) => "GetSequenceRange",
+            GetSequenceLastEntry(_) => "GetSequenceLastEntry",
+            GetSequencePublicPolicy(_) => "GetSequencePublicPolicy",
+            GetSequencePrivatePolicy(_) => "GetSequencePrivatePolicy",
+            GetSequenceUserPermissions(_) => "GetSequenceUserPermissions",
+            GetSequencePage(_) => "GetSequencePage",
+            GetRegister(_) => "GetRegister",
+            GetRegisterOwner(_) => "GetRegisterOwner",
+            ReadRegister(_) => "ReadRegister",
+            GetRegisterPolicy(_) => "GetRegisterPolicy",
+            GetRegisterUserPermissions(_) => "GetRegisterUserPermissions",
+            GetRegisterEntries(_) => "GetRegisterEntries",
+            GetRegisterEntriesSince(_) => "GetRegisterEntriesSince",
+            GetDataMetadata(_) => "GetDataMetadata",
+            GetBalance(_) => "GetBalance",
+            GetHistory(_) => "GetHistory",
+            GetStoreCost(_) => "GetStoreCost",
+            GetSectionWallet(_) => "GetSectionWallet",
+            GetTransferProof(_) => "GetTransferProof",
+            GetStoreCostQuote(_) => "GetStoreCostQuote",
+            Redirect { .. } => "Redirect",
+            Unknown { .. } => "Unknown",
+        }
+    }
+}
+
+/// How long a query response can be cached by clients and relays.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CacheHint {
+    /// Maximum time the response can be served from a cache.
+    pub max_age: Duration,
+    /// Whether the response can never change, and thus be cached forever.
+    pub immutable: bool,
+}
+
+impl CacheHint {
+    /// The response must not be cached.
+    pub const NO_CACHE: Self = Self {
+        max_age: Duration::from_secs(0),
+        immutable: false,
+    };
+
+    /// The response never changes.
+    pub const IMMUTABLE: Self = Self {
+        max_age: Duration::MAX,
+        immutable: true,
+    };
+
+    /// Maximum age of cached reads of mutable data.
+    pub const MUTABLE_DATA_MAX_AGE: Duration = Duration::from_secs(5);
+
+    fn mutable_data() -> Self {
+        Self {
+            max_age: Self::MUTABLE_DATA_MAX_AGE,
+            immutable: false,
+        }
+    }
+
+    /// Returns true if the response can be cached at all.
+    pub fn is_cacheable(&self) -> bool {
+        self.max_age > Duration::from_secs(0)
+    }
+}
+
+impl fmt::Display for QueryResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = if self.is_success() { "Ok" } else { "Err" };
+        write!(f, "QueryResponse::{}({})", self.name(), outcome)
+    }
 }
 
 /// Error type for an attempted conversion from `QueryResponse` to a type implementing
 /// `TryFrom<Response>`.
-#[derive(Debug, PartialEq)]
+#[derive(thiserror::Error, Debug, PartialEq)]
 #[allow(clippy::large_enum_variant)]
 pub enum TryFromError {
     /// Wrong variant found in `QueryResponse`.
+    #[error("Wrong variant found in query response")]
     WrongType,
     /// The `QueryResponse` contained an error.
+    #[error("Query response contained an error: {0}")]
     Response(Error),
 }
 
+impl TryFromError {
+    /// Converts into the client error it stands for: the error carried
+    /// by the response, or `Error::UnexpectedQueryResponse` for a wrong variant.
+    pub fn into_client_error(self) -> Error {
+        match self {
+            Self::WrongType => Error::UnexpectedQueryResponse,
+            Self::Response(error) => error,
+        }
+    }
+}
+
+impl From<TryFromError> for Error {
+    fn from(error: TryFromError) -> Self {
+        error.into_client_error()
+    }
+}
+
+/// Balance of a wallet, as found in a `QueryResponse::GetBalance`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Balance(pub Token);
+
+/// Cost of storing data, as found in a `QueryResponse::GetStoreCost`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StoreCost(pub Token);
+
 macro_rules! try_from {
     ($ok_type:ty, $($variant:ident),*) => {
         impl TryFrom<QueryResponse> for $ok_type {
@@ -455,9 +1404,22 @@ macro_rules! try_from {
             }
         }
     };
+    ($ok_type:ty, $variant:ident => $map:expr) => {
+        impl TryFrom<QueryResponse> for $ok_type {
+            type Error = TryFromError;
+            fn try_from(response: QueryResponse) -> std::result::Result<Self, Self::Error> {
+                match response {
+                    QueryResponse::$variant(Ok(data)) => Ok($map(data)),
+                    QueryResponse::$variant(Err(error)) => Err(TryFromError::Response(error)),
+                    _ => Err(TryFromError::WrongType),
+                }
+            }
+        }
+    };
 }
 
 try_from!(Blob, GetBlob);
+try_from!(bool, BlobExists);
 try_from!(Map, GetMap, GetMapShell);
 try_from!(u64, GetMapVersion);
 try_from!(MapEntries, ListMapEntries);
@@ -472,13 +1434,22 @@ try_from!((u64, SequenceEntry), GetSequenceLastEntry);
 try_from!(SequencePublicPolicy, GetSequencePublicPolicy);
 try_from!(SequencePrivatePolicy, GetSequencePrivatePolicy);
 try_from!(SequencePermissions, GetSequenceUserPermissions);
+try_from!(SequencePage, GetSequencePage);
 try_from!(Register, GetRegister);
 try_from!(PublicKey, GetRegisterOwner);
 try_from!(BTreeSet<(EntryHash, Entry)>, ReadRegister);
 try_from!(Policy, GetRegisterPolicy);
 try_from!(Permissions, GetRegisterUserPermissions);
-try_from!(Token, GetBalance);
-try_from!(ActorHistory, GetHistory);
+try_from!(BTreeMap<EntryHash, Entry>, GetRegisterEntries);
+try_from!(Vec<RegisterOp<Entry>>, GetRegisterEntriesSince);
+try_from!(DataMetadata, GetDataMetadata);
+try_from!((Token, usize), GetBalance);
+try_from!(Balance, GetBalance => |(balance, _)| Balance(balance));
+try_from!(StoreCost, GetStoreCost => |(_, cost, _)| StoreCost(cost));
+try_from!((ActorHistory, Option<usize>), GetHistory);
+try_from!((PublicKey, Token), GetSectionWallet);
+try_from!(TransferProof, GetTransferProof);
+try_from!(StoreCostQuote, GetStoreCostQuote);
 
 #[cfg(test)]
 mod tests {
@@ -507,9 +1478,10 @@ mod tests {
     #[test]
     fn debug_format_functional() -> Result<()> {
         if let Some(key) = gen_keys().first() {
-            let errored_response = QueryResponse::GetSequence(Err(Error::AccessDenied(*key)));
+            let errored_response =
+                QueryResponse::GetSequence(Err(Error::Data(DataError::AccessDenied(*key))));
             assert!(format!("{:?}", errored_response)
-                .contains("GetSequence(Err(AccessDenied(PublicKey::"));
+                .contains("GetSequence(Err(Data(AccessDenied(PublicKey::"));
             Ok(())
         } else {
             Err(anyhow!("Could not generate public key"))
@@ -524,15 +1496,22 @@ mod tests {
 
             let msg = ProcessMsg::Query {
                 id: MessageId::new(),
-                query: Query::Transfer(TransferQuery::GetBalance(public_key)),
+                query: Query::Transfer(TransferQuery::GetBalance {
+                    at: public_key,
+                    history_index: None,
+                }),
                 client_signed: ClientSigned {
                     public_key,
                     signature,
                 },
+                deadline: None,
+                anti_spam: None,
+                session: None,
             };
             let random_addr = DataAddress::Blob(BlobAddress::Public(XorName::random()));
-            let lazy_error =
-                msg.create_processing_error(Some(Error::DataNotFound(random_addr.clone())));
+            let lazy_error = msg.create_processing_error(Some(Error::Data(
+                DataError::DataNotFound(random_addr.clone()),
+            )));
 
             assert!(format!("{:?}", lazy_error).contains("TransferQuery::GetBalance"));
             assert!(format!("{:?}", lazy_error).contains("ProcessingError"));
@@ -554,14 +1533,20 @@ mod tests {
 
             let random_addr = DataAddress::Blob(BlobAddress::Public(XorName::random()));
             let errored_response = ProcessingError {
-                reason: Some(Error::DataNotFound(random_addr.clone())),
+                reason: Some(Error::Data(DataError::DataNotFound(random_addr.clone()))),
                 source_message: Some(ProcessMsg::Query {
                     id: MessageId::new(),
-                    query: Query::Transfer(TransferQuery::GetBalance(public_key)),
+                    query: Query::Transfer(TransferQuery::GetBalance {
+                        at: public_key,
+                        history_index: None,
+                    }),
                     client_signed: ClientSigned {
                         public_key,
                         signature,
                     },
+                    deadline: None,
+                    anti_spam: None,
+                    session: None,
                 }),
                 id: MessageId::new(),
             };
@@ -576,6 +1561,386 @@ mod tests {
         }
     }
 
+    #[test]
+    fn display_format_compact() -> Result<()> {
+        let keypair = gen_keypairs()
+            .pop()
+            .ok_or_else(|| anyhow!("Could not generate keypair"))?;
+        let public_key = keypair.public_key();
+        let id = MessageId::new();
+        let name = XorName::random();
+        let msg = ClientMsg::Process(ProcessMsg::Query {
+            id,
+            query: Query::Data(DataQuery::Blob(BlobRead::Get(BlobAddress::Public(name)))),
+            client_signed: ClientSigned {
+                public_key,
+                signature: keypair.sign(b"the query"),
+            },
+            deadline: None,
+            anti_spam: None,
+            session: None,
+        });
+        assert_eq!(
+            msg.to_string(),
+            format!(
+                "ProcessMsg::Query(Query::Data(Blob::Get, dst={}), id={})",
+                name, id
+            )
+        );
+
+        let query = Query::Transfer(TransferQuery::GetBalance {
+            at: public_key,
+            history_index: None,
+        });
+        assert_eq!(
+            query.to_string(),
+            format!(
+                "Query::Transfer(GetBalance(at={}), dst={})",
+                public_key,
+                XorName::from(public_key)
+            )
+        );
+
+        let response =
+            QueryResponse::GetSequence(Err(Error::Data(DataError::AccessDenied(public_key))));
+        assert_eq!(response.to_string(), "QueryResponse::GetSequence(Err)");
+        Ok(())
+    }
+
+    #[test]
+    fn dst_address_of_msgs() -> Result<()> {
+        let keypair = gen_keypairs()
+            .pop()
+            .ok_or_else(|| anyhow!("Could not generate keypair"))?;
+        let public_key = keypair.public_key();
+        let name = XorName::random();
+        let query = ProcessMsg::Query {
+            id: MessageId::new(),
+            query: Query::Data(DataQuery::Blob(BlobRead::Get(BlobAddress::Public(name)))),
+            client_signed: ClientSigned {
+                public_key,
+                signature: keypair.sign(b"the query"),
+            },
+            deadline: None,
+            anti_spam: None,
+            session: None,
+        };
+        let error = ClientMsg::ProcessingError(query.create_processing_error(None));
+        assert_eq!(ClientMsg::Process(query).dst_address(), Some(name));
+        assert_eq!(error.dst_address(), Some(name));
+
+        let response = ClientMsg::Process(ProcessMsg::QueryResponse {
+            id: MessageId::new(),
+            response: QueryResponse::GetBalance(Err(Error::Data(DataError::NoSuchKey))),
+            correlation_id: MessageId::new(),
+            origin: None,
+        });
+        assert_eq!(response.dst_address(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn correlation_id_of_msgs() -> Result<()> {
+        let keypair = gen_keypairs()
+            .pop()
+            .ok_or_else(|| anyhow!("Could not generate keypair"))?;
+        let public_key = keypair.public_key();
+        let query = ProcessMsg::Query {
+            id: MessageId::new(),
+            query: Query::Transfer(TransferQuery::GetBalance {
+                at: public_key,
+                history_index: None,
+            }),
+            client_signed: ClientSigned {
+                public_key,
+                signature: keypair.sign(b"the query"),
+            },
+            deadline: None,
+            anti_spam: None,
+            session: None,
+        };
+        assert_eq!(query.correlation_id(), None);
+
+        let error = ClientMsg::ProcessingError(query.create_processing_error(None));
+        assert_eq!(error.correlation_id(), Some(query.id()));
+
+        let response = ClientMsg::Process(ProcessMsg::QueryResponse {
+            id: MessageId::new(),
+            response: QueryResponse::GetBalance(Err(Error::Data(DataError::NoSuchKey))),
+            correlation_id: query.id(),
+            origin: None,
+        });
+        assert_eq!(response.correlation_id(), Some(query.id()));
+        Ok(())
+    }
+
+    #[test]
+    fn query_past_deadline_is_aborted() -> Result<()> {
+        let keypair = gen_keypairs()
+            .pop()
+            .ok_or_else(|| anyhow!("Could not generate keypair"))?;
+        let public_key = keypair.public_key();
+        let id = MessageId::new();
+        let query = ProcessMsg::Query {
+            id,
+            query: Query::Transfer(TransferQuery::GetBalance {
+                at: public_key,
+                history_index: None,
+            }),
+            client_signed: ClientSigned {
+                public_key,
+                signature: keypair.sign(b"the query"),
+            },
+            deadline: Some(1_000),
+            anti_spam: None,
+            session: None,
+        };
+        assert!(!query.is_past_deadline(1_000));
+        assert!(query.is_past_deadline(1_001));
+
+        match query.aborted_response() {
+            Some(ProcessMsg::QueryResponse {
+                response: QueryResponse::GetBalance(Err(Error::QueryAborted)),
+                correlation_id,
+                ..
+            }) => assert_eq!(correlation_id, id),
+            other => return Err(anyhow!("Unexpected response: {:?}", other)),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn query_anti_spam_check() -> Result<()> {
+        let keypair = gen_keypairs()
+            .pop()
+            .ok_or_else(|| anyhow!("Could not generate keypair"))?;
+        let public_key = keypair.public_key();
+        let section_key = threshold_crypto::SecretKey::random().public_key();
+        let id = MessageId::new();
+        let query = |anti_spam| ProcessMsg::Query {
+            id,
+            query: Query::Transfer(TransferQuery::GetBalance {
+                at: public_key,
+                history_index: None,
+            }),
+            client_signed: ClientSigned {
+                public_key,
+                signature: keypair.sign(b"the query"),
+            },
+            deadline: None,
+            anti_spam,
+            session: None,
+        };
+
+        assert_eq!(query(None).check_anti_spam(0, &section_key, 0), Ok(()));
+        assert_eq!(
+            query(None).check_anti_spam(4, &section_key, 0),
+            Err(Error::QueryThrottled)
+        );
+        let proof = AntiSpam::solve(&id, &public_key, 4)?;
+        assert_eq!(
+            query(Some(proof)).check_anti_spam(4, &section_key, 0),
+            Ok(())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cmd_signature() -> Result<()> {
+        let keypair = gen_keypairs()
+            .pop()
+            .ok_or_else(|| anyhow!("Could not generate keypair"))?;
+        let cmd = Cmd::CreateSession {
+            client: keypair.public_key(),
+            capabilities: Default::default(),
+            last_seen_key: None,
+        };
+        let signature = keypair.sign(&ProcessMsg::cmd_bytes(&cmd, 1)?);
+        let msg = |nonce| ProcessMsg::Cmd {
+            id: MessageId::new(),
+            cmd: cmd.clone(),
+            nonce,
+            client_signed: ClientSigned {
+                public_key: keypair.public_key(),
+                signature: signature.clone(),
+            },
+            session: None,
+        };
+
+        assert!(msg(1).verify_cmd_signature());
+        assert!(!msg(2).verify_cmd_signature());
+        Ok(())
+    }
+
+    #[test]
+    fn query_cancellation() -> Result<()> {
+        let mut keypairs = gen_keypairs();
+        let keypair = keypairs
+            .pop()
+            .ok_or_else(|| anyhow!("Could not generate keypair"))?;
+        let other = keypairs
+            .pop()
+            .ok_or_else(|| anyhow!("Could not generate keypair"))?;
+        let public_key = keypair.public_key();
+        let query = ProcessMsg::Query {
+            id: MessageId::new(),
+            query: Query::Transfer(TransferQuery::GetBalance {
+                at: public_key,
+                history_index: None,
+            }),
+            client_signed: ClientSigned {
+                public_key,
+                signature: keypair.sign(b"the query"),
+            },
+            deadline: None,
+            anti_spam: None,
+            session: None,
+        };
+        let cancel = |keypair: &Keypair, correlation_id| -> Result<ProcessMsg> {
+            Ok(ProcessMsg::CancelQuery {
+                id: MessageId::new(),
+                correlation_id,
+                client_signed: ClientSigned {
+                    public_key: keypair.public_key(),
+                    signature: keypair.sign(&ProcessMsg::cancel_query_bytes(&correlation_id)?),
+                },
+            })
+        };
+
+        assert!(cancel(&keypair, query.id())?.cancels(&query));
+        assert!(!cancel(&keypair, MessageId::new())?.cancels(&query));
+        assert!(!cancel(&other, query.id())?.cancels(&query));
+        Ok(())
+    }
+
+    #[test]
+    fn policy_digest() -> Result<()> {
+        use sn_data_types::register::PublicPolicy;
+
+        let owner = gen_keypairs()
+            .pop()
+            .ok_or_else(|| anyhow!("Could not generate keypair"))?
+            .public_key();
+        let policy = Policy::Public(PublicPolicy {
+            owner,
+            permissions: Default::default(),
+        });
+        let mut changed = policy.clone();
+        if let Policy::Public(policy) = &mut changed {
+            let _ = policy.permissions.insert(
+                sn_data_types::register::User::Anyone,
+                sn_data_types::register::PublicPermissions::new(Some(true)),
+            );
+        }
+
+        assert_eq!(
+            Event::policy_digest(&policy)?,
+            XorName::from_content(&[&policy.canonical_bytes()?])
+        );
+        assert_ne!(
+            Event::policy_digest(&policy)?,
+            Event::policy_digest(&changed)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn stale_responses() {
+        let keys: Vec<_> = (0..3)
+            .map(|_| threshold_crypto::SecretKey::random().public_key())
+            .collect();
+        let response = |section_key| ProcessMsg::CmdError {
+            id: MessageId::new(),
+            error: CmdError::Transfer(TransferCmdError::TransferRegistration(Error::Transfer(
+                TransferError::NoSuchSender,
+            ))),
+            correlation_id: MessageId::new(),
+            origin: Some(ResponseOrigin {
+                section_key,
+                handler_msg_id: MessageId::new(),
+                epoch: 0,
+            }),
+        };
+
+        assert!(response(keys[0]).is_stale_response(&keys));
+        assert!(!response(keys[2]).is_stale_response(&keys));
+        assert!(!response(keys[2]).is_stale_response(&keys[..2]));
+        assert!(!response(keys[0]).is_stale_response(&[]));
+    }
+
+    #[test]
+    fn fresher_responses() {
+        let section_key = threshold_crypto::SecretKey::random().public_key();
+        let response = |epoch: Option<u64>| ProcessMsg::QueryResponse {
+            id: MessageId::new(),
+            response: QueryResponse::GetMapVersion(Ok(1)),
+            correlation_id: MessageId::new(),
+            origin: epoch.map(|epoch| ResponseOrigin {
+                section_key,
+                handler_msg_id: MessageId::new(),
+                epoch,
+            }),
+        };
+
+        assert_eq!(response(Some(3)).epoch(), Some(3));
+        assert_eq!(response(Some(0)).epoch(), None);
+        assert_eq!(response(None).epoch(), None);
+        assert!(response(Some(3)).is_fresher_than(&response(Some(2))));
+        assert!(!response(Some(2)).is_fresher_than(&response(Some(3))));
+        assert!(response(Some(1)).is_fresher_than(&response(None)));
+        assert!(!response(None).is_fresher_than(&response(Some(0))));
+    }
+
+    #[test]
+    fn response_errors() {
+        let address = DataAddress::Blob(BlobAddress::Public(XorName::random()));
+        let not_found =
+            QueryResponse::GetBlob(Err(Error::Data(DataError::DataNotFound(address.clone()))));
+        assert!(!not_found.is_success());
+        assert!(not_found.is_data_not_found());
+        assert_eq!(
+            not_found.error(),
+            Some(&Error::Data(DataError::DataNotFound(address)))
+        );
+
+        let denied = QueryResponse::GetMapVersion(Err(Error::NodeOp(NodeOpError::NoSectionFunds)));
+        assert!(!denied.is_data_not_found());
+
+        let ok = QueryResponse::GetMapVersion(Ok(1));
+        assert!(ok.is_success());
+        assert_eq!(ok.error(), None);
+    }
+
+    #[test]
+    fn cache_hints() {
+        let blob = Blob::Public(PublicBlob::new(vec![1, 3, 1, 4]));
+        assert_eq!(
+            QueryResponse::GetBlob(Ok(blob)).cache_hint(),
+            CacheHint::IMMUTABLE
+        );
+        assert_eq!(
+            QueryResponse::GetBlob(Err(Error::Data(DataError::NoSuchEntry))).cache_hint(),
+            CacheHint::NO_CACHE
+        );
+        assert!(!QueryResponse::GetMapVersion(Ok(1))
+            .cache_hint()
+            .is_cacheable());
+
+        let hint = QueryResponse::ListMapKeys(Ok(BTreeSet::new())).cache_hint();
+        assert!(hint.is_cacheable());
+        assert!(!hint.immutable);
+        assert_eq!(hint.max_age, CacheHint::MUTABLE_DATA_MAX_AGE);
+
+        assert_eq!(
+            QueryResponse::BlobExists(Ok(true)).cache_hint(),
+            CacheHint::mutable_data()
+        );
+        assert_eq!(
+            QueryResponse::BlobExists(Ok(false)).cache_hint(),
+            CacheHint::NO_CACHE
+        );
+    }
+
     #[test]
     fn try_from() -> Result<()> {
         use QueryResponse::*;
@@ -585,7 +1950,7 @@ mod tests {
         };
 
         let i_data = Blob::Public(PublicBlob::new(vec![1, 3, 1, 4]));
-        let e = Error::AccessDenied(key);
+        let e = Error::Data(DataError::AccessDenied(key));
         assert_eq!(
             i_data,
             GetBlob(Ok(i_data.clone()))
@@ -615,8 +1980,209 @@ mod tests {
         );
         assert_eq!(
             Err(TryFromError::Response(e.clone())),
-            Map::try_from(GetMap(Err(e)))
+            Map::try_from(GetMap(Err(e.clone())))
         );
+        assert_eq!(
+            Map::try_from(GetBlob(Ok(i_data)))
+                .map_err(TryFromError::into_client_error)
+                .err(),
+            Some(Error::UnexpectedQueryResponse)
+        );
+        let balance = Token::from_nano(10);
+        let cost = Token::from_nano(3);
+        assert_eq!(
+            Balance::try_from(GetBalance(Ok((balance, 2)))),
+            Ok(Balance(balance))
+        );
+        assert_eq!(
+            StoreCost::try_from(GetStoreCost(Ok((4, cost, key)))),
+            Ok(StoreCost(cost))
+        );
+        assert_eq!(
+            StoreCost::try_from(GetBalance(Ok((balance, 2)))),
+            Err(TryFromError::WrongType)
+        );
+        assert_eq!(Error::from(TryFromError::Response(e.clone())), e);
+        Ok(())
+    }
+
+    #[test]
+    fn data_cmd_error_attribution() {
+        let address = BlobAddress::Private(XorName::random());
+        let cmd = DataCmd::Blob(BlobWrite::DeletePrivate(address));
+
+        assert_eq!(
+            cmd.error(Error::Data(DataError::NoSuchEntry)),
+            CmdError::Data {
+                error: Error::Data(DataError::NoSuchEntry),
+                address: DataAddress::Blob(address),
+                op: DataCmdKind::Delete,
+            }
+        );
+    }
+
+    #[test]
+    fn payment_required() {
+        let blob = Blob::Public(PublicBlob::new(vec![1, 2]));
+        let cmd = DataCmd::Blob(BlobWrite::New(blob));
+        let payee = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let error = cmd.payment_required(Token::from_nano(5), payee, 1_000);
+
+        assert_eq!(
+            error,
+            CmdError::PaymentRequired {
+                address: cmd.address(),
+                cost: Token::from_nano(5),
+                payee,
+                quote_expiry: 1_000,
+            }
+        );
+        assert!(error.is_payable(1_000));
+        assert!(!error.is_payable(1_001));
+        assert!(!cmd
+            .error(Error::Transfer(TransferError::InsufficientPayment))
+            .is_payable(0));
+        assert_eq!(error.to_string(), "CmdError::PaymentRequired(0.000000005)");
+    }
+
+    #[test]
+    fn blob_batch_outcomes() {
+        let blobs: Vec<_> = (0..3u8)
+            .map(|i| Blob::Public(PublicBlob::new(vec![i])))
+            .collect();
+        let cmd = BlobWrite::Batch(blobs.clone());
+        assert_eq!(cmd.address(), *blobs[0].address());
+        assert_eq!(cmd.dst_address(), *blobs[0].name());
+        assert_eq!(cmd.kind(), DataCmdKind::New);
+
+        let failed = *blobs[1].address();
+        let event = Event::BlobBatchStored {
+            outcomes: blobs
+                .iter()
+                .map(|blob| {
+                    let result = if *blob.address() == failed {
+                        Err(Error::Data(DataError::NoSuchEntry))
+                    } else {
+                        Ok(())
+                    };
+                    (*blob.address(), result)
+                })
+                .collect(),
+        };
+        assert_eq!(event.failed_blobs(), vec![failed]);
+        assert_eq!(event.to_string(), "Event::BlobBatchStored(3 blobs)");
+    }
+
+    #[test]
+    fn verify_section_changed() -> Result<()> {
+        let old_key = threshold_crypto::SecretKey::random();
+        let prefix = Prefix::default().pushed(false);
+        let new_key = threshold_crypto::SecretKey::random().public_key();
+        let elders: BTreeMap<_, _> = (0..3u16)
+            .map(|port| (XorName::random(), ([127, 0, 0, 1], port).into()))
+            .collect();
+        let bytes = Event::section_changed_bytes(&prefix, &new_key, &elders)?;
+        let event = Event::SectionChanged {
+            prefix,
+            new_key,
+            elders,
+            proof: Signed {
+                public_key: old_key.public_key(),
+                signature: old_key.sign(&bytes),
+            },
+        };
+        assert!(event.verify(&old_key.public_key()));
+        assert!(!event.verify(&new_key));
+        assert_eq!(
+            event.to_string(),
+            format!("Event::SectionChanged({:?})", prefix)
+        );
+
+        let tampered = match event {
+            Event::SectionChanged { prefix, proof, .. } => Event::SectionChanged {
+                prefix,
+                new_key,
+                elders: BTreeMap::new(),
+                proof,
+            },
+            _ => return Err(anyhow!("unexpected event")),
+        };
+        assert!(!tampered.verify(&old_key.public_key()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_redirect() -> Result<()> {
+        use secured_linked_list::SecuredLinkedList;
+
+        let genesis_key = threshold_crypto::SecretKey::random();
+        let section_key = threshold_crypto::SecretKey::random();
+        let mut chain = SecuredLinkedList::new(genesis_key.public_key());
+        chain.insert(
+            &genesis_key.public_key(),
+            section_key.public_key(),
+            genesis_key.sign(&bincode::serialize(&section_key.public_key())?),
+        )?;
+        let trusted = [genesis_key.public_key()];
+        let prefix = Prefix::default().pushed(true);
+        let elders: BTreeMap<_, _> = (0..3u16)
+            .map(|port| {
+                let name = prefix.substituted_in(XorName::random());
+                (name, ([127, 0, 0, 1], port).into())
+            })
+            .collect();
+        let redirect = |elders: BTreeMap<XorName, SocketAddr>| -> Result<QueryResponse> {
+            let bytes = QueryResponse::redirect_bytes(&prefix, &elders)?;
+            Ok(QueryResponse::Redirect {
+                prefix,
+                key: section_key.public_key(),
+                elders,
+                proof: Signed {
+                    public_key: section_key.public_key(),
+                    signature: section_key.sign(&bytes),
+                },
+                key_proof: SectionKeyProof {
+                    chain: chain.clone(),
+                },
+            })
+        };
+
+        let response = redirect(elders.clone())?;
+        assert!(response.verify_redirect(&trusted));
+        assert!(!response.verify_redirect(&[threshold_crypto::SecretKey::random().public_key()]));
+        assert!(!response.is_success());
+        assert_eq!(response.cache_hint(), CacheHint::NO_CACHE);
+        assert_eq!(Blob::try_from(response), Err(TryFromError::WrongType));
+
+        let mut stray = elders.clone();
+        let _ = stray.insert(
+            prefix.sibling().substituted_in(XorName::random()),
+            ([127, 0, 0, 1], 9).into(),
+        );
+        assert!(!redirect(stray)?.verify_redirect(&trusted));
+
+        // A redirect to a section whose key isn't the proven one is rejected.
+        let self_signed = match redirect(elders)? {
+            QueryResponse::Redirect {
+                prefix,
+                key,
+                elders,
+                proof,
+                ..
+            } => QueryResponse::Redirect {
+                prefix,
+                key,
+                elders,
+                proof,
+                key_proof: SectionKeyProof {
+                    chain: SecuredLinkedList::new(genesis_key.public_key()),
+                },
+            },
+            _ => return Err(anyhow!("unexpected response")),
+        };
+        assert!(!self_signed.verify_redirect(&trusted));
+
         Ok(())
     }
 
@@ -629,15 +2195,21 @@ mod tests {
         let id = MessageId::new();
         let message = ClientMsg::Process(ProcessMsg::Query {
             id,
-            query: Query::Transfer(TransferQuery::GetBalance(public_key)),
+            query: Query::Transfer(TransferQuery::GetBalance {
+                at: public_key,
+                history_index: None,
+            }),
             client_signed: ClientSigned {
                 public_key,
                 signature,
             },
+            deadline: None,
+            anti_spam: None,
+            session: None,
         });
 
         // test msgpack serialization
-        let dest = XorName::random();
+        let dest = DstLocation::Section(XorName::random());
         let dest_section_pk = threshold_crypto::SecretKey::random().public_key();
         let serialized = message.serialize(dest, dest_section_pk)?;
         let deserialized = ClientMsg::from(serialized)?;
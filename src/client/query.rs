@@ -9,6 +9,7 @@
 
 use super::{data::DataQuery, transfer::TransferQuery, Error, QueryResponse};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use xor_name::XorName;
 
 /// TODO: docs
@@ -41,3 +42,12 @@ impl Query {
         }
     }
 }
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Data(q) => write!(f, "Query::Data({}, dst={})", q, self.dst_address()),
+            Self::Transfer(q) => write!(f, "Query::Transfer({}, dst={})", q, self.dst_address()),
+        }
+    }
+}
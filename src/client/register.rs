@@ -7,13 +7,13 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::{CmdError, Error, QueryResponse};
+use super::{CmdError, DataCmdKind, DataError, Error, OwnerTransfer, QueryResponse};
 use serde::{Deserialize, Serialize};
 use sn_data_types::{
-    register::{Address, Entry, Register, RegisterOp, User},
-    PublicKey,
+    register::{Address, Entry, EntryHash, Permissions, Policy, Register, RegisterOp, User},
+    DataAddress, PublicKey,
 };
-use std::fmt;
+use std::{collections::BTreeSet, fmt};
 use xor_name::XorName;
 
 /// Register reading queries
@@ -34,6 +34,23 @@ pub enum RegisterRead {
     },
     /// Get current owner.
     GetOwner(Address),
+    /// Get the entries with the given hashes, e.g. to resolve a known
+    /// frontier of the Register without fetching all of it.
+    GetEntries {
+        /// Register address.
+        address: Address,
+        /// Hashes of the entries to get.
+        hashes: BTreeSet<EntryHash>,
+    },
+    /// Get the ops of the Register which aren't ancestors of the given frontier,
+    /// i.e. those applied since the client last synced, so that it doesn't need
+    /// to fetch the whole Register. An empty frontier gets all the ops.
+    GetEntriesSince {
+        /// Register address.
+        address: Address,
+        /// Hashes of the latest entries known to the client.
+        frontier: BTreeSet<EntryHash>,
+    },
 }
 
 /// Register writing commands
@@ -49,6 +66,50 @@ pub enum RegisterWrite {
     /// This operation MUST return an error if applied to public Register. Only the current
     /// owner(s) can perform this action.
     Delete(Address),
+    /// Replace the whole policy of the Register.
+    ///
+    /// The new policy MUST be of the same kind (public or private) as the Register.
+    /// Only the current owner(s) can perform this action.
+    SetPolicy {
+        /// Register address.
+        address: Address,
+        /// The new policy.
+        policy: Policy,
+    },
+    /// Set the permissions of a user of the Register.
+    SetUserPermissions {
+        /// Register address.
+        address: Address,
+        /// User to set the permissions for.
+        user: User,
+        /// The new permissions of the user.
+        permissions: Permissions,
+    },
+    /// Remove the permissions of a user of the Register.
+    DelUserPermissions {
+        /// Register address.
+        address: Address,
+        /// User to remove the permissions of.
+        user: User,
+    },
+    /// Transfer the ownership of the Register to a new owner.
+    SetOwner {
+        /// Register address.
+        address: Address,
+        /// The transfer, signed by the current owner.
+        transfer: OwnerTransfer,
+    },
+    /// Relay ops signed by their authors, e.g. ops made by an offline-first client
+    /// and synced from its op log, instead of writing them one by one.
+    RelayOps {
+        /// Register address.
+        address: Address,
+        /// The ops, in the order they were applied by the client.
+        ops: Vec<RegisterOp<Entry>>,
+        /// Hashes of the entries the first ops were applied on top of. The ops are
+        /// causally ready once all of them are held.
+        frontier: BTreeSet<EntryHash>,
+    },
 }
 
 impl RegisterRead {
@@ -63,6 +124,10 @@ impl RegisterRead {
                 QueryResponse::GetRegisterUserPermissions(Err(error))
             }
             RegisterRead::GetOwner(_) => QueryResponse::GetRegisterOwner(Err(error)),
+            RegisterRead::GetEntries { .. } => QueryResponse::GetRegisterEntries(Err(error)),
+            RegisterRead::GetEntriesSince { .. } => {
+                QueryResponse::GetRegisterEntriesSince(Err(error))
+            }
         }
     }
 
@@ -73,7 +138,9 @@ impl RegisterRead {
             | RegisterRead::Read(ref address)
             | RegisterRead::GetPolicy(ref address)
             | RegisterRead::GetUserPermissions { ref address, .. }
-            | RegisterRead::GetOwner(ref address) => *address.name(),
+            | RegisterRead::GetOwner(ref address)
+            | RegisterRead::GetEntries { ref address, .. }
+            | RegisterRead::GetEntriesSince { ref address, .. } => *address.name(),
         }
     }
 }
@@ -82,15 +149,59 @@ impl RegisterWrite {
     /// Creates a Response containing an error, with the Response variant corresponding to the
     /// Request variant.
     pub fn error(&self, error: Error) -> CmdError {
-        CmdError::Data(error)
+        CmdError::Data {
+            error,
+            address: DataAddress::Register(self.address()),
+            op: self.kind(),
+        }
+    }
+
+    /// Returns the address of the data targeted by the request.
+    pub fn address(&self) -> Address {
+        match self {
+            RegisterWrite::New(ref data) => *data.address(),
+            RegisterWrite::Delete(ref address)
+            | RegisterWrite::SetPolicy { ref address, .. }
+            | RegisterWrite::SetUserPermissions { ref address, .. }
+            | RegisterWrite::DelUserPermissions { ref address, .. }
+            | RegisterWrite::SetOwner { ref address, .. }
+            | RegisterWrite::RelayOps { ref address, .. } => *address,
+            RegisterWrite::Edit(ref op) => op.address,
+        }
+    }
+
+    /// Returns the kind of operation of the request.
+    pub fn kind(&self) -> DataCmdKind {
+        match self {
+            RegisterWrite::New(_) => DataCmdKind::New,
+            RegisterWrite::Delete(_) => DataCmdKind::Delete,
+            RegisterWrite::Edit(_) | RegisterWrite::RelayOps { .. } => DataCmdKind::Edit,
+            RegisterWrite::SetPolicy { .. } => DataCmdKind::SetPolicy,
+            RegisterWrite::SetUserPermissions { .. } => DataCmdKind::SetUserPermissions,
+            RegisterWrite::DelUserPermissions { .. } => DataCmdKind::DelUserPermissions,
+            RegisterWrite::SetOwner { .. } => DataCmdKind::SetOwner,
+        }
     }
 
     /// Returns the address of the destination for request.
     pub fn dst_address(&self) -> XorName {
         match self {
             RegisterWrite::New(ref data) => *data.name(),
-            RegisterWrite::Delete(ref address) => *address.name(),
             RegisterWrite::Edit(ref op) => *op.address.name(),
+            _ => *self.address().name(),
+        }
+    }
+
+    /// Checks relayed ops can be applied on a replica of the Register holding the entries
+    /// for which `holds` returns true, failing with `DataError::OpNotCausallyReady` if it's
+    /// missing some of the frontier, whose ops the client should then relay as well.
+    /// Other writes are always ready.
+    pub fn check_causally_ready(&self, holds: impl Fn(&EntryHash) -> bool) -> Result<(), Error> {
+        match self {
+            Self::RelayOps { frontier, .. } if !frontier.iter().all(holds) => {
+                Err(Error::Data(DataError::OpNotCausallyReady))
+            }
+            _ => Ok(()),
         }
     }
 
@@ -112,6 +223,19 @@ impl fmt::Debug for RegisterWrite {
                 RegisterWrite::New(register) => format!("New({:?})", register.address()),
                 RegisterWrite::Delete(address) => format!("Delete({:?})", address),
                 RegisterWrite::Edit(op) => format!("Edit({:?})", op),
+                RegisterWrite::SetPolicy { address, .. } => format!("SetPolicy({:?})", address),
+                RegisterWrite::SetUserPermissions { address, user, .. } => {
+                    format!("SetUserPermissions({:?}, {:?})", address, user)
+                }
+                RegisterWrite::DelUserPermissions { address, user } => {
+                    format!("DelUserPermissions({:?}, {:?})", address, user)
+                }
+                RegisterWrite::SetOwner { address, transfer } => {
+                    format!("SetOwner({:?}, {:?})", address, transfer.new_owner)
+                }
+                RegisterWrite::RelayOps { address, ops, .. } => {
+                    format!("RelayOps({:?}, {} ops)", address, ops.len())
+                }
             }
         )
     }
@@ -0,0 +1,168 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::QueryResponse;
+use crate::MessageId;
+use std::collections::{BTreeMap, BTreeSet};
+use xor_name::XorName;
+
+/// Distinct responses to a query, each with the names of the elders which returned it.
+pub type ResponseTally = Vec<(QueryResponse, BTreeSet<XorName>)>;
+
+/// Outcome of adding a response to a `ResponseAccumulator`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Accumulated {
+    /// More responses are needed to reach a quorum.
+    Pending,
+    /// A quorum of elders returned this response.
+    Quorum(QueryResponse),
+    /// The elders disagree, and too few of them are left to answer for a quorum to be reached.
+    Conflict(ResponseTally),
+}
+
+/// Accumulates the responses of the elders of a section to the queries of a client,
+/// until a quorum of them returned the same response.
+///
+/// Each elder is only counted once per query, its later responses being ignored.
+/// The responses to a query are dropped once a result is yielded, so the ones arriving
+/// afterwards start accumulating anew, and can be dropped with `remove`.
+#[derive(Clone, Debug)]
+pub struct ResponseAccumulator {
+    elder_count: usize,
+    quorum: usize,
+    queries: BTreeMap<MessageId, ResponseTally>,
+}
+
+impl ResponseAccumulator {
+    /// Creates an accumulator for queries sent to `elder_count` elders,
+    /// yielding a response once `quorum` of them returned it.
+    pub fn new(elder_count: usize, quorum: usize) -> Self {
+        Self {
+            elder_count,
+            quorum: quorum.max(1),
+            queries: BTreeMap::new(),
+        }
+    }
+
+    /// Creates an accumulator for queries sent to `elder_count` elders,
+    /// yielding a response once a majority of them returned it.
+    pub fn with_majority(elder_count: usize) -> Self {
+        Self::new(elder_count, elder_count / 2 + 1)
+    }
+
+    /// Adds the response of the given elder to the query with id `correlation_id`.
+    pub fn add(
+        &mut self,
+        correlation_id: MessageId,
+        elder: XorName,
+        response: QueryResponse,
+    ) -> Accumulated {
+        let quorum = self.quorum;
+        let tally = self.queries.entry(correlation_id).or_default();
+        if !tally.iter().any(|(_, elders)| elders.contains(&elder)) {
+            match tally.iter_mut().find(|(known, _)| *known == response) {
+                Some((_, elders)) => {
+                    let _ = elders.insert(elder);
+                }
+                None => tally.push((response, vec![elder].into_iter().collect())),
+            }
+        }
+
+        let answered: usize = tally.iter().map(|(_, elders)| elders.len()).sum();
+        let most_agreed = tally
+            .iter()
+            .map(|(_, elders)| elders.len())
+            .max()
+            .unwrap_or(0);
+        let agreed = tally.iter().position(|(_, elders)| elders.len() >= quorum);
+
+        if let Some(index) = agreed {
+            let mut tally = self.queries.remove(&correlation_id).unwrap_or_default();
+            Accumulated::Quorum(tally.swap_remove(index).0)
+        } else if most_agreed + self.elder_count.saturating_sub(answered) < quorum {
+            Accumulated::Conflict(self.queries.remove(&correlation_id).unwrap_or_default())
+        } else {
+            Accumulated::Pending
+        }
+    }
+
+    /// Drops the responses accumulated for the given query, e.g. once it timed out,
+    /// returning them.
+    pub fn remove(&mut self, correlation_id: &MessageId) -> Option<ResponseTally> {
+        self.queries.remove(correlation_id)
+    }
+
+    /// Number of queries with responses accumulated.
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    /// Returns true if no response is accumulated.
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_data_types::Token;
+
+    fn balance(nanos: u64) -> QueryResponse {
+        QueryResponse::GetBalance(Ok((Token::from_nano(nanos), 0)))
+    }
+
+    #[test]
+    fn accumulate_to_quorum() {
+        let mut accumulator = ResponseAccumulator::with_majority(3);
+        let elders: Vec<_> = (0..3).map(|_| XorName::random()).collect();
+        let query = MessageId::new();
+
+        assert_eq!(
+            accumulator.add(query, elders[0], balance(1)),
+            Accumulated::Pending
+        );
+        // the same elder is only counted once
+        assert_eq!(
+            accumulator.add(query, elders[0], balance(1)),
+            Accumulated::Pending
+        );
+        assert_eq!(
+            accumulator.add(query, elders[1], balance(2)),
+            Accumulated::Pending
+        );
+        assert_eq!(
+            accumulator.add(query, elders[2], balance(1)),
+            Accumulated::Quorum(balance(1))
+        );
+        assert!(accumulator.is_empty());
+    }
+
+    #[test]
+    fn report_conflict() {
+        let mut accumulator = ResponseAccumulator::with_majority(3);
+        let elders: Vec<_> = (0..3).map(|_| XorName::random()).collect();
+        let query = MessageId::new();
+
+        for (index, elder) in elders.iter().take(2).enumerate() {
+            assert_eq!(
+                accumulator.add(query, *elder, balance(index as u64)),
+                Accumulated::Pending
+            );
+        }
+        match accumulator.add(query, elders[2], balance(2)) {
+            Accumulated::Conflict(tally) => {
+                assert_eq!(tally.len(), 3);
+                assert!(tally.iter().all(|(_, elders)| elders.len() == 1));
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+        assert!(accumulator.is_empty());
+    }
+}
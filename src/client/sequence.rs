@@ -7,11 +7,11 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::{CmdError, Error, QueryResponse};
+use super::{CmdError, DataCmdKind, DataError, Error, OwnerTransfer, QueryResponse};
 use serde::{Deserialize, Serialize};
 use sn_data_types::{
-    PublicKey, Sequence, SequenceAddress as Address, SequenceEntry as Entry,
-    SequenceIndex as Index, SequenceOp, SequenceUser as User,
+    DataAddress, PublicKey, Sequence, SequenceAddress as Address, SequenceEntries,
+    SequenceEntry as Entry, SequenceIndex as Index, SequenceOp, SequenceUser as User,
 };
 use std::fmt;
 use xor_name::XorName;
@@ -50,6 +50,25 @@ pub enum SequenceRead {
         /// User to get permissions for.
         user: User,
     },
+    /// Get a page of entries from the Sequence, so long Sequences
+    /// can be consumed incrementally.
+    GetPage {
+        /// Sequence address.
+        address: Address,
+        /// Index of the first entry of the page.
+        start: u64,
+        /// Maximum number of entries of the page.
+        count: u64,
+    },
+}
+
+/// A page of entries of a Sequence.
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct SequencePage {
+    /// Entries of the page, in order.
+    pub entries: SequenceEntries,
+    /// Whether the Sequence has more entries after this page.
+    pub has_more: bool,
 }
 
 /// TODO: docs
@@ -65,6 +84,24 @@ pub enum SequenceWrite {
     /// This operation MUST return an error if applied to public Sequence. Only the current
     /// owner(s) can perform this action.
     Delete(Address),
+    /// Transfer the ownership of the Sequence to a new owner.
+    SetOwner {
+        /// Sequence address.
+        address: Address,
+        /// The transfer, signed by the current owner.
+        transfer: OwnerTransfer,
+    },
+    /// Relay ops signed by their authors, e.g. ops made by an offline-first client
+    /// and synced from its op log, instead of writing them one by one.
+    RelayOps {
+        /// Sequence address.
+        address: Address,
+        /// The ops, in the order they were applied by the client.
+        ops: Vec<SequenceOp<Entry>>,
+        /// Length of the Sequence the first op was applied on. As Sequences are append-only,
+        /// the ops are causally ready once the Sequence is at least that long.
+        base_len: u64,
+    },
 }
 
 impl SequenceRead {
@@ -79,6 +116,7 @@ impl SequenceRead {
             GetPublicPolicy(_) => QueryResponse::GetSequencePublicPolicy(Err(error)),
             GetPrivatePolicy(_) => QueryResponse::GetSequencePrivatePolicy(Err(error)),
             GetUserPermissions { .. } => QueryResponse::GetSequenceUserPermissions(Err(error)),
+            GetPage { .. } => QueryResponse::GetSequencePage(Err(error)),
         }
     }
 
@@ -91,7 +129,8 @@ impl SequenceRead {
             | GetLastEntry(ref address)
             | GetPublicPolicy(ref address)
             | GetPrivatePolicy(ref address)
-            | GetUserPermissions { ref address, .. } => *address.name(),
+            | GetUserPermissions { ref address, .. }
+            | GetPage { ref address, .. } => *address.name(),
         }
     }
 }
@@ -100,7 +139,32 @@ impl SequenceWrite {
     /// Creates a Response containing an error, with the Response variant corresponding to the
     /// Request variant.
     pub fn error(&self, error: Error) -> CmdError {
-        CmdError::Data(error)
+        CmdError::Data {
+            error,
+            address: DataAddress::Sequence(self.address()),
+            op: self.kind(),
+        }
+    }
+
+    /// Returns the address of the data targeted by the request.
+    pub fn address(&self) -> Address {
+        match self {
+            SequenceWrite::New(ref data) => *data.address(),
+            SequenceWrite::Delete(ref address)
+            | SequenceWrite::SetOwner { ref address, .. }
+            | SequenceWrite::RelayOps { ref address, .. } => *address,
+            SequenceWrite::Edit(ref op) => op.address,
+        }
+    }
+
+    /// Returns the kind of operation of the request.
+    pub fn kind(&self) -> DataCmdKind {
+        match self {
+            SequenceWrite::New(_) => DataCmdKind::New,
+            SequenceWrite::Delete(_) => DataCmdKind::Delete,
+            SequenceWrite::Edit(_) | SequenceWrite::RelayOps { .. } => DataCmdKind::Edit,
+            SequenceWrite::SetOwner { .. } => DataCmdKind::SetOwner,
+        }
     }
 
     /// Returns the address of the destination for request.
@@ -108,11 +172,25 @@ impl SequenceWrite {
         use SequenceWrite::*;
         match self {
             New(ref data) => *data.name(),
-            Delete(ref address) => *address.name(),
+            Delete(ref address) | SetOwner { ref address, .. } | RelayOps { ref address, .. } => {
+                *address.name()
+            }
             Edit(ref op) => *op.address.name(),
         }
     }
 
+    /// Checks relayed ops can be applied on a replica of the Sequence of the given length,
+    /// failing with `DataError::OpNotCausallyReady` if it's missing preceding ops, which the
+    /// client should then relay as well. Other writes are always ready.
+    pub fn check_causally_ready(&self, len: u64) -> Result<(), Error> {
+        match self {
+            Self::RelayOps { base_len, .. } if *base_len > len => {
+                Err(Error::Data(DataError::OpNotCausallyReady))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Owner of the SequenceWrite
     pub fn owner(&self) -> Option<PublicKey> {
         match self {
@@ -132,6 +210,12 @@ impl fmt::Debug for SequenceWrite {
                 New(seq) => format!("New({:?})", seq.address()),
                 Delete(address) => format!("Delete({:?})", address),
                 Edit(op) => format!("Edit({:?})", op),
+                SetOwner { address, transfer } => {
+                    format!("SetOwner({:?}, {:?})", address, transfer.new_owner)
+                }
+                RelayOps { address, ops, .. } => {
+                    format!("RelayOps({:?}, {} ops)", address, ops.len())
+                }
             }
         )
     }
@@ -0,0 +1,137 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{canonical::signing_bytes, Signed};
+use serde::{Deserialize, Serialize};
+use sn_data_types::PublicKey;
+use std::fmt;
+use threshold_crypto::PublicKey as BlsPublicKey;
+use tiny_keccak::{Hasher, Sha3};
+
+/// Features a client supports, which the section may rely on when messaging it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Highest messaging protocol version the client supports.
+    pub protocol_version: u16,
+    /// Whether the client accepts events pushed to it, e.g. `Event::SectionChanged`.
+    pub events: bool,
+}
+
+/// Identifier of a session, echoed by the client on the messages it sends within it.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct SessionId(pub [u8; 32]);
+
+impl fmt::Debug for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SessionId({:02x}{:02x}{:02x}..)",
+            self.0[0], self.0[1], self.0[2]
+        )
+    }
+}
+
+/// Token issued by a section to a client establishing a session with `Cmd::CreateSession`.
+/// As it's signed by the section, any of its elders can resume the session with
+/// `Cmd::ResumeSession`, e.g. after the client reconnected, without a full re-bootstrap.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct SessionToken {
+    /// Client the session belongs to.
+    pub client: PublicKey,
+    /// Capabilities of the client, as given when creating the session.
+    pub capabilities: Capabilities,
+    /// Time, in milliseconds since the Unix epoch, the session was created at.
+    pub created: u64,
+    /// Time, in milliseconds since the Unix epoch, past which the session can't be resumed.
+    pub expiry: u64,
+    /// Signature of the section over the other fields.
+    pub proof: Signed,
+}
+
+impl SessionToken {
+    /// Returns the bytes a section signs to issue a token.
+    pub fn bytes(
+        client: &PublicKey,
+        capabilities: &Capabilities,
+        created: u64,
+        expiry: u64,
+    ) -> crate::Result<Vec<u8>> {
+        signing_bytes(
+            "session-token",
+            &(
+                client,
+                (capabilities.protocol_version, capabilities.events),
+                created,
+                expiry,
+            ),
+        )
+    }
+
+    /// Returns the id of the session.
+    pub fn id(&self) -> crate::Result<SessionId> {
+        let mut hasher = Sha3::v256();
+        hasher.update(&Self::bytes(
+            &self.client,
+            &self.capabilities,
+            self.created,
+            self.expiry,
+        )?);
+        let mut id = [0; 32];
+        hasher.finalize(&mut id);
+        Ok(SessionId(id))
+    }
+
+    /// Verifies the token was issued by a section with one of the given keys, and can still
+    /// be resumed at the given time, in milliseconds since the Unix epoch.
+    pub fn verify(&self, section_keys: &[BlsPublicKey], now_millis: u64) -> bool {
+        now_millis <= self.expiry
+            && section_keys.contains(&self.proof.public_key)
+            && Self::bytes(&self.client, &self.capabilities, self.created, self.expiry)
+                .map(|bytes| self.proof.verify(&bytes))
+                .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_data_types::Keypair;
+
+    #[test]
+    fn verify_session_token() -> anyhow::Result<()> {
+        let client = PublicKey::from(&Keypair::new_ed25519(&mut rand::thread_rng()));
+        let section_key = threshold_crypto::SecretKey::random();
+        let capabilities = Capabilities {
+            protocol_version: 4,
+            events: true,
+        };
+        let bytes = SessionToken::bytes(&client, &capabilities, 10, 1_000)?;
+        let token = SessionToken {
+            client,
+            capabilities,
+            created: 10,
+            expiry: 1_000,
+            proof: Signed {
+                public_key: section_key.public_key(),
+                signature: section_key.sign(&bytes),
+            },
+        };
+        let keys = [section_key.public_key()];
+
+        assert!(token.verify(&keys, 1_000));
+        assert!(!token.verify(&keys, 1_001));
+        assert!(!token.verify(&[], 0));
+
+        let mut extended = token.clone();
+        extended.expiry = 2_000;
+        assert!(!extended.verify(&keys, 0));
+        assert_ne!(extended.id()?, token.id()?);
+        Ok(())
+    }
+}
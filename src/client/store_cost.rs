@@ -0,0 +1,136 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{canonical::signing_bytes, Signed};
+use serde::{Deserialize, Serialize};
+use sn_data_types::{PublicKey, Token, TransferAgreementProof};
+use std::fmt;
+use threshold_crypto::PublicKey as BlsPublicKey;
+use tiny_keccak::{Hasher, Sha3};
+
+/// Identifier of a store cost quote, referenced by the cmd storing the quoted data.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct QuoteId(pub [u8; 32]);
+
+impl fmt::Debug for QuoteId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "QuoteId({:02x}{:02x}{:02x}..)",
+            self.0[0], self.0[1], self.0[2]
+        )
+    }
+}
+
+/// Cost of storing data quoted by the elders of a section, as returned for a
+/// `TransferQuery::GetStoreCostQuote`. As it's signed by the section, the quoted cost is
+/// binding: the data is stored with `Cmd::QuotedData` referencing the quote, and the
+/// amount charged can be checked against the quote from the messages alone.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct StoreCostQuote {
+    /// Client the quote was issued to.
+    pub requester: PublicKey,
+    /// Number of bytes quoted for.
+    pub bytes: u64,
+    /// Cost of storing the bytes.
+    pub cost: Token,
+    /// Key of the section wallet the payment is to be made to.
+    pub payee: PublicKey,
+    /// Time, in milliseconds since the Unix epoch, past which the quote is no longer accepted.
+    pub expiry: u64,
+    /// Signature of the section over the other fields.
+    pub sig: Signed,
+}
+
+impl StoreCostQuote {
+    /// Returns the bytes a section signs to issue a quote.
+    pub fn bytes_to_sign(
+        requester: &PublicKey,
+        bytes: u64,
+        cost: Token,
+        payee: &PublicKey,
+        expiry: u64,
+    ) -> crate::Result<Vec<u8>> {
+        signing_bytes("store-cost-quote", &(requester, bytes, cost, payee, expiry))
+    }
+
+    fn signed_bytes(&self) -> crate::Result<Vec<u8>> {
+        Self::bytes_to_sign(
+            &self.requester,
+            self.bytes,
+            self.cost,
+            &self.payee,
+            self.expiry,
+        )
+    }
+
+    /// Returns the id of the quote.
+    pub fn id(&self) -> crate::Result<QuoteId> {
+        let mut hasher = Sha3::v256();
+        hasher.update(&self.signed_bytes()?);
+        let mut id = [0; 32];
+        hasher.finalize(&mut id);
+        Ok(QuoteId(id))
+    }
+
+    /// Verifies the quote was issued by a section with one of the given keys, and is still
+    /// accepted at the given time, in milliseconds since the Unix epoch.
+    pub fn verify(&self, section_keys: &[BlsPublicKey], now_millis: u64) -> bool {
+        now_millis <= self.expiry
+            && section_keys.contains(&self.sig.public_key)
+            && self
+                .signed_bytes()
+                .map(|bytes| self.sig.verify(&bytes))
+                .unwrap_or(false)
+    }
+
+    /// Returns true if the given payment is from the requester to the payee,
+    /// of at least the quoted cost.
+    pub fn is_paid_by(&self, payment: &TransferAgreementProof) -> bool {
+        payment.sender() == self.requester
+            && payment.recipient() == self.payee
+            && payment.amount() >= self.cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_data_types::Keypair;
+
+    #[test]
+    fn verify_quote() -> anyhow::Result<()> {
+        let requester = PublicKey::from(&Keypair::new_ed25519(&mut rand::thread_rng()));
+        let payee = PublicKey::from(threshold_crypto::SecretKey::random().public_key());
+        let section_key = threshold_crypto::SecretKey::random();
+        let cost = Token::from_nano(100);
+        let bytes = StoreCostQuote::bytes_to_sign(&requester, 1024, cost, &payee, 1_000)?;
+        let quote = StoreCostQuote {
+            requester,
+            bytes: 1024,
+            cost,
+            payee,
+            expiry: 1_000,
+            sig: Signed {
+                public_key: section_key.public_key(),
+                signature: section_key.sign(&bytes),
+            },
+        };
+
+        assert!(quote.verify(&[section_key.public_key()], 1_000));
+        assert!(!quote.verify(&[section_key.public_key()], 1_001));
+        assert!(!quote.verify(&[threshold_crypto::SecretKey::random().public_key()], 0));
+
+        let mut tampered = quote.clone();
+        tampered.cost = Token::from_nano(1);
+        assert!(!tampered.verify(&[section_key.public_key()], 0));
+        assert_ne!(tampered.id()?, quote.id()?);
+        Ok(())
+    }
+}
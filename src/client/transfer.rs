@@ -7,11 +7,14 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::{CmdError, Error, QueryResponse, TransferError};
+use super::{
+    transfer_validation::verify_agreement, CmdError, Error, QueryResponse, TransferCmdError,
+};
+use crate::{canonical::signing_bytes, Signed};
 use serde::{Deserialize, Serialize};
-#[cfg(feature = "simulated-payouts")]
-use sn_data_types::Transfer;
-use sn_data_types::{PublicKey, SignedTransfer, TransferAgreementProof};
+#[cfg(feature = "testnet")]
+use sn_data_types::Token;
+use sn_data_types::{DebitId, PublicKey, SignedTransfer, TransferAgreementProof};
 use std::fmt;
 use xor_name::XorName;
 
@@ -19,13 +22,20 @@ use xor_name::XorName;
 #[allow(clippy::large_enum_variant)]
 #[derive(Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum TransferCmd {
-    #[cfg(feature = "simulated-payouts")]
-    /// Cmd to simulate a farming payotu
-    SimulatePayout(Transfer),
     /// The cmd to validate a transfer.
     ValidateTransfer(SignedTransfer),
     /// The cmd to register the consensused transfer.
     RegisterTransfer(TransferAgreementProof),
+    /// Cmd to credit a wallet out of thin air, so local and test networks can fund
+    /// wallets through the standard message path. Only available with the `testnet`
+    /// feature, and rejected by networks not built with it.
+    #[cfg(feature = "testnet")]
+    SimulatePayout {
+        /// The wallet to credit.
+        to: PublicKey,
+        /// The amount to credit.
+        amount: Token,
+    },
 }
 
 /// Token query that is sent to network.
@@ -33,13 +43,20 @@ pub enum TransferCmd {
 #[derive(Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum TransferQuery {
     /// Get key balance.
-    GetBalance(PublicKey),
-    /// Get key transfers since specified version.
+    GetBalance {
+        /// The balance key.
+        at: PublicKey,
+        /// Index of the key's transfer history to evaluate the balance at, or the latest if `None`.
+        history_index: Option<usize>,
+    },
+    /// Get a page of key transfers, starting at the specified index.
     GetHistory {
         /// The balance key.
         at: PublicKey,
-        /// The last version of transfers we know of.
-        since_version: usize,
+        /// Index of the first transfer to return, i.e. the number of transfers we already know of.
+        since_index: usize,
+        /// Maximum number of transfers to return, or all of them if `None`.
+        max_items: Option<usize>,
     },
     /// Get the latest cost for writing given number of bytes to network.
     GetStoreCost {
@@ -48,6 +65,45 @@ pub enum TransferQuery {
         ///
         bytes: u64,
     },
+    /// Get the public wallet key and balance of the section closest to the given name.
+    GetSectionWallet(XorName),
+    /// Get the proof that the transfer with the given debit id was registered,
+    /// so that its recipient can verify the payment offline.
+    GetTransferProof(DebitId),
+    /// Get a quote, signed by the section, of the cost for writing given number of bytes
+    /// to the network, binding until its expiry.
+    GetStoreCostQuote {
+        /// The requester's key.
+        requester: PublicKey,
+        /// Number of bytes to write.
+        bytes: u64,
+    },
+}
+
+/// Proof that a transfer was registered by the section holding the wallet of its sender,
+/// as returned for a `TransferQuery::GetTransferProof`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TransferProof {
+    /// The agreement of the replicas on the transfer.
+    pub proof: TransferAgreementProof,
+    /// Signature of the section over the registered transfer.
+    pub registered: Signed,
+}
+
+impl TransferProof {
+    /// Returns the bytes the section signs for a registered transfer.
+    pub fn bytes_to_sign(proof: &TransferAgreementProof) -> crate::Result<Vec<u8>> {
+        signing_bytes("transfer-registered", proof)
+    }
+
+    /// Verifies the signatures of the replicas over the transfer, and of the section
+    /// over its registration. Whether the section key is trusted still needs checking.
+    pub fn verify(&self) -> bool {
+        verify_agreement(&self.proof)
+            && Self::bytes_to_sign(&self.proof)
+                .map(|bytes| self.registered.verify(&bytes))
+                .unwrap_or(false)
+    }
 }
 
 impl TransferCmd {
@@ -56,12 +112,12 @@ impl TransferCmd {
     pub fn error(&self, error: Error) -> CmdError {
         use CmdError::*;
         use TransferCmd::*;
-        use TransferError::*;
+        use TransferCmdError::*;
         match *self {
             ValidateTransfer(_) => Transfer(TransferValidation(error)),
             RegisterTransfer(_) => Transfer(TransferRegistration(error)),
-            #[cfg(feature = "simulated-payouts")]
-            SimulatePayout(_) => Transfer(TransferRegistration(error)),
+            #[cfg(feature = "testnet")]
+            SimulatePayout { .. } => Transfer(SimulatedPayout(error)),
         }
     }
 
@@ -71,8 +127,8 @@ impl TransferCmd {
         match self {
             RegisterTransfer(ref proof) => XorName::from(proof.sender()), // this is handled where the debit is made
             ValidateTransfer(ref signed_transfer) => XorName::from(signed_transfer.sender()), // this is handled where the debit is made
-            #[cfg(feature = "simulated-payouts")]
-            SimulatePayout(ref transfer) => XorName::from(transfer.debit().sender()), // this is handled where the debit is made
+            #[cfg(feature = "testnet")]
+            SimulatePayout { ref to, .. } => XorName::from(*to), // this is handled where the credit is made
         }
     }
 }
@@ -86,22 +142,46 @@ impl fmt::Debug for TransferCmd {
             match *self {
                 RegisterTransfer { .. } => "RegisterTransfer",
                 ValidateTransfer { .. } => "ValidateTransfer",
-                #[cfg(feature = "simulated-payouts")]
+                #[cfg(feature = "testnet")]
                 SimulatePayout { .. } => "SimulatePayout",
             }
         )
     }
 }
 
+impl fmt::Display for TransferCmd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ValidateTransfer(transfer) => write!(
+                f,
+                "ValidateTransfer(sender={}, counter={})",
+                transfer.sender(),
+                transfer.id().counter
+            ),
+            Self::RegisterTransfer(proof) => write!(
+                f,
+                "RegisterTransfer(sender={}, counter={})",
+                proof.sender(),
+                proof.id().counter
+            ),
+            #[cfg(feature = "testnet")]
+            Self::SimulatePayout { to, .. } => write!(f, "SimulatePayout(to={})", to),
+        }
+    }
+}
+
 impl TransferQuery {
     /// Creates a QueryResponse containing an error, with the QueryResponse variant corresponding to the
     /// Request variant.
     pub fn error(&self, error: Error) -> QueryResponse {
         use TransferQuery::*;
         match *self {
-            GetBalance(_) => QueryResponse::GetBalance(Err(error)),
+            GetBalance { .. } => QueryResponse::GetBalance(Err(error)),
             GetHistory { .. } => QueryResponse::GetHistory(Err(error)),
             GetStoreCost { .. } => QueryResponse::GetStoreCost(Err(error)),
+            GetSectionWallet(_) => QueryResponse::GetSectionWallet(Err(error)),
+            GetTransferProof(_) => QueryResponse::GetTransferProof(Err(error)),
+            GetStoreCostQuote { .. } => QueryResponse::GetStoreCostQuote(Err(error)),
         }
     }
 
@@ -109,9 +189,13 @@ impl TransferQuery {
     pub fn dst_address(&self) -> XorName {
         use TransferQuery::*;
         match self {
-            GetBalance(at) | GetHistory { at, .. } | GetStoreCost { requester: at, .. } => {
-                XorName::from(*at)
-            }
+            GetBalance { at, .. }
+            | GetHistory { at, .. }
+            | GetStoreCost { requester: at, .. }
+            | GetStoreCostQuote { requester: at, .. } => XorName::from(*at),
+            GetSectionWallet(name) => *name,
+            // this is handled where the debit is made
+            GetTransferProof(id) => XorName::from(id.actor),
         }
     }
 }
@@ -120,11 +204,66 @@ impl fmt::Debug for TransferQuery {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         use TransferQuery::*;
         match *self {
-            GetBalance(_) => write!(formatter, "TransferQuery::GetBalance"),
+            GetBalance { .. } => write!(formatter, "TransferQuery::GetBalance"),
             GetHistory { .. } => write!(formatter, "TransferQuery::GetHistory"),
             GetStoreCost { bytes, .. } => {
                 write!(formatter, "TransferQuery::GetStoreCost of {:?}", bytes)
             }
+            GetSectionWallet(name) => {
+                write!(formatter, "TransferQuery::GetSectionWallet of {:?}", name)
+            }
+            GetTransferProof(id) => {
+                write!(formatter, "TransferQuery::GetTransferProof of {:?}", id)
+            }
+            GetStoreCostQuote { bytes, .. } => {
+                write!(formatter, "TransferQuery::GetStoreCostQuote of {:?}", bytes)
+            }
         }
     }
 }
+
+impl fmt::Display for TransferQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TransferQuery::*;
+        match self {
+            GetBalance { at, .. } => write!(f, "GetBalance(at={})", at),
+            GetHistory {
+                at, since_index, ..
+            } => {
+                write!(f, "GetHistory(at={}, since_index={})", at, since_index)
+            }
+            GetStoreCost { bytes, .. } => write!(f, "GetStoreCost(bytes={})", bytes),
+            GetSectionWallet(name) => write!(f, "GetSectionWallet({})", name),
+            GetTransferProof(id) => write!(
+                f,
+                "GetTransferProof(sender={}, counter={})",
+                id.actor, id.counter
+            ),
+            GetStoreCostQuote { bytes, .. } => write!(f, "GetStoreCostQuote(bytes={})", bytes),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testnet"))]
+mod tests {
+    use super::*;
+    use crate::client::DataError;
+
+    #[test]
+    fn simulate_payout() {
+        let to = PublicKey::from(threshold_crypto::SecretKey::random().public_key());
+        let cmd = TransferCmd::SimulatePayout {
+            to,
+            amount: Token::from_nano(10),
+        };
+        assert_eq!(cmd.dst_address(), XorName::from(to));
+        assert_eq!(
+            cmd.error(Error::Data(DataError::InvalidOperation(
+                "no testnet".to_string()
+            ))),
+            CmdError::Transfer(TransferCmdError::SimulatedPayout(Error::Data(
+                DataError::InvalidOperation("no testnet".to_string())
+            )))
+        );
+    }
+}
@@ -0,0 +1,466 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{signature_aggregator::Error as AggregatorError, SignatureAggregator, SignedShare};
+use serde::Serialize;
+use sn_data_types::{
+    DebitId, Signature, SignatureShare, SignedCredit, SignedDebit, TransferAgreementProof,
+    TransferValidated,
+};
+use std::{collections::HashMap, time::Duration};
+use thiserror::Error;
+use threshold_crypto::{self as bls, PublicKeySet};
+
+/// Default duration, in milliseconds, for which a `TransferValidationAccumulator` keeps
+/// the validations of a transfer which didn't reach agreement.
+pub const DEFAULT_VALIDATION_TTL: u64 = 60 * 1000;
+
+/// Accumulator of the `TransferValidated` events returned by the replicas of a transfer,
+/// combining their signature shares into a `TransferAgreementProof` once enough of them
+/// are collected.
+///
+/// Validations of different debits or credits for the same transfer, e.g. sent by a faulty
+/// replica, are accumulated separately, so they can't prevent the others from reaching
+/// agreement. Validations are forgotten once their time to live has elapsed since the first
+/// validation of the transfer was added. Times are given in milliseconds since the UNIX epoch
+/// by the caller.
+pub struct TransferValidationAccumulator {
+    ttl: u64,
+    transfers: HashMap<DebitId, Vec<State>>,
+}
+
+impl Default for TransferValidationAccumulator {
+    fn default() -> Self {
+        Self::with_ttl(DEFAULT_VALIDATION_TTL)
+    }
+}
+
+impl TransferValidationAccumulator {
+    /// Create new empty accumulator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create new empty accumulator, keeping validations for `ttl` milliseconds.
+    pub fn with_ttl(ttl: u64) -> Self {
+        Self {
+            ttl,
+            transfers: HashMap::new(),
+        }
+    }
+
+    /// Add the validation of a transfer by one of its replicas, whose key set is expected to
+    /// be `replicas`, at `now`. If enough valid validations were collected, returns the proof
+    /// of agreement of the replicas on the transfer.
+    ///
+    /// Note: returned `TransferValidationError::NotEnoughShares` does not indicate a failure,
+    /// more validations are simply needed. The other error variants reject the validation
+    /// being added, keeping the ones already collected intact.
+    pub fn add(
+        &mut self,
+        validated: TransferValidated,
+        replicas: &PublicKeySet,
+        now: u64,
+    ) -> Result<TransferAgreementProof, TransferValidationError> {
+        self.prune(now);
+
+        if validated.replicas != *replicas {
+            return Err(TransferValidationError::UnexpectedReplicas);
+        }
+
+        if !verify_validation(&validated) {
+            return Err(TransferValidationError::InvalidShare);
+        }
+
+        let id = validated.signed_debit.id();
+        let candidates = self.transfers.entry(id).or_default();
+        let index = match candidates.iter().position(|state| {
+            state.signed_debit == validated.signed_debit
+                && state.signed_credit == validated.signed_credit
+        }) {
+            Some(index) => index,
+            None => {
+                candidates.push(State::new(
+                    validated.signed_debit.clone(),
+                    validated.signed_credit.clone(),
+                    now,
+                ));
+                candidates.len() - 1
+            }
+        };
+
+        let proof = candidates[index].add(&validated)?;
+        let _ = self.transfers.remove(&id);
+        Ok(proof)
+    }
+
+    /// Forgets the validations whose time to live elapsed at `now`.
+    pub fn prune(&mut self, now: u64) {
+        let ttl = self.ttl;
+        self.transfers.retain(|_, candidates| {
+            candidates.retain(|state| now.saturating_sub(state.added_at) < ttl);
+            !candidates.is_empty()
+        });
+    }
+
+    /// Number of transfers with validations accumulated.
+    pub fn len(&self) -> usize {
+        self.transfers.len()
+    }
+
+    /// Returns true if no validation is accumulated.
+    pub fn is_empty(&self) -> bool {
+        self.transfers.is_empty()
+    }
+}
+
+/// Error returned from TransferValidationAccumulator::add.
+#[derive(Debug, Error)]
+pub enum TransferValidationError {
+    /// There are not enough validations yet, more need to be added. This is not a failure.
+    #[error("not enough transfer validations")]
+    NotEnoughShares,
+    /// A signature share of the replica isn't valid for the transfer.
+    #[error("replica signature share is invalid")]
+    InvalidShare,
+    /// The replicas key set isn't the one expected for the transfer.
+    #[error("replicas key set isn't the expected one")]
+    UnexpectedReplicas,
+    /// The signature combination failed even though there are enough valid signature shares.
+    /// This should probably never happen.
+    #[error("failed to combine signature shares: {0}")]
+    Combine(bls::error::Error),
+}
+
+struct State {
+    signed_debit: SignedDebit,
+    signed_credit: SignedCredit,
+    // Time the first validation of this debit and credit was added at.
+    added_at: u64,
+    debit: Quorum,
+    credit: Quorum,
+}
+
+impl State {
+    fn new(signed_debit: SignedDebit, signed_credit: SignedCredit, added_at: u64) -> Self {
+        Self {
+            signed_debit,
+            signed_credit,
+            added_at,
+            debit: Quorum::default(),
+            credit: Quorum::default(),
+        }
+    }
+
+    // Adds the shares of a validation of this debit and credit, which were verified already,
+    // returning the proof of agreement once both are signed by the replicas.
+    fn add(
+        &mut self,
+        validated: &TransferValidated,
+    ) -> Result<TransferAgreementProof, TransferValidationError> {
+        let debit_sig = self.debit.add(
+            &self.signed_debit,
+            &validated.replicas,
+            &validated.replica_debit_sig,
+        )?;
+        let credit_sig = self.credit.add(
+            &self.signed_credit,
+            &validated.replicas,
+            &validated.replica_credit_sig,
+        )?;
+        match (debit_sig, credit_sig) {
+            (Some(debit_sig), Some(credit_sig)) => Ok(TransferAgreementProof {
+                signed_debit: self.signed_debit.clone(),
+                signed_credit: self.signed_credit.clone(),
+                debit_sig: Signature::Bls(debit_sig),
+                credit_sig: Signature::Bls(credit_sig),
+                debiting_replicas_keys: validated.replicas.clone(),
+            }),
+            _ => Err(TransferValidationError::NotEnoughShares),
+        }
+    }
+}
+
+// Replicas signatures over the debit or the credit of a transfer, aggregated from their shares.
+struct Quorum {
+    aggregator: SignatureAggregator,
+    signature: Option<bls::Signature>,
+}
+
+impl Default for Quorum {
+    fn default() -> Self {
+        // The validations of a transfer are forgotten along with their `State`,
+        // according to the caller's clock, so the shares don't expire on their own.
+        Self {
+            aggregator: SignatureAggregator::with_expiration(Duration::MAX),
+            signature: None,
+        }
+    }
+}
+
+impl Quorum {
+    // Adds the share of a replica, returning the signature of the replicas once aggregated.
+    fn add<T: Serialize>(
+        &mut self,
+        signed: &T,
+        replicas: &PublicKeySet,
+        share: &SignatureShare,
+    ) -> Result<Option<bls::Signature>, TransferValidationError> {
+        if self.signature.is_none() {
+            let payload =
+                bincode::serialize(signed).map_err(|_| TransferValidationError::InvalidShare)?;
+            let signed_share = SignedShare {
+                public_key_set: replicas.clone(),
+                index: share.index,
+                signature_share: share.share.clone(),
+            };
+            match self.aggregator.add(&payload, signed_share) {
+                Ok(signed) => self.signature = Some(signed.signature),
+                Err(AggregatorError::NotEnoughShares) => (),
+                Err(AggregatorError::InvalidShare) => {
+                    return Err(TransferValidationError::InvalidShare)
+                }
+                Err(AggregatorError::Combine(err)) => {
+                    return Err(TransferValidationError::Combine(err))
+                }
+            }
+        }
+        Ok(self.signature.clone())
+    }
+}
+
+/// Verifies the signature shares of a replica over the debit and credit of a transfer.
+pub(crate) fn verify_validation(validated: &TransferValidated) -> bool {
+    let debit_share = &validated.replica_debit_sig;
+    let credit_share = &validated.replica_credit_sig;
+    verify_share(
+        &validated.replicas,
+        debit_share.index,
+        &debit_share.share,
+        &validated.signed_debit,
+    ) && verify_share(
+        &validated.replicas,
+        credit_share.index,
+        &credit_share.share,
+        &validated.signed_credit,
+    )
+}
+
+/// Verifies the signatures of the replicas over the debit and credit of a transfer.
+pub(crate) fn verify_agreement(proof: &TransferAgreementProof) -> bool {
+    let replicas_key = proof.debiting_replicas_keys.public_key();
+    let replicas_verify = |sig: &Signature, bytes: bincode::Result<Vec<u8>>| match (sig, bytes) {
+        (Signature::Bls(sig), Ok(bytes)) => replicas_key.verify(sig, bytes),
+        _ => false,
+    };
+
+    replicas_verify(&proof.debit_sig, bincode::serialize(&proof.signed_debit))
+        && replicas_verify(&proof.credit_sig, bincode::serialize(&proof.signed_credit))
+}
+
+// Replicas sign the bincode serialization of the signed debit and credit.
+fn verify_share<T: Serialize>(
+    replicas: &PublicKeySet,
+    index: usize,
+    share: &bls::SignatureShare,
+    signed: &T,
+) -> bool {
+    match bincode::serialize(signed) {
+        Ok(bytes) => replicas.public_key_share(index).verify(share, bytes),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::{Event, TransferProof},
+        Signed,
+    };
+    use anyhow::Result;
+    use crdts::Dot;
+    use sn_data_types::{Credit, Debit, Keypair, PublicKey, SignatureShare, Token};
+
+    fn validations(
+        replicas: &bls::SecretKeySet,
+        count: usize,
+        amount: u64,
+    ) -> Result<Vec<TransferValidated>> {
+        let sender = Keypair::new_ed25519(&mut rand::thread_rng());
+        let debit = Debit {
+            id: Dot::new(sender.public_key(), 0),
+            amount: Token::from_nano(amount),
+        };
+        let credit = Credit {
+            id: debit.credit_id()?,
+            amount: debit.amount,
+            recipient: PublicKey::from(bls::SecretKey::random().public_key()),
+            msg: String::new(),
+        };
+        let signed_debit = SignedDebit {
+            actor_signature: sender.sign(&bincode::serialize(&debit)?),
+            debit,
+        };
+        let signed_credit = SignedCredit {
+            actor_signature: sender.sign(&bincode::serialize(&credit)?),
+            credit,
+        };
+
+        let debit_bytes = bincode::serialize(&signed_debit)?;
+        let credit_bytes = bincode::serialize(&signed_credit)?;
+        Ok((0..count)
+            .map(|index| {
+                let key_share = replicas.secret_key_share(index);
+                TransferValidated {
+                    signed_debit: signed_debit.clone(),
+                    signed_credit: signed_credit.clone(),
+                    replica_debit_sig: SignatureShare {
+                        index,
+                        share: key_share.sign(&debit_bytes),
+                    },
+                    replica_credit_sig: SignatureShare {
+                        index,
+                        share: key_share.sign(&credit_bytes),
+                    },
+                    replicas: replicas.public_keys(),
+                }
+            })
+            .collect())
+    }
+
+    #[test]
+    fn accumulate_agreement_proof() -> Result<()> {
+        let replicas = bls::SecretKeySet::random(1, &mut rand::thread_rng());
+        let public_keys = replicas.public_keys();
+        let mut shares = validations(&replicas, 2, 10)?;
+        let mut accumulator = TransferValidationAccumulator::new();
+
+        let mut tampered = shares[1].clone();
+        tampered.signed_credit = validations(&replicas, 1, 10)?.remove(0).signed_credit;
+        assert!(matches!(
+            accumulator.add(shares.remove(0), &public_keys, 0),
+            Err(TransferValidationError::NotEnoughShares)
+        ));
+        assert!(matches!(
+            accumulator.add(tampered, &public_keys, 0),
+            Err(TransferValidationError::InvalidShare)
+        ));
+
+        let last = shares.remove(0);
+        let any_key = public_keys.public_key();
+        assert!(Event::TransferValidated {
+            event: last.clone()
+        }
+        .verify(&any_key));
+        let proof = accumulator
+            .add(last, &public_keys, 0)
+            .map_err(|err| anyhow::anyhow!("{}", err))?;
+        assert!(accumulator.is_empty());
+        assert!(Event::TransferAgreementReached {
+            proof: proof.clone()
+        }
+        .verify(&any_key));
+
+        // the section signs the proof once it registered the transfer
+        let section_key = bls::SecretKey::random();
+        let mut transfer_proof = TransferProof {
+            registered: Signed {
+                public_key: section_key.public_key(),
+                signature: section_key.sign(TransferProof::bytes_to_sign(&proof)?),
+            },
+            proof,
+        };
+        assert!(transfer_proof.verify());
+
+        transfer_proof.proof.credit_sig = transfer_proof.proof.debit_sig.clone();
+        assert!(!transfer_proof.verify());
+        Ok(())
+    }
+
+    #[test]
+    fn reject_unexpected_replicas() -> Result<()> {
+        let replicas = bls::SecretKeySet::random(1, &mut rand::thread_rng());
+        let other_replicas = bls::SecretKeySet::random(1, &mut rand::thread_rng());
+        let mut accumulator = TransferValidationAccumulator::new();
+
+        // Validations are checked against the expected replicas,
+        // not the ones they claim to come from.
+        for validated in validations(&other_replicas, 2, 10)? {
+            assert!(matches!(
+                accumulator.add(validated, &replicas.public_keys(), 0),
+                Err(TransferValidationError::UnexpectedReplicas)
+            ));
+        }
+        assert!(accumulator.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn faulty_validation_does_not_block_agreement() -> Result<()> {
+        let replicas = bls::SecretKeySet::random(2, &mut rand::thread_rng());
+        let public_keys = replicas.public_keys();
+        let mut accumulator = TransferValidationAccumulator::new();
+        let honest = validations(&replicas, 3, 10)?;
+
+        // A faulty replica validates another credit for the same debit first.
+        let mut faulty = honest[0].clone();
+        faulty.signed_credit.credit.msg = "faulty".to_string();
+        let key_share = replicas.secret_key_share(3usize);
+        faulty.replica_debit_sig = SignatureShare {
+            index: 3,
+            share: key_share.sign(&bincode::serialize(&faulty.signed_debit)?),
+        };
+        faulty.replica_credit_sig = SignatureShare {
+            index: 3,
+            share: key_share.sign(&bincode::serialize(&faulty.signed_credit)?),
+        };
+        assert!(matches!(
+            accumulator.add(faulty, &public_keys, 0),
+            Err(TransferValidationError::NotEnoughShares)
+        ));
+
+        let mut results: Vec<_> = honest
+            .into_iter()
+            .map(|validated| accumulator.add(validated, &public_keys, 0))
+            .collect();
+        let proof = results
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("no result"))?
+            .map_err(|err| anyhow::anyhow!("{}", err))?;
+        assert_eq!(proof.signed_credit.credit.msg, String::new());
+        assert!(accumulator.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn prune_stale_validations() -> Result<()> {
+        let replicas = bls::SecretKeySet::random(1, &mut rand::thread_rng());
+        let public_keys = replicas.public_keys();
+        let mut accumulator = TransferValidationAccumulator::with_ttl(10);
+        let mut shares = validations(&replicas, 2, 10)?;
+
+        assert!(matches!(
+            accumulator.add(shares.remove(0), &public_keys, 0),
+            Err(TransferValidationError::NotEnoughShares)
+        ));
+        assert_eq!(accumulator.len(), 1);
+
+        // The first validation expired, so a single one isn't enough anymore.
+        assert!(matches!(
+            accumulator.add(shares.remove(0), &public_keys, 10),
+            Err(TransferValidationError::NotEnoughShares)
+        ));
+        assert_eq!(accumulator.len(), 1);
+
+        accumulator.prune(20);
+        assert!(accumulator.is_empty());
+        Ok(())
+    }
+}
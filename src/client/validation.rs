@@ -0,0 +1,473 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{
+    BlobWrite, ClientMsg, Cmd, DataCmd, DataQuery, MapWrite, ProcessMsg, Query, RegisterRead,
+    RegisterWrite, SequenceRead, SequenceWrite,
+};
+use crate::limits::{check_batch, check_chunk_size, check_entries, check_page_len, MAX_BATCH};
+use sn_data_types::{
+    register::{Permissions, Policy},
+    Map, MapEntryActions, SequenceIndex,
+};
+use thiserror::Error;
+use xor_name::Prefix;
+
+/// Violation of an invariant which a message needs to uphold to be processed.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationError {
+    /// The serialised size of a Blob is above the maximum.
+    #[error("Blob of {size} bytes exceeds the maximum of {max} bytes")]
+    BlobTooLarge {
+        /// Serialised size of the Blob.
+        size: u64,
+        /// Maximum serialised size of a Blob.
+        max: u64,
+    },
+    /// A Map has more entries than the maximum.
+    #[error("{count} Map entries exceed the maximum of {max}")]
+    TooManyMapEntries {
+        /// Number of entries.
+        count: usize,
+        /// Maximum number of entries.
+        max: usize,
+    },
+    /// An edit doesn't contain any change.
+    #[error("Empty batch of changes")]
+    EmptyBatch,
+    /// An edit contains more changes than the maximum.
+    #[error("Batch of {count} changes exceeds the maximum of {max}")]
+    BatchTooLarge {
+        /// Number of changes.
+        count: usize,
+        /// Maximum number of changes.
+        max: usize,
+    },
+    /// A page of entries is empty, or longer than the maximum.
+    #[error("Page of {count} entries is empty or exceeds the maximum of {max}")]
+    InvalidPageLen {
+        /// Number of entries of the page.
+        count: u64,
+        /// Maximum number of entries of a page.
+        max: u64,
+    },
+    /// Public data can't be deleted.
+    #[error("Public data can't be deleted")]
+    DeletePublicData,
+    /// The kind (public or private) of a policy or permissions doesn't match the one of the data.
+    #[error("Policy kind doesn't match the kind of the data")]
+    PolicyKindMismatch,
+    /// The start of a range is after its end.
+    #[error("Invalid range: {start:?} is after {end:?}")]
+    InvalidRange {
+        /// Start of the range.
+        start: SequenceIndex,
+        /// End of the range.
+        end: SequenceIndex,
+    },
+    /// The Blobs of a batch don't all have the same owner.
+    #[error("Blobs of a batch have different owners")]
+    MixedBatchOwners,
+    /// A relayed op isn't signed by its author, or targets other data than the relay.
+    #[error("Relayed op is unsigned or targets other data")]
+    InvalidRelayedOp,
+    /// Some Blobs of a batch don't belong to the section the batch was routed to.
+    #[error("Blobs of a batch belong to other sections")]
+    BatchSpansSections,
+}
+
+impl ClientMsg {
+    /// Checks the message upholds the invariants required to process it,
+    /// so it can be rejected before touching any storage.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+            Self::Process(msg) => msg.validate(),
+            Self::ProcessingError(_) => Ok(()),
+            Self::SupportingInfo(info) => info.source_message().validate(),
+        }
+    }
+}
+
+impl ProcessMsg {
+    /// Checks the message upholds the invariants required to process it,
+    /// so it can be rejected before touching any storage.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+            Self::Cmd {
+                cmd: Cmd::Data { cmd, .. } | Cmd::QuotedData { cmd, .. },
+                ..
+            } => validate_data_cmd(cmd),
+            Self::Query {
+                query: Query::Data(query),
+                ..
+            } => validate_data_query(query),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the message as `validate` does, and that the data it writes belongs to
+    /// the section of the given prefix, which handles it.
+    pub fn validate_in_section(&self, prefix: &Prefix) -> Result<(), ValidationError> {
+        self.validate()?;
+        match self {
+            Self::Cmd {
+                cmd:
+                    Cmd::Data {
+                        cmd: DataCmd::Blob(write),
+                        ..
+                    }
+                    | Cmd::QuotedData {
+                        cmd: DataCmd::Blob(write),
+                        ..
+                    },
+                ..
+            } if !write.within(prefix) => Err(ValidationError::BatchSpansSections),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn validate_data_cmd(cmd: &DataCmd) -> Result<(), ValidationError> {
+    match cmd {
+        DataCmd::Blob(BlobWrite::New(blob)) => check_chunk_size(blob.serialised_size())?,
+        DataCmd::Blob(BlobWrite::Batch(blobs)) => {
+            check_batch(blobs.len())?;
+            for blob in blobs {
+                check_chunk_size(blob.serialised_size())?;
+            }
+            let owner = blobs[0].owner();
+            if blobs.iter().any(|blob| blob.owner() != owner) {
+                return Err(ValidationError::MixedBatchOwners);
+            }
+        }
+        DataCmd::Map(MapWrite::New(map)) => {
+            let count = match map {
+                Map::Seq(data) => data.entries().len(),
+                Map::Unseq(data) => data.entries().len(),
+            };
+            check_entries(count)?;
+        }
+        DataCmd::Map(MapWrite::Edit { changes, .. }) => {
+            let count = match changes {
+                MapEntryActions::Seq(actions) => actions.actions().len(),
+                MapEntryActions::Unseq(actions) => actions.actions().len(),
+            };
+            check_batch(count)?;
+        }
+        DataCmd::Sequence(SequenceWrite::RelayOps { address, ops, .. }) => {
+            check_batch(ops.len())?;
+            if ops
+                .iter()
+                .any(|op| op.signature.is_none() || op.address != *address)
+            {
+                return Err(ValidationError::InvalidRelayedOp);
+            }
+        }
+        DataCmd::Register(RegisterWrite::RelayOps { address, ops, .. }) => {
+            check_batch(ops.len())?;
+            if ops
+                .iter()
+                .any(|op| op.signature.is_none() || op.address != *address)
+            {
+                return Err(ValidationError::InvalidRelayedOp);
+            }
+        }
+        DataCmd::Register(RegisterWrite::Delete(address)) if address.is_public() => {
+            return Err(ValidationError::DeletePublicData)
+        }
+        DataCmd::Register(RegisterWrite::SetPolicy { address, policy }) => {
+            check_policy_kind(address.is_public(), matches!(policy, Policy::Public(_)))?
+        }
+        DataCmd::Register(RegisterWrite::SetUserPermissions {
+            address,
+            permissions,
+            ..
+        }) => check_policy_kind(
+            address.is_public(),
+            matches!(permissions, Permissions::Public(_)),
+        )?,
+        _ => (),
+    }
+    Ok(())
+}
+
+fn check_policy_kind(public_data: bool, public_policy: bool) -> Result<(), ValidationError> {
+    if public_data == public_policy {
+        Ok(())
+    } else {
+        Err(ValidationError::PolicyKindMismatch)
+    }
+}
+
+fn validate_data_query(query: &DataQuery) -> Result<(), ValidationError> {
+    match query {
+        DataQuery::Sequence(SequenceRead::GetRange {
+            range: (start, end),
+            ..
+        }) => {
+            // Indices counted from the end decrease towards the end of the sequence,
+            // while ranges mixing both kinds of indices can only be checked against it.
+            let reversed = match (start, end) {
+                (SequenceIndex::FromStart(start), SequenceIndex::FromStart(end)) => start > end,
+                (SequenceIndex::FromEnd(start), SequenceIndex::FromEnd(end)) => start < end,
+                _ => false,
+            };
+            if reversed {
+                return Err(ValidationError::InvalidRange {
+                    start: *start,
+                    end: *end,
+                });
+            }
+        }
+        DataQuery::Sequence(SequenceRead::GetPage { count, .. }) => check_page_len(*count)?,
+        DataQuery::Register(RegisterRead::GetEntries { hashes, .. }) => check_batch(hashes.len())?,
+        DataQuery::Register(RegisterRead::GetEntriesSince { frontier, .. })
+            if frontier.len() > MAX_BATCH =>
+        {
+            return Err(ValidationError::BatchTooLarge {
+                count: frontier.len(),
+                max: MAX_BATCH,
+            })
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::{ClientSigned, DataError},
+        MessageId,
+    };
+    use anyhow::Result;
+    use sn_data_types::register::{Address as RegisterAddress, EntryHash, PublicPolicy};
+    use sn_data_types::{
+        Blob, BlobAddress, Keypair, MapAddress, MapUnseqEntryActions, PrivateBlob, PublicBlob,
+        SequenceAddress,
+    };
+    use std::collections::BTreeSet;
+    use xor_name::XorName;
+
+    fn range_query(start: SequenceIndex, end: SequenceIndex) -> ClientMsg {
+        let keypair = Keypair::new_ed25519(&mut rand::thread_rng());
+        let address = SequenceAddress::Public {
+            name: XorName::random(),
+            tag: 0,
+        };
+        ClientMsg::Process(ProcessMsg::Query {
+            id: MessageId::new(),
+            query: Query::Data(DataQuery::Sequence(SequenceRead::GetRange {
+                address,
+                range: (start, end),
+            })),
+            client_signed: ClientSigned {
+                public_key: keypair.public_key(),
+                signature: keypair.sign(b"query"),
+            },
+            deadline: None,
+            anti_spam: None,
+            session: None,
+        })
+    }
+
+    #[test]
+    fn sequence_range_order() {
+        use SequenceIndex::*;
+        assert_eq!(range_query(FromStart(0), FromStart(3)).validate(), Ok(()));
+        assert_eq!(range_query(FromEnd(3), FromEnd(0)).validate(), Ok(()));
+        assert_eq!(range_query(FromStart(3), FromEnd(0)).validate(), Ok(()));
+        assert_eq!(
+            range_query(FromStart(3), FromStart(1)).validate(),
+            Err(ValidationError::InvalidRange {
+                start: FromStart(3),
+                end: FromStart(1),
+            })
+        );
+        assert!(range_query(FromEnd(0), FromEnd(2)).validate().is_err());
+    }
+
+    #[test]
+    fn sequence_page_len() {
+        let page = |count| {
+            validate_data_query(&DataQuery::Sequence(SequenceRead::GetPage {
+                address: SequenceAddress::Public {
+                    name: XorName::random(),
+                    tag: 0,
+                },
+                start: 10,
+                count,
+            }))
+        };
+        assert_eq!(page(1), Ok(()));
+        assert_eq!(page(crate::limits::MAX_PAGE_LEN), Ok(()));
+        assert!(page(0).is_err());
+        assert!(page(crate::limits::MAX_PAGE_LEN + 1).is_err());
+    }
+
+    #[test]
+    fn register_entries_batch() {
+        let entries = |hashes: BTreeSet<EntryHash>| {
+            validate_data_query(&DataQuery::Register(RegisterRead::GetEntries {
+                address: RegisterAddress::Public {
+                    name: XorName::random(),
+                    tag: 0,
+                },
+                hashes,
+            }))
+        };
+        assert_eq!(entries(BTreeSet::new()), Err(ValidationError::EmptyBatch));
+        assert_eq!(entries(vec![[0; 32]].into_iter().collect()), Ok(()));
+
+        let since = |frontier: BTreeSet<EntryHash>| {
+            validate_data_query(&DataQuery::Register(RegisterRead::GetEntriesSince {
+                address: RegisterAddress::Public {
+                    name: XorName::random(),
+                    tag: 0,
+                },
+                frontier,
+            }))
+        };
+        assert_eq!(since(BTreeSet::new()), Ok(()));
+        assert!(since((0..=MAX_BATCH as u8).map(|i| [i; 32]).collect()).is_err());
+    }
+
+    #[test]
+    fn relayed_register_ops() {
+        let relay = RegisterWrite::RelayOps {
+            address: RegisterAddress::Public {
+                name: XorName::random(),
+                tag: 0,
+            },
+            ops: vec![],
+            frontier: vec![[1; 32]].into_iter().collect(),
+        };
+        assert_eq!(relay.check_causally_ready(|hash| *hash == [1; 32]), Ok(()));
+        assert_eq!(
+            relay.check_causally_ready(|_| false),
+            Err(crate::client::Error::Data(DataError::OpNotCausallyReady))
+        );
+        assert_eq!(
+            validate_data_cmd(&DataCmd::Register(relay)),
+            Err(ValidationError::EmptyBatch)
+        );
+    }
+
+    #[test]
+    fn blob_batch() {
+        let batch = |blobs: Vec<Blob>| validate_data_cmd(&DataCmd::Blob(BlobWrite::Batch(blobs)));
+        let private = |owner| Blob::Private(PrivateBlob::new(vec![1, 2, 3], owner));
+        let owner = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let other = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+
+        assert_eq!(batch(vec![]), Err(ValidationError::EmptyBatch));
+        assert_eq!(batch(vec![private(owner), private(owner)]), Ok(()));
+        assert_eq!(
+            batch(vec![private(owner), private(other)]),
+            Err(ValidationError::MixedBatchOwners)
+        );
+        assert!(matches!(
+            batch(vec![
+                private(owner),
+                Blob::Public(PublicBlob::new(vec![
+                    0;
+                    crate::limits::MAX_CHUNK_SIZE as usize
+                ]))
+            ]),
+            Err(ValidationError::BlobTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn blob_batch_within_section() {
+        let blob = |byte| Blob::Public(PublicBlob::new(vec![byte]));
+        let blobs: Vec<_> = (0..8).map(blob).collect();
+        let prefix = Prefix::default().pushed(blobs[0].name().bit(0));
+        let (inside, outside): (Vec<_>, Vec<_>) = blobs
+            .into_iter()
+            .partition(|blob| prefix.matches(blob.name()));
+
+        assert!(BlobWrite::Batch(inside.clone()).within(&prefix));
+        assert!(!outside.is_empty());
+        assert!(!BlobWrite::Batch(vec![inside[0].clone(), outside[0].clone()]).within(&prefix));
+    }
+
+    #[test]
+    fn data_cmds() -> Result<()> {
+        let blob = Blob::Public(PublicBlob::new(vec![
+            0;
+            crate::limits::MAX_CHUNK_SIZE as usize
+        ]));
+        assert!(matches!(
+            validate_data_cmd(&DataCmd::Blob(BlobWrite::New(blob))),
+            Err(ValidationError::BlobTooLarge { .. })
+        ));
+
+        let small = Blob::Public(PublicBlob::new(vec![1, 2, 3]));
+        assert_eq!(
+            validate_data_cmd(&DataCmd::Blob(BlobWrite::New(small))),
+            Ok(())
+        );
+        assert_eq!(
+            validate_data_cmd(&DataCmd::Blob(BlobWrite::DeletePrivate(
+                BlobAddress::Private(XorName::random())
+            ))),
+            Ok(())
+        );
+
+        let edit = DataCmd::Map(MapWrite::Edit {
+            address: MapAddress::Unseq {
+                name: XorName::random(),
+                tag: 0,
+            },
+            changes: MapEntryActions::Unseq(MapUnseqEntryActions::new()),
+        });
+        assert_eq!(validate_data_cmd(&edit), Err(ValidationError::EmptyBatch));
+
+        let public = RegisterAddress::Public {
+            name: XorName::random(),
+            tag: 0,
+        };
+        let private = RegisterAddress::Private {
+            name: XorName::random(),
+            tag: 0,
+        };
+        assert_eq!(
+            validate_data_cmd(&DataCmd::Register(RegisterWrite::Delete(public))),
+            Err(ValidationError::DeletePublicData)
+        );
+        assert_eq!(
+            validate_data_cmd(&DataCmd::Register(RegisterWrite::Delete(private))),
+            Ok(())
+        );
+
+        let owner = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let public_policy = Policy::Public(PublicPolicy {
+            owner,
+            permissions: Default::default(),
+        });
+        assert_eq!(
+            validate_data_cmd(&DataCmd::Register(RegisterWrite::SetPolicy {
+                address: public,
+                policy: public_policy.clone(),
+            })),
+            Ok(())
+        );
+        assert_eq!(
+            validate_data_cmd(&DataCmd::Register(RegisterWrite::SetPolicy {
+                address: private,
+                policy: public_policy,
+            })),
+            Err(ValidationError::PolicyKindMismatch)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::MessageId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Default maximum number of message ids remembered by a `DedupCache`.
+pub const DEFAULT_DEDUP_CAPACITY: usize = 10_000;
+
+/// Default duration, in milliseconds, for which a `DedupCache` remembers a message id.
+pub const DEFAULT_DEDUP_TTL: u64 = 5 * 60 * 1000;
+
+/// Cache of the ids of the messages recently seen, so that messages received several times,
+/// e.g. when re-broadcast, are only handled once.
+///
+/// Ids are forgotten once their time to live has elapsed since they were first seen, or,
+/// when the cache is full, oldest first. Times are given in milliseconds since the UNIX epoch
+/// by the caller, so the cache can be persisted across restarts.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "PersistedDedupCache", into = "PersistedDedupCache")]
+pub struct DedupCache {
+    capacity: usize,
+    ttl: u64,
+    seen: BTreeMap<MessageId, u64>,
+    // The same entries as `seen`, ordered by the time they were seen at.
+    by_time: BTreeSet<(u64, MessageId)>,
+}
+
+impl Default for DedupCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEDUP_CAPACITY, DEFAULT_DEDUP_TTL)
+    }
+}
+
+impl DedupCache {
+    /// Creates an empty cache remembering up to `capacity` ids, for `ttl` milliseconds each.
+    pub fn new(capacity: usize, ttl: u64) -> Self {
+        Self {
+            capacity,
+            ttl,
+            seen: BTreeMap::new(),
+            by_time: BTreeSet::new(),
+        }
+    }
+
+    /// Records the id of a message seen at `now`, returning true if it wasn't seen
+    /// before within the time to live, i.e. the message is to be handled.
+    pub fn insert(&mut self, id: MessageId, now: u64) -> bool {
+        self.prune(now);
+        if self.seen.contains_key(&id) {
+            return false;
+        }
+
+        while self.seen.len() >= self.capacity.max(1) {
+            match self.by_time.iter().next().copied() {
+                Some(oldest) => self.remove(oldest),
+                None => break,
+            }
+        }
+        let _ = self.seen.insert(id, now);
+        let _ = self.by_time.insert((now, id));
+        true
+    }
+
+    /// Returns true if the message with the given id was seen within the time to live.
+    pub fn contains(&self, id: &MessageId, now: u64) -> bool {
+        matches!(self.seen.get(id), Some(seen_at) if !self.is_expired(*seen_at, now))
+    }
+
+    /// Forgets the ids whose time to live elapsed at `now`.
+    pub fn prune(&mut self, now: u64) {
+        while let Some(oldest) = self.by_time.iter().next().copied() {
+            if !self.is_expired(oldest.0, now) {
+                break;
+            }
+            self.remove(oldest);
+        }
+    }
+
+    /// Number of ids currently remembered, including expired ones not pruned yet.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Returns true if no id is remembered.
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    fn is_expired(&self, seen_at: u64, now: u64) -> bool {
+        now.saturating_sub(seen_at) >= self.ttl
+    }
+
+    fn remove(&mut self, (seen_at, id): (u64, MessageId)) {
+        let _ = self.by_time.remove(&(seen_at, id));
+        let _ = self.seen.remove(&id);
+    }
+}
+
+// Serialized form of a `DedupCache`, from which the ordering by time is rebuilt.
+#[derive(Serialize, Deserialize)]
+struct PersistedDedupCache {
+    capacity: usize,
+    ttl: u64,
+    seen: BTreeMap<MessageId, u64>,
+}
+
+impl From<PersistedDedupCache> for DedupCache {
+    fn from(persisted: PersistedDedupCache) -> Self {
+        let by_time = persisted
+            .seen
+            .iter()
+            .map(|(id, seen_at)| (*seen_at, *id))
+            .collect();
+        Self {
+            capacity: persisted.capacity,
+            ttl: persisted.ttl,
+            seen: persisted.seen,
+            by_time,
+        }
+    }
+}
+
+impl From<DedupCache> for PersistedDedupCache {
+    fn from(cache: DedupCache) -> Self {
+        Self {
+            capacity: cache.capacity,
+            ttl: cache.ttl,
+            seen: cache.seen,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn dedup_with_ttl_and_capacity() -> Result<()> {
+        let mut cache = DedupCache::new(2, 100);
+        let ids: Vec<_> = (0..3).map(|_| MessageId::new()).collect();
+
+        assert!(cache.insert(ids[0], 0));
+        assert!(!cache.insert(ids[0], 50));
+        assert!(cache.contains(&ids[0], 99));
+        assert!(!cache.contains(&ids[0], 100));
+        assert!(cache.insert(ids[0], 100));
+
+        // the oldest id is evicted once full
+        assert!(cache.insert(ids[1], 110));
+        assert!(cache.insert(ids[2], 120));
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(&ids[0], 120));
+        assert!(cache.contains(&ids[1], 120));
+
+        // the cache survives a restart
+        let restored: DedupCache = bincode::deserialize(&bincode::serialize(&cache)?)?;
+        assert_eq!(restored, cache);
+        assert!(!restored.clone().insert(ids[2], 130));
+
+        cache.prune(220);
+        assert!(cache.is_empty());
+        Ok(())
+    }
+}
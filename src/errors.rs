@@ -30,4 +30,30 @@ pub enum Error {
     /// Message is of an unsupported kind.
     #[error("Unsupported messaging kind: {0}")]
     UnsupportedMessageKind(u8),
+    /// Message payload is larger than the maximum size accepted.
+    #[error("Message payload of {size} bytes exceeds the maximum size of {max} bytes")]
+    MessageTooLarge { size: usize, max: usize },
+    /// Message payload exceeds a limit enforced when deserialising it.
+    #[error("Message payload exceeds a deserialisation limit: {0}")]
+    LimitExceeded(String),
+    /// Signed payload was built with an unsupported layout version.
+    #[error("Unsupported signable payload version: {0}")]
+    UnsupportedSignableVersion(u16),
+    /// A tag of a flat message is out of the range of its kind.
+    #[error("Invalid {kind} tag: {tag}")]
+    InvalidTag { kind: &'static str, tag: u8 },
+    /// Message variant has no representation in the requested format.
+    #[error("Unsupported message variant: {0}")]
+    UnsupportedVariant(String),
+    /// Encrypted message can't be decrypted with the given key.
+    #[error("Failed to decrypt message")]
+    FailedToDecrypt,
+    /// Decrypted message doesn't match the id or destination left in clear in the
+    /// encrypted one, e.g. as those were rewritten in transit.
+    #[error("Decrypted message doesn't match the clear-text {0} of the encrypted one")]
+    EncryptedMismatch(&'static str),
+    /// Message payload doesn't match the checksum in its header, e.g. as it was
+    /// corrupted in transit.
+    #[error("Message payload checksum {actual:#010x} doesn't match {expected:#010x} in header")]
+    ChecksumMismatch { expected: u32, actual: u32 },
 }
@@ -0,0 +1,781 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! C-compatible representation of the most common client messages: Blob reads and writes,
+//! and their query responses, for bindings in other languages to exchange them through FFI
+//! rather than reimplementing their Msgpack encoding.
+//!
+//! All the types are `#[repr(C)]`, fixed size, with variable length data held in `FlatBytes`.
+//! Those own their buffer, allocated on the Rust side, which must be handed back to Rust to be
+//! freed, e.g. with `sn_flat_query_response_free`. The kind of each type is a `u8` tag, as C
+//! can hold any value in it, and is checked against the matching `Flat*Kind` when converted.
+//!
+//! The `sn_flat_*` functions are exported for C, and return one of the `FLAT_*` statuses.
+
+use crate::{
+    client::{BlobRead, BlobWrite, DataError, Error as ClientError, QueryResponse, TransferError},
+    DeserialisationLimits, Error, Result,
+};
+use sn_data_types::{Blob, BlobAddress, PrivateBlob, PublicBlob, PublicKey};
+use std::{convert::TryFrom, mem::ManuallyDrop, ptr, slice};
+use threshold_crypto::PK_SIZE;
+use xor_name::{XorName, XOR_NAME_LEN};
+
+/// Buffer of bytes allocated by Rust.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FlatBytes {
+    /// Pointer to the first byte, null if empty. The allocation belongs to Rust, so it must
+    /// only be freed or reallocated through this module.
+    pub ptr: *mut u8,
+    /// Number of bytes.
+    pub len: usize,
+    /// Capacity of the allocation, needed to free it.
+    pub capacity: usize,
+}
+
+impl FlatBytes {
+    /// An empty buffer, owning no allocation.
+    pub fn empty() -> Self {
+        Self {
+            ptr: ptr::null_mut(),
+            len: 0,
+            capacity: 0,
+        }
+    }
+
+    /// Takes ownership of the bytes, which are only freed by `into_vec`.
+    pub fn from_vec(bytes: Vec<u8>) -> Self {
+        if bytes.capacity() == 0 {
+            return Self::empty();
+        }
+        let mut bytes = ManuallyDrop::new(bytes);
+        Self {
+            ptr: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            capacity: bytes.capacity(),
+        }
+    }
+
+    /// Gives the ownership of the bytes back to Rust.
+    ///
+    /// # Safety
+    ///
+    /// The buffer must have been built by `from_vec`, unmodified but for the content of
+    /// its bytes, and not be used afterwards.
+    pub unsafe fn into_vec(self) -> Vec<u8> {
+        if self.ptr.is_null() {
+            Vec::new()
+        } else {
+            Vec::from_raw_parts(self.ptr, self.len, self.capacity)
+        }
+    }
+}
+
+/// The call succeeded.
+pub const FLAT_OK: i32 = 0;
+/// The flat message is invalid, e.g. one of its tags is out of range, or a byte
+/// string couldn't be decoded.
+pub const FLAT_INVALID: i32 = 1;
+/// The message has no flat representation.
+pub const FLAT_UNSUPPORTED: i32 = 2;
+
+macro_rules! flat_kind {
+    ($(#[$meta:meta])* $name:ident { $($(#[$variant_meta:meta])* $variant:ident = $tag:expr,)+ }) => {
+        $(#[$meta])*
+        #[repr(u8)]
+        #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant = $tag,)+
+        }
+
+        impl From<$name> for u8 {
+            fn from(kind: $name) -> Self {
+                kind as u8
+            }
+        }
+
+        impl TryFrom<u8> for $name {
+            type Error = Error;
+
+            fn try_from(tag: u8) -> Result<Self> {
+                match tag {
+                    $($tag => Ok(Self::$variant),)+
+                    tag => Err(Error::InvalidTag {
+                        kind: stringify!($name),
+                        tag,
+                    }),
+                }
+            }
+        }
+    };
+}
+
+flat_kind!(
+    /// Kind of a Blob.
+    FlatBlobKind {
+        Public = 0,
+        Private = 1,
+    }
+);
+
+/// Address of a Blob, of the given `FlatBlobKind`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FlatBlobAddress {
+    /// Tag of the `FlatBlobKind` of the Blob.
+    pub kind: u8,
+    /// Name of the Blob. Held inline, so the address owns no allocation.
+    pub name: [u8; XOR_NAME_LEN],
+}
+
+impl From<BlobAddress> for FlatBlobAddress {
+    fn from(address: BlobAddress) -> Self {
+        let (kind, name) = match address {
+            BlobAddress::Public(name) => (FlatBlobKind::Public, name),
+            BlobAddress::Private(name) => (FlatBlobKind::Private, name),
+        };
+        Self {
+            kind: kind.into(),
+            name: name.0,
+        }
+    }
+}
+
+impl TryFrom<FlatBlobAddress> for BlobAddress {
+    type Error = Error;
+
+    fn try_from(address: FlatBlobAddress) -> Result<Self> {
+        let name = XorName(address.name);
+        Ok(match FlatBlobKind::try_from(address.kind)? {
+            FlatBlobKind::Public => Self::Public(name),
+            FlatBlobKind::Private => Self::Private(name),
+        })
+    }
+}
+
+flat_kind!(
+    /// Kind of a public key.
+    FlatPublicKeyKind {
+        Ed25519 = 0,
+        Bls = 1,
+        BlsShare = 2,
+    }
+);
+
+/// Public key of the given `FlatPublicKeyKind`, in its compressed form. Ed25519 keys
+/// only use the first 32 bytes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FlatPublicKey {
+    /// Tag of the `FlatPublicKeyKind` of the key.
+    pub kind: u8,
+    /// Compressed bytes of the key, zero padded for Ed25519 keys. Held inline, so the key
+    /// owns no allocation.
+    pub bytes: [u8; PK_SIZE],
+}
+
+impl From<&PublicKey> for FlatPublicKey {
+    fn from(key: &PublicKey) -> Self {
+        let mut bytes = [0; PK_SIZE];
+        let kind = match key {
+            PublicKey::Ed25519(key) => {
+                bytes[..ed25519_dalek::PUBLIC_KEY_LENGTH].copy_from_slice(key.as_bytes());
+                FlatPublicKeyKind::Ed25519
+            }
+            PublicKey::Bls(key) => {
+                bytes = key.to_bytes();
+                FlatPublicKeyKind::Bls
+            }
+            PublicKey::BlsShare(key) => {
+                bytes = key.to_bytes();
+                FlatPublicKeyKind::BlsShare
+            }
+        };
+        Self {
+            kind: kind.into(),
+            bytes,
+        }
+    }
+}
+
+impl TryFrom<&FlatPublicKey> for PublicKey {
+    type Error = Error;
+
+    fn try_from(key: &FlatPublicKey) -> Result<Self> {
+        let invalid = || Error::FailedToParse("flat public key".to_string());
+        Ok(match FlatPublicKeyKind::try_from(key.kind)? {
+            FlatPublicKeyKind::Ed25519 => PublicKey::Ed25519(
+                ed25519_dalek::PublicKey::from_bytes(
+                    &key.bytes[..ed25519_dalek::PUBLIC_KEY_LENGTH],
+                )
+                .map_err(|_| invalid())?,
+            ),
+            FlatPublicKeyKind::Bls => PublicKey::Bls(
+                threshold_crypto::PublicKey::from_bytes(key.bytes).map_err(|_| invalid())?,
+            ),
+            FlatPublicKeyKind::BlsShare => PublicKey::BlsShare(
+                threshold_crypto::PublicKeyShare::from_bytes(key.bytes).map_err(|_| invalid())?,
+            ),
+        })
+    }
+}
+
+/// A Blob, i.e. a chunk, of the given `FlatBlobKind`. The owner is only set for private ones.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FlatBlob {
+    /// Tag of the `FlatBlobKind` of the Blob.
+    pub kind: u8,
+    /// Content of the Blob. The Blob owns the buffer, which is freed along with the message
+    /// holding it: by `FlatBlob::into_blob` or the encoding functions when handed to Rust,
+    /// or by `sn_flat_query_response_free` when returned by Rust.
+    pub value: FlatBytes,
+    /// Owner of a private Blob. Ignored for public ones, for which it is zeroed by Rust.
+    pub owner: FlatPublicKey,
+}
+
+impl FlatBlob {
+    fn empty() -> Self {
+        Self {
+            kind: FlatBlobKind::Public.into(),
+            value: FlatBytes::empty(),
+            owner: FlatPublicKey {
+                kind: FlatPublicKeyKind::Ed25519.into(),
+                bytes: [0; PK_SIZE],
+            },
+        }
+    }
+
+    /// Converts back into a Blob, taking ownership of its value.
+    ///
+    /// # Safety
+    ///
+    /// Same as `FlatBytes::into_vec`, for its value.
+    pub unsafe fn into_blob(self) -> Result<Blob> {
+        let value = self.value.into_vec();
+        Ok(match FlatBlobKind::try_from(self.kind)? {
+            FlatBlobKind::Public => Blob::Public(PublicBlob::new(value)),
+            FlatBlobKind::Private => {
+                Blob::Private(PrivateBlob::new(value, PublicKey::try_from(&self.owner)?))
+            }
+        })
+    }
+}
+
+impl From<Blob> for FlatBlob {
+    fn from(blob: Blob) -> Self {
+        let owner = blob.owner().map(FlatPublicKey::from);
+        let kind = if blob.is_public() {
+            FlatBlobKind::Public
+        } else {
+            FlatBlobKind::Private
+        };
+        Self {
+            kind: kind.into(),
+            value: FlatBytes::from_vec(blob.value().clone()),
+            owner: owner.unwrap_or_else(|| Self::empty().owner),
+        }
+    }
+}
+
+flat_kind!(
+    /// Kind of a Blob read.
+    FlatBlobReadKind {
+        Get = 0,
+        Exists = 1,
+    }
+);
+
+/// A `BlobRead`, of the given `FlatBlobReadKind`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FlatBlobRead {
+    /// Tag of the `FlatBlobReadKind` of the read.
+    pub kind: u8,
+    /// Address of the Blob to read. The read owns no allocation, so it can be copied freely.
+    pub address: FlatBlobAddress,
+}
+
+impl From<&BlobRead> for FlatBlobRead {
+    fn from(read: &BlobRead) -> Self {
+        let (kind, address) = match read {
+            BlobRead::Get(address) => (FlatBlobReadKind::Get, address),
+            BlobRead::Exists(address) => (FlatBlobReadKind::Exists, address),
+        };
+        Self {
+            kind: kind.into(),
+            address: (*address).into(),
+        }
+    }
+}
+
+impl TryFrom<FlatBlobRead> for BlobRead {
+    type Error = Error;
+
+    fn try_from(read: FlatBlobRead) -> Result<Self> {
+        let address = BlobAddress::try_from(read.address)?;
+        Ok(match FlatBlobReadKind::try_from(read.kind)? {
+            FlatBlobReadKind::Get => Self::Get(address),
+            FlatBlobReadKind::Exists => Self::Exists(address),
+        })
+    }
+}
+
+flat_kind!(
+    /// Kind of a Blob write. Batches aren't represented, each of their Blobs being
+    /// writable on its own.
+    FlatBlobWriteKind {
+        New = 0,
+        DeletePrivate = 1,
+    }
+);
+
+/// A `BlobWrite`, of the given `FlatBlobWriteKind`. The Blob is only set for `New`, and
+/// the address for `DeletePrivate`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FlatBlobWrite {
+    /// Tag of the `FlatBlobWriteKind` of the write.
+    pub kind: u8,
+    /// Blob to store for `New`, empty otherwise. The write owns the value of the Blob, which
+    /// is freed by `FlatBlobWrite::into_blob_write`, whatever the kind of the write.
+    pub blob: FlatBlob,
+    /// Address of the Blob to delete for `DeletePrivate`. Set to the address of the Blob
+    /// for `New` by Rust, and ignored when converted back.
+    pub address: FlatBlobAddress,
+}
+
+impl FlatBlobWrite {
+    /// Converts back into a `BlobWrite`, taking ownership of the value of its Blob,
+    /// which is freed if the write is invalid.
+    ///
+    /// # Safety
+    ///
+    /// Same as `FlatBytes::into_vec`, for the value of its Blob.
+    pub unsafe fn into_blob_write(self) -> Result<BlobWrite> {
+        match FlatBlobWriteKind::try_from(self.kind) {
+            Ok(FlatBlobWriteKind::New) => Ok(BlobWrite::New(self.blob.into_blob()?)),
+            Ok(FlatBlobWriteKind::DeletePrivate) => {
+                drop(self.blob.value.into_vec());
+                Ok(BlobWrite::DeletePrivate(BlobAddress::try_from(
+                    self.address,
+                )?))
+            }
+            Err(error) => {
+                drop(self.blob.value.into_vec());
+                Err(error)
+            }
+        }
+    }
+}
+
+impl TryFrom<BlobWrite> for FlatBlobWrite {
+    type Error = Error;
+
+    fn try_from(write: BlobWrite) -> Result<Self> {
+        let address = write.address().into();
+        match write {
+            BlobWrite::New(blob) => Ok(Self {
+                kind: FlatBlobWriteKind::New.into(),
+                blob: blob.into(),
+                address,
+            }),
+            BlobWrite::DeletePrivate(_) => Ok(Self {
+                kind: FlatBlobWriteKind::DeletePrivate.into(),
+                blob: FlatBlob::empty(),
+                address,
+            }),
+            BlobWrite::Batch(_) => Err(Error::UnsupportedVariant(
+                "a Blob batch as a flat Blob write".to_string(),
+            )),
+        }
+    }
+}
+
+flat_kind!(
+    /// Kind of the error of a response, the most common ones being told apart.
+    FlatErrorKind {
+        /// The response isn't an error.
+        None = 0,
+        DataNotFound = 1,
+        DataExists = 2,
+        AccessDenied = 3,
+        InsufficientPayment = 4,
+        Other = 255,
+    }
+);
+
+/// Error of a response, of the given `FlatErrorKind`, with its message in UTF-8.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FlatError {
+    /// Tag of the `FlatErrorKind` of the error, `None` when there is no error.
+    pub kind: u8,
+    /// Description of the error, in UTF-8 and without a trailing nul, empty when there is no
+    /// error. The error owns the buffer, which is freed along with the response holding it.
+    pub message: FlatBytes,
+}
+
+impl FlatError {
+    fn none() -> Self {
+        Self {
+            kind: FlatErrorKind::None.into(),
+            message: FlatBytes::empty(),
+        }
+    }
+}
+
+impl From<ClientError> for FlatError {
+    fn from(error: ClientError) -> Self {
+        let kind = match error {
+            ClientError::Data(DataError::DataNotFound(_)) => FlatErrorKind::DataNotFound,
+            ClientError::Data(DataError::DataExists) => FlatErrorKind::DataExists,
+            ClientError::Data(DataError::AccessDenied(_))
+            | ClientError::Data(DataError::AccessDeniedDetailed { .. }) => {
+                FlatErrorKind::AccessDenied
+            }
+            ClientError::Transfer(TransferError::InsufficientPayment) => {
+                FlatErrorKind::InsufficientPayment
+            }
+            _ => FlatErrorKind::Other,
+        };
+        Self {
+            kind: kind.into(),
+            message: FlatBytes::from_vec(error.to_string().into_bytes()),
+        }
+    }
+}
+
+flat_kind!(
+    /// Kind of a query response.
+    FlatQueryResponseKind {
+        GetBlob = 0,
+        BlobExists = 1,
+    }
+);
+
+/// A `QueryResponse` to a Blob read, of the given `FlatQueryResponseKind`. On error, only
+/// the error is set, otherwise the Blob for `GetBlob` and `exists` for `BlobExists`.
+/// It owns the buffers of its error and its Blob, which must be freed together with
+/// `sn_flat_query_response_free`, once and only once.
+#[repr(C)]
+#[derive(Debug)]
+pub struct FlatQueryResponse {
+    /// Tag of the `FlatQueryResponseKind` of the response.
+    pub kind: u8,
+    /// Error of the response, of kind `None` on success.
+    pub error: FlatError,
+    /// Blob read by a successful `GetBlob`, empty otherwise.
+    pub blob: FlatBlob,
+    /// Whether the Blob exists, for a successful `BlobExists`.
+    pub exists: bool,
+}
+
+impl FlatQueryResponse {
+    /// Frees the buffers of the response.
+    ///
+    /// # Safety
+    ///
+    /// Same as `FlatBytes::into_vec`, for all the buffers of the response.
+    pub unsafe fn free(self) {
+        drop(self.error.message.into_vec());
+        drop(self.blob.value.into_vec());
+    }
+}
+
+impl TryFrom<QueryResponse> for FlatQueryResponse {
+    type Error = Error;
+
+    fn try_from(response: QueryResponse) -> Result<Self> {
+        let (kind, result) = match response {
+            QueryResponse::GetBlob(result) => (
+                FlatQueryResponseKind::GetBlob,
+                result.map(|blob| (blob.into(), false)),
+            ),
+            QueryResponse::BlobExists(result) => (
+                FlatQueryResponseKind::BlobExists,
+                result.map(|exists| (FlatBlob::empty(), exists)),
+            ),
+            other => {
+                return Err(Error::UnsupportedVariant(format!(
+                    "a {} response as a flat query response",
+                    other.name()
+                )))
+            }
+        };
+        let kind = kind.into();
+        Ok(match result {
+            Ok((blob, exists)) => Self {
+                kind,
+                error: FlatError::none(),
+                blob,
+                exists,
+            },
+            Err(error) => Self {
+                kind,
+                error: error.into(),
+                blob: FlatBlob::empty(),
+                exists: false,
+            },
+        })
+    }
+}
+
+fn status(error: &Error) -> i32 {
+    match error {
+        Error::UnsupportedVariant(_) => FLAT_UNSUPPORTED,
+        _ => FLAT_INVALID,
+    }
+}
+
+unsafe fn encode<T: serde::Serialize>(value: Result<T>, out: *mut FlatBytes) -> i32 {
+    let value = match value {
+        Ok(value) => value,
+        Err(error) => return status(&error),
+    };
+    match rmp_serde::to_vec_named(&value) {
+        Ok(bytes) if !out.is_null() => {
+            out.write(FlatBytes::from_vec(bytes));
+            FLAT_OK
+        }
+        _ => FLAT_INVALID,
+    }
+}
+
+/// Copies `len` bytes from `ptr` into a buffer allocated by Rust, e.g. for the value of
+/// a Blob to write. It must be freed with `sn_flat_bytes_free`, unless its ownership is
+/// handed back to Rust with the message holding it.
+///
+/// # Safety
+///
+/// `ptr` must be null or valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sn_flat_bytes_new(ptr: *const u8, len: usize) -> FlatBytes {
+    if ptr.is_null() || len == 0 {
+        return FlatBytes::empty();
+    }
+    FlatBytes::from_vec(slice::from_raw_parts(ptr, len).to_vec())
+}
+
+/// Frees a buffer allocated by Rust.
+///
+/// # Safety
+///
+/// Same as `FlatBytes::into_vec`.
+#[no_mangle]
+pub unsafe extern "C" fn sn_flat_bytes_free(bytes: FlatBytes) {
+    drop(bytes.into_vec())
+}
+
+/// Writes the Msgpack encoding of the read, as held by a `DataQuery::Blob`, to `out`,
+/// which must then be freed with `sn_flat_bytes_free`.
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes. It is left untouched on failure.
+#[no_mangle]
+pub unsafe extern "C" fn sn_flat_blob_read_encode(read: FlatBlobRead, out: *mut FlatBytes) -> i32 {
+    encode(BlobRead::try_from(read), out)
+}
+
+/// Writes the Msgpack encoding of the write, as held by a `DataCmd::Blob`, to `out`,
+/// which must then be freed with `sn_flat_bytes_free`. Takes ownership of the value of
+/// the Blob of the write, which is freed in any case.
+///
+/// # Safety
+///
+/// Same as `FlatBlobWrite::into_blob_write`, and `out` must be null or valid for writes.
+/// It is left untouched on failure.
+#[no_mangle]
+pub unsafe extern "C" fn sn_flat_blob_write_encode(
+    write: FlatBlobWrite,
+    out: *mut FlatBytes,
+) -> i32 {
+    encode(write.into_blob_write(), out)
+}
+
+/// Decodes the Msgpack encoding of a query response, as held by a
+/// `ProcessMsg::QueryResponse`, into `out`, which must then be freed with
+/// `sn_flat_query_response_free`. The bytes are checked against the default
+/// `DeserialisationLimits` beforehand, as those of a `WireMsg` are.
+///
+/// # Safety
+///
+/// `ptr` must be null or valid for reads of `len` bytes, and `out` null or valid for
+/// writes. It is left untouched on failure.
+#[no_mangle]
+pub unsafe extern "C" fn sn_flat_query_response_decode(
+    ptr: *const u8,
+    len: usize,
+    out: *mut FlatQueryResponse,
+) -> i32 {
+    if ptr.is_null() || out.is_null() {
+        return FLAT_INVALID;
+    }
+    let response = match DeserialisationLimits::default()
+        .decode::<QueryResponse>(slice::from_raw_parts(ptr, len), "query response")
+    {
+        Ok(response) => response,
+        Err(_) => return FLAT_INVALID,
+    };
+    match FlatQueryResponse::try_from(response) {
+        Ok(response) => {
+            out.write(response);
+            FLAT_OK
+        }
+        Err(error) => status(&error),
+    }
+}
+
+/// Frees the buffers of a query response decoded by Rust.
+///
+/// # Safety
+///
+/// Same as `FlatQueryResponse::free`.
+#[no_mangle]
+pub unsafe extern "C" fn sn_flat_query_response_free(response: FlatQueryResponse) {
+    response.free()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Result};
+    use sn_data_types::DataAddress;
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn blob_read_round_trip() -> Result<()> {
+        let read = BlobRead::Exists(BlobAddress::Private(XorName::random()));
+        assert_eq!(BlobRead::try_from(FlatBlobRead::from(&read))?, read);
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_range_tags() {
+        let mut read = FlatBlobRead::from(&BlobRead::Get(BlobAddress::Public(XorName::random())));
+        read.address.kind = 7;
+        assert!(matches!(
+            BlobRead::try_from(read),
+            Err(Error::InvalidTag {
+                kind: "FlatBlobKind",
+                tag: 7
+            })
+        ));
+
+        read.address.kind = FlatBlobKind::Public.into();
+        read.kind = 2;
+        let mut out = FlatBytes::empty();
+        assert_eq!(
+            unsafe { sn_flat_blob_read_encode(read, &mut out) },
+            FLAT_INVALID
+        );
+        assert!(out.ptr.is_null());
+    }
+
+    #[test]
+    fn blob_write_round_trip() -> Result<()> {
+        let owner = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
+        let write = BlobWrite::New(Blob::Private(PrivateBlob::new(vec![1, 2, 3], owner)));
+        let flat = FlatBlobWrite::try_from(write.clone())?;
+        assert_eq!(flat.blob.kind, u8::from(FlatBlobKind::Private));
+        assert_eq!(unsafe { flat.into_blob_write() }?, write);
+
+        let delete = BlobWrite::DeletePrivate(BlobAddress::Private(XorName::random()));
+        let flat = FlatBlobWrite::try_from(delete.clone())?;
+        assert_eq!(unsafe { flat.into_blob_write() }?, delete);
+
+        assert!(matches!(
+            FlatBlobWrite::try_from(BlobWrite::Batch(vec![])),
+            Err(Error::UnsupportedVariant(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn encode_through_ffi() -> Result<()> {
+        let value = [5, 6, 7];
+        let mut write = FlatBlobWrite::try_from(BlobWrite::DeletePrivate(BlobAddress::Private(
+            XorName::random(),
+        )))?;
+        write.kind = FlatBlobWriteKind::New.into();
+        write.blob.value = unsafe { sn_flat_bytes_new(value.as_ptr(), value.len()) };
+
+        let mut out = FlatBytes::empty();
+        assert_eq!(
+            unsafe { sn_flat_blob_write_encode(write, &mut out) },
+            FLAT_OK
+        );
+        let bytes = unsafe { out.into_vec() };
+        assert_eq!(
+            rmp_serde::from_slice::<BlobWrite>(&bytes)?,
+            BlobWrite::New(Blob::Public(PublicBlob::new(value.to_vec())))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_through_ffi() -> Result<()> {
+        let blob = Blob::Public(PublicBlob::new(vec![4, 2]));
+        let bytes = rmp_serde::to_vec_named(&QueryResponse::GetBlob(Ok(blob.clone())))?;
+        let mut out = MaybeUninit::<FlatQueryResponse>::uninit();
+        assert_eq!(
+            unsafe { sn_flat_query_response_decode(bytes.as_ptr(), bytes.len(), out.as_mut_ptr()) },
+            FLAT_OK
+        );
+        let flat = unsafe { out.assume_init() };
+        assert_eq!(flat.kind, u8::from(FlatQueryResponseKind::GetBlob));
+        assert_eq!(unsafe { flat.blob.into_blob() }?, blob);
+        unsafe { sn_flat_bytes_free(flat.error.message) };
+
+        let bytes = rmp_serde::to_vec_named(&QueryResponse::GetMapVersion(Ok(1)))?;
+        let mut out = MaybeUninit::<FlatQueryResponse>::uninit();
+        assert_eq!(
+            unsafe { sn_flat_query_response_decode(bytes.as_ptr(), bytes.len(), out.as_mut_ptr()) },
+            FLAT_UNSUPPORTED
+        );
+
+        // larger than the default limits allow
+        let value = vec![0; crate::limits::MAX_MSG_SIZE];
+        let blob = Blob::Public(PublicBlob::new(value));
+        let bytes = rmp_serde::to_vec_named(&QueryResponse::GetBlob(Ok(blob)))?;
+        let mut out = MaybeUninit::<FlatQueryResponse>::uninit();
+        assert_eq!(
+            unsafe { sn_flat_query_response_decode(bytes.as_ptr(), bytes.len(), out.as_mut_ptr()) },
+            FLAT_INVALID
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn blob_responses() -> Result<()> {
+        let blob = Blob::Public(PublicBlob::new(vec![4, 2]));
+        let flat = FlatQueryResponse::try_from(QueryResponse::GetBlob(Ok(blob.clone())))?;
+        assert_eq!(flat.kind, u8::from(FlatQueryResponseKind::GetBlob));
+        assert_eq!(flat.error.kind, u8::from(FlatErrorKind::None));
+        let value = unsafe { std::slice::from_raw_parts(flat.blob.value.ptr, flat.blob.value.len) };
+        assert_eq!(value, blob.value().as_slice());
+        unsafe { sn_flat_query_response_free(flat) };
+
+        let address = DataAddress::Blob(*blob.address());
+        let flat = FlatQueryResponse::try_from(QueryResponse::BlobExists(Err(ClientError::Data(
+            DataError::DataNotFound(address),
+        ))))?;
+        assert_eq!(flat.error.kind, u8::from(FlatErrorKind::DataNotFound));
+        assert!(flat.blob.value.ptr.is_null());
+        let message = unsafe { flat.error.message.into_vec() };
+        assert!(String::from_utf8(message)?.starts_with("Requested data not found"));
+
+        match FlatQueryResponse::try_from(QueryResponse::GetMapVersion(Ok(1))) {
+            Err(Error::UnsupportedVariant(_)) => Ok(()),
+            other => Err(anyhow!("unexpected conversion: {:?}", other.map(|_| ()))),
+        }
+    }
+}
@@ -7,27 +7,43 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
+pub mod canonical;
 pub mod client;
+pub mod dedup_cache;
 mod errors;
+pub mod flat;
+pub mod limits;
 pub mod location;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 mod msg_id;
 #[cfg(not(feature = "client-only"))]
 pub mod node;
+pub mod prefix_map;
 pub mod sap;
+#[cfg(feature = "schema")]
+pub mod schema;
 pub mod section_info;
 pub mod serialisation;
+pub mod signature_aggregator;
+mod signed;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub use self::{
+    dedup_cache::DedupCache,
     errors::{Error, Result},
-    location::{Aggregation, DstLocation, EndUser, Itinerary, SrcLocation},
+    location::{Aggregation, DstLocation, EndUser, Itinerary, SocketId, SrcLocation},
     msg_id::{MessageId, MESSAGE_ID_LEN},
-    sap::SectionAuthorityProvider,
-    serialisation::WireMsg,
+    prefix_map::PrefixMap,
+    sap::{EldersError, SectionAuthorityProvider},
+    serialisation::{DeserialisationLimits, MessageSummary, WireMsg, MAX_CAUSED_BY_LEN},
+    signature_aggregator::SignatureAggregator,
+    signed::{Signable, Signed, SignedShare, SIGNABLE_VERSION},
 };
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use threshold_crypto::PublicKey;
-use xor_name::XorName;
 
 /// Type of message.
 /// Note this is part of this crate's public API but this enum is
@@ -53,6 +69,8 @@ pub enum MessageType {
         msg: node::NodeMsg,
         dest_info: DestInfo,
         src_section_pk: Option<PublicKey>,
+        aggregation: Aggregation,
+        signed_share: Option<node::SignedShare>,
     },
 }
 
@@ -60,13 +78,15 @@ pub enum MessageType {
 /// serialised with a valid 'WireMsgHeader'
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize, Ord, PartialOrd, Eq)]
 pub struct DestInfo {
-    pub dest: XorName,
+    pub dest: DstLocation,
     pub dest_section_pk: PublicKey,
 }
 
 impl MessageType {
     /// serialize the message type into bytes ready to be sent over the wire.
     pub fn serialize(&self) -> Result<Bytes> {
+        #[cfg(feature = "tracing")]
+        let _span = self.span().entered();
         match self {
             Self::SectionInfo { msg, dest_info } => {
                 WireMsg::serialize_section_info_msg(msg, dest_info.dest, dest_info.dest_section_pk)
@@ -79,16 +99,7 @@ impl MessageType {
                 WireMsg::serialize_routing_msg(msg, dest_info.dest, dest_info.dest_section_pk)
             }
             #[cfg(not(feature = "client-only"))]
-            Self::Node {
-                msg,
-                dest_info,
-                src_section_pk,
-            } => WireMsg::serialize_node_msg(
-                msg,
-                dest_info.dest,
-                dest_info.dest_section_pk,
-                *src_section_pk,
-            ),
+            Self::Node { .. } => self.to_wire_msg()?.serialize(),
         }
     }
 
@@ -109,16 +120,63 @@ impl MessageType {
                 msg,
                 dest_info,
                 src_section_pk,
-            } => WireMsg::new_node_msg(
+                aggregation,
+                signed_share,
+            } => WireMsg::new_aggregated_node_msg(
                 msg,
                 dest_info.dest,
                 dest_info.dest_section_pk,
                 *src_section_pk,
+                *aggregation,
+                signed_share.clone(),
             ),
         }
     }
 
-    pub fn update_dest_info(&mut self, dest_pk: Option<PublicKey>, dest: Option<XorName>) {
+    /// Returns the id of the message, if it carries one, e.g. to tag logs and traces with.
+    pub fn msg_id(&self) -> Option<MessageId> {
+        match self {
+            Self::SectionInfo { .. } => None,
+            Self::Client { msg, .. } => Some(msg.id()),
+            #[cfg(not(feature = "client-only"))]
+            Self::Routing { msg, .. } => Some(msg.id),
+            #[cfg(not(feature = "client-only"))]
+            Self::Node { msg, .. } => Some(msg.id()),
+        }
+    }
+
+    /// Returns the id of the message this one responds to, if any, so
+    /// a response can be traced back to its request.
+    pub fn correlation_id(&self) -> Option<MessageId> {
+        match self {
+            Self::SectionInfo { .. } => None,
+            Self::Client { msg, .. } => msg.correlation_id(),
+            #[cfg(not(feature = "client-only"))]
+            Self::Routing { .. } => None,
+            #[cfg(not(feature = "client-only"))]
+            Self::Node { msg, .. } => msg.correlation_id(),
+        }
+    }
+
+    /// Returns a span carrying the id of the message and of the one it responds to,
+    /// if any, so the handling of a request can be traced across the network.
+    #[cfg(feature = "tracing")]
+    pub fn span(&self) -> tracing::Span {
+        let span = tracing::debug_span!(
+            "message",
+            msg_id = tracing::field::Empty,
+            correlation_id = tracing::field::Empty
+        );
+        if let Some(msg_id) = self.msg_id() {
+            let _ = span.record("msg_id", tracing::field::display(msg_id));
+        }
+        if let Some(correlation_id) = self.correlation_id() {
+            let _ = span.record("correlation_id", tracing::field::display(correlation_id));
+        }
+        span
+    }
+
+    pub fn update_dest_info(&mut self, dest_pk: Option<PublicKey>, dest: Option<DstLocation>) {
         #[cfg(not(feature = "client-only"))]
         match self {
             Self::Client { dest_info, .. } | Self::SectionInfo { dest_info, .. } => {
@@ -0,0 +1,118 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Limits of the messaging protocol, shared by the serialisation and validation
+//! of messages so that every layer agrees on the same numbers.
+
+use crate::{client::ValidationError, Error, Result};
+
+/// Maximum serialised size in bytes of a chunk (i.e. a Blob).
+pub const MAX_CHUNK_SIZE: u64 = sn_data_types::MAX_BLOB_SIZE_IN_BYTES;
+
+/// Maximum size in bytes of the payload of a message.
+pub const MAX_MSG_SIZE: usize = 10 * 1024 * 1024;
+
+/// Maximum number of entries of a Map.
+pub const MAX_ENTRIES: usize = 1000;
+
+/// Maximum number of changes in a single batch, e.g. the entry actions of a Map edit.
+pub const MAX_BATCH: usize = 100;
+
+/// Maximum number of entries in a page of a Sequence.
+pub const MAX_PAGE_LEN: u64 = 1000;
+
+/// Checks the serialised size of a chunk is within `MAX_CHUNK_SIZE`.
+pub fn check_chunk_size(size: u64) -> std::result::Result<(), ValidationError> {
+    if size > MAX_CHUNK_SIZE {
+        Err(ValidationError::BlobTooLarge {
+            size,
+            max: MAX_CHUNK_SIZE,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks the number of entries of a Map is within `MAX_ENTRIES`.
+pub fn check_entries(count: usize) -> std::result::Result<(), ValidationError> {
+    if count > MAX_ENTRIES {
+        Err(ValidationError::TooManyMapEntries {
+            count,
+            max: MAX_ENTRIES,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks a batch of changes isn't empty, and is within `MAX_BATCH`.
+pub fn check_batch(count: usize) -> std::result::Result<(), ValidationError> {
+    if count == 0 {
+        Err(ValidationError::EmptyBatch)
+    } else if count > MAX_BATCH {
+        Err(ValidationError::BatchTooLarge {
+            count,
+            max: MAX_BATCH,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks a page isn't empty, and is within `MAX_PAGE_LEN`.
+pub fn check_page_len(count: u64) -> std::result::Result<(), ValidationError> {
+    if count == 0 || count > MAX_PAGE_LEN {
+        Err(ValidationError::InvalidPageLen {
+            count,
+            max: MAX_PAGE_LEN,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks the size of the payload of a message is within `MAX_MSG_SIZE`.
+pub fn check_msg_size(size: usize) -> Result<()> {
+    if size > MAX_MSG_SIZE {
+        Err(Error::MessageTooLarge {
+            size,
+            max: MAX_MSG_SIZE,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_bounds() {
+        assert_eq!(check_batch(0), Err(ValidationError::EmptyBatch));
+        assert_eq!(check_batch(1), Ok(()));
+        assert_eq!(check_batch(MAX_BATCH), Ok(()));
+        assert_eq!(
+            check_batch(MAX_BATCH + 1),
+            Err(ValidationError::BatchTooLarge {
+                count: MAX_BATCH + 1,
+                max: MAX_BATCH
+            })
+        );
+    }
+
+    #[test]
+    fn msg_size_bounds() {
+        assert!(check_msg_size(MAX_MSG_SIZE).is_ok());
+        assert!(matches!(
+            check_msg_size(MAX_MSG_SIZE + 1),
+            Err(Error::MessageTooLarge { .. })
+        ));
+    }
+}
@@ -7,9 +7,11 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use xor_name::{Prefix, XorName};
 
-type SocketId = XorName;
+/// Identifier of the socket an EndUser is connected through at the Elders proxying it.
+pub type SocketId = XorName;
 
 /// The planned route of a message.
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
@@ -57,7 +59,7 @@ pub enum Aggregation {
 
 /// An EndUser is represented by the name
 /// it's proxied through, and its socket id.
-#[derive(Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
 pub struct EndUser {
     /// The name it's proxied through
     pub xorname: XorName,
@@ -65,6 +67,13 @@ pub struct EndUser {
     pub socket_id: SocketId,
 }
 
+impl EndUser {
+    /// Derives the socket id used to refer to the given client socket address.
+    pub fn socket_id_for(addr: &SocketAddr) -> SocketId {
+        XorName::from_content(&[addr.to_string().as_bytes()])
+    }
+}
+
 /// Message source location.
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum SrcLocation {
@@ -116,7 +125,7 @@ impl SrcLocation {
 }
 
 /// Message destination location.
-#[derive(Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
 pub enum DstLocation {
     /// An EndUser.
     EndUser(EndUser),
@@ -0,0 +1,96 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Hooks reporting the messages serialised and deserialised by this crate, so operators
+//! can collect per kind counters and size histograms with the metrics library of their choice.
+//!
+//! A `MetricsRecorder` is installed once per process with `set_recorder`, after which
+//! every `WireMsg` serialised or deserialised is reported to it.
+
+use std::sync::OnceLock;
+
+static RECORDER: OnceLock<Box<dyn MetricsRecorder>> = OnceLock::new();
+
+/// Receives the metrics of the messages handled by this crate.
+/// The kind is the one of the message header: "SectionInfo", "Client", "Routing" or "Node".
+pub trait MetricsRecorder: Send + Sync {
+    /// Called for each message serialised, with its size in bytes, including the header.
+    fn msg_serialised(&self, kind: &'static str, size: usize);
+
+    /// Called for each message deserialised, with its size in bytes, including the header.
+    fn msg_deserialised(&self, kind: &'static str, size: usize);
+}
+
+/// Installs the recorder for the rest of the process.
+/// Returns the given recorder back if one was already installed.
+pub fn set_recorder(recorder: Box<dyn MetricsRecorder>) -> Result<(), Box<dyn MetricsRecorder>> {
+    RECORDER.set(recorder)
+}
+
+pub(crate) fn record_serialised(kind: &'static str, size: usize) {
+    if let Some(recorder) = RECORDER.get() {
+        recorder.msg_serialised(kind, size);
+    }
+}
+
+pub(crate) fn record_deserialised(kind: &'static str, size: usize) {
+    if let Some(recorder) = RECORDER.get() {
+        recorder.msg_deserialised(kind, size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{section_info::SectionInfoMsg, DstLocation, WireMsg};
+    use anyhow::{anyhow, Result};
+    use std::sync::{Arc, Mutex};
+    use xor_name::XorName;
+
+    type Records = Arc<Mutex<Vec<(&'static str, &'static str, usize)>>>;
+
+    struct Recorder(Records);
+
+    impl MetricsRecorder for Recorder {
+        fn msg_serialised(&self, kind: &'static str, size: usize) {
+            if let Ok(mut records) = self.0.lock() {
+                records.push(("serialised", kind, size));
+            }
+        }
+
+        fn msg_deserialised(&self, kind: &'static str, size: usize) {
+            if let Ok(mut records) = self.0.lock() {
+                records.push(("deserialised", kind, size));
+            }
+        }
+    }
+
+    #[test]
+    fn records_msgs() -> Result<()> {
+        let records = Records::default();
+        set_recorder(Box::new(Recorder(records.clone())))
+            .map_err(|_| anyhow!("recorder already set"))?;
+
+        let wire_msg = WireMsg::new_section_info_msg(
+            &SectionInfoMsg::GetNetworkQuery,
+            DstLocation::Section(XorName::random()),
+            threshold_crypto::SecretKey::random().public_key(),
+        )?;
+        let bytes = wire_msg.serialize()?;
+        let _ = WireMsg::from(bytes.clone())?;
+
+        let records = records
+            .lock()
+            .map_err(|_| anyhow!("records lock poisoned"))?;
+        assert!(records.contains(&("serialised", "SectionInfo", bytes.len())));
+        assert!(records.contains(&("deserialised", "SectionInfo", bytes.len())));
+
+        Ok(())
+    }
+}
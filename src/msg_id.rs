@@ -9,18 +9,28 @@
 
 use crate::{Error, Result};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{
+    convert::TryFrom,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use tiny_keccak::{Hasher, Sha3};
 use xor_name::XorName;
 
 /// Constant byte length of `MessageId`.
 pub const MESSAGE_ID_LEN: usize = 32;
 
+// Number of leading bytes of an ordered `MessageId` holding its creation time.
+const TIMESTAMP_LEN: usize = 8;
+
 /// Unique ID for messages.
 ///
 /// This is used for deduplication: Since the network sends messages redundantly along different
 /// routes, the same message will usually arrive more than once at any given node. A message with
 /// an ID that is already in the cache will be ignored.
+///
+/// Ids are ordered by their bytes, so ids created with `MessageId::new_ordered` sort
+/// chronologically.
 #[derive(Ord, PartialOrd, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Hash)]
 pub struct MessageId([u8; MESSAGE_ID_LEN]);
 
@@ -31,6 +41,27 @@ impl MessageId {
         Self(XorName::random().0)
     }
 
+    /// Generates a new `MessageId` whose leading bytes are its creation time, in milliseconds
+    /// since the Unix epoch (big endian), followed by random content. Such ids sort by their
+    /// creation time, while ids created within the same millisecond are ordered arbitrarily.
+    pub fn new_ordered() -> Self {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX))
+            .unwrap_or(0);
+        let mut id = XorName::random().0;
+        id[..TIMESTAMP_LEN].copy_from_slice(&millis.to_be_bytes());
+        Self(id)
+    }
+
+    /// Returns the creation time, in milliseconds since the Unix epoch, embedded in an id
+    /// generated with `MessageId::new_ordered`. For other ids this value is meaningless.
+    pub fn timestamp_millis(&self) -> u64 {
+        let mut millis = [0; TIMESTAMP_LEN];
+        millis.copy_from_slice(&self.0[..TIMESTAMP_LEN]);
+        u64::from_be_bytes(millis)
+    }
+
     /// Generates a new MessageId containing provided bytes
     pub fn with(id: [u8; MESSAGE_ID_LEN]) -> Self {
         Self(id)
@@ -102,3 +133,25 @@ impl fmt::Debug for MessageId {
         write!(f, "{}", self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn ordered_ids_sort_chronologically() {
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+        let first = MessageId::new_ordered();
+        thread::sleep(Duration::from_millis(2));
+        let second = MessageId::new_ordered();
+
+        assert!(first < second);
+        assert!(first.timestamp_millis() >= before);
+        assert!(second.timestamp_millis() > first.timestamp_millis());
+        assert_ne!(MessageId::new_ordered(), MessageId::new_ordered());
+    }
+}
@@ -6,8 +6,15 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{plain_message::PlainMessage, section::NodeState, signed::Signed};
-use crate::{MessageId, SectionAuthorityProvider};
+use super::{
+    plain_message::PlainMessage,
+    section::{ElderCandidates, NodeState},
+};
+use crate::{
+    canonical::CanonicalBytes,
+    signature_aggregator::{Error as AggregatorError, SignatureAggregator},
+    MessageId, SectionAuthorityProvider, Signable, Signed, SignedShare, SIGNABLE_VERSION,
+};
 use ed25519_dalek::{PublicKey, Signature};
 use hex_fmt::HexFmt;
 use secured_linked_list::SecuredLinkedList;
@@ -16,8 +23,10 @@ use std::{
     borrow::Borrow,
     collections::BTreeSet,
     fmt::{self, Debug, Formatter},
+    time::Duration,
 };
-use threshold_crypto::PublicKey as BlsPublicKey;
+use threshold_crypto::{PublicKey as BlsPublicKey, PublicKeySet, SecretKeyShare};
+use tiny_keccak::{Hasher, Sha3};
 use xor_name::{Prefix, XorName};
 
 /// SHA3-256 hash digest.
@@ -30,6 +39,28 @@ pub struct DkgKey {
     pub generation: u64,
 }
 
+impl DkgKey {
+    /// Creates the key of the DKG session for the given elder candidates, at their generation.
+    pub fn new(elder_candidates: &ElderCandidates) -> Self {
+        // Calculate the hash without involving serialization to avoid having to return `Result`.
+        let mut hasher = Sha3::v256();
+        let mut hash = Digest256::default();
+
+        for name in elder_candidates.elders.keys() {
+            hasher.update(&name.0);
+        }
+
+        hasher.update(&elder_candidates.prefix.name().0);
+        hasher.update(&elder_candidates.prefix.bit_count().to_le_bytes());
+        hasher.finalize(&mut hash);
+
+        Self {
+            hash,
+            generation: elder_candidates.generation,
+        }
+    }
+}
+
 impl Debug for DkgKey {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "DkgKey({:10}/{})", HexFmt(&self.hash), self.generation)
@@ -53,6 +84,35 @@ pub struct DkgFailureSignedSet {
 pub struct SectionSigned<T: Serialize> {
     pub value: T,
     pub signed: Signed,
+    /// Version of the layout the value was signed with.
+    pub version: u16,
+}
+
+impl<T: Serialize> SectionSigned<T> {
+    /// Wraps a value signed by the section with the current layout version.
+    pub fn new(value: T, signed: Signed) -> Self {
+        Self {
+            value,
+            signed,
+            version: SIGNABLE_VERSION,
+        }
+    }
+}
+
+impl<T: Serialize + CanonicalBytes> SectionSigned<T> {
+    /// Returns the bytes the section signs for `value`.
+    pub fn signable_bytes(value: &T) -> crate::Result<Vec<u8>> {
+        Signable::new(value).bytes()
+    }
+
+    /// Verifies the signature against the value, laid out with the version it was signed with.
+    /// Values signed with an unsupported version never verify.
+    pub fn verify(&self) -> bool {
+        Signable::with_version(self.version, &self.value)
+            .bytes()
+            .map(|bytes| self.signed.verify(&bytes))
+            .unwrap_or(false)
+    }
 }
 
 impl<T> Borrow<Prefix> for SectionSigned<T>
@@ -108,4 +168,219 @@ pub enum Proposal {
 
     // Proposal to change whether new nodes are allowed to join our section.
     JoinsAllowed((MessageId, bool)),
+
+    // Proposal to split our section into its two child sections. Like `OurElders`, both
+    // `SectionAuthorityProvider`s are already signed with the new keys of the respective child
+    // sections and this proposal signs both of them with the current key.
+    SectionSplit {
+        left: SectionSigned<SectionAuthorityProvider>,
+        right: SectionSigned<SectionAuthorityProvider>,
+    },
+}
+
+impl Proposal {
+    /// Returns the bytes the elders sign when voting for this proposal, laid out as described
+    /// in [`crate::canonical`]. Fails if the variant of the message to accumulate at the
+    /// source can't be encoded.
+    pub fn as_signable_bytes(&self) -> crate::Result<Vec<u8>> {
+        match self {
+            Self::Online {
+                node_state,
+                previous_name,
+                destination_key,
+            } => Signable::new((0u8, node_state, previous_name, destination_key)).bytes(),
+            Self::Offline(node_state) => Signable::new((1u8, node_state)).bytes(),
+            Self::SectionInfo(section_auth) => Signable::new((2u8, section_auth)).bytes(),
+            Self::OurElders(section_auth) => Signable::new((3u8, section_auth)).bytes(),
+            Self::AccumulateAtSrc {
+                message,
+                proof_chain,
+            } => {
+                let variant = rmp_serde::to_vec_named(&message.variant).map_err(|err| {
+                    crate::Error::Serialisation(format!(
+                        "could not serialize the message variant: {}",
+                        err
+                    ))
+                })?;
+                let message = (message.src, message.dst, message.dst_key, variant);
+                Signable::new((4u8, message, proof_chain)).bytes()
+            }
+            Self::JoinsAllowed((msg_id, allowed)) => Signable::new((5u8, msg_id, allowed)).bytes(),
+            Self::SectionSplit { left, right } => Signable::new((6u8, left, right)).bytes(),
+        }
+    }
+}
+
+impl SectionSigned<Proposal> {
+    /// Verifies the signature against the proposal.
+    /// Proposals signed with an unsupported version never verify.
+    pub fn verify(&self) -> bool {
+        self.version == SIGNABLE_VERSION
+            && self
+                .value
+                .as_signable_bytes()
+                .map(|bytes| self.signed.verify(&bytes))
+                .unwrap_or(false)
+    }
+}
+
+/// A single elder's vote for a `Proposal`, carrying its share of the section signature.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Vote {
+    pub proposal: Proposal,
+    pub sig_share: SignedShare,
+}
+
+impl Vote {
+    /// Signs the proposal with the given secret key share.
+    pub fn new(
+        proposal: Proposal,
+        public_key_set: PublicKeySet,
+        index: usize,
+        secret_key_share: &SecretKeyShare,
+    ) -> crate::Result<Self> {
+        let bytes = proposal.as_signable_bytes()?;
+        let sig_share = SignedShare::new(public_key_set, index, secret_key_share, &bytes);
+
+        Ok(Self {
+            proposal,
+            sig_share,
+        })
+    }
+
+    /// Verifies the signature share against the proposal.
+    pub fn verify(&self) -> bool {
+        self.proposal
+            .as_signable_bytes()
+            .map(|bytes| self.sig_share.verify(&bytes))
+            .unwrap_or(false)
+    }
+}
+
+/// Aggregator of `Vote`s, yielding the agreed `Proposal` once enough elders voted for it.
+#[derive(Default)]
+pub struct VoteAggregator(SignatureAggregator);
+
+impl VoteAggregator {
+    /// Create new aggregator with default expiration.
+    pub fn new() -> Self {
+        Self(SignatureAggregator::new())
+    }
+
+    /// Create new aggregator with the given expiration.
+    pub fn with_expiration(expiration: Duration) -> Self {
+        Self(SignatureAggregator::with_expiration(expiration))
+    }
+
+    /// Add a vote into the aggregator. Returns the proposal signed by the section once enough
+    /// valid votes were collected, with the same error semantics as `SignatureAggregator::add`.
+    /// A vote whose proposal cannot be serialised is rejected as `InvalidShare`.
+    pub fn add(&mut self, vote: Vote) -> Result<SectionSigned<Proposal>, AggregatorError> {
+        let bytes = vote
+            .proposal
+            .as_signable_bytes()
+            .map_err(|_| AggregatorError::InvalidShare)?;
+        let signed = self.0.add(&bytes, vote.sig_share)?;
+
+        Ok(SectionSigned::new(vote.proposal, signed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use rand::thread_rng;
+    use threshold_crypto::SecretKeySet;
+
+    #[test]
+    fn dkg_key_generation() {
+        let mut elder_candidates = ElderCandidates::default();
+        let _ = elder_candidates
+            .elders
+            .insert(XorName::random(), ([127, 0, 0, 1], 12000).into());
+
+        let first = DkgKey::new(&elder_candidates);
+        elder_candidates.generation += 1;
+        let second = DkgKey::new(&elder_candidates);
+
+        assert_eq!(first.hash, second.hash);
+        assert_ne!(first, second);
+        assert!(first.generation < second.generation);
+    }
+
+    #[test]
+    fn aggregate_votes() -> Result<()> {
+        let mut rng = thread_rng();
+        let sk_set = SecretKeySet::random(1, &mut rng);
+        let proposal = Proposal::JoinsAllowed((MessageId::new(), true));
+
+        let votes = (0..3)
+            .map(|index| {
+                Vote::new(
+                    proposal.clone(),
+                    sk_set.public_keys(),
+                    index,
+                    &sk_set.secret_key_share(index),
+                )
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        assert!(votes.iter().all(Vote::verify));
+
+        let mut aggregator = VoteAggregator::new();
+        assert!(matches!(
+            aggregator.add(votes[0].clone()),
+            Err(AggregatorError::NotEnoughShares)
+        ));
+
+        let agreed = aggregator.add(votes[1].clone())?;
+        assert_eq!(agreed.value, proposal);
+        assert!(agreed.signed.verify(&proposal.as_signable_bytes()?));
+        assert_eq!(agreed.signed.public_key, sk_set.public_keys().public_key());
+        assert!(agreed.verify());
+
+        let mut future = agreed.clone();
+        future.version += 1;
+        assert!(!future.verify());
+
+        let mut forged = votes[2].clone();
+        forged.proposal = Proposal::JoinsAllowed((MessageId::new(), false));
+        assert!(!forged.verify());
+        assert!(matches!(
+            aggregator.add(forged),
+            Err(AggregatorError::InvalidShare)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn accumulate_at_src_signable_bytes() -> Result<()> {
+        let key = threshold_crypto::SecretKey::random().public_key();
+        let message = PlainMessage {
+            src: XorName::random(),
+            dst: crate::DstLocation::Section(XorName::random()),
+            dst_key: key,
+            variant: crate::node::Variant::UserMessage(b"hello".to_vec()),
+        };
+        let proof_chain = SecuredLinkedList::new(key);
+        let proposal = Proposal::AccumulateAtSrc {
+            message: Box::new(message.clone()),
+            proof_chain: proof_chain.clone(),
+        };
+
+        let variant = rmp_serde::to_vec_named(&message.variant)?;
+        let expected = crate::canonical::signing_bytes(
+            "signable",
+            &(
+                SIGNABLE_VERSION,
+                4u8,
+                (message.src, message.dst, message.dst_key, variant),
+                proof_chain,
+            ),
+        )?;
+        assert_eq!(proposal.as_signable_bytes()?, expected);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,235 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{client::RegistrationExpiry, EndUser, SocketId};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, net::SocketAddr};
+use xor_name::XorName;
+
+/// Bookkeeping of the EndUsers registered at an Elder via `Cmd::RegisterEndUser`,
+/// mapping the client socket addresses to the EndUsers they are proxied as.
+///
+/// An EndUser can be reachable through several socket addresses (e.g. both over IPv4 and IPv6),
+/// all of which resolve to the socket id it was first registered with.
+/// The addresses are kept here rather than in `EndUser`, which is part of the fixed size
+/// destination of the wire message header. Only direct addresses are supported: reaching a
+/// client through a relay isn't.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndUserRegistry {
+    clients: BTreeMap<SocketAddr, EndUser>,
+    socket_id_mapping: BTreeMap<SocketId, Vec<SocketAddr>>,
+    last_seen: BTreeMap<SocketId, u64>,
+}
+
+impl EndUserRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the client at `socket_addr` as an EndUser proxied through `xorname`,
+    /// returning the registered `EndUser`.
+    /// If the socket address was already registered, its previous registration is replaced.
+    pub fn register(&mut self, xorname: XorName, socket_addr: SocketAddr) -> EndUser {
+        let _ = self.unregister(&socket_addr);
+        let end_user = EndUser {
+            xorname,
+            socket_id: EndUser::socket_id_for(&socket_addr),
+        };
+        self.socket_id_mapping
+            .entry(end_user.socket_id)
+            .or_default()
+            .push(socket_addr);
+        let _ = self.clients.insert(socket_addr, end_user);
+        end_user
+    }
+
+    /// Adds `socket_addr` as an additional address the already registered `end_user`
+    /// can be reached at.
+    /// Returns `false` if `end_user` isn't registered, or the address belongs to another EndUser.
+    pub fn add_socket_addr(&mut self, end_user: &EndUser, socket_addr: SocketAddr) -> bool {
+        if let Some(existing) = self.clients.get(&socket_addr) {
+            return existing == end_user;
+        }
+        match self.socket_id_mapping.get_mut(&end_user.socket_id) {
+            Some(addrs) => {
+                addrs.push(socket_addr);
+                let _ = self.clients.insert(socket_addr, *end_user);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the given socket address from the registry, returning the EndUser it belonged to.
+    /// The EndUser stays registered as long as it's reachable through any other address.
+    pub fn unregister(&mut self, socket_addr: &SocketAddr) -> Option<EndUser> {
+        let end_user = self.clients.remove(socket_addr)?;
+        if let Some(addrs) = self.socket_id_mapping.get_mut(&end_user.socket_id) {
+            addrs.retain(|addr| addr != socket_addr);
+            if addrs.is_empty() {
+                let _ = self.socket_id_mapping.remove(&end_user.socket_id);
+                let _ = self.last_seen.remove(&end_user.socket_id);
+            }
+        }
+        Some(end_user)
+    }
+
+    /// Records a `Cmd::KeepAlive` for the given socket id, received at `now`
+    /// in milliseconds since the Unix epoch.
+    /// Returns `false` if no EndUser is registered with the socket id.
+    pub fn keep_alive(&mut self, socket_id: &SocketId, now: u64) -> bool {
+        if !self.socket_id_mapping.contains_key(socket_id) {
+            return false;
+        }
+        let _ = self.last_seen.insert(*socket_id, now);
+        true
+    }
+
+    /// Unregisters the EndUsers which haven't been kept alive within the idle timeout
+    /// of `expiry`, at `now` in milliseconds since the Unix epoch, returning them.
+    /// EndUsers which never sent a keepalive are timed from the first prune seeing them.
+    pub fn prune_expired(&mut self, expiry: &RegistrationExpiry, now: u64) -> Vec<EndUser> {
+        let mut expired = Vec::new();
+        for socket_id in self.socket_id_mapping.keys() {
+            let last_seen = *self.last_seen.entry(*socket_id).or_insert(now);
+            if expiry.is_expired(last_seen, now) {
+                expired.push(*socket_id);
+            }
+        }
+        expired
+            .iter()
+            .filter_map(|socket_id| {
+                let addrs = self.socket_id_mapping.get(socket_id)?.clone();
+                addrs.iter().filter_map(|addr| self.unregister(addr)).last()
+            })
+            .collect()
+    }
+
+    /// Returns the EndUser registered for the given socket address, if any.
+    pub fn end_user(&self, socket_addr: &SocketAddr) -> Option<&EndUser> {
+        self.clients.get(socket_addr)
+    }
+
+    /// Returns all the socket addresses the given socket id is reachable at,
+    /// in the order they were registered.
+    pub fn socket_addrs(&self, socket_id: &SocketId) -> &[SocketAddr] {
+        self.socket_id_mapping
+            .get(socket_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns the address to reach the given socket id at, which is the most recently added one.
+    pub fn socket_addr(&self, socket_id: &SocketId) -> Option<&SocketAddr> {
+        self.socket_addrs(socket_id).last()
+    }
+
+    /// Returns the most recently added address of the given socket id which is of the
+    /// same IP family as `local_addr`, falling back to any address if there's none.
+    pub fn socket_addr_for(
+        &self,
+        socket_id: &SocketId,
+        local_addr: &SocketAddr,
+    ) -> Option<&SocketAddr> {
+        let addrs = self.socket_addrs(socket_id);
+        addrs
+            .iter()
+            .rev()
+            .find(|addr| addr.is_ipv6() == local_addr.is_ipv6())
+            .or_else(|| addrs.last())
+    }
+
+    /// Returns the number of registered socket addresses.
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Returns whether there are no registered EndUsers.
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn register_and_resolve() -> Result<()> {
+        let mut registry = EndUserRegistry::new();
+        let socket_addr: SocketAddr = "127.0.0.1:12000".parse()?;
+        let xorname = XorName::random();
+
+        let end_user = registry.register(xorname, socket_addr);
+        assert_eq!(end_user.xorname, xorname);
+        assert_eq!(registry.end_user(&socket_addr), Some(&end_user));
+        assert_eq!(
+            registry.socket_addr(&end_user.socket_id),
+            Some(&socket_addr)
+        );
+
+        assert_eq!(registry.unregister(&socket_addr), Some(end_user));
+        assert!(registry.is_empty());
+        assert_eq!(registry.socket_addr(&end_user.socket_id), None);
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_socket_addrs() -> Result<()> {
+        let mut registry = EndUserRegistry::new();
+        let v4: SocketAddr = "127.0.0.1:12000".parse()?;
+        let v6: SocketAddr = "[::1]:12000".parse()?;
+
+        let end_user = registry.register(XorName::random(), v4);
+        assert!(registry.add_socket_addr(&end_user, v6));
+        assert_eq!(registry.end_user(&v6), Some(&end_user));
+        assert_eq!(registry.socket_addrs(&end_user.socket_id), &[v4, v6]);
+
+        let local_v4: SocketAddr = "10.0.0.1:5483".parse()?;
+        assert_eq!(
+            registry.socket_addr_for(&end_user.socket_id, &local_v4),
+            Some(&v4)
+        );
+        assert_eq!(registry.socket_addr(&end_user.socket_id), Some(&v6));
+
+        // an address of another EndUser can't be taken over
+        let other = registry.register(XorName::random(), "127.0.0.2:12000".parse()?);
+        assert!(!registry.add_socket_addr(&other, v4));
+
+        // the EndUser stays reachable through its remaining address
+        assert_eq!(registry.unregister(&v4), Some(end_user));
+        assert_eq!(
+            registry.socket_addr_for(&end_user.socket_id, &local_v4),
+            Some(&v6)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn prune_expired() -> Result<()> {
+        let mut registry = EndUserRegistry::new();
+        let expiry = RegistrationExpiry { idle_timeout: 100 };
+        let alive = registry.register(XorName::random(), "127.0.0.1:12000".parse()?);
+        let v4: SocketAddr = "127.0.0.2:12000".parse()?;
+        let dead = registry.register(XorName::random(), v4);
+        assert!(registry.add_socket_addr(&dead, "[::1]:12000".parse()?));
+
+        assert!(registry.prune_expired(&expiry, 1_000).is_empty());
+        assert!(registry.keep_alive(&alive.socket_id, 1_050));
+        assert_eq!(registry.prune_expired(&expiry, 1_101), vec![dead]);
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.socket_addr(&dead.socket_id), None);
+        assert!(!registry.keep_alive(&dead.socket_id, 1_101));
+        assert_eq!(registry.prune_expired(&expiry, 1_151), vec![alive]);
+        assert!(registry.is_empty());
+        Ok(())
+    }
+}
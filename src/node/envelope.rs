@@ -0,0 +1,105 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::NodeMsg;
+use crate::{DstLocation, Result, WireMsg};
+use bytes::Bytes;
+use threshold_crypto::PublicKey as BlsPublicKey;
+use xor_name::XorName;
+
+/// A node message ready to be sent, to one or several recipients.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeMsgEnvelope {
+    /// A message to the single destination of its header.
+    Unicast(WireMsg),
+    /// A message to several nodes, e.g. elders broadcasting to all their adults.
+    /// The message is serialized once, and its payload shared by the copies sent
+    /// to each recipient, which only differ by the destination of their header.
+    Multicast {
+        /// Names of the recipient nodes.
+        recipients: Vec<XorName>,
+        /// The message, whose destination is set when splitting it per recipient.
+        msg: WireMsg,
+    },
+}
+
+impl NodeMsgEnvelope {
+    /// Creates an envelope of the given message to the given nodes of the section
+    /// with key `dest_section_pk`.
+    pub fn multicast(
+        msg: &NodeMsg,
+        recipients: Vec<XorName>,
+        dest_section_pk: BlsPublicKey,
+        src_section_pk: Option<BlsPublicKey>,
+    ) -> Result<Self> {
+        let msg = WireMsg::new_node_msg(
+            msg,
+            DstLocation::DirectAndUnrouted,
+            dest_section_pk,
+            src_section_pk,
+        )?;
+        Ok(Self::Multicast { recipients, msg })
+    }
+
+    /// Returns the message to send to each recipient, sharing the same payload.
+    pub fn split(&self) -> Vec<WireMsg> {
+        match self {
+            Self::Unicast(msg) => vec![msg.clone()],
+            Self::Multicast { recipients, msg } => recipients
+                .iter()
+                .map(|name| {
+                    let mut msg = msg.clone();
+                    msg.update_dest_info(None, Some(DstLocation::Node(*name)));
+                    msg
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the serialized message to send to each recipient.
+    pub fn serialize(&self) -> Result<Vec<Bytes>> {
+        self.split().iter().map(WireMsg::serialize).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        node::{NodeCmd, NodeSystemCmd},
+        MessageId, MessageType,
+    };
+    use anyhow::Result;
+
+    #[test]
+    fn split_multicast() -> Result<()> {
+        let section_pk = threshold_crypto::SecretKey::random().public_key();
+        let node_msg = NodeMsg::NodeCmd {
+            cmd: NodeCmd::System(NodeSystemCmd::BeginDecommission {
+                node: XorName::random(),
+            }),
+            id: MessageId::new(),
+        };
+        let recipients = vec![XorName::random(), XorName::random()];
+        let envelope = NodeMsgEnvelope::multicast(&node_msg, recipients.clone(), section_pk, None)?;
+
+        let msgs = envelope.serialize()?;
+        assert_eq!(msgs.len(), recipients.len());
+        for (bytes, name) in msgs.into_iter().zip(recipients) {
+            match WireMsg::deserialize(bytes)? {
+                MessageType::Node { msg, dest_info, .. } => {
+                    assert_eq!(msg, node_msg);
+                    assert_eq!(dest_info.dest, DstLocation::Node(name));
+                }
+                other => panic!("unexpected message: {:?}", other),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{canonical::signing_bytes, client::ClientMsg, Error, MessageType, Result, WireMsg};
+use bytes::Bytes;
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use threshold_crypto::PublicKey as BlsPublicKey;
+use xor_name::XorName;
+
+/// Node which relayed a message on behalf of its original sender.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MsgSender {
+    /// Public key of the relaying node.
+    pub public_key: PublicKey,
+    /// Key of the section of the relaying node.
+    pub section_pk: BlsPublicKey,
+}
+
+impl MsgSender {
+    /// Name of the relaying node.
+    pub fn name(&self) -> XorName {
+        XorName::from(sn_data_types::PublicKey::from(self.public_key))
+    }
+}
+
+/// A client message relayed by an elder, e.g. to the adults holding the data it targets,
+/// keeping the bytes signed by the client as received so that the recipients can verify
+/// the client signature themselves, rather than trusting the elder's word for it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Forwarded {
+    /// The serialized message, as received from the client.
+    #[serde(with = "serde_bytes")]
+    pub original: Vec<u8>,
+    /// Node which relayed the message.
+    pub relayer: MsgSender,
+    /// Signature of the relayer over the original message and its section key.
+    pub sig: Signature,
+}
+
+impl Forwarded {
+    /// Returns the bytes a node signs to relay the given message.
+    pub fn bytes_to_sign(original: &[u8], section_pk: &BlsPublicKey) -> Result<Vec<u8>> {
+        signing_bytes("forwarded", &(original, section_pk))
+    }
+
+    /// Wraps the given serialized message, relayed by the node with the given keypair
+    /// which is a member of the section with key `section_pk`.
+    pub fn new(original: Bytes, keypair: &Keypair, section_pk: BlsPublicKey) -> Result<Self> {
+        let sig = keypair.sign(&Self::bytes_to_sign(&original, &section_pk)?);
+        Ok(Self {
+            original: original.to_vec(),
+            relayer: MsgSender {
+                public_key: keypair.public,
+                section_pk,
+            },
+            sig,
+        })
+    }
+
+    /// Verifies the signature of the relayer.
+    /// Whether the relayer is trusted, e.g. an elder of its section, still needs checking.
+    pub fn verify(&self) -> bool {
+        Self::bytes_to_sign(&self.original, &self.relayer.section_pk)
+            .map(|bytes| self.relayer.public_key.verify(&bytes, &self.sig).is_ok())
+            .unwrap_or(false)
+    }
+
+    /// Deserializes the original client message, whose client signature can then be verified.
+    pub fn client_msg(&self) -> Result<ClientMsg> {
+        match WireMsg::deserialize(Bytes::copy_from_slice(&self.original))? {
+            MessageType::Client { msg, .. } => Ok(msg),
+            _ => Err(Error::FailedToParse(
+                "forwarded bytes as a client message".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::ProcessingError, DstLocation, MessageId};
+    use anyhow::Result;
+
+    #[test]
+    fn relay_client_msg() -> Result<()> {
+        let section_pk = threshold_crypto::SecretKey::random().public_key();
+        let client_msg =
+            ClientMsg::ProcessingError(ProcessingError::new(None, None, MessageId::new()));
+        let original = client_msg.serialize(DstLocation::Section(XorName::random()), section_pk)?;
+        let keypair = Keypair::generate(&mut rand::thread_rng());
+
+        let forwarded = Forwarded::new(original, &keypair, section_pk)?;
+        assert!(forwarded.verify());
+        assert_eq!(forwarded.client_msg()?, client_msg);
+
+        let mut tampered = forwarded.clone();
+        tampered.relayer.section_pk = threshold_crypto::SecretKey::random().public_key();
+        assert!(!tampered.verify());
+        Ok(())
+    }
+}
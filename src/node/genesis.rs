@@ -0,0 +1,198 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{agreement::SectionSigned, node_msg::verify_credit_signature, Secret, SectionKeyShare};
+use crate::{SectionAuthorityProvider, SignedShare};
+use serde::{Deserialize, Serialize};
+use sn_data_types::CreditAgreementProof;
+use std::fmt::{self, Debug, Formatter};
+use threshold_crypto::PublicKey as BlsPublicKey;
+
+/// Messages establishing the genesis section, when the first elders of a network are
+/// started together (e.g. a test network) rather than the section growing from a single node.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum GenesisMsg {
+    /// Sent by the genesis node to each of the other initial elders,
+    /// handing them their share of the genesis key.
+    KeyShare {
+        /// The genesis section, signed with the genesis key.
+        section_auth: SectionSigned<SectionAuthorityProvider>,
+        /// The recipient's share of the genesis key, with its index within the genesis
+        /// public key set. It must only be sent over an encrypted connection.
+        key_share: Secret<SectionKeyShare>,
+    },
+    /// Sent back by an initial elder once it installed its key share,
+    /// proving it by signing the genesis section with it.
+    KeyShareInstalled {
+        /// Signature share over the serialized genesis section.
+        signed_share: SignedShare,
+    },
+    /// Sent by the genesis node to the initial elders, crediting the
+    /// genesis wallet with the initial supply of tokens.
+    WalletSetup {
+        /// The credit of the genesis wallet, signed with the genesis key.
+        genesis_credit: CreditAgreementProof,
+    },
+}
+
+impl GenesisMsg {
+    /// Verifies the message against the given genesis key:
+    ///
+    /// - a key share must come with the genesis section signed with the genesis key, and
+    ///   match the share at its index in the genesis public key set;
+    /// - an installed key share must be a share of the genesis key. The signature share
+    ///   itself is over the genesis section, so only its sender can check it;
+    /// - the genesis credit must be signed with the genesis key.
+    pub fn verify(&self, genesis_key: &BlsPublicKey) -> bool {
+        match self {
+            Self::KeyShare {
+                section_auth,
+                key_share,
+            } => {
+                let public_key_set = &section_auth.value.public_key_set;
+                let share = key_share.expose_secret();
+                public_key_set.public_key() == *genesis_key
+                    && section_auth.signed.public_key == *genesis_key
+                    && section_auth.verify()
+                    && share.public_key_set == *public_key_set
+                    && share.secret_key_share.public_key_share()
+                        == public_key_set.public_key_share(share.index)
+            }
+            Self::KeyShareInstalled { signed_share } => {
+                signed_share.public_key_set.public_key() == *genesis_key
+            }
+            Self::WalletSetup { genesis_credit } => {
+                genesis_credit.debiting_replicas_keys.public_key() == *genesis_key
+                    && verify_credit_signature(genesis_credit)
+            }
+        }
+    }
+}
+
+impl Debug for GenesisMsg {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::KeyShare {
+                section_auth,
+                key_share,
+            } => f
+                .debug_struct("KeyShare")
+                .field("section_auth", &section_auth.value)
+                .field("index", &key_share.expose_secret().index)
+                .finish(),
+            Self::KeyShareInstalled { signed_share } => f
+                .debug_struct("KeyShareInstalled")
+                .field("signed_share", signed_share)
+                .finish(),
+            Self::WalletSetup { genesis_credit } => f
+                .debug_struct("WalletSetup")
+                .field("amount", &genesis_credit.amount())
+                .finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Signed;
+    use anyhow::{anyhow, Result};
+    use crdts::Dot;
+    use sn_data_types::{Credit, Debit, Keypair, PublicKey, Signature, SignedCredit, Token};
+    use std::collections::BTreeMap;
+    use threshold_crypto::{SecretKey, SecretKeySet};
+    use xor_name::Prefix;
+
+    #[test]
+    fn verify_key_share() -> Result<()> {
+        let sk_set = SecretKeySet::random(1, &mut rand::thread_rng());
+        let genesis_key = sk_set.public_keys().public_key();
+        let value = SectionAuthorityProvider {
+            prefix: Prefix::default(),
+            public_key_set: sk_set.public_keys(),
+            elders: BTreeMap::new(),
+            generation: 0,
+        };
+        let bytes = SectionSigned::signable_bytes(&value)?;
+        let shares: Vec<_> = (0..2)
+            .map(|index| (index, sk_set.secret_key_share(index).sign(&bytes)))
+            .collect();
+        let signature = sk_set
+            .public_keys()
+            .combine_signatures(shares.iter().map(|(index, share)| (*index, share)))
+            .map_err(|err| anyhow!("could not combine signatures: {:?}", err))?;
+        let section_auth = SectionSigned::new(
+            value,
+            Signed {
+                public_key: genesis_key,
+                signature,
+            },
+        );
+        let key_share = |index| GenesisMsg::KeyShare {
+            section_auth: section_auth.clone(),
+            key_share: Secret::new(SectionKeyShare {
+                public_key_set: sk_set.public_keys(),
+                index,
+                secret_key_share: sk_set.secret_key_share(1),
+            }),
+        };
+
+        assert!(key_share(1).verify(&genesis_key));
+        assert!(!key_share(0).verify(&genesis_key));
+        assert!(!key_share(1).verify(&SecretKey::random().public_key()));
+
+        let bytes = rmp_serde::to_vec_named(&key_share(1))?;
+        assert_eq!(rmp_serde::from_slice::<GenesisMsg>(&bytes)?, key_share(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_wallet_setup() -> Result<()> {
+        let sk_set = SecretKeySet::random(0, &mut rand::thread_rng());
+        let genesis_key = sk_set.public_keys().public_key();
+        let sender = Keypair::new_ed25519(&mut rand::thread_rng());
+        let debit = Debit {
+            id: Dot::new(sender.public_key(), 0),
+            amount: Token::from_nano(u32::MAX as u64 * 1_000_000_000),
+        };
+        let credit = Credit {
+            id: debit.credit_id()?,
+            amount: debit.amount,
+            recipient: PublicKey::from(genesis_key),
+            msg: "genesis".to_string(),
+        };
+        let signed_credit = SignedCredit {
+            actor_signature: sender.sign(&bincode::serialize(&credit)?),
+            credit,
+        };
+        let share = sk_set
+            .secret_key_share(0)
+            .sign(&bincode::serialize(&signed_credit)?);
+        let signature = sk_set
+            .public_keys()
+            .combine_signatures(vec![(0, &share)])
+            .map_err(|err| anyhow!("could not combine signatures: {:?}", err))?;
+        let wallet_setup = |signature| GenesisMsg::WalletSetup {
+            genesis_credit: CreditAgreementProof {
+                signed_credit: signed_credit.clone(),
+                debiting_replicas_sig: Signature::Bls(signature),
+                debiting_replicas_keys: sk_set.public_keys(),
+            },
+        };
+
+        assert!(wallet_setup(signature.clone()).verify(&genesis_key));
+        assert!(!wallet_setup(signature).verify(&SecretKey::random().public_key()));
+        let forged = SecretKey::random().sign(&bincode::serialize(&signed_credit)?);
+        assert!(!wallet_setup(forged).verify(&genesis_key));
+
+        Ok(())
+    }
+}
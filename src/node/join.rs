@@ -8,7 +8,7 @@
 
 use super::{agreement::SectionSigned, section::NodeState};
 use crate::SectionAuthorityProvider;
-use ed25519_dalek::Signature;
+use ed25519_dalek::{PublicKey, Signature, Verifier};
 use secured_linked_list::SecuredLinkedList;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -17,8 +17,12 @@ use std::{
     net::SocketAddr,
 };
 use threshold_crypto::PublicKey as BlsPublicKey;
+use tiny_keccak::{Hasher, Sha3};
 
-/// Request to join a section
+/// Request to join a section.
+/// The name of the joining node is the one of the key it signs the request with (see
+/// `SrcAuthority::Node`), so it's not repeated here, and neither is its age, which is
+/// derived from that name.
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct JoinRequest {
     /// The public key of the section to join.
@@ -52,6 +56,85 @@ pub struct ResourceProofResponse {
     pub nonce_signature: Signature,
 }
 
+impl ResourceProofResponse {
+    /// Expected number of attempts needed to solve a challenge of the given difficulty.
+    pub fn expected_work(difficulty: u8) -> u64 {
+        1u64.checked_shl(difficulty.into()).unwrap_or(u64::MAX)
+    }
+
+    /// Solves the challenge of a `JoinResponse::ResourceChallenge`, by finding a solution
+    /// which hashed along with the nonce and the challenge data gives a hash with at least
+    /// `difficulty` leading zero bits.
+    pub fn solve(
+        data_size: usize,
+        difficulty: u8,
+        nonce: [u8; 32],
+        nonce_signature: Signature,
+    ) -> Self {
+        let data = challenge_data(&nonce, data_size);
+        let solution = (0..)
+            .find(|solution| {
+                leading_zeros(&proof_hash(&nonce, &data, *solution)) >= difficulty.into()
+            })
+            .unwrap_or_default();
+
+        Self {
+            solution,
+            data,
+            nonce,
+            nonce_signature,
+        }
+    }
+
+    /// Verifies that this response solves the challenge of the given size and difficulty,
+    /// and that its nonce was signed with the given key, i.e. the challenge was issued by
+    /// its holder.
+    pub fn verify(&self, data_size: usize, difficulty: u8, challenger: &PublicKey) -> bool {
+        challenger
+            .verify(&self.nonce, &self.nonce_signature)
+            .is_ok()
+            && self.data == challenge_data(&self.nonce, data_size)
+            && leading_zeros(&proof_hash(&self.nonce, &self.data, self.solution))
+                >= difficulty.into()
+    }
+}
+
+// Deterministically expands the nonce into the data to be hashed for the challenge.
+fn challenge_data(nonce: &[u8; 32], data_size: usize) -> VecDeque<u8> {
+    let mut data = VecDeque::with_capacity(data_size);
+    let mut block = *nonce;
+    while data.len() < data_size {
+        let mut hasher = Sha3::v256();
+        hasher.update(&block);
+        hasher.finalize(&mut block);
+        data.extend(block.iter().take(data_size - data.len()));
+    }
+    data
+}
+
+fn proof_hash(nonce: &[u8; 32], data: &VecDeque<u8>, solution: u64) -> [u8; 32] {
+    let mut hasher = Sha3::v256();
+    hasher.update(nonce);
+    let (front, back) = data.as_slices();
+    hasher.update(front);
+    hasher.update(back);
+    hasher.update(&solution.to_be_bytes());
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+fn leading_zeros(hash: &[u8; 32]) -> u32 {
+    let mut zeros = 0;
+    for byte in hash {
+        zeros += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    zeros
+}
+
 /// Response to a request to join a section
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -119,3 +202,28 @@ pub enum JoinRejectionReason {
     /// The requesting node is not externally reachable
     NodeNotReachable(SocketAddr),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Keypair, Signer};
+
+    #[test]
+    fn resource_proof_solution() {
+        let elder = Keypair::generate(&mut rand::thread_rng());
+        let nonce = [7; 32];
+        let (data_size, difficulty) = (100, 8);
+
+        let mut response =
+            ResourceProofResponse::solve(data_size, difficulty, nonce, elder.sign(&nonce));
+        assert_eq!(response.data.len(), data_size);
+        assert!(response.verify(data_size, difficulty, &elder.public));
+
+        let other = Keypair::generate(&mut rand::thread_rng());
+        assert!(!response.verify(data_size, difficulty, &other.public));
+        assert!(!response.verify(data_size + 1, difficulty, &elder.public));
+
+        let _ = response.data.pop_back();
+        assert!(!response.verify(data_size, difficulty, &elder.public));
+    }
+}
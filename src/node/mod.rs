@@ -8,34 +8,54 @@
 // Software.
 
 mod agreement;
+mod end_user;
+mod envelope;
+mod forwarded;
+mod genesis;
 mod join;
 mod join_as_relocated;
 mod network;
 mod node_msg;
 mod plain_message;
-mod prefix_map;
 mod relocation;
+mod rewards;
 mod section;
-mod signature_aggregator;
-mod signed;
+mod section_chain;
+mod section_keys;
 mod src_authority;
 mod variant;
 
-pub use agreement::{DkgFailureSigned, DkgFailureSignedSet, DkgKey, Proposal, SectionSigned};
+pub use crate::{
+    prefix_map::PrefixMap,
+    section_info::SectionKeyProof,
+    signature_aggregator::{Error, SignatureAggregator},
+    signed::{Signed, SignedShare},
+};
+pub use agreement::{
+    DkgFailureSigned, DkgFailureSignedSet, DkgKey, Proposal, SectionSigned, Vote, VoteAggregator,
+};
+pub use end_user::EndUserRegistry;
+pub use envelope::NodeMsgEnvelope;
+pub use forwarded::{Forwarded, MsgSender};
+pub use genesis::GenesisMsg;
 pub use join::{JoinRejectionReason, JoinRequest, JoinResponse, ResourceProofResponse};
 pub use join_as_relocated::{JoinAsRelocatedRequest, JoinAsRelocatedResponse};
-pub use network::{Network, OtherSection};
+pub use network::{Network, NetworkStats, OtherSection};
 pub use node_msg::{
     NodeCmd, NodeCmdError, NodeDataError, NodeDataQueryResponse, NodeEvent, NodeMsg, NodeQuery,
-    NodeQueryResponse, NodeRewardQuery, NodeSystemCmd, NodeSystemQuery, NodeSystemQueryResponse,
-    NodeTransferCmd, NodeTransferError, NodeTransferQuery, NodeTransferQueryResponse,
+    NodeQueryResponse, NodeRewardQuery, NodeRewardQueryResponse, NodeSystemCmd, NodeSystemQuery,
+    NodeSystemQueryResponse, NodeTransferCmd, NodeTransferError, NodeTransferQuery,
+    NodeTransferQueryResponse, RewardPayout, UndeliverableReason,
 };
 pub use plain_message::PlainMessage;
-pub use prefix_map::PrefixMap;
 pub use relocation::{RelocateDetails, RelocatePayload, RelocatePromise, SignedRelocateDetails};
-pub use section::{ElderCandidates, MembershipState, NodeState, Peer, Section, SectionPeers};
-pub use signature_aggregator::{Error, SignatureAggregator};
-pub use signed::{Signed, SignedShare};
+pub use rewards::RewardProposals;
+pub use section::{
+    ElderCandidates, MembershipState, NodeState, Peer, Penalty, PenaltyReason, Section,
+    SectionPeers,
+};
+pub use section_chain::{ChainRetention, MergeError, SectionChainExt};
+pub use section_keys::{Secret, SectionKeyShare, SectionKeysProvider};
 pub use src_authority::SrcAuthority;
 pub use variant::Variant;
 
@@ -44,7 +64,6 @@ use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Debug, Formatter};
 use threshold_crypto::PublicKey as BlsPublicKey;
-use xor_name::XorName;
 
 /// Routing message sent over the network.
 #[derive(Clone, Eq, Serialize, Deserialize)]
@@ -81,7 +100,11 @@ impl RoutingMsg {
     }
 
     /// serialize this RoutingMsg into bytes ready to be sent over the wire.
-    pub fn serialize(&self, dest: XorName, dest_section_pk: BlsPublicKey) -> crate::Result<Bytes> {
+    pub fn serialize(
+        &self,
+        dest: DstLocation,
+        dest_section_pk: BlsPublicKey,
+    ) -> crate::Result<Bytes> {
         WireMsg::serialize_routing_msg(self, dest, dest_section_pk)
     }
 }
@@ -6,10 +6,15 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{agreement::SectionSigned, prefix_map::PrefixMap, signed::Signed};
-use crate::SectionAuthorityProvider;
+use super::agreement::SectionSigned;
+use crate::prefix_map::PrefixMap;
+use crate::{SectionAuthorityProvider, Signed};
 use serde::{Deserialize, Serialize};
-use std::borrow::Borrow;
+use std::{
+    borrow::Borrow,
+    fmt::{self, Display, Formatter},
+    iter,
+};
 use xor_name::Prefix;
 
 /// Container for storing information about other sections in the network.
@@ -19,6 +24,78 @@ pub struct Network {
     pub sections: PrefixMap<OtherSection>,
 }
 
+impl Network {
+    /// Returns network statistics.
+    pub fn network_stats(&self, our: &SectionAuthorityProvider) -> NetworkStats {
+        let (known_elders, total_elders, total_elders_exact) = self.network_elder_counts(our);
+
+        NetworkStats {
+            known_elders,
+            total_elders,
+            total_elders_exact,
+        }
+    }
+
+    /// Compute an estimate of the total number of elders in the network from the sections we
+    /// know of, including ours.
+    ///
+    /// Returns (known, total, exact), where `exact` indicates whether `total` is an exact number
+    /// or an estimate.
+    pub fn network_elder_counts(&self, our: &SectionAuthorityProvider) -> (u64, u64, bool) {
+        let known_prefixes = iter::once(&our.prefix).chain(
+            self.sections
+                .iter()
+                .map(|info| &info.section_auth.value.prefix),
+        );
+        let is_exact = Prefix::default().is_covered_by(known_prefixes.clone());
+
+        // Estimated fraction of the network that we know of.
+        // Computed as the sum of 1 / 2^(prefix.bit_count) for all known section prefixes.
+        let network_fraction: f64 = known_prefixes
+            .map(|prefix| 1.0 / (prefix.bit_count() as f64).exp2())
+            .sum();
+
+        let known = our.elders.len()
+            + self
+                .sections
+                .iter()
+                .map(|info| info.section_auth.value.elders.len())
+                .sum::<usize>();
+        let total = known as f64 / network_fraction;
+
+        (known as u64, total.ceil() as u64, is_exact)
+    }
+}
+
+/// Network statistics, estimated by a node from the sections it knows of.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct NetworkStats {
+    /// Number of elders the node knows of.
+    pub known_elders: u64,
+    /// Estimated total number of elders in the network.
+    pub total_elders: u64,
+    /// Whether `total_elders` is exact, i.e. the node knows of the whole network.
+    pub total_elders_exact: bool,
+}
+
+impl Display for NetworkStats {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.total_elders_exact {
+            write!(
+                f,
+                "*** Known elders: {}, Total: {} ***",
+                self.known_elders, self.total_elders
+            )
+        } else {
+            write!(
+                f,
+                "*** Known elders: {}, Estimated total: {} ***",
+                self.known_elders, self.total_elders
+            )
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub struct OtherSection {
     // If this is signed by our section, then `key_signed` is `None`. If this is signed by our
@@ -33,3 +110,59 @@ impl Borrow<Prefix> for OtherSection {
         &self.section_auth.value.prefix
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use threshold_crypto::{SecretKey, SecretKeySet};
+    use xor_name::XorName;
+
+    fn section_auth(prefix: Prefix, elder_count: u16) -> SectionAuthorityProvider {
+        SectionAuthorityProvider {
+            prefix,
+            public_key_set: SecretKeySet::random(0, &mut rand::thread_rng()).public_keys(),
+            elders: (0..elder_count)
+                .map(|port| (XorName::random(), ([127, 0, 0, 1], 12000 + port).into()))
+                .collect(),
+            generation: 0,
+        }
+    }
+
+    fn other_section(prefix: Prefix, elder_count: u16) -> OtherSection {
+        let secret_key = SecretKey::random();
+        OtherSection {
+            section_auth: SectionSigned::new(
+                section_auth(prefix, elder_count),
+                Signed {
+                    public_key: secret_key.public_key(),
+                    signature: secret_key.sign(b""),
+                },
+            ),
+            key_signed: None,
+        }
+    }
+
+    #[test]
+    fn network_stats() {
+        let root = Prefix::default();
+        let our = section_auth(root.pushed(false).pushed(false), 5);
+        let mut network = Network {
+            sections: PrefixMap::new(),
+        };
+        let _ = network
+            .sections
+            .insert(other_section(root.pushed(false).pushed(true), 7));
+
+        // We know half of the network, with 12 elders.
+        let stats = network.network_stats(&our);
+        assert_eq!(stats.known_elders, 12);
+        assert_eq!(stats.total_elders, 24);
+        assert!(!stats.total_elders_exact);
+
+        let _ = network.sections.insert(other_section(root.pushed(true), 6));
+        let stats = network.network_stats(&our);
+        assert_eq!(stats.known_elders, 18);
+        assert_eq!(stats.total_elders, 18);
+        assert!(stats.total_elders_exact);
+    }
+}
@@ -11,21 +11,32 @@
 // Error defined for the crate::node instead of client Result/Error
 use crate::client::{CmdError, Error, Result};
 use crate::{
+    canonical::signing_bytes,
     client::{
-        BlobRead, BlobWrite, ClientSigned, DataCmd as NodeDataCmd, DataExchange,
+        BlobRead, BlobWrite, ClientSigned, DataCmd as NodeDataCmd, DataCmdKind, DataExchange,
         DataQuery as NodeDataQuery,
     },
-    EndUser, MessageId, MessageType, WireMsg,
+    node::{Forwarded, NetworkStats, SectionSigned},
+    serialisation::{
+        unknown::{self, ForwardCompatible},
+        wire_msg_header::WireMsgHeader,
+    },
+    DstLocation, EndUser, MessageId, MessageType, SectionAuthorityProvider, Signed, WireMsg,
 };
 use bytes::Bytes;
-use serde::{Deserialize, Serialize};
+use secured_linked_list::SecuredLinkedList;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sn_data_types::{
-    ActorHistory, Blob, BlobAddress, CreditAgreementProof, NodeAge, PublicKey, ReplicaEvent,
-    SectionElders, Signature,
+    register::Address as RegisterAddress, ActorHistory, Blob, BlobAddress, CreditAgreementProof,
+    CreditId, DataAddress, Keypair, MapAddress, NodeAge, PublicKey, ReplicaEvent,
+    RewardAccumulation, RewardProposal, SectionElders, SequenceAddress, Signature, Token,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
 };
-use std::collections::BTreeMap;
-use threshold_crypto::PublicKey as BlsPublicKey;
-use xor_name::XorName;
+use threshold_crypto::{PublicKey as BlsPublicKey, PublicKeySet};
+use xor_name::{Prefix, XorName};
 
 // -------------- Node Cmd Messages --------------
 // TODO: this messages hierarchy needs to be merged into
@@ -87,6 +98,26 @@ pub enum NodeMsg {
         /// ID of causing cmd.
         correlation_id: MessageId,
     },
+    /// A client message relayed by an elder, with the bytes signed by the client.
+    Forwarded {
+        /// The relayed message.
+        forwarded: Forwarded,
+        /// Message ID.
+        id: MessageId,
+    },
+    /// A message signed with a section key its recipient doesn't trust, returned to its
+    /// sender along with the latest key of that section the recipient knows, so the
+    /// sender can resend it with a proof chain extending from that key.
+    BouncedUntrusted {
+        /// The serialized message, as received.
+        msg: Bytes,
+        /// Latest key of the sender's section known to the recipient.
+        known_key: BlsPublicKey,
+        /// Message ID.
+        id: MessageId,
+        /// ID of the bounced message.
+        correlation_id: MessageId,
+    },
 }
 
 impl NodeMsg {
@@ -98,10 +129,37 @@ impl NodeMsg {
             | Self::NodeEvent { id, .. }
             | Self::NodeQueryResponse { id, .. }
             | Self::NodeCmdError { id, .. }
-            | Self::NodeMsgError { id, .. } => *id,
+            | Self::NodeMsgError { id, .. }
+            | Self::Forwarded { id, .. }
+            | Self::BouncedUntrusted { id, .. } => *id,
         }
     }
 
+    /// Gets the ID of the message this one responds to, if any.
+    pub fn correlation_id(&self) -> Option<MessageId> {
+        match self {
+            Self::NodeCmd { .. } | Self::NodeQuery { .. } | Self::Forwarded { .. } => None,
+            Self::NodeEvent { correlation_id, .. }
+            | Self::NodeQueryResponse { correlation_id, .. }
+            | Self::NodeCmdError { correlation_id, .. }
+            | Self::NodeMsgError { correlation_id, .. }
+            | Self::BouncedUntrusted { correlation_id, .. } => Some(*correlation_id),
+        }
+    }
+
+    /// Returns the message bouncing the given one, received as `bytes`, whose source section
+    /// key isn't trusted, back to its sender along with the latest key of its section we know.
+    /// The bytes are bounced unchanged; only the header is read for the ID of the message.
+    pub fn bounce_untrusted(bytes: Bytes, known_key: BlsPublicKey) -> crate::Result<Self> {
+        let (header, _) = WireMsgHeader::from(bytes.clone())?;
+        Ok(Self::BouncedUntrusted {
+            msg: bytes,
+            known_key,
+            id: MessageId::new(),
+            correlation_id: header.msg_id(),
+        })
+    }
+
     /// Convenience function to deserialize a 'NodeMsg' from bytes received over the wire.
     /// It returns an error if the bytes don't correspond to a node command message.
     pub fn from(bytes: Bytes) -> crate::Result<Self> {
@@ -118,7 +176,7 @@ impl NodeMsg {
     /// serialize this NodeCmd message into bytes ready to be sent over the wire.
     pub fn serialize(
         &self,
-        dest: XorName,
+        dest: DstLocation,
         dest_section_pk: BlsPublicKey,
         src_section_pk: Option<BlsPublicKey>,
     ) -> crate::Result<Bytes> {
@@ -129,6 +187,8 @@ impl NodeMsg {
 ///
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+#[non_exhaustive]
 pub enum NodeCmd {
     /// Metadata is handled by Elders
     Metadata {
@@ -146,6 +206,90 @@ pub enum NodeCmd {
     Transfers(NodeTransferCmd),
     /// Cmds related to the running of a node.
     System(NodeSystemCmd),
+    /// A variant introduced by a newer version, which this version can't process.
+    /// It is kept as received, Msgpack encoded, so it can still be forwarded.
+    #[serde(skip)]
+    Unknown {
+        /// Index of the variant.
+        tag: u32,
+        /// Content of the variant.
+        bytes: Vec<u8>,
+    },
+}
+
+impl ForwardCompatible for NodeCmd {
+    const NAME: &'static str = "NodeCmd";
+    const VARIANTS: &'static [&'static str] = &["Metadata", "Chunks", "Transfers", "System"];
+
+    fn deserialize_known<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::deserialize(deserializer)
+    }
+
+    fn unknown(tag: u32, bytes: Vec<u8>) -> Self {
+        Self::Unknown { tag, bytes }
+    }
+}
+
+impl Serialize for NodeCmd {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Unknown { tag, bytes } => {
+                unknown::serialize_unknown(Self::NAME, *tag, bytes, serializer)
+            }
+            _ => Self::serialize(self, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for NodeCmd {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        unknown::deserialize(deserializer)
+    }
+}
+
+impl fmt::Display for NodeCmd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Metadata { cmd, origin, .. } => write!(
+                f,
+                "NodeCmd::Metadata({}, dst={}, origin={})",
+                cmd,
+                cmd.dst_address(),
+                origin.xorname
+            ),
+            Self::Chunks { cmd, origin, .. } => write!(
+                f,
+                "NodeCmd::Chunks(Blob::{:?}, dst={}, origin={})",
+                cmd.kind(),
+                cmd.dst_address(),
+                origin.xorname
+            ),
+            Self::Transfers(cmd) => {
+                let name = match cmd {
+                    NodeTransferCmd::PropagateTransfer(_) => "PropagateTransfer",
+                    NodeTransferCmd::PropagateTransfers { .. } => "PropagateTransfers",
+                };
+                write!(f, "NodeCmd::Transfers({})", name)
+            }
+            Self::System(cmd) => {
+                let name = match cmd {
+                    NodeSystemCmd::RegisterWallet { .. } => "RegisterWallet",
+                    NodeSystemCmd::StorageFull { .. } => "StorageFull",
+                    NodeSystemCmd::ReplicateChunk(_) => "ReplicateChunk",
+                    NodeSystemCmd::RepublishChunk(_) => "RepublishChunk",
+                    NodeSystemCmd::ProposeRewardPayout { .. } => "ProposeRewardPayout",
+                    NodeSystemCmd::AccumulateRewardPayout { .. } => "AccumulateRewardPayout",
+                    NodeSystemCmd::ReceiveExistingData { .. } => "ReceiveExistingData",
+                    NodeSystemCmd::SectionSplit { .. } => "SectionSplit",
+                    NodeSystemCmd::ReplicateChunksFrom { .. } => "ReplicateChunksFrom",
+                    NodeSystemCmd::BeginDecommission { .. } => "BeginDecommission",
+                    NodeSystemCmd::AbandonQuery { .. } => "AbandonQuery",
+                };
+                write!(f, "NodeCmd::System({})", name)
+            }
+            Self::Unknown { tag, .. } => write!(f, "NodeCmd::Unknown(tag={})", tag),
+        }
+    }
 }
 
 /// Cmds related to the running of a node.
@@ -153,7 +297,17 @@ pub enum NodeCmd {
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum NodeSystemCmd {
     /// Register a wallet for reward payouts.
-    RegisterWallet(PublicKey),
+    RegisterWallet {
+        /// The wallet receiving the rewards of the node.
+        wallet: PublicKey,
+        /// Public key of the registering node.
+        node: PublicKey,
+        /// Strictly increasing for each registration of the node, so that
+        /// a previous registration can't be replayed.
+        nonce: u64,
+        /// Signature of the node over the wallet and nonce.
+        signature: Signature,
+    },
     /// Notify Elders on nearing max capacity
     StorageFull {
         /// Node Id
@@ -166,9 +320,23 @@ pub enum NodeSystemCmd {
     /// Tells the Elders to re-publish a chunk in the data section
     RepublishChunk(Blob),
     /// When new section key, all propose a reward payout.
-    ProposeRewardPayout(sn_data_types::RewardProposal),
+    ProposeRewardPayout {
+        /// The proposed rewards.
+        proposal: RewardProposal,
+        /// The reward epoch, incremented with each new section key.
+        epoch: u64,
+        /// Id of the proposal, see `NodeSystemCmd::reward_proposal_id`.
+        proposal_id: MessageId,
+    },
     /// When proposal has been agreed, they all accumulate the reward payout.
-    AccumulateRewardPayout(sn_data_types::RewardAccumulation),
+    AccumulateRewardPayout {
+        /// The agreed rewards.
+        accumulation: RewardAccumulation,
+        /// The reward epoch of the proposal.
+        epoch: u64,
+        /// Id of the accumulated proposal.
+        proposal_id: MessageId,
+    },
     /// Sent to all promoted nodes (also sibling if any) after
     /// a completed transition to a new constellation.
     ReceiveExistingData {
@@ -179,6 +347,203 @@ pub enum NodeSystemCmd {
         /// Metadata
         metadata: DataExchange,
     },
+    /// Sent by the elders of a section that just split, to inform
+    /// sibling and neighbour sections of both child sections at once.
+    SectionSplit {
+        /// The child section whose prefix ends with a `0` bit.
+        left: SectionSigned<SectionAuthorityProvider>,
+        /// The child section whose prefix ends with a `1` bit.
+        right: SectionSigned<SectionAuthorityProvider>,
+        /// Section chain proving the keys of both child sections.
+        chain: SecuredLinkedList,
+    },
+    /// Sent by the Elders to an Adult after churn, for it to fetch the given chunks
+    /// from their current holder. Progress is reported with `NodeEvent::ReplicationProgress`.
+    ReplicateChunksFrom {
+        /// Name of the Adult currently holding the chunks.
+        holder: XorName,
+        /// Addresses of the chunks to replicate.
+        addresses: Vec<BlobAddress>,
+    },
+    /// Sent by a node to its Elders when its operator shuts it down, for its chunks
+    /// to be handed over and its rewards settled before it goes offline.
+    BeginDecommission {
+        /// Name of the leaving node.
+        node: XorName,
+    },
+    /// Sent by the Elders to the Adults they forwarded a query to, once the client
+    /// cancelled it with `ProcessMsg::CancelQuery`, for them to stop processing it.
+    AbandonQuery {
+        /// Id of the `NodeMsg::NodeQuery` the query was forwarded with.
+        query_id: MessageId,
+    },
+}
+
+impl NodeSystemCmd {
+    /// Creates a wallet registration, signed by the registering node.
+    pub fn register_wallet(wallet: PublicKey, nonce: u64, node: &Keypair) -> crate::Result<Self> {
+        let bytes = Self::register_wallet_bytes(&wallet, nonce)?;
+        Ok(Self::RegisterWallet {
+            wallet,
+            node: node.public_key(),
+            nonce,
+            signature: node.sign(&bytes),
+        })
+    }
+
+    /// Returns the bytes a node signs to register its wallet.
+    pub fn register_wallet_bytes(wallet: &PublicKey, nonce: u64) -> crate::Result<Vec<u8>> {
+        signing_bytes("register-wallet", &(wallet, nonce))
+    }
+
+    /// Creates a reward payout proposal for the given epoch.
+    pub fn propose_reward_payout(proposal: RewardProposal, epoch: u64) -> crate::Result<Self> {
+        let credit_ids = proposal.rewards.iter().map(|share| *share.id()).collect();
+        let proposal_id = Self::reward_proposal_id(&proposal.section_key, epoch, credit_ids)?;
+        Ok(Self::ProposeRewardPayout {
+            proposal,
+            epoch,
+            proposal_id,
+        })
+    }
+
+    /// Creates the accumulation of an agreed reward payout proposal of the given epoch.
+    pub fn accumulate_reward_payout(
+        accumulation: RewardAccumulation,
+        epoch: u64,
+    ) -> crate::Result<Self> {
+        let credit_ids = accumulation
+            .rewards
+            .iter()
+            .map(|reward| *reward.signed_credit.id())
+            .collect();
+        let proposal_id = Self::reward_proposal_id(&accumulation.section_key, epoch, credit_ids)?;
+        Ok(Self::AccumulateRewardPayout {
+            accumulation,
+            epoch,
+            proposal_id,
+        })
+    }
+
+    /// Returns the id of a reward payout proposal, derived from the section key,
+    /// the epoch and the credits, so it's the same at every proposing Elder
+    /// and for the accumulation of the proposal.
+    pub fn reward_proposal_id(
+        section_key: &PublicKey,
+        epoch: u64,
+        credit_ids: BTreeSet<CreditId>,
+    ) -> crate::Result<MessageId> {
+        MessageId::from_content(&(section_key, epoch, credit_ids))
+    }
+
+    /// Returns the epoch and id of the proposal a reward payout cmd refers to.
+    pub fn reward_proposal(&self) -> Option<(u64, MessageId)> {
+        match self {
+            Self::ProposeRewardPayout {
+                epoch, proposal_id, ..
+            }
+            | Self::AccumulateRewardPayout {
+                epoch, proposal_id, ..
+            } => Some((*epoch, *proposal_id)),
+            _ => None,
+        }
+    }
+
+    /// Verifies the proofs carried by the cmd. The chain proving the keys of the sections
+    /// resulting from a split must be trusted through one of the `trusted_keys`.
+    /// For a wallet registration, the recipient still needs to check the signing
+    /// node is the sender, and the nonce is greater than the one last seen from it.
+    pub fn verify(&self, trusted_keys: &[BlsPublicKey]) -> bool {
+        match self {
+            Self::RegisterWallet {
+                wallet,
+                node,
+                nonce,
+                signature,
+            } => Self::register_wallet_bytes(wallet, *nonce)
+                .map(|bytes| node.verify(signature, bytes).is_ok())
+                .unwrap_or(false),
+            Self::ProposeRewardPayout {
+                proposal,
+                epoch,
+                proposal_id,
+            } => Self::reward_proposal_id(
+                &proposal.section_key,
+                *epoch,
+                proposal.rewards.iter().map(|share| *share.id()).collect(),
+            )
+            .map(|id| id == *proposal_id)
+            .unwrap_or(false),
+            Self::AccumulateRewardPayout {
+                accumulation,
+                epoch,
+                proposal_id,
+            } => Self::reward_proposal_id(
+                &accumulation.section_key,
+                *epoch,
+                accumulation
+                    .rewards
+                    .iter()
+                    .map(|reward| *reward.signed_credit.id())
+                    .collect(),
+            )
+            .map(|id| id == *proposal_id)
+            .unwrap_or(false),
+            Self::SectionSplit { left, right, chain } => {
+                let parent = left.value.prefix.popped();
+                left.value.prefix.bit_count() > 0
+                    && left.value.prefix == parent.pushed(false)
+                    && right.value.prefix == parent.pushed(true)
+                    && chain.check_trust(trusted_keys)
+                    && [left, right]
+                        .iter()
+                        .all(|section_auth| verify_section_auth(section_auth, chain))
+            }
+            // Only sent by a node about itself, which its signature on the routing message
+            // already authenticates.
+            Self::StorageFull { .. } | Self::BeginDecommission { .. } => true,
+            // Chunks are addressed by their content, which the recipient checks when storing them.
+            Self::ReplicateChunk(_) | Self::RepublishChunk(_) => true,
+            // Only acted upon when sent by our elders, as established by the section authority
+            // of the routing message; there is nothing else to prove.
+            Self::ReceiveExistingData { .. }
+            | Self::ReplicateChunksFrom { .. }
+            | Self::AbandonQuery { .. } => true,
+        }
+    }
+}
+
+fn verify_section_auth(
+    section_auth: &SectionSigned<SectionAuthorityProvider>,
+    chain: &SecuredLinkedList,
+) -> bool {
+    let public_key = section_auth.value.public_key_set.public_key();
+    section_auth.signed.public_key == public_key
+        && chain.has_key(&public_key)
+        && section_auth.verify()
+}
+
+/// Verifies the credit of the proof was signed by one of the `known_replicas`, the key sets
+/// of the replicas the recipient knows of, as anyone can sign a credit with a key set of their own.
+pub(crate) fn verify_credit_proof(
+    proof: &CreditAgreementProof,
+    known_replicas: &[PublicKeySet],
+) -> bool {
+    known_replicas.contains(&proof.debiting_replicas_keys) && verify_credit_signature(proof)
+}
+
+/// Verifies the signature of the debiting replicas over the signed credit of the proof,
+/// against the key set carried by the proof.
+pub(crate) fn verify_credit_signature(proof: &CreditAgreementProof) -> bool {
+    match (
+        &proof.debiting_replicas_sig,
+        bincode::serialize(&proof.signed_credit),
+    ) {
+        (Signature::Bls(sig), Ok(bytes)) => {
+            proof.debiting_replicas_keys.public_key().verify(sig, bytes)
+        }
+        _ => false,
+    }
 }
 
 ///
@@ -187,6 +552,43 @@ pub enum NodeSystemCmd {
 pub enum NodeTransferCmd {
     ///
     PropagateTransfer(CreditAgreementProof),
+    /// A batch of credits propagated at once, e.g. during reward payouts.
+    PropagateTransfers {
+        /// The credits being propagated.
+        credit_proofs: Vec<CreditAgreementProof>,
+        /// Signature of the sending section over the serialized batch of credits.
+        section_signed: Signed,
+    },
+}
+
+impl NodeTransferCmd {
+    /// Returns the bytes the sending section signs for a batch of credits.
+    pub fn credit_proofs_bytes(credit_proofs: &[CreditAgreementProof]) -> crate::Result<Vec<u8>> {
+        signing_bytes("propagate-transfers", credit_proofs)
+    }
+
+    /// Verifies each propagated credit was signed by one of the `known_replicas`, and that
+    /// a batch of credits was signed by the sending section with one of the `section_keys`
+    /// the recipient knows it by.
+    pub fn verify(&self, known_replicas: &[PublicKeySet], section_keys: &[BlsPublicKey]) -> bool {
+        match self {
+            Self::PropagateTransfer(credit_proof) => {
+                verify_credit_proof(credit_proof, known_replicas)
+            }
+            Self::PropagateTransfers {
+                credit_proofs,
+                section_signed,
+            } => {
+                credit_proofs
+                    .iter()
+                    .all(|credit_proof| verify_credit_proof(credit_proof, known_replicas))
+                    && section_keys.contains(&section_signed.public_key)
+                    && Self::credit_proofs_bytes(credit_proofs)
+                        .map(|bytes| section_signed.verify(&bytes))
+                        .unwrap_or(false)
+            }
+        }
+    }
 }
 
 // -------------- Node Events --------------
@@ -207,6 +609,134 @@ pub enum NodeEvent {
     },
     /// Adults ack read/write of chunks as to convey responsivity.
     ChunkWriteHandled(Result<(), CmdError>),
+    /// The elders of a section changed, emitted by the elders to adults,
+    /// clients and neighbour sections.
+    EldersChanged {
+        /// Prefix of the section.
+        prefix: Prefix,
+        /// The new section key.
+        key: BlsPublicKey,
+        /// Names of the promoted elders.
+        added: BTreeSet<XorName>,
+        /// Names of the demoted elders.
+        removed: BTreeSet<XorName>,
+        /// Names of the elders that kept their role.
+        remaining: BTreeSet<XorName>,
+        /// Signature of the section, with the key preceding the change,
+        /// over the serialized change.
+        proof: Signed,
+    },
+    /// Progress of a `NodeSystemCmd::ReplicateChunksFrom`, emitted by the replicating
+    /// Adult, received by elders.
+    ReplicationProgress {
+        /// Name of the Adult the chunks are replicated from.
+        holder: XorName,
+        /// Chunks replicated since the previous progress event.
+        replicated: Vec<BlobAddress>,
+        /// Chunks which couldn't be fetched from the holder.
+        failed: Vec<BlobAddress>,
+        /// Number of chunks still to be replicated.
+        remaining: usize,
+    },
+    /// The Elders accepted a `NodeSystemCmd::BeginDecommission` and started
+    /// handing over the chunks of the leaving node.
+    DecommissionAcknowledged {
+        /// Name of the leaving node.
+        node: XorName,
+    },
+    /// The chunks of the leaving node have been handed over and its rewards settled,
+    /// so it can go offline.
+    DecommissionCompleted {
+        /// Name of the leaving node.
+        node: XorName,
+        /// Rewards paid out to the wallet of the node.
+        reward: Token,
+    },
+    /// A message couldn't be delivered as its destination is unreachable, emitted by
+    /// the routing layer back to the elder which sent it, so it learns about the black
+    /// hole rather than waiting for a timeout.
+    Undeliverable {
+        /// ID of the undelivered message.
+        original_id: MessageId,
+        /// Destination of the undelivered message.
+        dst: DstLocation,
+        /// Why the message couldn't be delivered.
+        reason: UndeliverableReason,
+    },
+}
+
+/// Why a message couldn't be delivered, as reported by `NodeEvent::Undeliverable`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum UndeliverableReason {
+    /// No known section is closer to the destination, e.g. as it's outside
+    /// of the network known to the routing node.
+    NoRoute,
+    /// The destination peer, or all the elders of the destination section,
+    /// couldn't be connected to.
+    PeerUnreachable,
+}
+
+impl NodeEvent {
+    /// Returns the bytes the section signs for an elders change.
+    pub fn elders_changed_bytes(
+        prefix: &Prefix,
+        key: &BlsPublicKey,
+        added: &BTreeSet<XorName>,
+        removed: &BTreeSet<XorName>,
+        remaining: &BTreeSet<XorName>,
+    ) -> crate::Result<Vec<u8>> {
+        signing_bytes("elders-changed", &(prefix, key, added, removed, remaining))
+    }
+
+    /// Returns the event reporting that the given message couldn't be delivered,
+    /// to send back to its sender.
+    pub fn undeliverable(original: &WireMsg, reason: UndeliverableReason) -> NodeMsg {
+        NodeMsg::NodeEvent {
+            event: Self::Undeliverable {
+                original_id: original.msg_id(),
+                dst: original.dest(),
+                reason,
+            },
+            id: MessageId::new(),
+            correlation_id: original.msg_id(),
+        }
+    }
+
+    /// Returns whether the event reports the end of a chunk replication.
+    pub fn is_replication_done(&self) -> bool {
+        matches!(self, Self::ReplicationProgress { remaining: 0, .. })
+    }
+
+    /// Verifies the proof carried by the event. An elders change must be signed with one of
+    /// the `section_keys` the recipient knows the section by, i.e. the key preceding the change.
+    pub fn verify(&self, section_keys: &[BlsPublicKey]) -> bool {
+        match self {
+            Self::EldersChanged {
+                prefix,
+                key,
+                added,
+                removed,
+                remaining,
+                proof,
+            } => {
+                section_keys.contains(&proof.public_key)
+                    && Self::elders_changed_bytes(prefix, key, added, removed, remaining)
+                        .map(|bytes| proof.verify(&bytes))
+                        .unwrap_or(false)
+            }
+            // The proof is the signature of the recipient elders themselves, so they check it
+            // against their own section key.
+            Self::ReplicationCompleted { .. } => true,
+            // Reports of a node about its own work, authenticated by its signature on the
+            // routing message.
+            Self::ChunkWriteHandled(_) | Self::ReplicationProgress { .. } => true,
+            // Replies of our elders to a request of the recipient, which only trusts them
+            // under the section authority of the routing message.
+            Self::DecommissionAcknowledged { .. } | Self::DecommissionCompleted { .. } => true,
+            // Emitted by the routing layer of the recipient's own peer, nothing to prove.
+            Self::Undeliverable { .. } => true,
+        }
+    }
 }
 
 ///
@@ -237,6 +767,8 @@ pub enum NodeRewardQuery {
     /// its peer Elders for the replicas' public key set
     /// and the history of events of the section wallet.
     GetSectionWalletHistory,
+    /// Get the rewards paid out to the wallet of the node with the given name.
+    GetRewardHistory(XorName),
 }
 
 ///
@@ -246,6 +778,14 @@ pub enum NodeTransferQuery {
     /// need to query for events of
     /// the existing Replicas. (Sent to the other Elders).
     GetReplicaEvents,
+    /// Get a page of the events of the existing Replicas,
+    /// so large histories can be synced incrementally.
+    GetReplicaEventsSince {
+        /// Index of the first event to return, i.e. the number of events already synced.
+        since_index: usize,
+        /// Maximum number of events to return.
+        max_items: usize,
+    },
 }
 
 ///
@@ -257,6 +797,8 @@ pub enum NodeSystemQuery {
     /// Acquire the chunk from current holders for replication.
     /// providing the address of the blob to be replicated.
     GetChunk(BlobAddress),
+    /// Get the recipient's estimate of the network size.
+    GetNetworkStats,
 }
 
 ///
@@ -264,9 +806,48 @@ pub enum NodeSystemQuery {
 pub enum NodeSystemQueryResponse {
     /// On Elder change, all Elders need to query
     /// network for the new wallet's replicas' public key set
-    GetSectionElders(SectionElders),
+    GetSectionElders {
+        /// The elders of the section.
+        elders: SectionElders,
+        /// Section chain proving the key which signed the elders.
+        proof_chain: SecuredLinkedList,
+        /// Signature of the section over the elders.
+        signed: Signed,
+    },
     /// Respond elders with the requested chunk for replication
     GetChunk(Blob),
+    /// The recipient's estimate of the network size.
+    GetNetworkStats(NetworkStats),
+}
+
+impl NodeSystemQueryResponse {
+    /// Returns the bytes a section signs for its elders.
+    pub fn section_elders_bytes(elders: &SectionElders) -> crate::Result<Vec<u8>> {
+        signing_bytes("section-elders", elders)
+    }
+
+    /// Verifies that the elders were signed with their own section key, and that the proof
+    /// chain connects this key to one of the trusted keys.
+    pub fn verify(&self, trusted_keys: &[BlsPublicKey]) -> bool {
+        match self {
+            Self::GetSectionElders {
+                elders,
+                proof_chain,
+                signed,
+            } => {
+                signed.public_key == elders.key()
+                    && proof_chain.has_key(&signed.public_key)
+                    && proof_chain.check_trust(trusted_keys)
+                    && Self::section_elders_bytes(elders)
+                        .map(|bytes| signed.verify(&bytes))
+                        .unwrap_or(false)
+            }
+            // The chunk is checked against the address it was queried at.
+            Self::GetChunk(_) => true,
+            // Statistics are an estimate of the responding node, there is nothing to prove.
+            Self::GetNetworkStats(_) => true,
+        }
+    }
 }
 
 ///
@@ -279,6 +860,26 @@ pub enum NodeQueryResponse {
     Transfers(NodeTransferQueryResponse),
     ///
     System(NodeSystemQueryResponse),
+    ///
+    Rewards(NodeRewardQueryResponse),
+}
+
+/// Reward query responses.
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub enum NodeRewardQueryResponse {
+    /// The rewards paid out to a node wallet, oldest first.
+    GetRewardHistory(Result<Vec<RewardPayout>>),
+}
+
+/// A reward paid out to a node wallet.
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RewardPayout {
+    /// Key of the section during whose epoch the reward was paid out.
+    pub section_key: BlsPublicKey,
+    /// The wallet the reward was paid to.
+    pub wallet: PublicKey,
+    /// The amount paid out.
+    pub amount: Token,
 }
 
 ///
@@ -289,6 +890,9 @@ pub enum NodeTransferQueryResponse {
     /// need to query for events of
     /// the existing Replicas.
     GetReplicaEvents(Result<Vec<ReplicaEvent>>),
+    /// A page of the events of the existing Replicas, along with
+    /// the index to query the next page from, if there are any more.
+    GetReplicaEventsSince(Result<(Vec<ReplicaEvent>, Option<usize>)>),
 }
 
 ///
@@ -319,6 +923,60 @@ pub enum NodeDataError {
         ///
         error: Error,
     },
+    /// Failure of an elder to write a client cmd to the metadata it holds.
+    MetadataWrite {
+        /// Address of the data.
+        address: DataAddress,
+        /// Kind of operation of the cmd.
+        op: DataCmdKind,
+        /// The cause.
+        error: Error,
+    },
+    /// Failure to sync a Map handed over during churn.
+    MapSync {
+        /// Address of the Map.
+        address: MapAddress,
+        /// The cause.
+        error: Error,
+    },
+    /// Failure to sync a Sequence handed over during churn.
+    SequenceSync {
+        /// Address of the Sequence.
+        address: SequenceAddress,
+        /// The cause.
+        error: Error,
+    },
+    /// Failure to sync a Register handed over during churn.
+    RegisterSync {
+        /// Address of the Register.
+        address: RegisterAddress,
+        /// The cause.
+        error: Error,
+    },
+}
+
+impl NodeDataError {
+    /// Returns the address of the data the error is about.
+    pub fn address(&self) -> DataAddress {
+        match self {
+            Self::ChunkReplication { address, .. } => DataAddress::Blob(*address),
+            Self::MetadataWrite { address, .. } => address.clone(),
+            Self::MapSync { address, .. } => DataAddress::Map(*address),
+            Self::SequenceSync { address, .. } => DataAddress::Sequence(*address),
+            Self::RegisterSync { address, .. } => DataAddress::Register(*address),
+        }
+    }
+
+    /// Returns the cause of the error.
+    pub fn error(&self) -> &Error {
+        match self {
+            Self::ChunkReplication { error, .. }
+            | Self::MetadataWrite { error, .. }
+            | Self::MapSync { error, .. }
+            | Self::SequenceSync { error, .. }
+            | Self::RegisterSync { error, .. } => error,
+        }
+    }
 }
 
 ///
@@ -327,3 +985,427 @@ pub enum NodeTransferError {
     /// The error of propagation of TransferRegistered event.
     TransferPropagation(Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::DataError;
+    use anyhow::{anyhow, Result};
+    use crdts::Dot;
+    use sn_data_types::{Credit, Debit, SignedCredit};
+    use threshold_crypto::{SecretKey, SecretKeySet};
+
+    #[test]
+    fn bounce_untrusted_msg() -> Result<()> {
+        let original = NodeMsg::NodeCmd {
+            cmd: NodeCmd::System(NodeSystemCmd::BeginDecommission {
+                node: XorName::random(),
+            }),
+            id: MessageId::new(),
+        };
+        let wire_msg = WireMsg::new_node_msg(
+            &original,
+            DstLocation::Section(XorName::random()),
+            SecretKey::random().public_key(),
+            Some(SecretKey::random().public_key()),
+        )?;
+        let known_key = SecretKey::random().public_key();
+        let bytes = wire_msg.serialize()?;
+
+        let bounced = NodeMsg::bounce_untrusted(bytes.clone(), known_key)?;
+        assert_eq!(bounced.correlation_id(), Some(original.id()));
+        match bounced {
+            NodeMsg::BouncedUntrusted {
+                msg,
+                known_key: key,
+                ..
+            } => {
+                assert_eq!(key, known_key);
+                assert_eq!(msg, bytes);
+            }
+            other => return Err(anyhow!("unexpected message: {:?}", other)),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn undeliverable_event() -> Result<()> {
+        let dst = DstLocation::Section(XorName::random());
+        let original = NodeMsg::NodeCmd {
+            cmd: NodeCmd::System(NodeSystemCmd::BeginDecommission {
+                node: XorName::random(),
+            }),
+            id: MessageId::new(),
+        };
+        let wire_msg =
+            WireMsg::new_node_msg(&original, dst, SecretKey::random().public_key(), None)?;
+
+        let nack = NodeEvent::undeliverable(&wire_msg, UndeliverableReason::NoRoute);
+        assert_eq!(nack.correlation_id(), Some(original.id()));
+        assert_eq!(
+            nack,
+            NodeMsg::NodeEvent {
+                event: NodeEvent::Undeliverable {
+                    original_id: original.id(),
+                    dst,
+                    reason: UndeliverableReason::NoRoute,
+                },
+                id: nack.id(),
+                correlation_id: original.id(),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn node_data_error_address() {
+        let address = SequenceAddress::Public {
+            name: XorName::random(),
+            tag: 7,
+        };
+        let error = NodeDataError::SequenceSync {
+            address,
+            error: Error::Data(DataError::OpNotCausallyReady),
+        };
+        assert_eq!(error.address(), DataAddress::Sequence(address));
+        assert_eq!(error.error(), &Error::Data(DataError::OpNotCausallyReady));
+
+        let address = DataAddress::Map(MapAddress::Seq {
+            name: XorName::random(),
+            tag: 3,
+        });
+        let error = NodeDataError::MetadataWrite {
+            address: address.clone(),
+            op: DataCmdKind::Edit,
+            error: Error::Data(DataError::InvalidSuccessor(2)),
+        };
+        assert_eq!(error.address(), address);
+    }
+
+    #[test]
+    fn verify_wallet_registration() -> Result<()> {
+        let node = Keypair::new_ed25519(&mut rand::thread_rng());
+        let wallet = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let cmd = NodeSystemCmd::register_wallet(wallet, 7, &node)?;
+        assert!(cmd.verify(&[]));
+
+        let spoofed = match cmd {
+            NodeSystemCmd::RegisterWallet {
+                node,
+                nonce,
+                signature,
+                ..
+            } => NodeSystemCmd::RegisterWallet {
+                wallet: Keypair::new_ed25519(&mut rand::thread_rng()).public_key(),
+                node,
+                nonce,
+                signature,
+            },
+            _ => return Err(anyhow!("unexpected cmd")),
+        };
+        assert!(!spoofed.verify(&[]));
+
+        Ok(())
+    }
+
+    fn credit_proof(replicas: &SecretKeySet) -> Result<CreditAgreementProof> {
+        let sender = Keypair::new_ed25519(&mut rand::thread_rng());
+        let debit = Debit {
+            id: Dot::new(sender.public_key(), 0),
+            amount: Token::from_nano(10),
+        };
+        let credit = Credit {
+            id: debit.credit_id()?,
+            amount: debit.amount,
+            recipient: PublicKey::from(SecretKey::random().public_key()),
+            msg: String::new(),
+        };
+        let signed_credit = SignedCredit {
+            actor_signature: sender.sign(&bincode::serialize(&credit)?),
+            credit,
+        };
+        let bytes = bincode::serialize(&signed_credit)?;
+        let shares: Vec<_> = (0..=replicas.threshold())
+            .map(|index| (index, replicas.secret_key_share(index).sign(&bytes)))
+            .collect();
+        let signature = replicas
+            .public_keys()
+            .combine_signatures(shares.iter().map(|(index, share)| (*index, share)))
+            .map_err(|err| anyhow!("could not combine signatures: {:?}", err))?;
+
+        Ok(CreditAgreementProof {
+            signed_credit,
+            debiting_replicas_sig: Signature::Bls(signature),
+            debiting_replicas_keys: replicas.public_keys(),
+        })
+    }
+
+    #[test]
+    fn verify_propagated_transfer() -> Result<()> {
+        let replicas = SecretKeySet::random(1, &mut rand::thread_rng());
+        let known_replicas = [replicas.public_keys()];
+        let proof = credit_proof(&replicas)?;
+        assert!(NodeTransferCmd::PropagateTransfer(proof.clone()).verify(&known_replicas, &[]));
+
+        let mut forged = proof;
+        forged.signed_credit.credit.amount = Token::from_nano(1_000);
+        assert!(!NodeTransferCmd::PropagateTransfer(forged).verify(&known_replicas, &[]));
+
+        let mut unknown_replicas = credit_proof(&replicas)?;
+        unknown_replicas.debiting_replicas_keys =
+            SecretKeySet::random(1, &mut rand::thread_rng()).public_keys();
+        assert!(!NodeTransferCmd::PropagateTransfer(unknown_replicas).verify(&known_replicas, &[]));
+
+        // A credit minted with a key set of the sender's own is validly signed, but not
+        // by replicas the recipient knows of.
+        let self_signed = credit_proof(&SecretKeySet::random(1, &mut rand::thread_rng()))?;
+        assert!(verify_credit_signature(&self_signed));
+        assert!(!NodeTransferCmd::PropagateTransfer(self_signed).verify(&known_replicas, &[]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_propagated_transfers() -> Result<()> {
+        let secret_key = SecretKey::random();
+        let replicas = SecretKeySet::random(1, &mut rand::thread_rng());
+        let known_replicas = [replicas.public_keys()];
+        let section_keys = [secret_key.public_key()];
+        let credit_proofs = vec![credit_proof(&replicas)?];
+        let bytes = NodeTransferCmd::credit_proofs_bytes(&credit_proofs)?;
+        let cmd = NodeTransferCmd::PropagateTransfers {
+            credit_proofs,
+            section_signed: Signed {
+                public_key: secret_key.public_key(),
+                signature: secret_key.sign(&bytes),
+            },
+        };
+        assert!(cmd.verify(&known_replicas, &section_keys));
+        assert!(!cmd.verify(&known_replicas, &[SecretKey::random().public_key()]));
+
+        // A batch of self-signed credits, signed by a section key of the sender's own.
+        let forger_replicas = SecretKeySet::random(1, &mut rand::thread_rng());
+        let forger_key = SecretKey::random();
+        let credit_proofs = vec![credit_proof(&forger_replicas)?];
+        let bytes = NodeTransferCmd::credit_proofs_bytes(&credit_proofs)?;
+        let forged = NodeTransferCmd::PropagateTransfers {
+            credit_proofs,
+            section_signed: Signed {
+                public_key: forger_key.public_key(),
+                signature: forger_key.sign(&bytes),
+            },
+        };
+        assert!(!forged.verify(&known_replicas, &section_keys));
+        assert!(!forged.verify(&[forger_replicas.public_keys()], &section_keys));
+
+        let other_key = SecretKey::random();
+        let cmd = NodeTransferCmd::PropagateTransfers {
+            credit_proofs: vec![credit_proof(&replicas)?],
+            section_signed: Signed {
+                public_key: other_key.public_key(),
+                signature: secret_key.sign(&bytes),
+            },
+        };
+        assert!(!cmd.verify(&known_replicas, &[other_key.public_key()]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_section_split() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let parent_key = SecretKey::random();
+        let mut chain = SecuredLinkedList::new(parent_key.public_key());
+
+        let mut child = |prefix: Prefix| -> Result<SectionSigned<SectionAuthorityProvider>> {
+            // With a threshold of zero, a single share is the full section signature.
+            let sk_set = SecretKeySet::random(0, &mut rng);
+            let public_key_set = sk_set.public_keys();
+            let public_key = public_key_set.public_key();
+            let value = SectionAuthorityProvider {
+                prefix,
+                public_key_set: public_key_set.clone(),
+                elders: BTreeMap::new(),
+                generation: 0,
+            };
+            let share = sk_set
+                .secret_key_share(0)
+                .sign(&SectionSigned::signable_bytes(&value)?);
+            let signature = public_key_set
+                .combine_signatures(std::iter::once((0, &share)))
+                .map_err(|err| anyhow!("could not combine signatures: {:?}", err))?;
+            let bytes = bincode::serialize(&public_key)?;
+            chain.insert(
+                &parent_key.public_key(),
+                public_key,
+                parent_key.sign(&bytes),
+            )?;
+            Ok(SectionSigned::new(
+                value,
+                Signed {
+                    public_key,
+                    signature,
+                },
+            ))
+        };
+
+        let parent = Prefix::default().pushed(true);
+        let left = child(parent.pushed(false))?;
+        let right = child(parent.pushed(true))?;
+        let stray = child(Prefix::default().pushed(false))?;
+
+        let trusted = [parent_key.public_key()];
+        let cmd = NodeSystemCmd::SectionSplit {
+            left: left.clone(),
+            right: right.clone(),
+            chain: chain.clone(),
+        };
+        assert!(cmd.verify(&trusted));
+        assert!(!cmd.verify(&[SecretKey::random().public_key()]));
+
+        let swapped = NodeSystemCmd::SectionSplit {
+            left: right.clone(),
+            right: left.clone(),
+            chain: chain.clone(),
+        };
+        assert!(!swapped.verify(&trusted));
+
+        let not_siblings = NodeSystemCmd::SectionSplit {
+            left: stray,
+            right: right.clone(),
+            chain: chain.clone(),
+        };
+        assert!(!not_siblings.verify(&trusted));
+
+        let unproven = NodeSystemCmd::SectionSplit {
+            left: left.clone(),
+            right: right.clone(),
+            chain: SecuredLinkedList::new(parent_key.public_key()),
+        };
+        assert!(!unproven.verify(&trusted));
+
+        // A well-formed chain rooted at a key of the sender's own isn't trusted.
+        let forger_key = SecretKey::random();
+        let mut forged_chain = SecuredLinkedList::new(forger_key.public_key());
+        for section_auth in &[&left, &right] {
+            let public_key = section_auth.value.public_key_set.public_key();
+            forged_chain.insert(
+                &forger_key.public_key(),
+                public_key,
+                forger_key.sign(&bincode::serialize(&public_key)?),
+            )?;
+        }
+        let untrusted = NodeSystemCmd::SectionSplit {
+            left,
+            right,
+            chain: forged_chain,
+        };
+        assert!(!untrusted.verify(&trusted));
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_elders_changed() -> Result<()> {
+        let section_key = SecretKey::random();
+        let prefix = Prefix::default().pushed(true);
+        let key = SecretKey::random().public_key();
+        let added: BTreeSet<_> = vec![XorName::random()].into_iter().collect();
+        let removed: BTreeSet<_> = vec![XorName::random()].into_iter().collect();
+        let remaining: BTreeSet<_> = (0..4).map(|_| XorName::random()).collect();
+        let bytes = NodeEvent::elders_changed_bytes(&prefix, &key, &added, &removed, &remaining)?;
+        let section_keys = [section_key.public_key()];
+        let event = NodeEvent::EldersChanged {
+            prefix,
+            key,
+            added: added.clone(),
+            removed: removed.clone(),
+            remaining: remaining.clone(),
+            proof: Signed {
+                public_key: section_key.public_key(),
+                signature: section_key.sign(&bytes),
+            },
+        };
+        assert!(event.verify(&section_keys));
+
+        // An event signed by any other key isn't the section's.
+        let random_key = SecretKey::random();
+        let forged = NodeEvent::EldersChanged {
+            prefix,
+            key,
+            added: added.clone(),
+            removed: removed.clone(),
+            remaining,
+            proof: Signed {
+                public_key: random_key.public_key(),
+                signature: random_key.sign(&bytes),
+            },
+        };
+        assert!(!forged.verify(&section_keys));
+
+        let tampered = match event {
+            NodeEvent::EldersChanged {
+                prefix, key, proof, ..
+            } => NodeEvent::EldersChanged {
+                prefix,
+                key,
+                added: removed,
+                removed: added,
+                remaining: BTreeSet::new(),
+                proof,
+            },
+            _ => unreachable!(),
+        };
+        assert!(!tampered.verify(&section_keys));
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_section_elders() -> Result<()> {
+        let genesis_key = SecretKey::random();
+        // With a threshold of zero, a single share is the full section signature.
+        let sk_set = SecretKeySet::random(0, &mut rand::thread_rng());
+        let key_set = sk_set.public_keys();
+        let section_key = key_set.public_key();
+        let mut proof_chain = SecuredLinkedList::new(genesis_key.public_key());
+        proof_chain.insert(
+            &genesis_key.public_key(),
+            section_key,
+            genesis_key.sign(&bincode::serialize(&section_key)?),
+        )?;
+
+        let elders = SectionElders {
+            prefix: Prefix::default().pushed(false),
+            names: (0..3).map(|_| XorName::random()).collect(),
+            key_set: key_set.clone(),
+        };
+        let share = sk_set
+            .secret_key_share(0)
+            .sign(NodeSystemQueryResponse::section_elders_bytes(&elders)?);
+        let signed = Signed {
+            public_key: section_key,
+            signature: key_set
+                .combine_signatures(std::iter::once((0, &share)))
+                .map_err(|err| anyhow!("could not combine signatures: {:?}", err))?,
+        };
+        let response = NodeSystemQueryResponse::GetSectionElders {
+            elders: elders.clone(),
+            proof_chain: proof_chain.clone(),
+            signed: signed.clone(),
+        };
+        assert!(response.verify(&[genesis_key.public_key()]));
+        assert!(!response.verify(&[SecretKey::random().public_key()]));
+
+        let mut bogus = elders;
+        let _ = bogus.names.insert(XorName::random());
+        let tampered = NodeSystemQueryResponse::GetSectionElders {
+            elders: bogus,
+            proof_chain,
+            signed,
+        };
+        assert!(!tampered.verify(&[genesis_key.public_key()]));
+
+        Ok(())
+    }
+}
@@ -9,7 +9,9 @@
 //! Relocation related types and utilities.
 
 use super::RoutingMsg;
+use crate::{Signable, SIGNABLE_VERSION};
 pub use ed25519_dalek::{Keypair, Signature, Verifier};
+use ed25519_dalek::{PublicKey, Signer};
 use serde::{Deserialize, Serialize};
 use threshold_crypto::PublicKey as BlsPublicKey;
 use xor_name::XorName;
@@ -42,6 +44,42 @@ pub struct RelocatePayload {
     pub details: SignedRelocateDetails,
     /// The new name of the node signed using its old public_key, to prove the node identity.
     pub signature_of_new_name_with_old_key: Signature,
+    /// Version of the layout the new name was signed with.
+    pub version: u16,
+}
+
+impl RelocatePayload {
+    /// Signs the new name of the relocated node with its old keypair.
+    pub fn new(
+        details: SignedRelocateDetails,
+        new_name: &XorName,
+        old_keypair: &Keypair,
+    ) -> crate::Result<Self> {
+        let bytes = Self::new_name_bytes(new_name)?;
+        Ok(Self {
+            details,
+            signature_of_new_name_with_old_key: old_keypair.sign(&bytes),
+            version: SIGNABLE_VERSION,
+        })
+    }
+
+    /// Returns the bytes a relocated node signs with its old key to prove its identity.
+    pub fn new_name_bytes(new_name: &XorName) -> crate::Result<Vec<u8>> {
+        Signable::new(new_name).bytes()
+    }
+
+    /// Verifies the new name was signed with the old key of the relocated node,
+    /// laid out with the version it was signed with.
+    pub fn verify_identity(&self, new_name: &XorName, old_public_key: &PublicKey) -> bool {
+        Signable::with_version(self.version, new_name)
+            .bytes()
+            .map(|bytes| {
+                old_public_key
+                    .verify(&bytes, &self.signature_of_new_name_with_old_key)
+                    .is_ok()
+            })
+            .unwrap_or(false)
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
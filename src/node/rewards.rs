@@ -0,0 +1,145 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::NodeSystemCmd;
+use crate::{
+    client::{Error, NodeOpError, Result},
+    MessageId,
+};
+use std::collections::BTreeMap;
+
+/// Bookkeeping of the reward payouts proposed by the Elders via
+/// `NodeSystemCmd::ProposeRewardPayout`, so that each one is accumulated at most once,
+/// and only during the epoch it was proposed for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RewardProposals {
+    epoch: u64,
+    outstanding: BTreeMap<MessageId, u64>,
+}
+
+impl RewardProposals {
+    /// Create an empty tracker, at epoch 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current reward epoch.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Moves to a new reward epoch, e.g. on a new section key, dropping the
+    /// proposals of past epochs which were never accumulated.
+    pub fn start_epoch(&mut self, epoch: u64) {
+        self.epoch = self.epoch.max(epoch);
+        let current = self.epoch;
+        self.outstanding.retain(|_, epoch| *epoch >= current);
+    }
+
+    /// Returns whether the given proposal is awaiting its accumulation.
+    pub fn is_outstanding(&self, proposal_id: &MessageId) -> bool {
+        self.outstanding.contains_key(proposal_id)
+    }
+
+    /// Records a reward payout proposal, or checks a reward payout accumulation
+    /// matches an outstanding proposal, which is then no longer outstanding.
+    /// Other cmds are ignored. This should only be called once the cmd has been verified.
+    pub fn check_and_record(&mut self, cmd: &NodeSystemCmd) -> Result<()> {
+        match cmd {
+            NodeSystemCmd::ProposeRewardPayout {
+                epoch, proposal_id, ..
+            } => {
+                if *epoch < self.epoch {
+                    return Err(Error::NodeOp(NodeOpError::StaleRewardEpoch(*epoch)));
+                }
+                let _ = self.outstanding.insert(*proposal_id, *epoch);
+                Ok(())
+            }
+            NodeSystemCmd::AccumulateRewardPayout {
+                epoch, proposal_id, ..
+            } => match self.outstanding.get(proposal_id) {
+                Some(proposed) if proposed == epoch => {
+                    let _ = self.outstanding.remove(proposal_id);
+                    Ok(())
+                }
+                _ => Err(Error::NodeOp(NodeOpError::UnknownRewardProposal(
+                    *proposal_id,
+                ))),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use sn_data_types::{Keypair, RewardAccumulation, RewardProposal};
+
+    fn payout(epoch: u64) -> Result<(NodeSystemCmd, NodeSystemCmd)> {
+        let section_key = Keypair::new_ed25519(&mut rand::thread_rng()).public_key();
+        let proposal = NodeSystemCmd::propose_reward_payout(
+            RewardProposal {
+                section_key,
+                rewards: vec![],
+            },
+            epoch,
+        )?;
+        let accumulation = NodeSystemCmd::accumulate_reward_payout(
+            RewardAccumulation {
+                section_key,
+                rewards: vec![],
+            },
+            epoch,
+        )?;
+        Ok((proposal, accumulation))
+    }
+
+    #[test]
+    fn accumulation_applies_once() -> Result<()> {
+        let (proposal, accumulation) = payout(1)?;
+        assert!(proposal.verify(&[]));
+        assert!(accumulation.verify(&[]));
+        assert_eq!(proposal.reward_proposal(), accumulation.reward_proposal());
+
+        let mut proposals = RewardProposals::new();
+        let id = match accumulation.reward_proposal() {
+            Some((_, id)) => id,
+            None => anyhow::bail!("not a reward payout"),
+        };
+        assert_eq!(
+            proposals.check_and_record(&accumulation),
+            Err(Error::NodeOp(NodeOpError::UnknownRewardProposal(id)))
+        );
+        proposals.check_and_record(&proposal)?;
+        assert!(proposals.is_outstanding(&id));
+        proposals.check_and_record(&accumulation)?;
+        assert_eq!(
+            proposals.check_and_record(&accumulation),
+            Err(Error::NodeOp(NodeOpError::UnknownRewardProposal(id)))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn proposals_expire_with_epoch() -> Result<()> {
+        let (proposal, accumulation) = payout(1)?;
+        let mut proposals = RewardProposals::new();
+        proposals.check_and_record(&proposal)?;
+        proposals.start_epoch(2);
+        assert!(proposals.check_and_record(&accumulation).is_err());
+        assert_eq!(
+            proposals.check_and_record(&proposal),
+            Err(Error::NodeOp(NodeOpError::StaleRewardEpoch(1)))
+        );
+        Ok(())
+    }
+}
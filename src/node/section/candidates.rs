@@ -6,6 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::sap::{check_elders, EldersError};
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, fmt::Debug, net::SocketAddr};
 use xor_name::{Prefix, XorName};
@@ -17,4 +18,24 @@ pub struct ElderCandidates {
     pub elders: BTreeMap<XorName, SocketAddr>,
     /// The section prefix. It matches all the members' names.
     pub prefix: Prefix,
+    /// Generation of the section's elders. It increases every time the elders change, so
+    /// DKG sessions for the same prefix can be told apart and ordered.
+    pub generation: u64,
+}
+
+impl ElderCandidates {
+    /// Creates new `ElderCandidates`, checking the elders are well-formed.
+    pub fn new_checked(
+        elders: BTreeMap<XorName, SocketAddr>,
+        prefix: Prefix,
+        generation: u64,
+    ) -> Result<Self, EldersError> {
+        check_elders(&prefix, &elders)?;
+
+        Ok(Self {
+            elders,
+            prefix,
+            generation,
+        })
+    }
 }
@@ -11,18 +11,23 @@ mod node_state;
 mod peer;
 
 pub use candidates::ElderCandidates;
-pub use node_state::{MembershipState, NodeState};
+pub use node_state::{MembershipState, NodeState, Penalty, PenaltyReason};
 pub use peer::Peer;
 
 use crate::{node::agreement::SectionSigned, SectionAuthorityProvider};
+use itertools::Itertools;
 use secured_linked_list::SecuredLinkedList;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{btree_map, BTreeMap},
+    cmp::Ordering,
+    collections::{
+        btree_map::{self, Entry},
+        BTreeMap,
+    },
     hash::{Hash, Hasher},
 };
 use threshold_crypto::PublicKey as BlsPublicKey;
-use xor_name::XorName;
+use xor_name::{Prefix, XorName};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Section {
@@ -50,6 +55,162 @@ impl Hash for SectionPeers {
     }
 }
 
+impl SectionPeers {
+    /// Update a member of our section.
+    ///
+    /// Returns whether anything actually changed. To maintain commutativity, the only allowed
+    /// transitions are:
+    /// - Joined -> Left
+    /// - Joined -> Relocated
+    /// - Joined -> Penalized
+    /// - Penalized -> Left
+    /// - Penalized -> Relocated
+    /// - Relocated -> Left (should not happen, but needed for consistency)
+    ///
+    /// A `Penalized` member must carry its penalty.
+    pub fn update(&mut self, new_info: SectionSigned<NodeState>) -> bool {
+        if new_info.value.state == MembershipState::Penalized && new_info.value.penalty.is_none() {
+            return false;
+        }
+
+        match self.members.entry(new_info.value.peer.name) {
+            Entry::Vacant(entry) => {
+                let _ = entry.insert(new_info);
+                true
+            }
+            Entry::Occupied(mut entry) => {
+                match (entry.get().value.state, new_info.value.state) {
+                    (MembershipState::Joined, MembershipState::Left)
+                    | (MembershipState::Joined, MembershipState::Relocated(_))
+                    | (MembershipState::Joined, MembershipState::Penalized)
+                    | (MembershipState::Penalized, MembershipState::Left)
+                    | (MembershipState::Penalized, MembershipState::Relocated(_))
+                    | (MembershipState::Relocated(_), MembershipState::Left) => {}
+                    _ => return false,
+                };
+
+                let _ = entry.insert(new_info);
+                true
+            }
+        }
+    }
+
+    /// Returns the joined members that joined our section before the given time, in seconds
+    /// since the UNIX epoch.
+    pub fn joined_before(&self, time: u64) -> impl Iterator<Item = &NodeState> {
+        self.joined().filter(move |info| info.joined_at < time)
+    }
+
+    /// Returns the joined members that have been relocated fewer than `max_relocations` times,
+    /// longest-serving first.
+    pub fn relocation_candidates(&self, max_relocations: u32) -> Vec<&NodeState> {
+        let mut candidates: Vec<_> = self
+            .joined()
+            .filter(|info| info.relocation_count < max_relocations)
+            .collect();
+        candidates.sort_by_key(|info| (info.joined_at, info.peer.name));
+        candidates
+    }
+
+    /// Returns the candidates for elders out of all the nodes in our section.
+    pub fn elder_candidates(
+        &self,
+        elder_size: usize,
+        current_elders: &SectionAuthorityProvider,
+    ) -> Vec<Peer> {
+        elder_candidates(
+            elder_size,
+            current_elders,
+            self.members
+                .values()
+                .filter(|info| is_active(&info.value, current_elders)),
+        )
+    }
+
+    /// Returns the candidates for elders out of all nodes matching the prefix.
+    pub fn elder_candidates_matching_prefix(
+        &self,
+        prefix: &Prefix,
+        elder_size: usize,
+        current_elders: &SectionAuthorityProvider,
+    ) -> Vec<Peer> {
+        elder_candidates(
+            elder_size,
+            current_elders,
+            self.members.values().filter(|info| {
+                info.value.state == MembershipState::Joined && prefix.matches(&info.value.peer.name)
+            }),
+        )
+    }
+
+    fn joined(&self) -> impl Iterator<Item = &NodeState> {
+        self.members
+            .values()
+            .map(|info| &info.value)
+            .filter(|info| info.state == MembershipState::Joined)
+    }
+}
+
+// Returns the best elder candidates out of the given members. Only reachable members can be
+// promoted.
+fn elder_candidates<'a, I>(
+    elder_size: usize,
+    current_elders: &SectionAuthorityProvider,
+    members: I,
+) -> Vec<Peer>
+where
+    I: IntoIterator<Item = &'a SectionSigned<NodeState>>,
+{
+    members
+        .into_iter()
+        .filter(|info| info.value.peer.reachable)
+        .sorted_by(|lhs, rhs| cmp_elder_candidates(lhs, rhs, current_elders))
+        .map(|info| info.value.peer)
+        .take(elder_size)
+        .collect()
+}
+
+// Compare candidates for the next elders. The one comparing `Less` wins.
+fn cmp_elder_candidates(
+    lhs: &SectionSigned<NodeState>,
+    rhs: &SectionSigned<NodeState>,
+    current_elders: &SectionAuthorityProvider,
+) -> Ordering {
+    // Older nodes are preferred. In case of a tie, prefer current elders. If still a tie, break
+    // it comparing by the signed signatures because it's impossible for a node to predict its
+    // signature and therefore game its chances of promotion. The names make the order total.
+    rhs.value
+        .peer
+        .age()
+        .cmp(&lhs.value.peer.age())
+        .then_with(|| {
+            let lhs_is_elder = is_elder(&lhs.value, current_elders);
+            let rhs_is_elder = is_elder(&rhs.value, current_elders);
+
+            match (lhs_is_elder, rhs_is_elder) {
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                _ => Ordering::Equal,
+            }
+        })
+        .then_with(|| lhs.signed.signature.cmp(&rhs.signed.signature))
+        .then_with(|| lhs.value.peer.name.cmp(&rhs.value.peer.name))
+}
+
+// A member is active if it is joined, or if it is a current elder being relocated, which keeps
+// its role until the new elders take over.
+fn is_active(info: &NodeState, current_elders: &SectionAuthorityProvider) -> bool {
+    match info.state {
+        MembershipState::Joined => true,
+        MembershipState::Relocated(_) => is_elder(info, current_elders),
+        _ => false,
+    }
+}
+
+fn is_elder(info: &NodeState, current_elders: &SectionAuthorityProvider) -> bool {
+    current_elders.elders.contains_key(&info.peer.name)
+}
+
 pub struct IntoIter(btree_map::IntoIter<XorName, SectionSigned<NodeState>>);
 
 impl Iterator for IntoIter {
@@ -68,3 +229,144 @@ impl IntoIterator for SectionPeers {
         IntoIter(self.members.into_iter())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Signed;
+    use threshold_crypto::SecretKey;
+
+    fn signed(
+        peer: Peer,
+        state: MembershipState,
+        penalty: Option<Penalty>,
+    ) -> SectionSigned<NodeState> {
+        sign(NodeState {
+            peer,
+            state,
+            penalty,
+            joined_at: 0,
+            relocation_count: 0,
+            last_seen: 0,
+        })
+    }
+
+    fn sign(value: NodeState) -> SectionSigned<NodeState> {
+        let secret_key = SecretKey::random();
+        SectionSigned::new(
+            value,
+            Signed {
+                public_key: secret_key.public_key(),
+                signature: secret_key.sign(b""),
+            },
+        )
+    }
+
+    fn peer() -> Peer {
+        Peer {
+            name: XorName::random(),
+            addr: ([127, 0, 0, 1], 12000).into(),
+            reachable: true,
+        }
+    }
+
+    #[test]
+    fn penalized_member_transitions() {
+        let peer = peer();
+        let penalty = Penalty {
+            reason: PenaltyReason::Unresponsive,
+            proof: vec![],
+        };
+        let mut members = SectionPeers::default();
+
+        assert!(members.update(signed(peer, MembershipState::Joined, None)));
+        assert!(!members.update(signed(peer, MembershipState::Penalized, None)));
+        assert!(members.update(signed(
+            peer,
+            MembershipState::Penalized,
+            Some(penalty.clone())
+        )));
+        assert!(!members.update(signed(peer, MembershipState::Joined, None)));
+        assert!(!members.update(signed(peer, MembershipState::Penalized, Some(penalty))));
+        assert!(members.update(signed(peer, MembershipState::Left, None)));
+        assert!(!members.update(signed(peer, MembershipState::Joined, None)));
+    }
+
+    #[test]
+    fn churn_filters() {
+        let mut members = SectionPeers::default();
+        let infos: Vec<_> = [(30, 0), (10, 2), (20, 1)]
+            .iter()
+            .map(|&(joined_at, relocation_count)| NodeState {
+                peer: peer(),
+                state: MembershipState::Joined,
+                penalty: None,
+                joined_at,
+                relocation_count,
+                last_seen: joined_at,
+            })
+            .collect();
+        for info in &infos {
+            assert!(members.update(sign(info.clone())));
+        }
+        let mut left = infos[1].clone();
+        left.peer = peer();
+        left.state = MembershipState::Left;
+        assert!(members.update(sign(left)));
+
+        let mut joined_before: Vec<_> = members.joined_before(30).collect();
+        joined_before.sort_by_key(|info| info.joined_at);
+        assert_eq!(joined_before, vec![&infos[1], &infos[2]]);
+
+        assert_eq!(members.relocation_candidates(2), vec![&infos[2], &infos[0]]);
+    }
+
+    fn peer_with_age(prefix: &Prefix, age: u8) -> Peer {
+        let mut name = prefix.substituted_in(XorName::random());
+        name.0[xor_name::XOR_NAME_LEN - 1] = age;
+        Peer {
+            name,
+            addr: ([127, 0, 0, 1], 12000).into(),
+            reachable: true,
+        }
+    }
+
+    #[test]
+    fn elder_candidates_selection() {
+        let prefix = Prefix::default();
+        let mut members = SectionPeers::default();
+        let old = peer_with_age(&prefix, 10);
+        let young = peer_with_age(&prefix, 5);
+        let young_elder = peer_with_age(&prefix, 5);
+        let mut unreachable = peer_with_age(&prefix, 20);
+        unreachable.reachable = false;
+
+        for peer in &[old, young, young_elder, unreachable] {
+            assert!(members.update(signed(*peer, MembershipState::Joined, None)));
+        }
+
+        let current_elders = SectionAuthorityProvider {
+            prefix,
+            public_key_set: threshold_crypto::SecretKeySet::random(0, &mut rand::thread_rng())
+                .public_keys(),
+            elders: vec![(young_elder.name, young_elder.addr)]
+                .into_iter()
+                .collect(),
+            generation: 0,
+        };
+
+        assert_eq!(
+            members.elder_candidates(2, &current_elders),
+            vec![old, young_elder]
+        );
+        assert_eq!(
+            members.elder_candidates(3, &current_elders),
+            vec![old, young_elder, young]
+        );
+
+        let child = prefix.pushed(old.name.bit(0));
+        let candidates = members.elder_candidates_matching_prefix(&child, 3, &current_elders);
+        assert_eq!(candidates.first(), Some(&old));
+        assert!(candidates.iter().all(|peer| child.matches(&peer.name)));
+    }
+}
@@ -11,10 +11,18 @@ use serde::{Deserialize, Serialize};
 use xor_name::XorName;
 
 /// Information about a member of our section.
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
 pub struct NodeState {
     pub peer: Peer,
     pub state: MembershipState,
+    /// Why the node was penalized and the evidence of it, set when `state` is `Penalized`.
+    pub penalty: Option<Penalty>,
+    /// When the node joined our section, in seconds since the UNIX epoch.
+    pub joined_at: u64,
+    /// Number of times the node has been relocated so far.
+    pub relocation_count: u32,
+    /// When the node was last seen responding, in seconds since the UNIX epoch.
+    pub last_seen: u64,
 }
 
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
@@ -25,4 +33,25 @@ pub enum MembershipState {
     Left,
     /// Node was relocated to a different section.
     Relocated(XorName),
+    /// Node misbehaved and is tracked until it is voted off.
+    Penalized,
+}
+
+/// Record of a misbehaviour of a member of our section.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct Penalty {
+    pub reason: PenaltyReason,
+    /// The serialized offending message, as evidence of the misbehaviour.
+    #[serde(with = "serde_bytes")]
+    pub proof: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum PenaltyReason {
+    /// Node didn't respond to messages in time.
+    Unresponsive,
+    /// Node sent a message it should not have sent, or an invalid one.
+    InvalidMessage,
+    /// Node failed to provide data it is responsible for.
+    MissingData,
 }
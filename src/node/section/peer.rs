@@ -12,7 +12,7 @@ use std::{
     hash::Hash,
     net::SocketAddr,
 };
-use xor_name::XorName;
+use xor_name::{XorName, XOR_NAME_LEN};
 
 /// Network p2p peer identity.
 /// When a node knows another p2p_node as a `Peer` it's implicitly connected to it. This is separate
@@ -27,6 +27,13 @@ pub struct Peer {
     pub reachable: bool,
 }
 
+impl Peer {
+    /// The age of the peer, which is derived from its name.
+    pub fn age(&self) -> u8 {
+        self.name[XOR_NAME_LEN - 1]
+    }
+}
+
 impl Display for Peer {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "{} at {}", self.name, self.addr)
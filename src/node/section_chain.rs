@@ -0,0 +1,386 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::section_info::SectionKeyProof;
+use secured_linked_list::{error::Error, SecuredLinkedList};
+use thiserror::Error;
+use threshold_crypto::PublicKey as BlsPublicKey;
+
+/// Error returned when merging section chains.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MergeError {
+    /// The chains could not be merged, e.g. because they have no key in common.
+    #[error(transparent)]
+    Chain(#[from] Error),
+    /// The last keys of the two chains are on different branches.
+    #[error("chains fork at key {at_key:?}")]
+    ForkDetected {
+        /// The last key the two chains have in common.
+        at_key: BlsPublicKey,
+        /// The keys of our chain after the fork, oldest first.
+        left: Vec<BlsPublicKey>,
+        /// The keys of the other chain after the fork, oldest first.
+        right: Vec<BlsPublicKey>,
+    },
+}
+
+/// Helpers on top of `SecuredLinkedList`, the chain of section keys.
+pub trait SectionChainExt {
+    /// Creates a compact proof of `key` starting from `trusted_key`.
+    /// Returns `Error::KeyNotFound` if any of the keys is not in the chain and
+    /// `Error::InvalidOperation` if `trusted_key` is not an ancestor of `key`.
+    fn prove_key(
+        &self,
+        trusted_key: &BlsPublicKey,
+        key: &BlsPublicKey,
+    ) -> Result<SectionKeyProof, Error>;
+
+    /// Returns the shortest slice of the chain that connects its last key to any of the
+    /// `trusted` keys. Returns `Error::Untrusted` if none of them is an ancestor of the last key.
+    fn minimal_slice(&self, trusted: &[BlsPublicKey]) -> Result<SecuredLinkedList, Error>;
+
+    /// Merges the other chain into this one, like `SecuredLinkedList::merge`, but only if the
+    /// last key of one chain is an ancestor of the last key of the other. Otherwise the chains
+    /// forked, and `MergeError::ForkDetected` is returned with both branches so the caller can
+    /// decide how to resolve it. This chain is left unchanged on error.
+    fn merge_checked(&mut self, other: SecuredLinkedList) -> Result<(), MergeError>;
+
+    /// Returns the main branch of the chain truncated to its last `max_len` keys.
+    /// Unlike `SecuredLinkedList::truncate`, a `max_len` of 0 is rejected with
+    /// `Error::InvalidOperation`.
+    fn truncate_to(&self, max_len: usize) -> Result<SecuredLinkedList, Error>;
+
+    /// Returns the main branch of the chain trimmed according to the retention policy.
+    /// Returns `Error::KeyNotFound` or `Error::InvalidOperation` if the policy requires keeping
+    /// a key which is not in the chain, or not on its main branch.
+    fn trimmed(&self, retention: &ChainRetention) -> Result<SecuredLinkedList, Error>;
+}
+
+/// How much of a section chain to keep when trimming it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChainRetention {
+    /// Maximum number of keys to keep, unless more are needed to honour the other settings.
+    pub max_len: usize,
+    /// If greater than 1, the root of the trimmed chain is moved back to the closest key whose
+    /// position on the main branch, counted from the current root, is a multiple of this
+    /// interval. Chains trimmed by different nodes then tend to share their root keys, which keeps
+    /// them mergeable and recognisable by their receivers.
+    pub checkpoint_interval: usize,
+    /// Key the trimmed chain must reach back to, if any.
+    pub keep_back_to: Option<BlsPublicKey>,
+}
+
+impl ChainRetention {
+    /// Keep at most `max_len` keys.
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            max_len,
+            checkpoint_interval: 1,
+            keep_back_to: None,
+        }
+    }
+}
+
+impl SectionChainExt for SecuredLinkedList {
+    fn prove_key(
+        &self,
+        trusted_key: &BlsPublicKey,
+        key: &BlsPublicKey,
+    ) -> Result<SectionKeyProof, Error> {
+        if !self.has_key(trusted_key) {
+            return Err(Error::KeyNotFound);
+        }
+
+        let branch = branch_to(self, key)?;
+        if !branch.has_key(trusted_key) {
+            return Err(Error::InvalidOperation);
+        }
+
+        Ok(SectionKeyProof {
+            chain: branch.get_proof_chain_to_current(trusted_key)?,
+        })
+    }
+
+    fn minimal_slice(&self, trusted: &[BlsPublicKey]) -> Result<SecuredLinkedList, Error> {
+        let main_branch = main_branch(self);
+        let anchor = main_branch
+            .keys()
+            .rev()
+            .find(|key| trusted.contains(key))
+            .ok_or(Error::Untrusted)?;
+
+        main_branch.get_proof_chain_to_current(anchor)
+    }
+
+    fn truncate_to(&self, max_len: usize) -> Result<SecuredLinkedList, Error> {
+        self.trimmed(&ChainRetention::new(max_len))
+    }
+
+    fn trimmed(&self, retention: &ChainRetention) -> Result<SecuredLinkedList, Error> {
+        if retention.max_len == 0 {
+            return Err(Error::InvalidOperation);
+        }
+
+        let main_branch = main_branch(self);
+        let keys: Vec<_> = main_branch.keys().collect();
+
+        let mut start = keys.len().saturating_sub(retention.max_len);
+        if retention.checkpoint_interval > 1 {
+            start -= start % retention.checkpoint_interval;
+        }
+
+        if let Some(key) = &retention.keep_back_to {
+            if !self.has_key(key) {
+                return Err(Error::KeyNotFound);
+            }
+            let position = keys
+                .iter()
+                .position(|main_key| *main_key == key)
+                .ok_or(Error::InvalidOperation)?;
+            start = start.min(position);
+        }
+
+        main_branch.get_proof_chain_to_current(keys[start])
+    }
+
+    fn merge_checked(&mut self, other: SecuredLinkedList) -> Result<(), MergeError> {
+        let our_last_key = *self.last_key();
+        let their_last_key = *other.last_key();
+
+        let mut merged = self.clone();
+        merged.merge(other)?;
+
+        // Both branches start at the root of the merged chain, so they share at least it.
+        let ours: Vec<_> = branch_to(&merged, &our_last_key)?.keys().copied().collect();
+        let theirs: Vec<_> = branch_to(&merged, &their_last_key)?
+            .keys()
+            .copied()
+            .collect();
+        let common = ours
+            .iter()
+            .zip(&theirs)
+            .take_while(|(our_key, their_key)| our_key == their_key)
+            .count();
+
+        if common == ours.len() || common == theirs.len() {
+            *self = merged;
+            return Ok(());
+        }
+
+        Err(MergeError::ForkDetected {
+            at_key: ours[common - 1],
+            left: ours[common..].to_vec(),
+            right: theirs[common..].to_vec(),
+        })
+    }
+}
+
+// The main branch of the chain: the keys leading from its root to its last key, without forks.
+fn main_branch(chain: &SecuredLinkedList) -> SecuredLinkedList {
+    chain.truncate(chain.len())
+}
+
+// The keys leading from the root of the chain to `key`, without forks.
+fn branch_to(chain: &SecuredLinkedList, key: &BlsPublicKey) -> Result<SecuredLinkedList, Error> {
+    chain
+        .get_proof_chain(chain.root_key(), key)
+        .map(|slice| main_branch(&slice))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use threshold_crypto::SecretKey;
+
+    // Builds a chain of `len` keys, with a fork of one key off the second one.
+    fn chain(len: usize) -> Result<(SecuredLinkedList, Vec<SecretKey>, SecretKey)> {
+        let keys: Vec<_> = (0..len).map(|_| SecretKey::random()).collect();
+        let mut chain = SecuredLinkedList::new(keys[0].public_key());
+        for pair in keys.windows(2) {
+            insert(&mut chain, &pair[0], &pair[1])?;
+        }
+
+        let fork = SecretKey::random();
+        insert(&mut chain, &keys[1], &fork)?;
+
+        Ok((chain, keys, fork))
+    }
+
+    fn insert(chain: &mut SecuredLinkedList, parent: &SecretKey, key: &SecretKey) -> Result<()> {
+        let signature = parent.sign(&bincode::serialize(&key.public_key())?);
+        chain.insert(&parent.public_key(), key.public_key(), signature)?;
+        Ok(())
+    }
+
+    #[test]
+    fn prove_key() -> Result<()> {
+        let (chain, keys, fork) = chain(5)?;
+        let trusted = keys[1].public_key();
+        let key = keys[4].public_key();
+
+        let proof = chain.prove_key(&trusted, &key)?;
+        assert_eq!(proof.key(), &key);
+        assert_eq!(proof.anchor(), &trusted);
+        assert_eq!(proof.chain.len(), 4);
+        assert!(proof.verify(&[trusted]));
+        assert!(!proof.verify(&[keys[0].public_key()]));
+
+        let fork_proof = chain.prove_key(&trusted, &fork.public_key())?;
+        assert_eq!(fork_proof.chain.len(), 2);
+        assert!(fork_proof.verify(&[trusted]));
+
+        let self_proof = chain.prove_key(&key, &key)?;
+        assert_eq!(self_proof.chain.len(), 1);
+        assert!(self_proof.verify(&[key]));
+
+        assert_eq!(
+            chain.prove_key(&key, &trusted),
+            Err(Error::InvalidOperation)
+        );
+        assert_eq!(
+            chain.prove_key(&SecretKey::random().public_key(), &key),
+            Err(Error::KeyNotFound)
+        );
+
+        // A proof can't hold a key which isn't signed by its parent key.
+        let mut forged = SecuredLinkedList::new(trusted);
+        let forged_signature = keys[0].sign(&bincode::serialize(&key)?);
+        assert_eq!(
+            forged.insert(&trusted, key, forged_signature),
+            Err(Error::FailedSignature)
+        );
+        let forged = SectionKeyProof {
+            chain: SecuredLinkedList::new(key),
+        };
+        assert!(!forged.verify(&[trusted]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn wrong_section_key_error() -> Result<()> {
+        use crate::section_info::Error as TargetSectionError;
+
+        let (chain, keys, _) = chain(4)?;
+        let used_key = keys[1].public_key();
+        let error =
+            TargetSectionError::WrongSectionKey(chain.prove_key(&used_key, &keys[3].public_key())?);
+
+        let error: TargetSectionError = bincode::deserialize(&bincode::serialize(&error)?)?;
+        match error {
+            TargetSectionError::WrongSectionKey(proof) => {
+                assert!(proof.verify(&[used_key]));
+                assert_eq!(proof.key(), &keys[3].public_key());
+            }
+            other => anyhow::bail!("unexpected error: {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn minimal_slice() -> Result<()> {
+        let (chain, keys, fork) = chain(5)?;
+        let last_key = *chain.last_key();
+        assert_eq!(last_key, keys[4].public_key());
+
+        let slice = chain.minimal_slice(&[keys[0].public_key(), keys[2].public_key()])?;
+        assert_eq!(slice.root_key(), &keys[2].public_key());
+        assert_eq!(slice.last_key(), &last_key);
+        assert_eq!(slice.len(), 3);
+        assert!(slice.check_trust(&[keys[2].public_key()]));
+
+        let slice = chain.minimal_slice(&[last_key])?;
+        assert_eq!(slice.len(), 1);
+
+        // The fork is not on the path to the last key, so it's never included.
+        let slice = chain.minimal_slice(&[keys[1].public_key()])?;
+        assert!(!slice.has_key(&fork.public_key()));
+        assert_eq!(slice.len(), 4);
+
+        assert_eq!(
+            chain.minimal_slice(&[fork.public_key()]),
+            Err(Error::Untrusted)
+        );
+        assert_eq!(chain.minimal_slice(&[]), Err(Error::Untrusted));
+
+        Ok(())
+    }
+
+    #[test]
+    fn merge_checked() -> Result<()> {
+        let (chain, keys, fork) = chain(4)?;
+
+        // Extending a chain with its own continuation merges fine.
+        let mut ours = chain.minimal_slice(&[keys[0].public_key()])?;
+        let next = SecretKey::random();
+        let mut theirs = chain.minimal_slice(&[keys[2].public_key()])?;
+        insert(&mut theirs, &keys[3], &next)?;
+        ours.merge_checked(theirs)?;
+        assert_eq!(ours.last_key(), &next.public_key());
+
+        // A chain whose last key is on another branch is reported as a fork.
+        let mut theirs = SecuredLinkedList::new(keys[1].public_key());
+        insert(&mut theirs, &keys[1], &fork)?;
+        let before = ours.clone();
+        assert_eq!(
+            ours.merge_checked(theirs),
+            Err(MergeError::ForkDetected {
+                at_key: keys[1].public_key(),
+                left: vec![
+                    keys[2].public_key(),
+                    keys[3].public_key(),
+                    next.public_key()
+                ],
+                right: vec![fork.public_key()],
+            })
+        );
+        assert_eq!(ours, before);
+
+        // Chains without any key in common can't be merged at all.
+        let unrelated = SecuredLinkedList::new(SecretKey::random().public_key());
+        assert_eq!(
+            ours.merge_checked(unrelated),
+            Err(MergeError::Chain(Error::InvalidOperation))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn trimmed() -> Result<()> {
+        let (chain, keys, fork) = chain(10)?;
+        let public_keys: Vec<_> = keys.iter().map(SecretKey::public_key).collect();
+
+        let truncated = chain.truncate_to(3)?;
+        assert_eq!(
+            truncated.keys().copied().collect::<Vec<_>>(),
+            &public_keys[7..]
+        );
+        assert!(!truncated.has_key(&fork.public_key()));
+        assert_eq!(chain.truncate_to(0), Err(Error::InvalidOperation));
+        assert_eq!(chain.truncate_to(20)?.len(), 10);
+
+        // The root is moved back to the closest checkpoint.
+        let mut retention = ChainRetention::new(3);
+        retention.checkpoint_interval = 4;
+        let trimmed = chain.trimmed(&retention)?;
+        assert_eq!(trimmed.root_key(), &public_keys[4]);
+        assert_eq!(trimmed.last_key(), &public_keys[9]);
+
+        retention.keep_back_to = Some(public_keys[2]);
+        assert_eq!(chain.trimmed(&retention)?.root_key(), &public_keys[2]);
+
+        retention.keep_back_to = Some(fork.public_key());
+        assert_eq!(chain.trimmed(&retention), Err(Error::InvalidOperation));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::agreement::DkgKey;
+use crate::SignedShare;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+};
+use threshold_crypto::{
+    serde_impl::SerdeSecret, PublicKey as BlsPublicKey, PublicKeySet, SecretKeyShare,
+};
+use zeroize::Zeroize;
+
+/// Default number of pending key shares kept by a `SectionKeysProvider`.
+const DEFAULT_MAX_PENDING: usize = 4;
+
+/// Wrapper of secret material, which is zeroized when dropped and kept out of debug output.
+/// The secret can only be accessed explicitly via `expose_secret`.
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    /// Wrap the secret.
+    pub fn new(secret: T) -> Self {
+        Self(secret)
+    }
+
+    /// Access the secret.
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(secret: T) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl<T: Zeroize + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: Zeroize + PartialEq> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Zeroize + Eq> Eq for Secret<T> {}
+
+impl<T: Zeroize> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Secret(..)")
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// A share of a section key held by one of its elders.
+#[derive(PartialEq, Eq, Debug)]
+pub struct SectionKeyShare {
+    /// Public key set to verify threshold signatures and combine shares.
+    pub public_key_set: PublicKeySet,
+    /// Index of the owner of this key share within the set of all section elders.
+    pub index: usize,
+    /// Secret key share.
+    pub secret_key_share: SecretKeyShare,
+}
+
+impl SectionKeyShare {
+    /// Signs the payload with this key share.
+    pub fn sign(&self, payload: &[u8]) -> SignedShare {
+        SignedShare::new(
+            self.public_key_set.clone(),
+            self.index,
+            &self.secret_key_share,
+            payload,
+        )
+    }
+}
+
+impl Zeroize for SectionKeyShare {
+    fn zeroize(&mut self) {
+        // Replacing the share drops the previous one, which zeroizes its secret key.
+        self.secret_key_share = SecretKeyShare::default();
+        self.index.zeroize();
+    }
+}
+
+// Only a wrapped share can be cloned, e.g. along with the genesis message handing it over, so
+// that every copy of it is zeroized once dropped.
+impl Clone for Secret<SectionKeyShare> {
+    fn clone(&self) -> Self {
+        let share = &self.0;
+        Self::new(SectionKeyShare {
+            public_key_set: share.public_key_set.clone(),
+            index: share.index,
+            secret_key_share: share.secret_key_share.clone(),
+        })
+    }
+}
+
+// Only a wrapped share can be serialized, e.g. to hand it over to its elder, so that it isn't
+// leaked by accident.
+impl Serialize for Secret<SectionKeyShare> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let share = &self.0;
+        (
+            &share.public_key_set,
+            share.index,
+            SerdeSecret(&share.secret_key_share),
+        )
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret<SectionKeyShare> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (public_key_set, index, SerdeSecret(secret_key_share)) =
+            Deserialize::deserialize(deserializer)?;
+        Ok(Self::new(SectionKeyShare {
+            public_key_set,
+            index,
+            secret_key_share,
+        }))
+    }
+}
+
+/// Keeps the key share of the current section key, along with the shares of DKG sessions that
+/// completed but whose section key hasn't been agreed on yet.
+///
+/// During a key handover, messages can then be signed and verified with either key until the
+/// churn completes and the matching pending share is promoted to the current one. All the shares
+/// are zeroized once dropped, e.g. when they are rotated out.
+#[derive(Debug)]
+pub struct SectionKeysProvider {
+    current: Option<Secret<SectionKeyShare>>,
+    pending: HashMap<DkgKey, Secret<SectionKeyShare>>,
+    max_pending: usize,
+}
+
+impl SectionKeysProvider {
+    /// Create a provider holding the given current share, if any.
+    pub fn new(current: Option<SectionKeyShare>) -> Self {
+        Self::with_max_pending(current, DEFAULT_MAX_PENDING)
+    }
+
+    /// Create a provider holding the given current share, if any, and at most `max_pending`
+    /// pending shares.
+    pub fn with_max_pending(current: Option<SectionKeyShare>, max_pending: usize) -> Self {
+        Self {
+            current: current.map(Secret::new),
+            pending: HashMap::new(),
+            max_pending,
+        }
+    }
+
+    /// The share of the current section key, if we are an elder.
+    pub fn key_share(&self) -> Option<&SectionKeyShare> {
+        self.current.as_ref().map(Secret::expose_secret)
+    }
+
+    /// Keep the share outcome of the given DKG session until its key is agreed on.
+    /// If there are more than `max_pending` pending shares, the ones of the oldest generations
+    /// are dropped.
+    pub fn insert_pending(&mut self, dkg_key: DkgKey, share: SectionKeyShare) {
+        let _ = self.pending.insert(dkg_key, Secret::new(share));
+
+        while self.pending.len() > self.max_pending {
+            let oldest = self
+                .pending
+                .keys()
+                .min_by_key(|dkg_key| (dkg_key.generation, dkg_key.hash))
+                .copied();
+            if let Some(oldest) = oldest {
+                let _ = self.pending.remove(&oldest);
+            }
+        }
+    }
+
+    /// The pending share of the given DKG session, if any.
+    pub fn pending(&self, dkg_key: &DkgKey) -> Option<&SectionKeyShare> {
+        self.pending.get(dkg_key).map(Secret::expose_secret)
+    }
+
+    /// Number of pending shares.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// The share, current or pending, of the given section key.
+    pub fn share_for(&self, public_key: &BlsPublicKey) -> Option<&SectionKeyShare> {
+        self.current
+            .iter()
+            .chain(self.pending.values())
+            .map(Secret::expose_secret)
+            .find(|share| share.public_key_set.public_key() == *public_key)
+    }
+
+    /// Signs the payload with our share of the given section key, current or pending.
+    pub fn sign_with(&self, public_key: &BlsPublicKey, payload: &[u8]) -> Option<SignedShare> {
+        self.share_for(public_key).map(|share| share.sign(payload))
+    }
+
+    /// Promote the pending share of the given section key to the current one, once the churn that
+    /// introduced it completed. Pending shares of the same or older generations are dropped, as
+    /// their keys can no longer be agreed on.
+    ///
+    /// Returns whether the share was found and promoted, otherwise nothing is changed.
+    pub fn promote(&mut self, public_key: &BlsPublicKey) -> bool {
+        let dkg_key = self
+            .pending
+            .iter()
+            .find(|(_, share)| share.expose_secret().public_key_set.public_key() == *public_key)
+            .map(|(dkg_key, _)| *dkg_key);
+
+        let dkg_key = match dkg_key {
+            Some(dkg_key) => dkg_key,
+            None => return false,
+        };
+
+        self.current = self.pending.remove(&dkg_key);
+        self.pending
+            .retain(|pending_key, _| pending_key.generation > dkg_key.generation);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+    use threshold_crypto::SecretKeySet;
+
+    fn key_share() -> SectionKeyShare {
+        let sk_set = SecretKeySet::random(0, &mut thread_rng());
+        SectionKeyShare {
+            public_key_set: sk_set.public_keys(),
+            index: 0,
+            secret_key_share: sk_set.secret_key_share(0),
+        }
+    }
+
+    fn dkg_key(generation: u64) -> DkgKey {
+        DkgKey {
+            hash: [generation as u8; 32],
+            generation,
+        }
+    }
+
+    #[test]
+    fn handover_and_promotion() {
+        let current = key_share();
+        let current_key = current.public_key_set.public_key();
+        let mut provider = SectionKeysProvider::with_max_pending(Some(current), 2);
+
+        let shares: Vec<_> = (1..=3).map(|_| key_share()).collect();
+        let keys: Vec<_> = shares
+            .iter()
+            .map(|share| share.public_key_set.public_key())
+            .collect();
+        for (generation, share) in shares.into_iter().enumerate() {
+            provider.insert_pending(dkg_key(generation as u64 + 1), share);
+        }
+
+        // The oldest pending share got dropped.
+        assert_eq!(provider.pending_len(), 2);
+        assert!(provider.pending(&dkg_key(1)).is_none());
+
+        // Both the current and pending keys can sign during the handover.
+        let payload = b"payload";
+        let new_key = keys[1];
+        for key in &[current_key, new_key] {
+            let signed_share = provider.sign_with(key, payload);
+            assert!(matches!(signed_share, Some(share) if share.verify(payload)));
+        }
+
+        assert!(!provider.promote(&keys[0]));
+        assert!(provider.promote(&new_key));
+        assert_eq!(
+            provider
+                .key_share()
+                .map(|share| share.public_key_set.public_key()),
+            Some(new_key)
+        );
+        assert!(provider.sign_with(&current_key, payload).is_none());
+        assert!(provider.pending(&dkg_key(3)).is_some());
+        assert_eq!(provider.pending_len(), 1);
+    }
+
+    #[test]
+    fn secrets_are_zeroized() {
+        let mut share = key_share();
+        share.zeroize();
+        assert_eq!(share.secret_key_share, SecretKeyShare::default());
+
+        let secret = Secret::new(key_share());
+        assert_eq!(format!("{:?}", secret), "Secret(..)");
+        assert_ne!(
+            secret.expose_secret().secret_key_share,
+            SecretKeyShare::default()
+        );
+        assert_eq!(secret.clone(), secret);
+    }
+}
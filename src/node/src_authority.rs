@@ -6,11 +6,11 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::signed::{Signed, SignedShare};
-use ed25519_dalek::PublicKey;
-use ed25519_dalek::Signature;
+use crate::signed::{Signed, SignedShare};
+use ed25519_dalek::{PublicKey, Signature, Verifier};
 use secured_linked_list::SecuredLinkedList;
 use serde::{Deserialize, Serialize};
+use threshold_crypto::PublicKey as BlsPublicKey;
 use xor_name::XorName;
 
 /// Source authority of a message.
@@ -47,3 +47,114 @@ pub enum SrcAuthority {
         section_chain: SecuredLinkedList,
     },
 }
+
+impl SrcAuthority {
+    /// Verifies the signature carried by this authority against the payload.
+    ///
+    /// Note this only checks the signature itself, the caller still needs to check whether the
+    /// signing key is trusted, e.g. by checking the section chain.
+    pub fn verify(&self, payload: &[u8]) -> bool {
+        match self {
+            Self::Node {
+                public_key,
+                signature,
+            } => public_key.verify(payload, signature).is_ok(),
+            Self::BlsShare { signed_share, .. } => signed_share.verify(payload),
+            Self::Section { signed, .. } => signed.verify(payload),
+        }
+    }
+
+    /// Returns whether this authority is of a whole section.
+    pub fn is_section(&self) -> bool {
+        matches!(self, Self::Section { .. })
+    }
+
+    /// Returns whether this authority is of a single node, signing either with its own key
+    /// or with its BLS key share.
+    pub fn is_node(&self) -> bool {
+        !self.is_section()
+    }
+
+    /// Returns the section BLS public key this authority was signed with, if any.
+    /// For a `BlsShare` this is the public key of the key set the share belongs to.
+    pub fn bls_public_key(&self) -> Option<BlsPublicKey> {
+        match self {
+            Self::Node { .. } => None,
+            Self::BlsShare { signed_share, .. } => Some(signed_share.public_key_set.public_key()),
+            Self::Section { signed, .. } => Some(signed.public_key),
+        }
+    }
+
+    /// Returns the section chain of the sender, if any.
+    pub fn section_chain(&self) -> Option<&SecuredLinkedList> {
+        match self {
+            Self::Node { .. } => None,
+            Self::BlsShare { section_chain, .. } | Self::Section { section_chain, .. } => {
+                Some(section_chain)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Keypair, Signer};
+    use threshold_crypto::{SecretKey, SecretKeySet};
+
+    #[test]
+    fn verify_node() {
+        let keypair = Keypair::generate(&mut rand::thread_rng());
+        let authority = SrcAuthority::Node {
+            public_key: keypair.public,
+            signature: keypair.sign(b"hello"),
+        };
+
+        assert!(authority.verify(b"hello"));
+        assert!(!authority.verify(b"bye"));
+        assert!(authority.is_node());
+        assert_eq!(authority.bls_public_key(), None);
+    }
+
+    #[test]
+    fn verify_section() {
+        let sk = SecretKey::random();
+        let authority = SrcAuthority::Section {
+            src_name: XorName::random(),
+            signed: Signed {
+                public_key: sk.public_key(),
+                signature: sk.sign(b"hello"),
+            },
+            section_chain: SecuredLinkedList::new(sk.public_key()),
+        };
+
+        assert!(authority.verify(b"hello"));
+        assert!(!authority.verify(b"bye"));
+        assert!(authority.is_section());
+        assert_eq!(authority.bls_public_key(), Some(sk.public_key()));
+    }
+
+    #[test]
+    fn verify_bls_share() {
+        let sk_set = SecretKeySet::random(1, &mut rand::thread_rng());
+        let signed_share = SignedShare::new(
+            sk_set.public_keys(),
+            0,
+            &sk_set.secret_key_share(0),
+            b"hello",
+        );
+        let authority = SrcAuthority::BlsShare {
+            src_name: XorName::random(),
+            signed_share,
+            section_chain: SecuredLinkedList::new(sk_set.public_keys().public_key()),
+        };
+
+        assert!(authority.verify(b"hello"));
+        assert!(!authority.verify(b"bye"));
+        assert!(authority.is_node());
+        assert_eq!(
+            authority.bls_public_key(),
+            Some(sk_set.public_keys().public_key())
+        );
+    }
+}
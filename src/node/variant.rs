@@ -7,16 +7,16 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    agreement::{DkgFailureSigned, DkgFailureSignedSet, DkgKey, Proposal, SectionSigned},
+    agreement::{DkgFailureSigned, DkgFailureSignedSet, DkgKey, Proposal, SectionSigned, Vote},
+    genesis::GenesisMsg,
     join::{JoinRequest, JoinResponse},
     join_as_relocated::{JoinAsRelocatedRequest, JoinAsRelocatedResponse},
     network::Network,
     relocation::{RelocateDetails, RelocatePromise},
     section::{ElderCandidates, Section},
-    signed::SignedShare,
     RoutingMsg,
 };
-use crate::{DestInfo, SectionAuthorityProvider};
+use crate::{DestInfo, SectionAuthorityProvider, SignedShare};
 use bls_dkg::key_gen::message::Message as DkgMessage;
 use hex_fmt::HexFmt;
 use itertools::Itertools;
@@ -94,6 +94,7 @@ pub enum Variant {
     /// a DKG failure.
     DkgFailureAgreement(DkgFailureSignedSet),
     /// Message containing a single `Proposal` to be aggregated in the proposal aggregator.
+    /// This is the wire form of a `Vote`.
     Propose {
         content: Proposal,
         signed_share: SignedShare,
@@ -107,6 +108,17 @@ pub enum Variant {
         last_known_key: Option<BlsPublicKey>,
         msg: Box<RoutingMsg>,
     },
+    /// Message establishing the genesis section.
+    Genesis(Box<GenesisMsg>),
+}
+
+impl From<Vote> for Variant {
+    fn from(vote: Vote) -> Self {
+        Self::Propose {
+            content: vote.proposal,
+            signed_share: vote.sig_share,
+        }
+    }
 }
 
 impl Debug for Variant {
@@ -181,6 +193,7 @@ impl Debug for Variant {
                 .field("signed_share", signed_share)
                 .finish(),
             Self::SectionKnowledgeQuery { .. } => write!(f, "SectionKnowledgeQuery"),
+            Self::Genesis(msg) => write!(f, "Genesis({:?})", msg),
         }
     }
 }
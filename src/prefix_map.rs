@@ -96,6 +96,39 @@ where
             .map(|entry| &entry.0)
     }
 
+    /// Returns an iterator over all the entries whose prefixes match `name`, in order by
+    /// prefixes, i.e. from the shortest to the longest one.
+    pub fn values_matching<'a>(&'a self, name: &'a XorName) -> impl Iterator<Item = &'a T> + 'a {
+        self.0
+            .iter()
+            .filter(move |entry| entry.prefix().matches(name))
+            .map(|entry| &entry.0)
+    }
+
+    /// Gets the given prefix's entry in the map for in-place manipulation.
+    pub fn entry(&mut self, prefix: Prefix) -> PrefixEntry<'_, T> {
+        PrefixEntry { map: self, prefix }
+    }
+
+    /// Retains only the entries for which `f` returns `true`.
+    /// Note that removing entries never makes other entries redundant, so nothing is pruned.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.0.retain(|entry| f(&entry.0))
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     /// Returns an iterator over the entries, in order by prefixes.
     pub fn iter(&self) -> impl Iterator<Item = &T> + Clone {
         self.0.iter().map(|entry| &entry.0)
@@ -133,6 +166,60 @@ where
     }
 }
 
+/// A view into a single entry of a `PrefixMap`, which may either be vacant or occupied.
+pub struct PrefixEntry<'a, T>
+where
+    T: Borrow<Prefix>,
+{
+    map: &'a mut PrefixMap<T>,
+    prefix: Prefix,
+}
+
+impl<'a, T> PrefixEntry<'a, T>
+where
+    T: Borrow<Prefix>,
+{
+    /// The prefix of this entry.
+    pub fn prefix(&self) -> &Prefix {
+        &self.prefix
+    }
+
+    /// The value of this entry, if occupied.
+    pub fn get(&self) -> Option<&T> {
+        self.map.get(&self.prefix)
+    }
+
+    /// Modifies the value of this entry, if occupied. The value must keep its prefix.
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut T),
+    {
+        if let Some(Entry(mut value)) = self.map.0.take(&self.prefix) {
+            f(&mut value);
+            debug_assert_eq!(value.borrow(), &self.prefix);
+            let _ = self.map.0.insert(Entry(value));
+        }
+
+        self
+    }
+
+    /// Inserts the value returned by `f` if this entry is vacant, with the same rules as
+    /// `PrefixMap::insert`. Returns the value of this entry, which is `None` if it was vacant and
+    /// the value was not inserted because some descendants of the prefix are in the map.
+    pub fn or_insert_with<F>(self, f: F) -> Option<&'a T>
+    where
+        F: FnOnce() -> T,
+    {
+        if self.map.get(&self.prefix).is_none() {
+            let value = f();
+            debug_assert_eq!(value.borrow(), &self.prefix);
+            let _ = self.map.insert(value);
+        }
+
+        self.map.get(&self.prefix)
+    }
+}
+
 impl<T> Default for PrefixMap<T>
 where
     T: Borrow<Prefix>,
@@ -406,6 +493,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn values_matching() {
+        let mut rng = rand::thread_rng();
+
+        let mut map = PrefixMap::new();
+        let _ = map.insert((prefix("0"), 0));
+        let _ = map.insert((prefix("000"), 1));
+        let _ = map.insert((prefix("1"), 2));
+
+        let name = prefix("000").substituted_in(rng.gen());
+        assert_eq!(
+            map.values_matching(&name).collect::<Vec<_>>(),
+            vec![&(prefix("0"), 0), &(prefix("000"), 1)]
+        );
+
+        let name = prefix("01").substituted_in(rng.gen());
+        assert_eq!(
+            map.values_matching(&name).collect::<Vec<_>>(),
+            vec![&(prefix("0"), 0)]
+        );
+    }
+
+    #[test]
+    fn entry() {
+        let mut map = PrefixMap::new();
+        let _ = map.insert((prefix("00"), 0));
+
+        assert_eq!(
+            map.entry(prefix("1")).or_insert_with(|| (prefix("1"), 1)),
+            Some(&(prefix("1"), 1))
+        );
+        assert_eq!(
+            map.entry(prefix("1"))
+                .and_modify(|(_, value)| *value += 1)
+                .or_insert_with(|| (prefix("1"), 10)),
+            Some(&(prefix("1"), 2))
+        );
+        // Not inserted because a descendant is already present.
+        assert_eq!(
+            map.entry(prefix("0")).or_insert_with(|| (prefix("0"), 3)),
+            None
+        );
+        assert_eq!(map.entry(prefix("0")).get(), None);
+    }
+
+    #[test]
+    fn retain_and_len() {
+        let mut map = PrefixMap::new();
+        assert!(map.is_empty());
+
+        let _ = map.insert((prefix("0"), 0));
+        let _ = map.insert((prefix("10"), 1));
+        let _ = map.insert((prefix("11"), 2));
+        assert_eq!(map.len(), 3);
+
+        map.retain(|(_, value)| *value != 1);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&prefix("10")), None);
+        assert_eq!(map.get(&prefix("11")), Some(&(prefix("11"), 2)));
+    }
+
     fn prefix(s: &str) -> Prefix {
         s.parse().unwrap()
     }
@@ -10,13 +10,62 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Borrow,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Debug, Display, Formatter},
     net::SocketAddr,
 };
+use thiserror::Error;
 use threshold_crypto::PublicKeySet;
 use xor_name::{Prefix, XorName};
 
+/// Maximum number of elders of a section.
+pub const ELDER_SIZE: usize = 7;
+
+/// Minimum number of elders of a section.
+pub const MIN_ELDER_COUNT: usize = 1;
+
+/// Error returned when a set of elders is malformed.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum EldersError {
+    /// There are fewer elders than `MIN_ELDER_COUNT`.
+    #[error("too few elders: {0}")]
+    TooFew(usize),
+    /// There are more elders than `ELDER_SIZE`.
+    #[error("too many elders: {0}")]
+    TooMany(usize),
+    /// The name of an elder doesn't match the section prefix.
+    #[error("elder {0} doesn't match the section prefix")]
+    PrefixMismatch(XorName),
+    /// Several elders share the same socket address.
+    #[error("elders share the socket address {0}")]
+    DuplicateAddress(SocketAddr),
+}
+
+/// Checks that the elders are within count bounds, all match the prefix and have unique addresses.
+pub(crate) fn check_elders(
+    prefix: &Prefix,
+    elders: &BTreeMap<XorName, SocketAddr>,
+) -> Result<(), EldersError> {
+    if elders.len() < MIN_ELDER_COUNT {
+        return Err(EldersError::TooFew(elders.len()));
+    }
+
+    if elders.len() > ELDER_SIZE {
+        return Err(EldersError::TooMany(elders.len()));
+    }
+
+    if let Some(name) = elders.keys().find(|name| !prefix.matches(name)) {
+        return Err(EldersError::PrefixMismatch(*name));
+    }
+
+    let mut addrs = BTreeSet::new();
+    if let Some(addr) = elders.values().find(|addr| !addrs.insert(*addr)) {
+        return Err(EldersError::DuplicateAddress(*addr));
+    }
+
+    Ok(())
+}
+
 /// A new `SectionAuthorityProvider` is created whenever the elders change,
 /// due to an elder being added or removed, or the section splitting or merging.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize)]
@@ -27,6 +76,29 @@ pub struct SectionAuthorityProvider {
     pub public_key_set: PublicKeySet,
     // The section's complete set of elders as a map from their name to their socket address.
     pub elders: BTreeMap<XorName, SocketAddr>,
+    /// Generation of the section's elders. It increases every time the elders change.
+    /// Zero if unknown, e.g. for providers from older elders.
+    #[serde(default)]
+    pub generation: u64,
+}
+
+impl SectionAuthorityProvider {
+    /// Creates a new `SectionAuthorityProvider`, checking the elders are well-formed.
+    pub fn new_checked(
+        prefix: Prefix,
+        public_key_set: PublicKeySet,
+        elders: BTreeMap<XorName, SocketAddr>,
+        generation: u64,
+    ) -> Result<Self, EldersError> {
+        check_elders(&prefix, &elders)?;
+
+        Ok(Self {
+            prefix,
+            public_key_set,
+            elders,
+            generation,
+        })
+    }
 }
 
 impl Borrow<Prefix> for SectionAuthorityProvider {
@@ -39,10 +111,11 @@ impl Debug for SectionAuthorityProvider {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         write!(
             formatter,
-            "SectionAuthorityProvider {{ prefix: ({:b}), section_key: {:?}, elders: {{{:?}}} }}",
+            "SectionAuthorityProvider {{ prefix: ({:b}), section_key: {:?}, elders: {{{:?}}}, generation: {} }}",
             self.prefix,
             self.public_key_set.public_key(),
             self.elders.iter().format(", "),
+            self.generation,
         )
     }
 }
@@ -57,3 +130,105 @@ impl Display for SectionAuthorityProvider {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Result};
+    use threshold_crypto::SecretKeySet;
+
+    #[test]
+    fn new_checked() -> Result<()> {
+        let prefix = Prefix::default().pushed(true);
+        let public_key_set = SecretKeySet::random(0, &mut rand::thread_rng()).public_keys();
+        let elders: BTreeMap<_, _> = (0..ELDER_SIZE as u16)
+            .map(|port| {
+                (
+                    prefix.substituted_in(XorName::random()),
+                    SocketAddr::from(([127, 0, 0, 1], 12000 + port)),
+                )
+            })
+            .collect();
+
+        let check = |elders: &BTreeMap<XorName, SocketAddr>| {
+            SectionAuthorityProvider::new_checked(prefix, public_key_set.clone(), elders.clone(), 0)
+                .map(|_| ())
+        };
+
+        assert_eq!(check(&elders), Ok(()));
+        assert_eq!(check(&BTreeMap::new()), Err(EldersError::TooFew(0)));
+
+        let mut too_many = elders.clone();
+        let _ = too_many.insert(
+            prefix.substituted_in(XorName::random()),
+            ([127, 0, 0, 1], 13000).into(),
+        );
+        assert_eq!(check(&too_many), Err(EldersError::TooMany(ELDER_SIZE + 1)));
+
+        let (first_name, first_addr) = elders
+            .iter()
+            .next()
+            .map(|(name, addr)| (*name, *addr))
+            .ok_or_else(|| anyhow!("no elders"))?;
+
+        let mut stray = elders.clone();
+        let _ = stray.remove(&first_name);
+        let name = Prefix::default()
+            .pushed(false)
+            .substituted_in(XorName::random());
+        let _ = stray.insert(name, first_addr);
+        assert_eq!(check(&stray), Err(EldersError::PrefixMismatch(name)));
+
+        let mut duplicate = elders;
+        for addr in duplicate.values_mut() {
+            *addr = first_addr;
+        }
+        assert_eq!(
+            check(&duplicate),
+            Err(EldersError::DuplicateAddress(first_addr))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn decode_without_generation() -> Result<()> {
+        // A provider as serialised before its generation was added.
+        #[derive(Serialize)]
+        struct Previous {
+            prefix: Prefix,
+            public_key_set: PublicKeySet,
+            elders: BTreeMap<XorName, SocketAddr>,
+        }
+
+        let prefix = Prefix::default().pushed(true);
+        let public_key_set = SecretKeySet::random(0, &mut rand::thread_rng()).public_keys();
+        let elders: BTreeMap<_, _> = std::iter::once((
+            prefix.substituted_in(XorName::random()),
+            SocketAddr::from(([127, 0, 0, 1], 12000)),
+        ))
+        .collect();
+
+        let bytes = rmp_serde::to_vec_named(&Previous {
+            prefix,
+            public_key_set: public_key_set.clone(),
+            elders: elders.clone(),
+        })?;
+        let sap: SectionAuthorityProvider = rmp_serde::from_slice(&bytes)?;
+        assert_eq!(
+            sap,
+            SectionAuthorityProvider::new_checked(prefix, public_key_set, elders, 0)?
+        );
+
+        let bytes = rmp_serde::to_vec_named(&SectionAuthorityProvider {
+            generation: 3,
+            ..sap
+        })?;
+        assert_eq!(
+            rmp_serde::from_slice::<SectionAuthorityProvider>(&bytes)?.generation,
+            3
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,972 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Machine-readable description of the messages, for the SDKs written in other
+//! languages to generate their bindings from, in sync with the types of this crate.
+//!
+//! The description is traced from the serde implementations of the messages with
+//! [`serde_reflection`], so it describes the data model their payloads are serialized
+//! from, the same way for any serde format. `export_schema` returns it as a JSON document:
+//!
+//! ```json
+//! { "version": 2, "roots": ["ClientMsg", ...], "types": { "ClientMsg": <container>, ... } }
+//! ```
+//!
+//! where `version` is the messaging protocol version, `roots` the types of the payloads,
+//! and `types` all the named types reachable from them, each being a
+//! [`serde_reflection::ContainerFormat`] as serialized by serde.
+//!
+//! Types are named after their serde name. Instances of generic types are named after their
+//! type arguments too, as in `ResultVecReplicaEventError` for `Result<Vec<ReplicaEvent>,
+//! Error>`, and other types sharing a name get a numeric suffix, e.g. `PublicKey` and
+//! `PublicKey2`.
+//! Exporting fails if any of the types can't be traced entirely, but for the variants
+//! holding an empty enum listed in `UNINHABITED_VARIANTS`, which are left out.
+//!
+//! The tracer only explores all the variants of the enums it reaches from the type it is
+//! traced from, so every enum nested in the messages is traced on its own from the list in
+//! `trace_nested_enums`, which can't be derived from the registry as tracing needs the Rust
+//! type. A new message type, or any enum or `Result` newly nested in one, has to be added to
+//! that list, else exporting fails reporting it has variants left to trace.
+
+#[cfg(not(feature = "client-only"))]
+use crate::node::{self, NodeMsg, RoutingMsg};
+use crate::{
+    client::{self, ClientMsg},
+    section_info::{self, SectionInfoMsg},
+    serialisation::wire_msg_header::MESSAGING_PROTO_VERSION,
+    Error, Result,
+};
+use secured_linked_list::SecuredLinkedList;
+use serde::{
+    de::{
+        self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess,
+        VariantAccess, Visitor,
+    },
+    Serialize,
+};
+use serde_reflection::{
+    ContainerFormat, Format, FormatHolder, Registry, Samples, Tracer, TracerConfig,
+};
+use std::{
+    any::type_name,
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    marker::PhantomData,
+    sync::Mutex,
+};
+use threshold_crypto::{poly::BivarPoly, SecretKey, SecretKeySet};
+
+// Variants holding an empty enum, which can't be traced as they never hold a value, by
+// serde name of their enum. The tracer explores variants by index once it has explored
+// them by name, so these have to be the last variants of their enum.
+const UNINHABITED_VARIANTS: &[(&str, &str)] = &[("ClientMsg", "SupportingInfo")];
+
+// Traces each of the given types, returning their names.
+macro_rules! trace_all {
+    ($tracer:expr, $samples:expr, $names:expr, [$($ty:ty),* $(,)?]) => {
+        vec![$(trace::<$ty>($tracer, $samples, $names)?),*]
+    };
+}
+
+/// Returns the JSON description of all the messages, as documented in this module.
+pub fn export_schema() -> Result<String> {
+    let mut tracer = new_tracer();
+    let mut samples = Samples::new();
+    let sampled = record_samples(&mut tracer, &mut samples)?;
+    let names = RefCell::new(Names::new(&samples, sampled));
+
+    #[allow(unused_mut)]
+    let mut roots = trace_all!(&mut tracer, &samples, &names, [ClientMsg, SectionInfoMsg]);
+    #[cfg(not(feature = "client-only"))]
+    roots.extend(trace_all!(
+        &mut tracer,
+        &samples,
+        &names,
+        [NodeMsg, RoutingMsg]
+    ));
+    trace_nested_enums(&mut tracer, &samples, &names)?;
+    let registry = tracer.registry().map_err(trace_error)?;
+
+    let mut reachable = BTreeSet::new();
+    for root in &roots {
+        collect_reachable(&registry, root, &mut reachable)?;
+    }
+    let schema = Schema {
+        version: MESSAGING_PROTO_VERSION,
+        types: registry
+            .iter()
+            .filter(|(name, _)| reachable.contains(name.as_str()))
+            .collect(),
+        roots,
+    };
+    serde_json::to_string(&schema).map_err(|err| {
+        Error::Serialisation(format!("could not serialize the messages schema: {}", err))
+    })
+}
+
+#[derive(Serialize)]
+struct Schema<'a> {
+    version: u16,
+    roots: Vec<String>,
+    types: BTreeMap<&'a String, &'a ContainerFormat>,
+}
+
+fn new_tracer() -> Tracer {
+    // Samples are replayed for all the structs, e.g. BLS public key sets and ciphertexts.
+    // Integers are positive, as the big integers of the ratios identifying the entries of
+    // CRDT lists have to be to make up a denominator, and bytes are sized as an ed25519
+    // public key, which is deserialized from bytes.
+    Tracer::new(
+        TracerConfig::default()
+            .record_samples_for_tuple_structs(true)
+            .record_samples_for_structs(true)
+            .default_u8_value(1)
+            .default_u16_value(1)
+            .default_u32_value(1)
+            .default_u64_value(1)
+            .default_u128_value(1)
+            .default_i8_value(1)
+            .default_i16_value(1)
+            .default_i32_value(1)
+            .default_i64_value(1)
+            .default_i128_value(1)
+            .default_borrowed_bytes_value(&[0; 32])
+            .default_byte_buf_value(vec![0; 32]),
+    )
+}
+
+fn trace_error(err: serde_reflection::Error) -> Error {
+    Error::Serialisation(format!("could not trace the messages schema: {}", err))
+}
+
+// Records values of the types whose deserialization validates their content, such as BLS
+// keys, which can't be made up. They are replayed when tracing the messages.
+// Returns the serde names of the samples, along with the crates of their types.
+fn record_samples(
+    tracer: &mut Tracer,
+    samples: &mut Samples,
+) -> Result<BTreeSet<(&'static str, &'static str)>> {
+    let mut sampled = BTreeSet::new();
+    let secret_key_set = SecretKeySet::random(1, &mut rand::thread_rng());
+    let public_key_set = secret_key_set.public_keys();
+    let secret_key_share = secret_key_set.secret_key_share(0);
+    let public_key = public_key_set.public_key();
+    let secret_key = SecretKey::random();
+
+    record(tracer, samples, &mut sampled, &public_key_set)?;
+    record(tracer, samples, &mut sampled, &public_key)?;
+    record(
+        tracer,
+        samples,
+        &mut sampled,
+        &public_key_set.public_key_share(0),
+    )?;
+    record(tracer, samples, &mut sampled, &secret_key_share.sign(b""))?;
+    record(
+        tracer,
+        samples,
+        &mut sampled,
+        &public_key.encrypt(b"schema"),
+    )?;
+    record(
+        tracer,
+        samples,
+        &mut sampled,
+        &BivarPoly::random(1, &mut rand::thread_rng()).commitment(),
+    )?;
+    record(tracer, samples, &mut sampled, &secret_key.sign(b""))?;
+
+    let next_key = SecretKey::random().public_key();
+    let mut chain = SecuredLinkedList::new(secret_key.public_key());
+    let signature = bincode::serialize(&next_key)
+        .map(|bytes| secret_key.sign(&bytes))
+        .map_err(|err| Error::Serialisation(err.to_string()))?;
+    chain
+        .insert(&secret_key.public_key(), next_key, signature)
+        .map_err(|err| Error::Serialisation(err.to_string()))?;
+    record(tracer, samples, &mut sampled, &chain)?;
+
+    Ok(sampled)
+}
+
+fn record<T: Serialize>(
+    tracer: &mut Tracer,
+    samples: &mut Samples,
+    sampled: &mut BTreeSet<(&'static str, &'static str)>,
+    value: &T,
+) -> Result<()> {
+    if let (Format::TypeName(name), _) = tracer.trace_value(samples, value).map_err(trace_error)? {
+        let _ = sampled.insert((intern(name), crate_name(type_name::<T>())));
+    }
+    Ok(())
+}
+
+fn crate_name(rust_type: &'static str) -> &'static str {
+    rust_type.split("::").next().unwrap_or(rust_type)
+}
+
+// The tracer explores the variants of an enum only while it has some left, after which it
+// only reaches the first one, so all the enums nested in the messages are traced on their
+// own, or through the type wrapping them if they are private, as the sequence data is.
+// Those left out are reported by `collect_reachable`.
+fn trace_nested_enums(
+    tracer: &mut Tracer,
+    samples: &Samples,
+    names: &RefCell<Names>,
+) -> Result<()> {
+    use crate::{
+        client::{
+            AntiSpam, BlobRead, BlobWrite, Cmd, CmdError, DataCmd, DataCmdKind, DataMetadata,
+            DataQuery, Event, MapRead, MapWrite, ProcessMsg, Query, QueryResponse, RegisterRead,
+            RegisterWrite, RequiredPermission, SequencePage, SequenceRead, SequenceWrite,
+            StoreCostQuote, TransferCmd, TransferCmdError, TransferProof, TransferQuery,
+        },
+        Aggregation, DstLocation,
+    };
+    use sn_data_types::{
+        register, ActorHistory, Blob, BlobAddress, DataAddress, Map, MapAction, MapAddress,
+        MapEntries, MapEntryActions, MapPermissionSet, MapSeqEntryAction, MapUnseqEntryAction,
+        MapValue, MapValues, PublicKey, ReplicaEvent, Sequence, SequenceAddress, SequenceIndex,
+        SequencePermissions, SequencePrivatePolicy, SequencePublicPolicy, SequenceUser, Signature,
+        Token,
+    };
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        net::SocketAddr,
+    };
+
+    let _ = trace_all!(
+        tracer,
+        samples,
+        names,
+        [
+            ProcessMsg,
+            Cmd,
+            CmdError,
+            TransferCmdError,
+            Query,
+            QueryResponse,
+            Event,
+            client::Error,
+            RequiredPermission,
+            AntiSpam,
+            BlobRead,
+            BlobWrite,
+            DataCmd,
+            DataCmdKind,
+            DataQuery,
+            MapRead,
+            MapWrite,
+            RegisterRead,
+            RegisterWrite,
+            SequenceRead,
+            SequenceWrite,
+            TransferCmd,
+            TransferQuery,
+            section_info::Error,
+            section_info::GetSectionResponse,
+            DstLocation,
+            Aggregation,
+            SocketAddr,
+            Blob,
+            BlobAddress,
+            DataAddress,
+            Map,
+            MapAction,
+            MapAddress,
+            MapEntries,
+            MapEntryActions,
+            MapSeqEntryAction,
+            MapUnseqEntryAction,
+            MapValue,
+            MapValues,
+            PublicKey,
+            ReplicaEvent,
+            Sequence,
+            SequenceAddress,
+            SequenceIndex,
+            SequencePermissions,
+            SequenceUser,
+            Signature,
+            register::Address,
+            register::Permissions,
+            register::Policy,
+            register::User,
+            crdts::list::Op<Vec<u8>, String>,
+            std::result::Result<(), CmdError>,
+            client::Result<()>,
+            client::Result<bool>,
+            client::Result<u64>,
+            client::Result<Vec<u8>>,
+            client::Result<Vec<Vec<u8>>>,
+            client::Result<(u64, Vec<u8>)>,
+            client::Result<BTreeSet<Vec<u8>>>,
+            client::Result<BTreeSet<([u8; 32], Vec<u8>)>>,
+            client::Result<BTreeMap<[u8; 32], Vec<u8>>>,
+            client::Result<Blob>,
+            client::Result<Map>,
+            client::Result<MapEntries>,
+            client::Result<MapPermissionSet>,
+            client::Result<BTreeMap<PublicKey, MapPermissionSet>>,
+            client::Result<MapValue>,
+            client::Result<MapValues>,
+            client::Result<Sequence>,
+            client::Result<SequencePage>,
+            client::Result<SequencePermissions>,
+            client::Result<SequencePrivatePolicy>,
+            client::Result<SequencePublicPolicy>,
+            client::Result<register::Register>,
+            client::Result<register::Permissions>,
+            client::Result<register::Policy>,
+            client::Result<Vec<register::RegisterOp<Vec<u8>>>>,
+            client::Result<DataMetadata>,
+            client::Result<StoreCostQuote>,
+            client::Result<PublicKey>,
+            client::Result<(PublicKey, Token)>,
+            client::Result<(Token, usize)>,
+            client::Result<(u64, Token, PublicKey)>,
+            client::Result<(ActorHistory, Option<usize>)>,
+            client::Result<TransferProof>,
+            client::Result<Vec<ReplicaEvent>>,
+            client::Result<(Vec<ReplicaEvent>, Option<usize>)>,
+        ]
+    );
+    #[cfg(not(feature = "client-only"))]
+    let _ = trace_all!(
+        tracer,
+        samples,
+        names,
+        [
+            node::Variant,
+            node::SrcAuthority,
+            node::Proposal,
+            node::GenesisMsg,
+            node::JoinResponse,
+            node::JoinRejectionReason,
+            node::JoinAsRelocatedResponse,
+            node::MembershipState,
+            node::PenaltyReason,
+            node::NodeCmd,
+            node::NodeCmdError,
+            node::NodeDataError,
+            node::NodeDataQueryResponse,
+            node::NodeEvent,
+            node::NodeQuery,
+            node::NodeQueryResponse,
+            node::NodeRewardQuery,
+            node::NodeRewardQueryResponse,
+            node::NodeSystemCmd,
+            node::NodeSystemQuery,
+            node::NodeSystemQueryResponse,
+            node::NodeTransferCmd,
+            node::NodeTransferError,
+            node::NodeTransferQuery,
+            node::NodeTransferQueryResponse,
+            node::UndeliverableReason,
+            client::Result<Vec<node::RewardPayout>>,
+            bls_dkg::message::Message,
+        ]
+    );
+    Ok(())
+}
+
+// Traces `T`, passing over it until the enums it reaches have no variants left to trace,
+// and returns its name.
+fn trace<'de, T: Deserialize<'de>>(
+    tracer: &mut Tracer,
+    samples: &'de Samples,
+    names: &RefCell<Names>,
+) -> Result<String> {
+    loop {
+        let name = match tracer
+            .trace_type_once_with_seed(samples, Named::<T>::new(names))
+            .map_err(trace_error)?
+        {
+            (Format::TypeName(name), _) => name,
+            (format, _) => {
+                return Err(Error::Serialisation(format!(
+                    "{} is traced as {:?} rather than as a named type",
+                    type_name::<T>(),
+                    format
+                )))
+            }
+        };
+        if !has_incomplete_enums(tracer, names) {
+            return Ok(name);
+        }
+    }
+}
+
+// Returns whether some of the enums reached by the last pass have variants left to trace,
+// resetting them for the next pass.
+fn has_incomplete_enums(tracer: &mut Tracer, names: &RefCell<Names>) -> bool {
+    names
+        .borrow()
+        .enums
+        .iter()
+        .filter(|name| tracer.check_incomplete_enum(name).is_some())
+        .count()
+        > 0
+}
+
+fn collect_reachable<'a>(
+    registry: &'a Registry,
+    name: &'a str,
+    reachable: &mut BTreeSet<&'a str>,
+) -> Result<()> {
+    if !reachable.insert(name) {
+        return Ok(());
+    }
+    let container = registry
+        .get(name)
+        .ok_or_else(|| Error::Serialisation(format!("{} is referenced but wasn't traced", name)))?;
+    // Variants explored by name only are given provisional indices, past the others.
+    if let ContainerFormat::Enum(variants) = container {
+        if variants
+            .keys()
+            .any(|index| *index as usize >= variants.len())
+        {
+            return Err(Error::Serialisation(format!(
+                "{} has variants left to trace",
+                name
+            )));
+        }
+    }
+    let mut referenced = Vec::new();
+    container
+        .visit(&mut |format| {
+            if let Format::TypeName(name) = format {
+                referenced.push(name.clone());
+            }
+            Ok(())
+        })
+        .map_err(trace_error)?;
+    for name in referenced {
+        if let Some((name, _)) = registry.get_key_value(&name) {
+            collect_reachable(registry, name, reachable)?;
+        }
+    }
+    Ok(())
+}
+
+// Names of the traced containers, unique across Rust types.
+struct Names<'a> {
+    samples: &'a Samples,
+    // Serde names of the samples, along with the crates of their types.
+    sampled: BTreeSet<(&'static str, &'static str)>,
+    // Unique names of the containers, by serde name and Rust type.
+    unique: BTreeMap<(&'static str, &'static str), &'static str>,
+    // Unique names of the enums, whose variants are traced over several passes.
+    enums: BTreeSet<&'static str>,
+    // Rust types of the options being traced, whose nested occurrences are traced as `None`.
+    options: BTreeSet<&'static str>,
+}
+
+impl<'a> Names<'a> {
+    fn new(samples: &'a Samples, sampled: BTreeSet<(&'static str, &'static str)>) -> Self {
+        Self {
+            samples,
+            sampled,
+            unique: BTreeMap::new(),
+            enums: BTreeSet::new(),
+            options: BTreeSet::new(),
+        }
+    }
+
+    // Returns the unique name of the struct of the given serde name and Rust type.
+    // Sampled structs are replayed by their serde name, so they keep it. They are told
+    // apart from the types of other crates sharing it, e.g. the `SignatureShare` of
+    // sn_data_types, by crate, as they may be deserialized through another type.
+    fn struct_name(&mut self, name: &'static str, rust_type: &'static str) -> &'static str {
+        if self.sampled.contains(&(name, crate_name(rust_type))) {
+            return name;
+        }
+        self.name(name, rust_type)
+    }
+
+    // Returns the unique name of the enum of the given serde name and Rust type.
+    fn enum_name(&mut self, name: &'static str, rust_type: &'static str) -> &'static str {
+        let name = self.name(name, rust_type);
+        let _ = self.enums.insert(name);
+        name
+    }
+
+    fn name(&mut self, name: &'static str, rust_type: &'static str) -> &'static str {
+        if let Some(unique) = self.unique.get(&(name, rust_type)) {
+            return unique;
+        }
+
+        let mut base = name.to_string();
+        if let Some(start) = rust_type.find('<') {
+            let arguments = rust_type[start..]
+                .split(|c: char| !c.is_alphanumeric() && c != '_' && c != ':')
+                .filter_map(|path| path.rsplit("::").next())
+                .filter(|ident| !ident.is_empty());
+            for ident in arguments {
+                let mut chars = ident.chars();
+                base.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+                base.extend(chars);
+            }
+        }
+        let mut unique = intern(base.clone());
+        let mut suffix = 1;
+        while self.samples.value(unique).is_some()
+            || self.unique.values().any(|taken| *taken == unique)
+        {
+            suffix += 1;
+            unique = intern(format!("{}{}", base, suffix));
+        }
+
+        let _ = self.unique.insert((name, rust_type), unique);
+        unique
+    }
+}
+
+// Container names have to be `'static`, so they are leaked, but only once per name.
+fn intern(name: String) -> &'static str {
+    static INTERNED: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+    let mut interned = INTERNED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(name) = interned.get(name.as_str()) {
+        return name;
+    }
+    let name = Box::leak(name.into_boxed_str());
+    let _ = interned.insert(name);
+    name
+}
+
+// Returns the variants of the enum of the given serde name, but for its uninhabited ones.
+fn inhabited_variants(
+    name: &'static str,
+    variants: &'static [&'static str],
+) -> &'static [&'static str] {
+    static INHABITED: Mutex<BTreeMap<&'static str, &'static [&'static str]>> =
+        Mutex::new(BTreeMap::new());
+    if !UNINHABITED_VARIANTS
+        .iter()
+        .any(|(enum_name, _)| *enum_name == name)
+    {
+        return variants;
+    }
+    let mut inhabited = INHABITED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    inhabited.entry(name).or_insert_with(|| {
+        let variants = variants
+            .iter()
+            .copied()
+            .filter(|variant| !UNINHABITED_VARIANTS.contains(&(name, variant)))
+            .collect::<Vec<_>>();
+        Box::leak(variants.into_boxed_slice())
+    })
+}
+
+// What follows wraps the deserializer of the tracer, to rename the containers
+// with their unique names. Values are deserialized as they would be otherwise.
+
+// Seed deserializing a `T`.
+struct Named<'n, 'a, T> {
+    names: &'n RefCell<Names<'a>>,
+    value: PhantomData<T>,
+}
+
+impl<'n, 'a, T> Named<'n, 'a, T> {
+    fn new(names: &'n RefCell<Names<'a>>) -> Self {
+        Self {
+            names,
+            value: PhantomData,
+        }
+    }
+}
+
+impl<'de, 'n, 'a, T: Deserialize<'de>> DeserializeSeed<'de> for Named<'n, 'a, T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        T::deserialize(Renaming::new(deserializer, self.names))
+    }
+}
+
+// Seed wrapping another one.
+struct NamedSeed<'n, 'a, S> {
+    seed: S,
+    names: &'n RefCell<Names<'a>>,
+}
+
+impl<'de, 'n, 'a, S: DeserializeSeed<'de>> DeserializeSeed<'de> for NamedSeed<'n, 'a, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Value, D::Error> {
+        self.seed
+            .deserialize(Renaming::new(deserializer, self.names))
+    }
+}
+
+// Wraps a deserializer, or the accessors and visitors it hands its content to.
+struct Renaming<'n, 'a, T> {
+    inner: T,
+    names: &'n RefCell<Names<'a>>,
+}
+
+impl<'n, 'a, T> Renaming<'n, 'a, T> {
+    fn new(inner: T, names: &'n RefCell<Names<'a>>) -> Self {
+        Self { inner, names }
+    }
+
+    fn wrap<U>(&self, inner: U) -> Renaming<'n, 'a, U> {
+        Renaming::new(inner, self.names)
+    }
+
+    fn seed<S>(&self, seed: S) -> NamedSeed<'n, 'a, S> {
+        NamedSeed {
+            seed,
+            names: self.names,
+        }
+    }
+
+    fn struct_name<V>(&self, name: &'static str) -> &'static str {
+        self.names.borrow_mut().struct_name(name, type_name::<V>())
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($method:ident)*) => {
+        $(fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+            let visitor = self.wrap(visitor);
+            self.inner.$method(visitor)
+        })*
+    };
+}
+
+impl<'de, 'n, 'a, D: Deserializer<'de>> Deserializer<'de> for Renaming<'n, 'a, D> {
+    type Error = D::Error;
+
+    forward_deserialize! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_unit deserialize_seq deserialize_map deserialize_identifier
+        deserialize_ignored_any
+    }
+
+    // The tracer explores the first variant of an enum it is already tracing, so recursive
+    // messages, e.g. a `RoutingMsg` optionally nested in its first variant, are cut at the
+    // option.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        let rust_type = type_name::<V::Value>();
+        if !self.names.borrow_mut().options.insert(rust_type) {
+            return visitor.visit_none();
+        }
+        let visitor = self.wrap(visitor);
+        let result = self.inner.deserialize_option(visitor);
+        let _ = self.names.borrow_mut().options.remove(rust_type);
+        result
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let name = self.struct_name::<V::Value>(name);
+        let visitor = self.wrap(visitor);
+        self.inner.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let name = self.struct_name::<V::Value>(name);
+        let visitor = self.wrap(visitor);
+        self.inner.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = self.wrap(visitor);
+        self.inner.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let name = self.struct_name::<V::Value>(name);
+        let visitor = self.wrap(visitor);
+        self.inner.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let name = self.struct_name::<V::Value>(name);
+        let visitor = self.wrap(visitor);
+        self.inner.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let variants = inhabited_variants(name, variants);
+        let name = self
+            .names
+            .borrow_mut()
+            .enum_name(name, type_name::<V::Value>());
+        let visitor = self.wrap(visitor);
+        self.inner.deserialize_enum(name, variants, visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+macro_rules! forward_visit {
+    ($($method:ident: $ty:ty,)*) => {
+        $(fn $method<E: de::Error>(self, value: $ty) -> Result<V::Value, E> {
+            self.inner.$method(value)
+        })*
+    };
+}
+
+impl<'de, 'n, 'a, V: Visitor<'de>> Visitor<'de> for Renaming<'n, 'a, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(formatter)
+    }
+
+    forward_visit! {
+        visit_bool: bool,
+        visit_i8: i8,
+        visit_i16: i16,
+        visit_i32: i32,
+        visit_i64: i64,
+        visit_i128: i128,
+        visit_u8: u8,
+        visit_u16: u16,
+        visit_u32: u32,
+        visit_u64: u64,
+        visit_u128: u128,
+        visit_f32: f32,
+        visit_f64: f64,
+        visit_char: char,
+        visit_str: &str,
+        visit_borrowed_str: &'de str,
+        visit_string: String,
+        visit_bytes: &[u8],
+        visit_borrowed_bytes: &'de [u8],
+        visit_byte_buf: Vec<u8>,
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        let deserializer = self.wrap(deserializer);
+        self.inner.visit_some(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<V::Value, D::Error> {
+        let deserializer = self.wrap(deserializer);
+        self.inner.visit_newtype_struct(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        let seq = self.wrap(seq);
+        self.inner.visit_seq(seq)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        let map = self.wrap(map);
+        self.inner.visit_map(map)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        let data = self.wrap(data);
+        self.inner.visit_enum(data)
+    }
+}
+
+impl<'de, 'n, 'a, A: SeqAccess<'de>> SeqAccess<'de> for Renaming<'n, 'a, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.next_element_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, 'n, 'a, A: MapAccess<'de>> MapAccess<'de> for Renaming<'n, 'a, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.next_key_seed(seed)
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.next_value_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, 'n, 'a, A: EnumAccess<'de>> EnumAccess<'de> for Renaming<'n, 'a, A> {
+    type Error = A::Error;
+    type Variant = Renaming<'n, 'a, A::Variant>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), A::Error> {
+        let names = self.names;
+        self.inner
+            .variant_seed(seed)
+            .map(|(value, variant)| (value, Renaming::new(variant, names)))
+    }
+}
+
+impl<'de, 'n, 'a, A: VariantAccess<'de>> VariantAccess<'de> for Renaming<'n, 'a, A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.inner.unit_variant()
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.newtype_variant_seed(seed)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, A::Error> {
+        let visitor = self.wrap(visitor);
+        self.inner.tuple_variant(len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        let visitor = self.wrap(visitor);
+        self.inner.struct_variant(fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use serde::Deserialize;
+
+    #[test]
+    fn export_all_messages() -> Result<()> {
+        let schema: serde_json::Value = serde_json::from_str(&export_schema()?)?;
+        assert_eq!(schema["version"], MESSAGING_PROTO_VERSION);
+        assert_eq!(schema["roots"][0], "ClientMsg");
+        assert_eq!(schema["roots"][1], "SectionInfoMsg");
+
+        let client_msg = &schema["types"]["ClientMsg"]["ENUM"];
+        assert!(client_msg["1"]["ProcessingError"].is_object());
+        assert!(client_msg["2"].is_null());
+
+        let query_response = &schema["types"]["QueryResponse"]["ENUM"];
+        assert_eq!(
+            query_response["0"]["GetBlob"]["NEWTYPE"]["TYPENAME"],
+            "ResultDataError"
+        );
+        assert_eq!(
+            query_response["1"]["GetMap"]["NEWTYPE"]["TYPENAME"],
+            "ResultDataError2"
+        );
+        assert!(schema["types"]["PublicKey2"].is_object());
+
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    enum Tree {
+        Leaf(std::result::Result<u8, String>),
+        Node(Box<Tree>, std::result::Result<bool, String>),
+    }
+
+    #[allow(dead_code)]
+    #[derive(Deserialize)]
+    enum Never {
+        Empty(Empty),
+    }
+
+    #[derive(Deserialize)]
+    enum Empty {}
+
+    #[test]
+    fn trace_generic_types() -> Result<()> {
+        let mut tracer = new_tracer();
+        let samples = Samples::new();
+        let names = RefCell::new(Names::new(&samples, BTreeSet::new()));
+        while {
+            assert_eq!(trace::<Tree>(&mut tracer, &samples, &names)?, "Tree");
+            has_incomplete_enums(&mut tracer, &names)
+        } {}
+
+        let registry = tracer.registry().map_err(trace_error)?;
+        assert!(registry.contains_key("ResultU8String"));
+        assert!(registry.contains_key("ResultBoolString"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn untraceable_types_fail() {
+        let mut tracer = new_tracer();
+        let samples = Samples::new();
+        let names = RefCell::new(Names::new(&samples, BTreeSet::new()));
+        assert!(trace::<Never>(&mut tracer, &samples, &names).is_err());
+    }
+}
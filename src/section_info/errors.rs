@@ -6,6 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::SectionKeyProof;
 use crate::SectionAuthorityProvider;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -28,4 +29,10 @@ pub enum Error {
     /// Invalid data in the bootstrap cmd
     #[error("Invalid data in the bootstrap cmd")]
     InvalidBootstrap(String),
+    /// Target section key provided with message is not the current one. The proof links the
+    /// provided key to the current key of the section, which is `SectionKeyProof::key`.
+    #[error(
+        "Target section key is not the current one. A proof of the current key has been provided."
+    )]
+    WrongSectionKey(SectionKeyProof),
 }
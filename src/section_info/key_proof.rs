@@ -0,0 +1,89 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use secured_linked_list::SecuredLinkedList;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use threshold_crypto::PublicKey as BlsPublicKey;
+
+/// Compact proof that a key belongs to a section chain: the slice of the chain leading to the key
+/// from a key the receiver already trusts, without the rest of the chain.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct SectionKeyProof {
+    /// Every key on the path from the trusted key, its root, to the proven key, its last key,
+    /// each signed by the previous one. Its signatures are checked when it's deserialized.
+    pub chain: SecuredLinkedList,
+}
+
+impl SectionKeyProof {
+    /// The key the proof starts from.
+    pub fn anchor(&self) -> &BlsPublicKey {
+        self.chain.root_key()
+    }
+
+    /// The key this proof is for.
+    pub fn key(&self) -> &BlsPublicKey {
+        self.chain.last_key()
+    }
+
+    /// Verifies one of the `trusted_keys` leads to the proven key.
+    pub fn verify<'a, I>(&self, trusted_keys: I) -> bool
+    where
+        I: IntoIterator<Item = &'a BlsPublicKey>,
+    {
+        self.chain.check_trust(trusted_keys)
+    }
+}
+
+// `SecuredLinkedList` isn't `Ord`, so proofs are ordered by the keys of their chains, from the
+// root. Those determine the rest of a proof's chain, as it's a single path, each key signed by
+// the previous one, and BLS signatures are deterministic, so the order is consistent with the
+// equality of proofs.
+impl Ord for SectionKeyProof {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.chain.keys().cmp(other.chain.keys())
+    }
+}
+
+impl PartialOrd for SectionKeyProof {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use threshold_crypto::SecretKey;
+
+    fn proof(keys: &[SecretKey]) -> Result<SectionKeyProof> {
+        let mut chain = SecuredLinkedList::new(keys[0].public_key());
+        for pair in keys.windows(2) {
+            let key = pair[1].public_key();
+            let signature = pair[0].sign(&bincode::serialize(&key)?);
+            chain.insert(&pair[0].public_key(), key, signature)?;
+        }
+        Ok(SectionKeyProof { chain })
+    }
+
+    #[test]
+    fn ordered_by_chain_keys() -> Result<()> {
+        let keys: Vec<_> = (0..3).map(|_| SecretKey::random()).collect();
+        let long = proof(&keys)?;
+        let short = proof(&keys[..2])?;
+
+        assert_eq!(long.cmp(&proof(&keys)?), Ordering::Equal);
+        assert_eq!(short.cmp(&long), Ordering::Less);
+
+        let other = proof(&[SecretKey::random()])?;
+        assert_eq!(other.cmp(&long), other.anchor().cmp(long.anchor()),);
+        Ok(())
+    }
+}
@@ -7,14 +7,15 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod errors;
+mod key_proof;
 
-use crate::{MessageId, MessageType, SectionAuthorityProvider, WireMsg};
+use crate::{DstLocation, MessageId, MessageType, PrefixMap, SectionAuthorityProvider, WireMsg};
 use bytes::Bytes;
 pub use errors::Error;
+pub use key_proof::SectionKeyProof;
 use serde::{Deserialize, Serialize};
 use sn_data_types::PublicKey;
 use threshold_crypto::PublicKey as BlsPublicKey;
-use xor_name::XorName;
 
 /// Messages for exchanging network info, specifically on a target section for a msg.
 #[allow(clippy::large_enum_variant)]
@@ -26,8 +27,17 @@ pub enum SectionInfoMsg {
     GetSectionResponse(GetSectionResponse),
     /// Updated info related to section
     SectionInfoUpdate(ErrorResponse),
+    /// SectionInfoMsg to request a snapshot of all the sections known to the recipient.
+    GetNetworkQuery,
+    /// Response to `GetNetworkQuery`.
+    GetNetworkResponse(NetworkPrefixMap),
 }
 
+/// Snapshot of the sections known to a node: the prefix, key and elders of each of them.
+/// It lets clients bootstrap their knowledge of the network and check redirects locally, by
+/// looking up the section matching a name with `get_matching`.
+pub type NetworkPrefixMap = PrefixMap<SectionAuthorityProvider>;
+
 // Infrastructure error wrapper to add correltion info for triggering message
 #[derive(Debug, Serialize, Deserialize, Hash, PartialEq, PartialOrd, Ord, Eq, Clone)]
 pub struct ErrorResponse {
@@ -65,7 +75,11 @@ impl SectionInfoMsg {
     }
 
     /// serialize this Query into bytes ready to be sent over the wire.
-    pub fn serialize(&self, dest: XorName, dest_section_pk: BlsPublicKey) -> crate::Result<Bytes> {
+    pub fn serialize(
+        &self,
+        dest: DstLocation,
+        dest_section_pk: BlsPublicKey,
+    ) -> crate::Result<Bytes> {
         WireMsg::serialize_section_info_msg(self, dest, dest_section_pk)
     }
 }
@@ -0,0 +1,254 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{Error, Result};
+use crate::limits::MAX_MSG_SIZE;
+use serde::de::DeserializeOwned;
+use std::convert::TryInto;
+
+// Default maximum number of elements in any collection (sequence or map) of a payload.
+const DEFAULT_MAX_COLLECTION_LEN: usize = 1024 * 1024;
+// Default maximum nesting depth of the collections of a payload.
+const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Caps enforced on a message payload before deserialising it, so a crafted
+/// message can't make the recipient allocate excessive memory or overflow its stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeserialisationLimits {
+    /// Maximum size in bytes of the payload.
+    pub max_payload_size: usize,
+    /// Maximum number of elements in any sequence or map of the payload.
+    pub max_collection_len: usize,
+    /// Maximum nesting depth of the sequences and maps of the payload.
+    pub max_depth: usize,
+}
+
+impl Default for DeserialisationLimits {
+    fn default() -> Self {
+        Self {
+            max_payload_size: MAX_MSG_SIZE,
+            max_collection_len: DEFAULT_MAX_COLLECTION_LEN,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+impl DeserialisationLimits {
+    /// Walks through the structure of the Msgpack encoded payload, checking it's within
+    /// the limits, and that no length it declares goes beyond the end of the payload.
+    pub(crate) fn check(&self, payload: &[u8]) -> Result<()> {
+        if payload.len() > self.max_payload_size {
+            return Err(Error::MessageTooLarge {
+                size: payload.len(),
+                max: self.max_payload_size,
+            });
+        }
+
+        let mut reader = Reader { bytes: payload };
+        // Number of values yet to be read at each level of nesting,
+        // starting with the single top level value.
+        let mut pending = vec![1usize];
+
+        while let Some(remaining) = pending.last_mut() {
+            if *remaining == 0 {
+                let _ = pending.pop();
+                continue;
+            }
+            *remaining -= 1;
+
+            if let Some(len) = reader.skip_value()? {
+                if len > self.max_collection_len {
+                    return Err(Error::LimitExceeded(format!(
+                        "collection of {} elements, while the maximum is {}",
+                        len, self.max_collection_len
+                    )));
+                }
+                // Each element takes at least a byte, so don't trust any greater length.
+                if len > reader.bytes.len() {
+                    return Err(truncated());
+                }
+                if pending.len() > self.max_depth {
+                    return Err(Error::LimitExceeded(format!(
+                        "nesting deeper than the maximum of {}",
+                        self.max_depth
+                    )));
+                }
+                pending.push(len);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deserialises the Msgpack encoded payload into a `T`, if it's within the limits.
+    /// `what` names the payload in the error returned if it can't be parsed.
+    pub(crate) fn decode<T: DeserializeOwned>(&self, payload: &[u8], what: &str) -> Result<T> {
+        self.check(payload)?;
+        rmp_serde::from_slice(payload)
+            .map_err(|err| Error::FailedToParse(format!("{} as Msgpack: {}", what, err)))
+    }
+}
+
+fn truncated() -> Error {
+    Error::FailedToParse("Msgpack payload is truncated".to_string())
+}
+
+// Minimal reader of the Msgpack format markers.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.bytes.len() {
+            return Err(truncated());
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn read_len(&mut self, size: usize) -> Result<usize> {
+        let bytes = self.take(size)?;
+        let len = match size {
+            1 => u64::from(bytes[0]),
+            2 => u64::from(u16::from_be_bytes(
+                bytes.try_into().map_err(|_| truncated())?,
+            )),
+            _ => u64::from(u32::from_be_bytes(
+                bytes.try_into().map_err(|_| truncated())?,
+            )),
+        };
+        Ok(len as usize)
+    }
+
+    // Skips the next value if it's a scalar, returning `None`. If it's a sequence or map,
+    // only its marker is skipped, returning the number of values it contains.
+    fn skip_value(&mut self) -> Result<Option<usize>> {
+        let marker = self.take(1)?[0];
+        let skip = match marker {
+            // positive and negative fixint, nil, false, true
+            0x00..=0x7f | 0xe0..=0xff | 0xc0 | 0xc2 | 0xc3 => 0,
+            // fixmap
+            0x80..=0x8f => return Ok(Some(2 * usize::from(marker & 0x0f))),
+            // fixarray
+            0x90..=0x9f => return Ok(Some(usize::from(marker & 0x0f))),
+            // fixstr
+            0xa0..=0xbf => usize::from(marker & 0x1f),
+            // bin and str 8/16/32
+            0xc4 | 0xd9 => self.read_len(1)?,
+            0xc5 | 0xda => self.read_len(2)?,
+            0xc6 | 0xdb => self.read_len(4)?,
+            // ext 8/16/32, followed by their type byte
+            0xc7 => self.read_len(1)? + 1,
+            0xc8 => self.read_len(2)? + 1,
+            0xc9 => self.read_len(4)? + 1,
+            // uint, int and float
+            0xcc | 0xd0 => 1,
+            0xcd | 0xd1 => 2,
+            0xca | 0xce | 0xd2 => 4,
+            0xcb | 0xcf | 0xd3 => 8,
+            // fixext 1/2/4/8/16, followed by their type byte
+            0xd4 => 2,
+            0xd5 => 3,
+            0xd6 => 5,
+            0xd7 => 9,
+            0xd8 => 17,
+            // array 16/32
+            0xdc => return self.read_len(2).map(Some),
+            0xdd => return self.read_len(4).map(Some),
+            // map 16/32
+            0xde => return self.read_len(2).map(|len| Some(2 * len)),
+            0xdf => return self.read_len(4).map(|len| Some(2 * len)),
+            // 0xc1 is never used
+            _ => {
+                return Err(Error::FailedToParse(format!(
+                    "invalid Msgpack marker: {:#x}",
+                    marker
+                )))
+            }
+        };
+        let _ = self.take(skip)?;
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Result};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn payload_within_limits() -> Result<()> {
+        let mut map = BTreeMap::new();
+        let _ = map.insert("key".to_string(), vec![vec![1u64, u64::MAX], vec![]]);
+        let payload = rmp_serde::to_vec_named(&(map, -1i8, 1.5f64, "text", true, ()))?;
+
+        DeserialisationLimits::default().check(&payload)?;
+        Ok(())
+    }
+
+    #[test]
+    fn payload_too_large() -> Result<()> {
+        let payload = rmp_serde::to_vec_named(&vec![0u8; 100])?;
+        let limits = DeserialisationLimits {
+            max_payload_size: 10,
+            ..Default::default()
+        };
+
+        match limits.check(&payload) {
+            Err(Error::MessageTooLarge { size, max: 10 }) if size == payload.len() => Ok(()),
+            other => Err(anyhow!("unexpected result: {:?}", other)),
+        }
+    }
+
+    #[test]
+    fn collection_too_long() -> Result<()> {
+        let payload = rmp_serde::to_vec_named(&vec![0u8; 100])?;
+        let limits = DeserialisationLimits {
+            max_collection_len: 99,
+            ..Default::default()
+        };
+        assert!(matches!(
+            limits.check(&payload),
+            Err(Error::LimitExceeded(_))
+        ));
+
+        // array32 claiming more elements than bytes in the payload
+        let payload = [0xdd, 0x00, 0x01, 0x00, 0x00, 0x01];
+        assert!(matches!(
+            DeserialisationLimits::default().check(&payload),
+            Err(Error::FailedToParse(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn nesting_too_deep() -> Result<()> {
+        let payload = rmp_serde::to_vec_named(&vec![vec![vec![0u8]]])?;
+        let limits = DeserialisationLimits {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert!(matches!(
+            limits.check(&payload),
+            Err(Error::LimitExceeded(_))
+        ));
+
+        let limits = DeserialisationLimits {
+            max_depth: 3,
+            ..Default::default()
+        };
+        limits.check(&payload)?;
+
+        Ok(())
+    }
+}
@@ -7,17 +7,29 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
+mod limits;
+mod summary;
+pub(crate) mod unknown;
+#[cfg(feature = "testing")]
+pub mod vectors;
 pub mod wire_msg_header;
 
-use self::wire_msg_header::{MessageKind, WireMsgHeader};
+pub use self::limits::DeserialisationLimits;
+pub use self::summary::MessageSummary;
+pub use self::wire_msg_header::MAX_CAUSED_BY_LEN;
+use self::wire_msg_header::{MessageKind, WireMsgHeader, PREVIOUS_MESSAGING_PROTO_VERSION};
 #[cfg(not(feature = "client-only"))]
 use super::node::{self, Variant};
-use super::{client, section_info, DestInfo, Error, MessageId, MessageType, Result};
+use super::{
+    client, section_info, Aggregation, DestInfo, DstLocation, Error, MessageId, MessageType, Result,
+};
 use bytes::Bytes;
 use cookie_factory::{combinator::slice, gen_simple};
 use std::fmt::Debug;
 use threshold_crypto::PublicKey;
-use xor_name::XorName;
+
+// Smallest size the payload of a padded message is rounded up to.
+const PADDING_MIN_BUCKET_SIZE: usize = 512;
 
 // In order to send a message over the wire, it needs to be serialized
 // along with a header (WireMsgHeader) which contains the information needed
@@ -33,7 +45,7 @@ impl WireMsg {
     /// Creates a new instance keeping a (serialized) copy of the 'SectionInfo' message provided.
     pub fn new_section_info_msg(
         query: &section_info::SectionInfoMsg,
-        dest: XorName,
+        dest: DstLocation,
         dest_section_pk: PublicKey,
     ) -> Result<Self> {
         let payload_vec = rmp_serde::to_vec_named(&query).map_err(|err| {
@@ -47,6 +59,7 @@ impl WireMsg {
             header: WireMsgHeader::new(
                 MessageId::new(),
                 MessageKind::SectionInfo,
+                Aggregation::None,
                 dest,
                 dest_section_pk,
                 None,
@@ -58,7 +71,7 @@ impl WireMsg {
     /// Creates a new instance keeping a (serialized) copy of the client 'Message' message provided.
     pub fn new_client_msg(
         msg: &client::ClientMsg,
-        dest: XorName,
+        dest: DstLocation,
         dest_section_pk: PublicKey,
     ) -> Result<Self> {
         let payload_vec = rmp_serde::to_vec_named(&msg).map_err(|err| {
@@ -70,7 +83,14 @@ impl WireMsg {
         })?;
 
         Ok(Self {
-            header: WireMsgHeader::new(msg.id(), MessageKind::Client, dest, dest_section_pk, None),
+            header: WireMsgHeader::new(
+                msg.id(),
+                MessageKind::Client,
+                Aggregation::None,
+                dest,
+                dest_section_pk,
+                None,
+            ),
             payload: Bytes::from(payload_vec),
         })
     }
@@ -79,7 +99,7 @@ impl WireMsg {
     #[cfg(not(feature = "client-only"))]
     pub fn new_routing_msg(
         msg: &node::RoutingMsg,
-        dest: XorName,
+        dest: DstLocation,
         dest_section_pk: PublicKey,
     ) -> Result<Self> {
         let payload_vec = rmp_serde::to_vec_named(&msg).map_err(|err| {
@@ -90,7 +110,14 @@ impl WireMsg {
         })?;
 
         Ok(Self {
-            header: WireMsgHeader::new(msg.id, MessageKind::Routing, dest, dest_section_pk, None),
+            header: WireMsgHeader::new(
+                msg.id,
+                MessageKind::Routing,
+                msg.aggregation,
+                dest,
+                dest_section_pk,
+                None,
+            ),
             payload: Bytes::from(payload_vec),
         })
     }
@@ -99,11 +126,41 @@ impl WireMsg {
     #[cfg(not(feature = "client-only"))]
     pub fn new_node_msg(
         msg: &node::NodeMsg,
-        dest: XorName,
+        dest: DstLocation,
         dest_section_pk: PublicKey,
         src_section_pk: Option<PublicKey>,
     ) -> Result<Self> {
-        let payload_vec = rmp_serde::to_vec_named(&msg).map_err(|err| {
+        Self::new_aggregated_node_msg(
+            msg,
+            dest,
+            dest_section_pk,
+            src_section_pk,
+            Aggregation::None,
+            None,
+        )
+    }
+
+    /// Creates a new instance keeping a (serialized) copy of the node 'Message' message provided,
+    /// declaring the aggregation scheme to be applied to it in the header.
+    /// When it's to be aggregated at destination, the sender's signature share over the
+    /// serialized node message needs to be provided.
+    #[cfg(not(feature = "client-only"))]
+    pub fn new_aggregated_node_msg(
+        msg: &node::NodeMsg,
+        dest: DstLocation,
+        dest_section_pk: PublicKey,
+        src_section_pk: Option<PublicKey>,
+        aggregation: Aggregation,
+        signed_share: Option<node::SignedShare>,
+    ) -> Result<Self> {
+        if aggregation == Aggregation::AtDestination && signed_share.is_none() {
+            return Err(Error::Serialisation(format!(
+                "node message (id: {}) to be aggregated at destination is missing a signature share",
+                msg.id()
+            )));
+        }
+
+        let payload_vec = rmp_serde::to_vec_named(&(msg, signed_share)).map_err(|err| {
             Error::Serialisation(format!(
                 "could not serialize a node command message payload with Msgpack: {}",
                 err
@@ -114,6 +171,7 @@ impl WireMsg {
             header: WireMsgHeader::new(
                 msg.id(),
                 MessageKind::Node,
+                aggregation,
                 dest,
                 dest_section_pk,
                 src_section_pk,
@@ -124,14 +182,65 @@ impl WireMsg {
 
     /// Attempts to create an instance of WireMsg by deserialising the bytes provided.
     /// To succeed, the bytes should contain at least a valid WireMsgHeader.
+    /// The payload is only checked against the `DeserialisationLimits` once decoded
+    /// with `to_message` or `to_message_with_limits`.
     pub fn from(bytes: Bytes) -> Result<Self> {
         // Deserialize the header bytes first
+        #[cfg(feature = "metrics")]
+        let size = bytes.len();
         let (header, payload) = WireMsgHeader::from(bytes)?;
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_deserialised(header.kind().name(), size);
 
         // We can now create a deserialized WireMsg using the read bytes
         Ok(Self { header, payload })
     }
 
+    /// Deserializes the bytes of a message of the current or the previous protocol version,
+    /// so peers of the previous version can still bootstrap during a rolling upgrade.
+    /// Messages of the previous version are translated into the current one, which they are
+    /// then forwarded with.
+    ///
+    /// Only `SectionInfoMsg`s are translated. The payloads of client, routing and node
+    /// messages were reshaped by the current version, so those of the previous version are
+    /// rejected with `Error::UnsupportedVersion` rather than misread: peers have to upgrade
+    /// before exchanging them.
+    pub fn from_any(bytes: Bytes) -> Result<Self> {
+        if WireMsgHeader::version(&bytes)? != PREVIOUS_MESSAGING_PROTO_VERSION {
+            return Self::from(bytes);
+        }
+
+        #[cfg(feature = "metrics")]
+        let size = bytes.len();
+        let (header, payload) = WireMsgHeader::from_previous(bytes)?;
+        match header.kind() {
+            // Variants were only appended to these since, and the fields added to the types
+            // they embed, e.g. `SectionAuthorityProvider::generation`, default when missing.
+            MessageKind::SectionInfo => (),
+            _ => return Err(Error::UnsupportedVersion(PREVIOUS_MESSAGING_PROTO_VERSION)),
+        }
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_deserialised(header.kind().name(), size);
+
+        Ok(Self { header, payload })
+    }
+
+    /// Summarises the serialised message from its header, without decoding its payload,
+    /// e.g. to triage captured messages.
+    pub fn inspect(bytes: Bytes) -> Result<MessageSummary> {
+        let (header, payload) = WireMsgHeader::from(bytes)?;
+        Ok(MessageSummary {
+            kind: header.kind().name(),
+            msg_id: header.msg_id(),
+            caused_by: header.caused_by().to_vec(),
+            dest: header.dest(),
+            dest_section_pk: header.dest_section_pk(),
+            src_section_pk: header.src_section_pk(),
+            aggregation: header.aggregation(),
+            payload_len: payload.len(),
+        })
+    }
+
     #[cfg(not(feature = "client-only"))]
     pub fn is_join_request(&self) -> Result<bool> {
         if let MessageKind::Routing = self.header.kind() {
@@ -152,23 +261,74 @@ impl WireMsg {
     /// Return the serialized WireMsg, which contains the WireMsgHeader bytes,
     /// followed by the payload bytes, i.e. the serialized Message.
     pub fn serialize(&self) -> Result<Bytes> {
+        self.serialize_with_padding(0)
+    }
+
+    /// Return the serialized WireMsg, with its payload padded with zeroes up to the next
+    /// power of two (and at least 512 bytes), so the size of the message as seen on an
+    /// encrypted transport doesn't reveal the exact size of its content.
+    /// The actual payload length is kept in the header, and padding is dropped when deserializing.
+    pub fn serialize_padded(&self) -> Result<Bytes> {
+        let payload_len = self.payload.len();
+        let bucket_size = payload_len
+            .max(PADDING_MIN_BUCKET_SIZE)
+            .checked_next_power_of_two()
+            .unwrap_or(payload_len);
+        self.serialize_with_padding(bucket_size - payload_len)
+    }
+
+    fn serialize_with_padding(&self, padding_len: usize) -> Result<Bytes> {
+        // Don't send a payload which recipients would reject with the default limits
+        crate::limits::check_msg_size(self.payload.len())?;
+
         // First we create a buffer with the exact size
-        // needed to serialize the wire msg
-        let mut buffer = vec![0u8; self.size()];
+        // needed to serialize the wire msg, zeroed so that
+        // the bytes after the payload make up the padding
+        let mut buffer = vec![0u8; self.size() + padding_len];
 
-        let buf_at_payload = self.header.write(&mut buffer)?;
+        let buf_at_payload = self.header.write(&mut buffer, &self.payload)?;
 
         // ...and finally we write the bytes of the serialized payload to the original buffer
         let _ = gen_simple(slice(self.payload.clone()), buf_at_payload).map_err(|err| {
             Error::Serialisation(format!("message payload couldn't be serialized: {}", err))
         })?;
 
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_serialised(self.header.kind().name(), buffer.len());
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            msg_id = %self.msg_id(),
+            kind = self.header.kind().name(),
+            size = buffer.len(),
+            "message serialised"
+        );
+
         // We can now return the buffer containing the written bytes
         Ok(Bytes::from(buffer))
     }
 
     /// Deserialize the payload from this WireMsg returning a Message instance.
+    /// The payload is checked against the default `DeserialisationLimits` beforehand.
     pub fn to_message(&self) -> Result<MessageType> {
+        self.to_message_with_limits(&DeserialisationLimits::default())
+    }
+
+    /// Deserialize the payload from this WireMsg returning a Message instance,
+    /// if the payload is within the given limits.
+    pub fn to_message_with_limits(&self, limits: &DeserialisationLimits) -> Result<MessageType> {
+        limits.check(&self.payload)?;
+        let msg = self.decode_payload()?;
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            parent: &msg.span(),
+            kind = self.header.kind().name(),
+            "message deserialised"
+        );
+        Ok(msg)
+    }
+
+    // Deserializes the payload into a message of the kind declared in the header.
+    fn decode_payload(&self) -> Result<MessageType> {
         let dest_info = DestInfo {
             dest: self.dest(),
             dest_section_pk: self.dest_section_pk(),
@@ -216,7 +376,7 @@ impl WireMsg {
             }
             #[cfg(not(feature = "client-only"))]
             MessageKind::Node => {
-                let node_cmd: node::NodeMsg =
+                let (node_cmd, signed_share): (node::NodeMsg, Option<node::SignedShare>) =
                     rmp_serde::from_slice(&self.payload).map_err(|err| {
                         Error::FailedToParse(format!("NodeCmd message payload as Msgpack: {}", err))
                     })?;
@@ -224,7 +384,9 @@ impl WireMsg {
                 Ok(MessageType::Node{
                     msg: node_cmd,
                     dest_info,
-                    src_section_pk: self.src_section_pk()
+                    src_section_pk: self.src_section_pk(),
+                    aggregation: self.aggregation(),
+                    signed_share,
                 })
             }
         }
@@ -235,13 +397,18 @@ impl WireMsg {
         self.header.msg_id()
     }
 
+    /// Return the aggregation scheme declared for this message
+    pub fn aggregation(&self) -> Aggregation {
+        self.header.aggregation()
+    }
+
     /// Return the destination section PublicKey for this message
     pub fn dest_section_pk(&self) -> PublicKey {
         self.header.dest_section_pk()
     }
 
     /// Return the destination for this message
-    pub fn dest(&self) -> XorName {
+    pub fn dest(&self) -> DstLocation {
         self.header.dest()
     }
 
@@ -252,20 +419,56 @@ impl WireMsg {
         self.header.src_section_pk()
     }
 
+    /// Return the ids of the messages which caused this one, oldest first.
+    pub fn caused_by(&self) -> &[MessageId] {
+        self.header.caused_by()
+    }
+
+    /// Set the ids of the messages which caused this one, oldest first.
+    /// Only the most recent `MAX_CAUSED_BY_LEN` ids are kept.
+    pub fn set_caused_by(&mut self, caused_by: Vec<MessageId>) {
+        self.header.set_caused_by(caused_by)
+    }
+
+    /// Record the given message as the cause of this one, carrying over its own chain of causes.
+    pub fn set_cause(&mut self, cause: &WireMsg) {
+        let mut caused_by = cause.caused_by().to_vec();
+        caused_by.push(cause.msg_id());
+        self.set_caused_by(caused_by)
+    }
+
     // The following functions are just for convenience, which allow users to
     // not need to create an instance of WireMsg beforehand.
 
     /// Convenience function which creates a temporary WireMsg from the provided
     /// bytes, returning the deserialized message.
+    /// The payload is checked against the default `DeserialisationLimits` beforehand.
     pub fn deserialize(bytes: Bytes) -> Result<MessageType> {
-        Self::from(bytes)?.to_message()
+        Self::deserialize_with_limits(bytes, &DeserialisationLimits::default())
+    }
+
+    /// Convenience function which creates a temporary WireMsg from the provided
+    /// bytes, returning the deserialized message if its payload is within the given limits.
+    pub fn deserialize_with_limits(
+        bytes: Bytes,
+        limits: &DeserialisationLimits,
+    ) -> Result<MessageType> {
+        Self::from(bytes)?.to_message_with_limits(limits)
+    }
+
+    /// Convenience function which creates a temporary WireMsg from the provided bytes,
+    /// of the current or the previous protocol version as accepted by `from_any`,
+    /// returning the deserialized message.
+    /// The payload is checked against the default `DeserialisationLimits` beforehand.
+    pub fn deserialize_any(bytes: Bytes) -> Result<MessageType> {
+        Self::from_any(bytes)?.to_message()
     }
 
     /// Convenience function which creates a temporary WireMsg from the provided
     /// MsgEnvelope, returning the serialized WireMsg.
     pub fn serialize_section_info_msg(
         query: &section_info::SectionInfoMsg,
-        dest: XorName,
+        dest: DstLocation,
         dest_section_pk: PublicKey,
     ) -> Result<Bytes> {
         Self::new_section_info_msg(query, dest, dest_section_pk)?.serialize()
@@ -275,7 +478,7 @@ impl WireMsg {
     /// Message, returning the serialized WireMsg.
     pub fn serialize_client_msg(
         msg: &client::ClientMsg,
-        dest: XorName,
+        dest: DstLocation,
         dest_section_pk: PublicKey,
     ) -> Result<Bytes> {
         Self::new_client_msg(msg, dest, dest_section_pk)?.serialize()
@@ -286,7 +489,7 @@ impl WireMsg {
     #[cfg(not(feature = "client-only"))]
     pub fn serialize_routing_msg(
         msg: &node::RoutingMsg,
-        dest: XorName,
+        dest: DstLocation,
         dest_section_pk: PublicKey,
     ) -> Result<Bytes> {
         Self::new_routing_msg(msg, dest, dest_section_pk)?.serialize()
@@ -297,7 +500,7 @@ impl WireMsg {
     #[cfg(not(feature = "client-only"))]
     pub fn serialize_node_msg(
         msg: &node::NodeMsg,
-        dest: XorName,
+        dest: DstLocation,
         dest_section_pk: PublicKey,
         src_section_pk: Option<PublicKey>,
     ) -> Result<Bytes> {
@@ -311,7 +514,7 @@ impl WireMsg {
     }
 
     /// Update dest_pk and or dest in the WireMsg
-    pub fn update_dest_info(&mut self, dest_pk: Option<PublicKey>, dest: Option<XorName>) {
+    pub fn update_dest_info(&mut self, dest_pk: Option<PublicKey>, dest: Option<DstLocation>) {
         if let Some(dest) = dest {
             self.header.dest = dest
         }
@@ -324,13 +527,14 @@ impl WireMsg {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use anyhow::Result;
-    use threshold_crypto::SecretKey;
-    use xor_name::XorName;
+    use crate::SectionAuthorityProvider;
+    use anyhow::{anyhow, Result};
+    use threshold_crypto::{SecretKey, SecretKeySet};
+    use xor_name::{Prefix, XorName};
 
     #[test]
     fn serialisation_section_info_msg() -> Result<()> {
-        let dest = XorName::random();
+        let dest = DstLocation::Section(XorName::random());
         let dest_section_pk = SecretKey::random().public_key();
 
         let query = section_info::SectionInfoMsg::GetSectionQuery(dest_section_pk.into());
@@ -360,9 +564,74 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn serialisation_network_snapshot_msg() -> Result<()> {
+        let dest = DstLocation::Section(XorName::random());
+        let dest_section_pk = SecretKey::random().public_key();
+
+        let network: section_info::NetworkPrefixMap = (0..2)
+            .map(|bit| SectionAuthorityProvider {
+                prefix: Prefix::default().pushed(bit == 1),
+                public_key_set: SecretKeySet::random(0, &mut rand::thread_rng()).public_keys(),
+                elders: vec![(XorName::random(), ([127, 0, 0, 1], 12000 + bit).into())]
+                    .into_iter()
+                    .collect(),
+                generation: 0,
+            })
+            .collect();
+        assert_eq!(network.len(), 2);
+
+        let response = section_info::SectionInfoMsg::GetNetworkResponse(network);
+        let wire_msg = WireMsg::new_section_info_msg(&response, dest, dest_section_pk)?;
+        let deserialized = WireMsg::from(wire_msg.serialize()?)?;
+
+        assert_eq!(
+            deserialized.to_message()?,
+            MessageType::SectionInfo {
+                msg: response,
+                dest_info: DestInfo {
+                    dest,
+                    dest_section_pk
+                }
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn serialisation_padded_section_info_msg() -> Result<()> {
+        let dest = DstLocation::Section(XorName::random());
+        let dest_section_pk = SecretKey::random().public_key();
+
+        let query = section_info::SectionInfoMsg::GetSectionQuery(dest_section_pk.into());
+        let wire_msg = WireMsg::new_section_info_msg(&query, dest, dest_section_pk)?;
+        let serialized = wire_msg.serialize_padded()?;
+        assert_eq!(
+            serialized.len(),
+            wire_msg.header.size() as usize + PADDING_MIN_BUCKET_SIZE
+        );
+
+        // padding is dropped when deserialising
+        let deserialized = WireMsg::from(serialized)?;
+        assert_eq!(deserialized, wire_msg);
+        assert_eq!(
+            deserialized.to_message()?,
+            MessageType::SectionInfo {
+                msg: query,
+                dest_info: DestInfo {
+                    dest,
+                    dest_section_pk
+                }
+            }
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn serialisation_and_update_dest_for_section_info_msg() -> Result<()> {
-        let dest = XorName::random();
+        let dest = DstLocation::Section(XorName::random());
         let dest_section_pk = SecretKey::random().public_key();
 
         let query = section_info::SectionInfoMsg::GetSectionQuery(dest_section_pk.into());
@@ -370,7 +639,7 @@ mod tests {
         let serialized = wire_msg.serialize()?;
 
         let wire_msg2 = wire_msg.clone();
-        let dest_new = XorName::random();
+        let dest_new = DstLocation::Node(XorName::random());
         wire_msg.update_dest_info(None, Some(dest_new));
         let serialised_second_msg = wire_msg.serialize()?;
 
@@ -404,12 +673,16 @@ mod tests {
         use crate::MessageId;
         use node::{NodeCmd, NodeMsg, NodeSystemCmd};
 
-        let dest = XorName::random();
+        let dest = DstLocation::Section(XorName::random());
         let src_section_pk = SecretKey::random().public_key();
         let dest_section_pk = SecretKey::random().public_key();
 
         let node_cmd = NodeMsg::NodeCmd {
-            cmd: NodeCmd::System(NodeSystemCmd::RegisterWallet(dest_section_pk.into())),
+            cmd: NodeCmd::System(NodeSystemCmd::register_wallet(
+                dest_section_pk.into(),
+                0,
+                &sn_data_types::Keypair::new_ed25519(&mut rand::thread_rng()),
+            )?),
             id: MessageId::new(),
         };
 
@@ -434,10 +707,17 @@ mod tests {
                     dest,
                     dest_section_pk
                 },
-                src_section_pk: None
+                src_section_pk: None,
+                aggregation: Aggregation::None,
+                signed_share: None,
             }
         );
 
+        // the message ids are available for tracing
+        let msg = deserialized.to_message()?;
+        assert_eq!(msg.msg_id(), Some(node_cmd.id()));
+        assert_eq!(msg.correlation_id(), None);
+
         // let's now test including a source section public key in the header
         let wire_msg_with_src_pk =
             WireMsg::new_node_msg(&node_cmd, dest, dest_section_pk, Some(src_section_pk))?;
@@ -460,10 +740,353 @@ mod tests {
                     dest,
                     dest_section_pk
                 },
-                src_section_pk: Some(src_section_pk)
+                src_section_pk: Some(src_section_pk),
+                aggregation: Aggregation::None,
+                signed_share: None,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "client-only"))]
+    fn serialisation_caused_by_chain() -> Result<()> {
+        use crate::MessageId;
+        use node::{NodeCmd, NodeMsg, NodeSystemCmd};
+
+        let dest = DstLocation::Section(XorName::random());
+        let src_section_pk = SecretKey::random().public_key();
+        let dest_section_pk = SecretKey::random().public_key();
+
+        let node_cmd = NodeMsg::NodeCmd {
+            cmd: NodeCmd::System(NodeSystemCmd::register_wallet(
+                dest_section_pk.into(),
+                0,
+                &sn_data_types::Keypair::new_ed25519(&mut rand::thread_rng()),
+            )?),
+            id: MessageId::new(),
+        };
+
+        let cause = WireMsg::new_section_info_msg(
+            &section_info::SectionInfoMsg::GetSectionQuery(dest_section_pk.into()),
+            dest,
+            dest_section_pk,
+        )?;
+        let mut wire_msg =
+            WireMsg::new_node_msg(&node_cmd, dest, dest_section_pk, Some(src_section_pk))?;
+        wire_msg.set_cause(&cause);
+        assert_eq!(wire_msg.caused_by(), &[cause.msg_id()]);
+
+        // the chain and the source section key can both be read back
+        let deserialized = WireMsg::from(wire_msg.serialize()?)?;
+        assert_eq!(deserialized, wire_msg);
+        assert_eq!(deserialized.caused_by(), &[cause.msg_id()]);
+        assert_eq!(deserialized.src_section_pk(), Some(src_section_pk));
+
+        // the chain is bounded, keeping the most recent causes
+        let chain: Vec<_> = (0..MAX_CAUSED_BY_LEN + 2)
+            .map(|_| MessageId::new())
+            .collect();
+        wire_msg.set_caused_by(chain.clone());
+        assert_eq!(wire_msg.caused_by(), &chain[2..]);
+
+        let deserialized = WireMsg::from(wire_msg.serialize()?)?;
+        assert_eq!(deserialized.caused_by(), &chain[2..]);
+        assert_eq!(deserialized.src_section_pk(), Some(src_section_pk));
+        assert_eq!(deserialized.to_message()?, wire_msg.to_message()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn inspect_wire_bytes() -> Result<()> {
+        let dest = DstLocation::Section(XorName::random());
+        let dest_section_pk = SecretKey::random().public_key();
+        let msg = section_info::SectionInfoMsg::GetSectionQuery(dest_section_pk.into());
+        let cause = MessageId::new();
+        let mut wire_msg = WireMsg::new_section_info_msg(&msg, dest, dest_section_pk)?;
+        wire_msg.set_caused_by(vec![cause]);
+
+        let summary = WireMsg::inspect(wire_msg.serialize_padded()?)?;
+        assert_eq!(
+            summary,
+            MessageSummary {
+                kind: "SectionInfo",
+                msg_id: wire_msg.msg_id(),
+                caused_by: vec![cause],
+                dest,
+                dest_section_pk,
+                src_section_pk: None,
+                aggregation: Aggregation::None,
+                payload_len: wire_msg.payload.len(),
+            }
+        );
+        let display = summary.to_string();
+        assert!(display.starts_with(&format!("SectionInfo msg {}", wire_msg.msg_id())));
+        assert!(display.contains(&format!("caused_by=[{}]", cause)));
+
+        assert!(WireMsg::inspect(Bytes::from_static(b"not a msg")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(not(feature = "client-only"))]
+    fn serialisation_aggregated_node_msg() -> Result<()> {
+        use crate::MessageId;
+        use node::{NodeCmd, NodeMsg, NodeSystemCmd, SignedShare};
+        use threshold_crypto::SecretKeySet;
+
+        let dest = DstLocation::Section(XorName::random());
+        let dest_section_pk = SecretKey::random().public_key();
+
+        let node_cmd = NodeMsg::NodeCmd {
+            cmd: NodeCmd::System(NodeSystemCmd::register_wallet(
+                dest_section_pk.into(),
+                0,
+                &sn_data_types::Keypair::new_ed25519(&mut rand::thread_rng()),
+            )?),
+            id: MessageId::new(),
+        };
+
+        // a signature share is required when aggregating at destination
+        assert!(WireMsg::new_aggregated_node_msg(
+            &node_cmd,
+            dest,
+            dest_section_pk,
+            None,
+            Aggregation::AtDestination,
+            None
+        )
+        .is_err());
+
+        let sk_set = SecretKeySet::random(1, &mut rand::thread_rng());
+        let payload = rmp_serde::to_vec_named(&node_cmd)?;
+        let signed_share = SignedShare::new(
+            sk_set.public_keys(),
+            0,
+            &sk_set.secret_key_share(0),
+            &payload,
+        );
+
+        let wire_msg = WireMsg::new_aggregated_node_msg(
+            &node_cmd,
+            dest,
+            dest_section_pk,
+            None,
+            Aggregation::AtDestination,
+            Some(signed_share.clone()),
+        )?;
+        let deserialized = WireMsg::from(wire_msg.serialize()?)?;
+        assert_eq!(deserialized.aggregation(), Aggregation::AtDestination);
+
+        assert_eq!(
+            deserialized.to_message()?,
+            MessageType::Node {
+                msg: node_cmd,
+                dest_info: DestInfo {
+                    dest,
+                    dest_section_pk
+                },
+                src_section_pk: None,
+                aggregation: Aggregation::AtDestination,
+                signed_share: Some(signed_share),
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reject_previous_version() -> Result<()> {
+        // A message as sent by peers of version 1, whose header layout differs
+        // from the current one, is only accepted through `from_any`.
+        let previous = Bytes::from_static(include_bytes!(
+            "../../tests/vectors/v1/section_info_get_section_query.bin"
+        ));
+        assert!(matches!(
+            WireMsg::deserialize(previous.clone()),
+            Err(Error::UnsupportedVersion(1))
+        ));
+        assert!(matches!(
+            WireMsg::inspect(previous),
+            Err(Error::UnsupportedVersion(1))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_previous_version() -> Result<()> {
+        let previous = Bytes::from_static(include_bytes!(
+            "../../tests/vectors/v1/section_info_get_section_query.bin"
+        ));
+        let wire_msg = WireMsg::from_any(previous.clone())?;
+        assert_eq!(wire_msg.msg_id(), MessageId::with([1; 32]));
+        assert_eq!(wire_msg.dest(), DstLocation::Section(XorName([7; 32])));
+        assert!(wire_msg.caused_by().is_empty());
+        assert!(matches!(
+            WireMsg::deserialize_any(previous.clone())?,
+            MessageType::SectionInfo {
+                msg: section_info::SectionInfoMsg::GetSectionQuery { .. },
+                ..
             }
+        ));
+
+        // it's forwarded with the current version
+        let upgraded = wire_msg.serialize()?;
+        assert_eq!(WireMsg::from(upgraded.clone())?, wire_msg);
+        assert_eq!(WireMsg::from_any(upgraded)?, wire_msg);
+
+        // messages of the current version are accepted as they are
+        let dest = DstLocation::Section(XorName::random());
+        let dest_section_pk = threshold_crypto::SecretKey::random().public_key();
+        let current =
+            section_info::SectionInfoMsg::GetNetworkQuery.serialize(dest, dest_section_pk)?;
+        assert_eq!(WireMsg::from_any(current.clone())?, WireMsg::from(current)?);
+
+        // the section authority provider of responses predates its generation
+        let response = Bytes::from_static(include_bytes!(
+            "../../tests/vectors/v1/section_info_get_section_response.bin"
+        ));
+        let sap = match WireMsg::deserialize_any(response)? {
+            MessageType::SectionInfo {
+                msg:
+                    section_info::SectionInfoMsg::GetSectionResponse(
+                        section_info::GetSectionResponse::Success(sap),
+                    ),
+                ..
+            } => sap,
+            msg => return Err(anyhow!("unexpected message {:?}", msg)),
+        };
+        assert_eq!(sap.prefix, Prefix::default().pushed(false));
+        assert_eq!(sap.elders.len(), 1);
+        assert_eq!(sap.generation, 0);
+
+        // the payloads of other kinds of messages were reshaped, so they aren't translated
+        let mut client = previous.to_vec();
+        client[36] = 0x01;
+        assert!(matches!(
+            WireMsg::deserialize_any(Bytes::from(client)),
+            Err(Error::UnsupportedVersion(1))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn detect_corrupted_payload() -> Result<()> {
+        let dest = DstLocation::Section(XorName::random());
+        let dest_section_pk = SecretKey::random().public_key();
+        let msg = section_info::SectionInfoMsg::GetSectionQuery(dest_section_pk.into());
+        let wire_msg = WireMsg::new_section_info_msg(&msg, dest, dest_section_pk)?;
+
+        let mut bytes = wire_msg.serialize_padded()?.to_vec();
+        let last = wire_msg.size() - 1;
+        bytes[last] ^= 0x01;
+        assert!(matches!(
+            WireMsg::from(Bytes::from(bytes.clone())),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+
+        // padding isn't covered by the checksum
+        bytes[last] ^= 0x01;
+        let padding_index = bytes.len() - 1;
+        bytes[padding_index] = 0xFF;
+        assert_eq!(WireMsg::from(Bytes::from(bytes))?, wire_msg);
+
+        Ok(())
+    }
+
+    #[test]
+    fn payload_is_checked_against_limits() -> Result<()> {
+        let dest = DstLocation::Section(XorName::random());
+        let dest_section_pk = SecretKey::random().public_key();
+        let msg = section_info::SectionInfoMsg::GetSectionQuery(dest_section_pk.into());
+        let wire_msg = WireMsg::from(
+            WireMsg::new_section_info_msg(&msg, dest, dest_section_pk)?.serialize()?,
+        )?;
+
+        let limits = DeserialisationLimits {
+            max_payload_size: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            wire_msg.to_message_with_limits(&limits),
+            Err(Error::MessageTooLarge { max: 1, .. })
+        ));
+        assert_eq!(
+            wire_msg.to_message()?,
+            wire_msg.to_message_with_limits(&DeserialisationLimits::default())?
         );
 
         Ok(())
     }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn deserialised_msg_is_traced_with_its_ids() -> Result<()> {
+        use crate::{
+            client::{ClientMsg, DataError, Error as ClientError, ProcessMsg, QueryResponse},
+            MessageId,
+        };
+        use std::{
+            io,
+            sync::{Arc, Mutex, PoisonError},
+        };
+
+        // Collects the formatted events, along with the fields of their spans.
+        #[derive(Clone, Default)]
+        struct Output(Arc<Mutex<Vec<u8>>>);
+
+        impl io::Write for Output {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let id = MessageId::new();
+        let correlation_id = MessageId::new();
+        let msg = ClientMsg::Process(ProcessMsg::QueryResponse {
+            id,
+            response: QueryResponse::GetBalance(Err(ClientError::Data(DataError::NoSuchKey))),
+            correlation_id,
+            origin: None,
+        });
+        let dest = DstLocation::EndUser(crate::EndUser {
+            xorname: XorName::random(),
+            socket_id: XorName::random(),
+        });
+        let wire_msg = WireMsg::new_client_msg(&msg, dest, SecretKey::random().public_key())?;
+
+        let output = Output::default();
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_writer(move || writer.clone())
+            .finish();
+        let received = tracing::subscriber::with_default(subscriber, || {
+            WireMsg::from(wire_msg.serialize()?)?.to_message()
+        })?;
+        assert_eq!(received.correlation_id(), Some(correlation_id));
+
+        let output = output.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let event = String::from_utf8_lossy(&output)
+            .lines()
+            .find(|line| line.contains("message deserialised"))
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("no deserialisation event"))?;
+        assert!(event.contains(&format!("msg_id={}", id)));
+        assert!(event.contains(&format!("correlation_id={}", correlation_id)));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,64 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{Aggregation, DstLocation, MessageId};
+use hex_fmt::HexFmt;
+use std::fmt::{self, Display, Formatter};
+use threshold_crypto::PublicKey;
+
+/// Summary of a serialised message, as read from its header without decoding its payload.
+/// See `WireMsg::inspect`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageSummary {
+    /// Kind of message: "SectionInfo", "Client", "Routing" or "Node".
+    pub kind: &'static str,
+    /// Message ID.
+    pub msg_id: MessageId,
+    /// Ids of the messages which caused this one, oldest first.
+    pub caused_by: Vec<MessageId>,
+    /// Destination of the message.
+    pub dest: DstLocation,
+    /// Key of the destination section.
+    pub dest_section_pk: PublicKey,
+    /// Key of the source section, if included.
+    pub src_section_pk: Option<PublicKey>,
+    /// Aggregation scheme of the message.
+    pub aggregation: Aggregation,
+    /// Length of the serialised payload, in bytes.
+    pub payload_len: usize,
+}
+
+impl Display for MessageSummary {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} msg {}, dst={:?}, dst_pk={:10}",
+            self.kind,
+            self.msg_id,
+            self.dest,
+            HexFmt(self.dest_section_pk.to_bytes())
+        )?;
+        if let Some(src_section_pk) = &self.src_section_pk {
+            write!(f, ", src_pk={:10}", HexFmt(src_section_pk.to_bytes()))?;
+        }
+        if !self.caused_by.is_empty() {
+            write!(f, ", caused_by=[")?;
+            for (index, id) in self.caused_by.iter().enumerate() {
+                let separator = if index == 0 { "" } else { ", " };
+                write!(f, "{}{}", separator, id)?;
+            }
+            write!(f, "]")?;
+        }
+        write!(
+            f,
+            ", aggregation={:?}, payload={} bytes",
+            self.aggregation, self.payload_len
+        )
+    }
+}
@@ -0,0 +1,336 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Tolerance to enum variants introduced by newer versions of this crate.
+//!
+//! Enums implementing `ForwardCompatible` derive their serde implementations with
+//! `#[serde(remote = "Self")]`, and implement `Serialize` and `Deserialize` by calling
+//! the functions of this module, falling back to those derived implementations.
+//! A variant unknown to this version is then deserialized as `Unknown { tag, bytes }`,
+//! `tag` being its index and `bytes` its content as Msgpack, and serialized back as it
+//! was received, so that it can be forwarded although it can't be processed.
+//! Only self-describing formats, such as the Msgpack used on the wire, can carry them.
+
+use serde::{
+    de::{self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, Visitor},
+    forward_to_deserialize_any,
+    ser::{self, SerializeMap, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{cell::Cell, convert::TryFrom, fmt};
+
+/// An enum carrying the variants unknown to this version as `Unknown { tag, bytes }`.
+pub(crate) trait ForwardCompatible: Sized {
+    /// Name of the enum.
+    const NAME: &'static str;
+
+    /// Names of the variants known to this version, in order, `Unknown` excluded.
+    const VARIANTS: &'static [&'static str];
+
+    /// Deserializes one of the variants known to this version, with the derived implementation.
+    fn deserialize_known<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+
+    /// Builds the `Unknown` variant.
+    fn unknown(tag: u32, bytes: Vec<u8>) -> Self;
+}
+
+/// Serializes an unknown variant back as it was received.
+pub(crate) fn serialize_unknown<S: Serializer>(
+    name: &'static str,
+    tag: u32,
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let content: RawValue = rmp_serde::from_slice(bytes).map_err(ser::Error::custom)?;
+    serializer.serialize_newtype_variant(name, tag, "Unknown", &content)
+}
+
+/// Deserializes a known variant of `T`, or its `Unknown` variant.
+pub(crate) fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: ForwardCompatible,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_enum(T::NAME, T::VARIANTS, TagVisitor::<T>(Default::default()))
+}
+
+struct TagVisitor<T>(std::marker::PhantomData<T>);
+
+impl<'de, T: ForwardCompatible> Visitor<'de> for TagVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "enum {}", T::NAME)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<T, A::Error> {
+        let (tag, variant) = data.variant_seed(Tag::<T>(Default::default()))?;
+        let unknown = Cell::new(None);
+        let known = T::deserialize_known(KnownVariant {
+            tag,
+            variant,
+            unknown: &unknown,
+        });
+
+        match (known, unknown.take()) {
+            (_, Some(content)) => rmp_serde::to_vec(&content)
+                .map(|bytes| T::unknown(tag, bytes))
+                .map_err(de::Error::custom),
+            (known, None) => known,
+        }
+    }
+}
+
+// Reads the tag of a variant, given as its index or, by formats naming the variants,
+// as its name.
+struct Tag<T>(std::marker::PhantomData<T>);
+
+impl<'de, T: ForwardCompatible> DeserializeSeed<'de> for Tag<T> {
+    type Value = u32;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<u32, D::Error> {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de, T: ForwardCompatible> Visitor<'de> for Tag<T> {
+    type Value = u32;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a variant of enum {}", T::NAME)
+    }
+
+    fn visit_u64<E: de::Error>(self, tag: u64) -> Result<u32, E> {
+        u32::try_from(tag).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(tag), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<u32, E> {
+        T::VARIANTS
+            .iter()
+            .position(|variant| *variant == name)
+            .map(|tag| tag as u32)
+            .ok_or_else(|| E::unknown_variant(name, T::VARIANTS))
+    }
+}
+
+// Hands the variant, whose tag was already read, over to the derived implementation.
+// If the tag is unknown, its content is read and stashed in `unknown` instead.
+struct KnownVariant<'a, A> {
+    tag: u32,
+    variant: A,
+    unknown: &'a Cell<Option<RawValue>>,
+}
+
+impl<'de, 'a, A: de::VariantAccess<'de>> Deserializer<'de> for KnownVariant<'a, A> {
+    type Error = A::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, A::Error> {
+        visitor.visit_enum(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, 'a, A: de::VariantAccess<'de>> EnumAccess<'de> for KnownVariant<'a, A> {
+    type Error = A::Error;
+    type Variant = A;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, A), A::Error> {
+        let tag: de::value::U32Deserializer<A::Error> = self.tag.into_deserializer();
+        match seed.deserialize(tag) {
+            Ok(value) => Ok((value, self.variant)),
+            Err(error) => {
+                self.unknown
+                    .set(Some(self.variant.newtype_variant::<RawValue>()?));
+                Err(error)
+            }
+        }
+    }
+}
+
+// Any value of a self-describing format.
+#[derive(Debug, PartialEq)]
+enum RawValue {
+    Nil,
+    Bool(bool),
+    Unsigned(u64),
+    Signed(i64),
+    F32(f32),
+    F64(f64),
+    Str(String),
+    Bin(Vec<u8>),
+    Array(Vec<RawValue>),
+    Map(Vec<(RawValue, RawValue)>),
+}
+
+impl Serialize for RawValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Nil => serializer.serialize_unit(),
+            Self::Bool(value) => serializer.serialize_bool(*value),
+            Self::Unsigned(value) => serializer.serialize_u64(*value),
+            Self::Signed(value) => serializer.serialize_i64(*value),
+            Self::F32(value) => serializer.serialize_f32(*value),
+            Self::F64(value) => serializer.serialize_f64(*value),
+            Self::Str(value) => serializer.serialize_str(value),
+            Self::Bin(value) => serializer.serialize_bytes(value),
+            Self::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Self::Map(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RawValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RawValueVisitor)
+    }
+}
+
+struct RawValueVisitor;
+
+impl<'de> Visitor<'de> for RawValueVisitor {
+    type Value = RawValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "any value")
+    }
+
+    fn visit_unit<E>(self) -> Result<RawValue, E> {
+        Ok(RawValue::Nil)
+    }
+
+    fn visit_none<E>(self) -> Result<RawValue, E> {
+        Ok(RawValue::Nil)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<RawValue, D::Error> {
+        RawValue::deserialize(deserializer)
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<RawValue, E> {
+        Ok(RawValue::Bool(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<RawValue, E> {
+        Ok(RawValue::Unsigned(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<RawValue, E> {
+        Ok(RawValue::Signed(value))
+    }
+
+    fn visit_f32<E>(self, value: f32) -> Result<RawValue, E> {
+        Ok(RawValue::F32(value))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<RawValue, E> {
+        Ok(RawValue::F64(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<RawValue, E> {
+        Ok(RawValue::Str(value.to_string()))
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<RawValue, E> {
+        Ok(RawValue::Bin(value.to_vec()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<RawValue, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(RawValue::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<RawValue, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(RawValue::Map(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::{ProcessMsg, QueryResponse};
+    use anyhow::Result;
+
+    // Checks `T::VARIANTS` lists as many variants as the enum derives.
+    fn check_variants_count<T: ForwardCompatible + for<'de> Deserialize<'de>>(
+        is_unknown: fn(&T) -> bool,
+    ) {
+        let tag = |index: usize| vec![0x81, index as u8, 0xc0];
+        let last = rmp_serde::from_slice::<T>(&tag(T::VARIANTS.len() - 1));
+        assert!(
+            !matches!(last, Ok(value) if is_unknown(&value)),
+            "{}",
+            T::NAME
+        );
+        let next = rmp_serde::from_slice::<T>(&tag(T::VARIANTS.len()));
+        assert!(
+            matches!(next, Ok(value) if is_unknown(&value)),
+            "{}",
+            T::NAME
+        );
+    }
+
+    #[test]
+    fn variants_count() {
+        check_variants_count::<ProcessMsg>(|msg| matches!(msg, ProcessMsg::Unknown { .. }));
+        check_variants_count::<QueryResponse>(|response| {
+            matches!(response, QueryResponse::Unknown { .. })
+        });
+        #[cfg(not(feature = "client-only"))]
+        check_variants_count::<crate::node::NodeCmd>(|cmd| {
+            matches!(cmd, crate::node::NodeCmd::Unknown { .. })
+        });
+    }
+
+    #[test]
+    fn unknown_variant_round_trip() -> Result<()> {
+        let response = QueryResponse::BlobExists(Ok(true));
+        let bytes = rmp_serde::to_vec_named(&(&response, 7u8))?;
+        assert_eq!(
+            rmp_serde::from_slice::<(QueryResponse, u8)>(&bytes)?,
+            (response, 7)
+        );
+
+        // A tuple, holding a single entry map from the variant index to its content.
+        let mut newer = bytes;
+        assert_eq!(&newer[..2], &[0x92, 0x81]);
+        newer[2] = 0x7f;
+
+        let (unknown, next): (QueryResponse, u8) = rmp_serde::from_slice(&newer)?;
+        assert!(matches!(unknown, QueryResponse::Unknown { tag: 0x7f, .. }));
+        assert!(!unknown.is_success());
+        assert_eq!(next, 7);
+        assert_eq!(rmp_serde::to_vec_named(&(&unknown, next))?, newer);
+
+        Ok(())
+    }
+}
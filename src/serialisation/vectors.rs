@@ -0,0 +1,332 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Canonical serialized samples of the messages, and the harness checking the
+//! vectors stored from the wire format of the current and previous versions.
+//! Vectors of previous versions are kept to check that they are rejected by
+//! `WireMsg::deserialize`, rather than misread with the current layout, while those
+//! of the previous version which `WireMsg::deserialize_any` translates still decode.
+//!
+//! The samples are built from fixed keys and ids only, so they are byte for byte
+//! reproducible. The vectors of new samples are stored by running the tests with the
+//! `SN_MESSAGING_WRITE_VECTORS` environment variable set, but stored vectors are never
+//! rewritten: each must still decode to its sample, proving that payloads of peers
+//! predating an additive change, e.g. a new `#[serde(default)]` field, still decode.
+//! Setting such a field gets a new named sample instead. Deliberate wire format breaks
+//! bump the protocol version, whose vectors are then stored in a new directory.
+
+use super::{wire_msg_header::MESSAGING_PROTO_VERSION, WireMsg};
+use crate::{
+    client::{
+        BlobRead, ClientMsg, ClientSigned, Cmd, DataError, DataQuery, Error as ClientError,
+        ProcessMsg, ProcessingError, Query, QueryResponse, ResponseOrigin,
+    },
+    section_info::SectionInfoMsg,
+    DstLocation, EndUser, MessageId, Result,
+};
+#[cfg(not(feature = "client-only"))]
+use crate::{
+    node::{NodeCmd, NodeMsg, NodeSystemCmd, RoutingMsg, SignedShare, SrcAuthority, Variant},
+    Aggregation,
+};
+use bytes::Bytes;
+use ed25519_dalek::{Keypair, PublicKey as Ed25519PublicKey, SecretKey, Signer};
+use sn_data_types::{BlobAddress, PublicKey, Signature, Token};
+#[cfg(not(feature = "client-only"))]
+use threshold_crypto::{poly::Poly, SecretKeySet};
+use threshold_crypto::{IntoFr, SecretKey as BlsSecretKey};
+use xor_name::XorName;
+
+/// Directory, relative to the crate root, where the vectors are stored.
+/// Each protocol version has its own subdirectory, e.g. `v2`.
+pub const VECTORS_DIR: &str = "tests/vectors";
+
+fn keypair(seed: u8) -> Keypair {
+    let secret = SecretKey::from_bytes(&[seed; 32]).expect("invalid secret key length");
+    let public = Ed25519PublicKey::from(&secret);
+    Keypair { secret, public }
+}
+
+fn bls_secret_key(seed: u64) -> BlsSecretKey {
+    BlsSecretKey::from_mut(&mut seed.into_fr())
+}
+
+fn client_signed(keypair: &Keypair, payload: &[u8]) -> ClientSigned {
+    ClientSigned {
+        public_key: PublicKey::from(keypair.public),
+        signature: Signature::Ed25519(keypair.sign(payload)),
+    }
+}
+
+/// Returns a canonical serialized sample of each kind of message, along with its name.
+pub fn samples() -> Result<Vec<(&'static str, Bytes)>> {
+    let section_key = bls_secret_key(1).public_key();
+    let client = keypair(1);
+    let name = XorName([7; 32]);
+    let end_user = EndUser {
+        xorname: name,
+        socket_id: XorName([8; 32]),
+    };
+    let id = MessageId::with([1; 32]);
+    let correlation_id = MessageId::with([2; 32]);
+
+    let query = Query::Data(DataQuery::Blob(BlobRead::Get(BlobAddress::Public(name))));
+    let socket_addr = ([127, 0, 0, 1], 12000).into();
+    let cmd = Cmd::RegisterEndUser {
+        socket_addr,
+        socketaddr_sig: Signature::Ed25519(
+            client.sign(&Cmd::register_end_user_bytes(&socket_addr)?),
+        ),
+    };
+
+    #[cfg_attr(feature = "client-only", allow(unused_mut))]
+    let mut samples = vec![
+        ("section_info_get_section_query", {
+            let mut wire_msg = WireMsg::new_section_info_msg(
+                &SectionInfoMsg::GetSectionQuery(section_key.into()),
+                DstLocation::Section(name),
+                section_key,
+            )?;
+            wire_msg.header.set_msg_id(id);
+            wire_msg.serialize()?
+        }),
+        (
+            "client_query",
+            ClientMsg::Process(ProcessMsg::Query {
+                id,
+                query,
+                client_signed: client_signed(&client, b"query"),
+                deadline: Some(1_600_000_000_000),
+                anti_spam: None,
+                session: None,
+            })
+            .serialize(DstLocation::Section(name), section_key)?,
+        ),
+        (
+            "client_cmd",
+            ClientMsg::Process(ProcessMsg::Cmd {
+                id,
+                cmd,
+                nonce: 1,
+                client_signed: client_signed(&client, b"cmd"),
+                session: None,
+            })
+            .serialize(DstLocation::Section(name), section_key)?,
+        ),
+        (
+            "client_query_response",
+            ClientMsg::Process(ProcessMsg::QueryResponse {
+                id,
+                response: QueryResponse::GetBalance(Ok((Token::from_nano(1_000_000_000), 3))),
+                correlation_id,
+                origin: Some(ResponseOrigin {
+                    section_key,
+                    handler_msg_id: MessageId::with([4; 32]),
+                    epoch: 7,
+                }),
+            })
+            .serialize(DstLocation::EndUser(end_user), section_key)?,
+        ),
+        ("client_query_response_caused_by", {
+            let mut wire_msg = WireMsg::new_client_msg(
+                &ClientMsg::Process(ProcessMsg::QueryResponse {
+                    id,
+                    response: QueryResponse::GetBalance(Ok((Token::from_nano(1), 0))),
+                    correlation_id,
+                    origin: None,
+                }),
+                DstLocation::EndUser(end_user),
+                section_key,
+            )?;
+            wire_msg.set_caused_by(vec![MessageId::with([3; 32]), correlation_id]);
+            wire_msg.serialize()?
+        }),
+        (
+            "client_processing_error",
+            ClientMsg::ProcessingError(ProcessingError::new(
+                Some(ClientError::Data(DataError::NoSuchEntry)),
+                None,
+                id,
+            ))
+            .serialize(DstLocation::EndUser(end_user), section_key)?,
+        ),
+    ];
+
+    #[cfg(not(feature = "client-only"))]
+    {
+        let node = keypair(2);
+        let payload = b"user message".to_vec();
+        let routing_msg = RoutingMsg {
+            id,
+            src: SrcAuthority::Node {
+                public_key: node.public,
+                signature: node.sign(&payload),
+            },
+            dst: DstLocation::Node(name),
+            aggregation: Aggregation::None,
+            variant: Variant::UserMessage(payload),
+            section_pk: section_key,
+        };
+
+        let node_msg = NodeMsg::NodeCmd {
+            cmd: NodeCmd::System(NodeSystemCmd::register_wallet(
+                PublicKey::from(node.public),
+                1,
+                &sn_data_types::Keypair::from(keypair(2).secret),
+            )?),
+            id,
+        };
+
+        let secret_key_set = SecretKeySet::from(Poly::monomial(1) + 7u64);
+        let signed_share = SignedShare {
+            public_key_set: secret_key_set.public_keys(),
+            index: 0,
+            signature_share: secret_key_set.secret_key_share(0).sign(b"node msg"),
+        };
+
+        samples.extend(vec![
+            (
+                "routing_user_message",
+                routing_msg.serialize(DstLocation::Node(name), section_key)?,
+            ),
+            (
+                "node_cmd",
+                node_msg.serialize(
+                    DstLocation::Section(name),
+                    section_key,
+                    Some(secret_key_set.public_keys().public_key()),
+                )?,
+            ),
+            (
+                "node_cmd_aggregated_at_destination",
+                WireMsg::new_aggregated_node_msg(
+                    &node_msg,
+                    DstLocation::Section(name),
+                    section_key,
+                    Some(secret_key_set.public_keys().public_key()),
+                    Aggregation::AtDestination,
+                    Some(signed_share),
+                )?
+                .serialize()?,
+            ),
+            ("node_cmd_caused_by", {
+                let mut wire_msg = WireMsg::new_node_msg(
+                    &node_msg,
+                    DstLocation::Section(name),
+                    section_key,
+                    Some(secret_key_set.public_keys().public_key()),
+                )?;
+                wire_msg.set_caused_by(vec![correlation_id]);
+                wire_msg.serialize()?
+            }),
+        ]);
+    }
+
+    Ok(samples)
+}
+
+/// Directory where the vectors of the current protocol version are stored.
+pub fn current_vectors_dir() -> String {
+    format!("{}/v{}", VECTORS_DIR, MESSAGING_PROTO_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::wire_msg_header::PREVIOUS_MESSAGING_PROTO_VERSION, *};
+    use anyhow::{anyhow, Result};
+    use std::{fs, path::PathBuf};
+
+    fn crate_path(path: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path)
+    }
+
+    #[test]
+    fn samples_match_current_vectors() -> Result<()> {
+        let dir = crate_path(&current_vectors_dir());
+        let write = std::env::var_os("SN_MESSAGING_WRITE_VECTORS").is_some();
+        for (name, bytes) in samples()? {
+            let path = dir.join(format!("{}.bin", name));
+            if write && !path.exists() {
+                fs::create_dir_all(&dir)?;
+                fs::write(&path, &bytes)?;
+            }
+
+            let stored =
+                fs::read(&path).map_err(|err| anyhow!("missing vector for {}: {}", name, err))?;
+            assert_eq!(
+                WireMsg::deserialize(Bytes::from(stored))?,
+                WireMsg::deserialize(bytes)?,
+                "stored vector of {} doesn't decode to the sample",
+                name
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn stored_vectors_can_be_parsed() -> Result<()> {
+        for version_dir in fs::read_dir(crate_path(VECTORS_DIR))? {
+            let version_dir = version_dir?.path();
+            let version: u16 = version_dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix('v'))
+                .and_then(|version| version.parse().ok())
+                .ok_or_else(|| anyhow!("unexpected vectors dir {}", version_dir.display()))?;
+
+            for vector in fs::read_dir(&version_dir)? {
+                let path = vector?.path();
+                #[cfg(feature = "client-only")]
+                {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    if name.starts_with("routing_") || name.starts_with("node_") {
+                        continue;
+                    }
+                }
+
+                let bytes = Bytes::from(fs::read(&path)?);
+                if version < MESSAGING_PROTO_VERSION {
+                    assert!(
+                        matches!(
+                            WireMsg::deserialize(bytes.clone()),
+                            Err(crate::Error::UnsupportedVersion(v)) if v == version
+                        ),
+                        "{} wasn't rejected",
+                        path.display()
+                    );
+
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    if version == PREVIOUS_MESSAGING_PROTO_VERSION
+                        && name.starts_with("section_info_")
+                    {
+                        let _ = WireMsg::deserialize_any(bytes).map_err(|err| {
+                            anyhow!("{} can't be translated: {}", path.display(), err)
+                        })?;
+                    } else {
+                        assert!(
+                            matches!(
+                                WireMsg::deserialize_any(bytes),
+                                Err(crate::Error::UnsupportedVersion(v)) if v == version
+                            ),
+                            "{} wasn't rejected",
+                            path.display()
+                        );
+                    }
+                    continue;
+                }
+
+                let _ = WireMsg::deserialize(bytes)
+                    .map_err(|err| anyhow!("{} can't be parsed: {}", path.display(), err))?;
+            }
+        }
+
+        Ok(())
+    }
+}
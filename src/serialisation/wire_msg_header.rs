@@ -7,10 +7,10 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use crate::{Error, MessageId, Result, MESSAGE_ID_LEN};
+use crate::{Aggregation, DstLocation, EndUser, Error, MessageId, Result, MESSAGE_ID_LEN};
 use bytes::Bytes;
 use cookie_factory::{
-    bytes::{be_u16, be_u8},
+    bytes::{be_u16, be_u32, be_u8},
     combinator::slice,
     gen,
 };
@@ -19,8 +19,15 @@ use threshold_crypto::{PublicKey, PK_SIZE};
 use xor_name::{XorName, XOR_NAME_LEN};
 
 // Current version of the messaging protocol.
-// At this point this implementation supports only this version.
-const MESSAGING_PROTO_VERSION: u16 = 1u16;
+pub(crate) const MESSAGING_PROTO_VERSION: u16 = 2u16;
+
+// Previous version of the messaging protocol, whose messages `WireMsgHeader::from_previous`
+// translates into the current layout, for `WireMsg::from_any`.
+pub(crate) const PREVIOUS_MESSAGING_PROTO_VERSION: u16 = 1u16;
+
+/// Maximum number of ids of causing messages carried in the header of a message.
+/// When the chain grows beyond it, the oldest causes are dropped.
+pub const MAX_CAUSED_BY_LEN: usize = 8;
 
 // Header to be serialisied at the front of the wire message.
 // This header contains the information needed to deserialize the payload.
@@ -30,9 +37,11 @@ pub(crate) struct WireMsgHeader {
     header_size: u16,
     version: u16,
     kind: MessageKind,
-    pub(crate) dest: XorName,
+    aggregation: Aggregation,
+    pub(crate) dest: DstLocation,
     pub(crate) dest_section_pk: PublicKey,
     src_section_pk: Option<PublicKey>,
+    caused_by: Vec<MessageId>,
 }
 
 // Bytes length in the header for the 'header_size' field
@@ -52,9 +61,34 @@ const HDR_MSG_ID_BYTES_END: usize = HDR_MSG_ID_BYTES_START + HDR_MSG_ID_BYTES_LE
 const HDR_KIND_BYTES_START: usize = HDR_MSG_ID_BYTES_END;
 const HDR_KIND_BYTES_LEN: usize = 1;
 
-// Bytes index in the header for the 'dest' field
-const HDR_DEST_BYTES_START: usize = HDR_KIND_BYTES_START + HDR_KIND_BYTES_LEN;
-const HDR_DEST_BYTES_LEN: usize = XOR_NAME_LEN;
+// Bytes index in the header for the 'aggregation' field
+const HDR_AGGREGATION_BYTES_START: usize = HDR_KIND_BYTES_START + HDR_KIND_BYTES_LEN;
+const HDR_AGGREGATION_BYTES_LEN: usize = 1;
+
+// Bytes index in the header for the number of ids in the 'caused_by' field,
+// the ids themselves being written at the end of the header.
+const HDR_CAUSED_BY_LEN_BYTES_START: usize =
+    HDR_AGGREGATION_BYTES_START + HDR_AGGREGATION_BYTES_LEN;
+const HDR_CAUSED_BY_LEN_BYTES_LEN: usize = 1;
+
+// Bytes index and size in the header for the 'payload_len' field. This is the length
+// of the actual payload, which can be followed by padding bytes on the wire.
+const HDR_PAYLOAD_LEN_BYTES_START: usize =
+    HDR_CAUSED_BY_LEN_BYTES_START + HDR_CAUSED_BY_LEN_BYTES_LEN;
+const HDR_PAYLOAD_LEN_BYTES_LEN: usize = size_of::<u32>();
+const HDR_PAYLOAD_LEN_BYTES_END: usize = HDR_PAYLOAD_LEN_BYTES_START + HDR_PAYLOAD_LEN_BYTES_LEN;
+
+// Bytes index and size in the header for the 'checksum' field, the CRC-32 of the actual payload.
+const HDR_CHECKSUM_BYTES_START: usize = HDR_PAYLOAD_LEN_BYTES_END;
+const HDR_CHECKSUM_BYTES_LEN: usize = size_of::<u32>();
+const HDR_CHECKSUM_BYTES_END: usize = HDR_CHECKSUM_BYTES_START + HDR_CHECKSUM_BYTES_LEN;
+
+// Bytes index in the header for the 'dest' field. The destination location is
+// written as a 1 byte tag followed by two name slots, the second one only being
+// used by an EndUser destination (for its socket id), and zeroed otherwise.
+const HDR_DEST_BYTES_START: usize = HDR_CHECKSUM_BYTES_END;
+const HDR_DEST_TAG_BYTES_LEN: usize = 1;
+const HDR_DEST_BYTES_LEN: usize = HDR_DEST_TAG_BYTES_LEN + 2 * XOR_NAME_LEN;
 const HDR_DEST_BYTES_END: usize = HDR_DEST_BYTES_START + HDR_DEST_BYTES_LEN;
 
 // Bytes index in the header for the 'dest_section_pk' field
@@ -67,10 +101,23 @@ const HDR_SRC_PK_BYTES_START: usize = HDR_DEST_PK_BYTES_END;
 const HDR_SRC_PK_BYTES_LEN: usize = PK_SIZE;
 const HDR_SRC_PK_BYTES_END: usize = HDR_SRC_PK_BYTES_START + HDR_SRC_PK_BYTES_LEN;
 
+// The header of the previous version is its size, version, message id and kind, followed by
+// the destination name, the destination section public key and, for a NodeMsg, the source
+// section public key. It has no aggregation, causing messages, payload length nor checksum.
+const PREV_HDR_DEST_BYTES_START: usize = HDR_KIND_BYTES_START + HDR_KIND_BYTES_LEN;
+const PREV_HDR_DEST_BYTES_END: usize = PREV_HDR_DEST_BYTES_START + XOR_NAME_LEN;
+const PREV_HDR_DEST_PK_BYTES_END: usize = PREV_HDR_DEST_BYTES_END + PK_SIZE;
+const PREV_HDR_SRC_PK_BYTES_END: usize = PREV_HDR_DEST_PK_BYTES_END + PK_SIZE;
+const PREV_HEADER_MIN_SIZE: usize = PREV_HDR_DEST_PK_BYTES_END;
+
 const HEADER_MIN_SIZE: usize = HDR_SIZE_BYTES_LEN
     + HDR_VERSION_BYTES_LEN
     + HDR_MSG_ID_BYTES_LEN
     + HDR_KIND_BYTES_LEN
+    + HDR_AGGREGATION_BYTES_LEN
+    + HDR_CAUSED_BY_LEN_BYTES_LEN
+    + HDR_PAYLOAD_LEN_BYTES_LEN
+    + HDR_CHECKSUM_BYTES_LEN
     + HDR_DEST_BYTES_LEN
     + HDR_DEST_PK_BYTES_LEN;
 
@@ -79,18 +126,21 @@ impl WireMsgHeader {
     pub fn new(
         msg_id: MessageId,
         kind: MessageKind,
-        dest: XorName,
+        aggregation: Aggregation,
+        dest: DstLocation,
         dest_section_pk: PublicKey,
         src_section_pk: Option<PublicKey>,
     ) -> Self {
         Self {
             msg_id,
-            header_size: Self::bytes_size(src_section_pk.is_some()) as u16,
+            header_size: Self::bytes_size(src_section_pk.is_some(), 0) as u16,
             version: MESSAGING_PROTO_VERSION,
             kind,
+            aggregation,
             dest,
             dest_section_pk,
             src_section_pk,
+            caused_by: Vec::new(),
         }
     }
 
@@ -99,18 +149,30 @@ impl WireMsgHeader {
         self.msg_id
     }
 
+    // Override the message id, so samples of the messages which
+    // get a random one assigned can be made reproducible
+    #[cfg(feature = "testing")]
+    pub(crate) fn set_msg_id(&mut self, msg_id: MessageId) {
+        self.msg_id = msg_id;
+    }
+
     // Return the kind of this message
     pub fn kind(&self) -> MessageKind {
         self.kind
     }
 
+    // Return the aggregation scheme declared for this message
+    pub fn aggregation(&self) -> Aggregation {
+        self.aggregation
+    }
+
     // Return the destination section PublicKey for this message
     pub fn dest_section_pk(&self) -> PublicKey {
         self.dest_section_pk
     }
 
     // Return the destination for this message
-    pub fn dest(&self) -> XorName {
+    pub fn dest(&self) -> DstLocation {
         self.dest
     }
 
@@ -120,10 +182,101 @@ impl WireMsgHeader {
         self.src_section_pk
     }
 
+    // Return the ids of the messages which caused this one, oldest first
+    pub fn caused_by(&self) -> &[MessageId] {
+        &self.caused_by
+    }
+
+    // Set the ids of the messages which caused this one, oldest first,
+    // keeping only the most recent ones if the chain is too long
+    pub fn set_caused_by(&mut self, mut caused_by: Vec<MessageId>) {
+        if caused_by.len() > MAX_CAUSED_BY_LEN {
+            let _ = caused_by.drain(..caused_by.len() - MAX_CAUSED_BY_LEN);
+        }
+        self.header_size = Self::bytes_size(self.src_section_pk.is_some(), caused_by.len()) as u16;
+        self.caused_by = caused_by;
+    }
+
+    // Reads the protocol version of the message in the provided bytes.
+    pub fn version(bytes: &[u8]) -> Result<u16> {
+        if bytes.len() < HDR_VERSION_BYTES_END {
+            return Err(Error::FailedToParse(format!(
+                "not enough bytes received ({}) to read the protocol version",
+                bytes.len()
+            )));
+        }
+        let mut version_bytes = [0; HDR_VERSION_BYTES_LEN];
+        version_bytes[0..].copy_from_slice(&bytes[HDR_VERSION_BYTES_START..HDR_VERSION_BYTES_END]);
+        Ok(u16::from_be_bytes(version_bytes))
+    }
+
+    // Parses the provided bytes of a message of the previous protocol version, returning
+    // its header translated into the current version, as well as the payload bytes, which
+    // are left as they are. Its destination name is taken to be a section's, as the previous
+    // version didn't tell destinations apart, and it isn't aggregated.
+    pub fn from_previous(mut bytes: Bytes) -> Result<(Self, Bytes)> {
+        let version = Self::version(&bytes)?;
+        if version != PREVIOUS_MESSAGING_PROTO_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let length = bytes.len();
+        let mut header_size_bytes = [0; HDR_SIZE_BYTES_LEN];
+        header_size_bytes[0..].copy_from_slice(&bytes[0..HDR_SIZE_BYTES_LEN]);
+        let header_size = usize::from(u16::from_be_bytes(header_size_bytes));
+        if length < header_size || PREV_HEADER_MIN_SIZE > header_size {
+            return Err(Error::FailedToParse(format!(
+                "not enough bytes received ({}) to deserialize wire message header",
+                length
+            )));
+        }
+
+        let mut msg_id_bytes = [0; HDR_MSG_ID_BYTES_LEN];
+        msg_id_bytes[0..].copy_from_slice(&bytes[HDR_MSG_ID_BYTES_START..HDR_MSG_ID_BYTES_END]);
+        let msg_id = MessageId::with(msg_id_bytes);
+
+        let kind = MessageKind::try_from(bytes[HDR_KIND_BYTES_START])?;
+
+        let mut dest_bytes = [0; XOR_NAME_LEN];
+        dest_bytes[0..].copy_from_slice(&bytes[PREV_HDR_DEST_BYTES_START..PREV_HDR_DEST_BYTES_END]);
+        let dest = DstLocation::Section(XorName(dest_bytes));
+
+        let dest_section_pk = read_public_key(
+            &bytes[PREV_HDR_DEST_BYTES_END..PREV_HDR_DEST_PK_BYTES_END],
+            "destination",
+        )?;
+
+        // As in the current version, the source section public key is only read
+        // from a NodeMsg header sized to hold it.
+        let src_section_pk =
+            if kind == MessageKind::Node && header_size == PREV_HDR_SRC_PK_BYTES_END {
+                Some(read_public_key(
+                    &bytes[PREV_HDR_DEST_PK_BYTES_END..PREV_HDR_SRC_PK_BYTES_END],
+                    "source",
+                )?)
+            } else {
+                None
+            };
+
+        let header = Self::new(
+            msg_id,
+            kind,
+            Aggregation::None,
+            dest,
+            dest_section_pk,
+            src_section_pk,
+        );
+        let payload_bytes = bytes.split_off(header_size);
+
+        Ok((header, payload_bytes))
+    }
+
     // Parses the provided bytes to deserialize a WireMsgHeader,
     // returning the created WireMsgHeader, as well as the remaining bytes which
-    // correspond to the message payload. The caller shall then take care of
+    // correspond to the message payload, stripped of any padding. The caller shall then take care of
     // deserializing the payload using the information provided in the WireMsgHeader.
+    // The payload is checked against the checksum in the header, so that bytes corrupted
+    // in transit are reported with `Error::ChecksumMismatch`.
     pub fn from(mut bytes: Bytes) -> Result<(Self, Bytes)> {
         // Let's make sure there is a minimum number of bytes to parse the header size part.
         let length = bytes.len();
@@ -139,6 +292,15 @@ impl WireMsgHeader {
         header_size_bytes[0..].copy_from_slice(&bytes[0..HDR_SIZE_BYTES_LEN]);
         let header_size = u16::from_be_bytes(header_size_bytes);
 
+        // ...now let's read the serialization protocol version bytes, before checking the
+        // header size, as headers of other versions have different sizes
+        let version = Self::version(&bytes)?;
+        // Make sure we support this version. Messages of the previous version are only
+        // translated by `from_previous`.
+        if version != MESSAGING_PROTO_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
         // TODO: since the header is currently (and temporarily) of a
         // varian length, we check that at least we have the minimum number of bytes
         // for the header of any kind of message to be deserialised.
@@ -149,15 +311,6 @@ impl WireMsgHeader {
             )));
         }
 
-        // ...now let's read the serialization protocol version bytes
-        let mut version_bytes = [0; HDR_VERSION_BYTES_LEN];
-        version_bytes[0..].copy_from_slice(&bytes[HDR_VERSION_BYTES_START..HDR_VERSION_BYTES_END]);
-        let version = u16::from_be_bytes(version_bytes);
-        // Make sure we support this version
-        if version != MESSAGING_PROTO_VERSION {
-            return Err(Error::UnsupportedVersion(version));
-        }
-
         // ...read the message id bytes
         let mut msg_id_bytes = [0; HDR_MSG_ID_BYTES_LEN];
         msg_id_bytes[0..].copy_from_slice(&bytes[HDR_MSG_ID_BYTES_START..HDR_MSG_ID_BYTES_END]);
@@ -166,10 +319,45 @@ impl WireMsgHeader {
         // ...read the message kind value (only 1 byte)
         let kind = MessageKind::try_from(bytes[HDR_KIND_BYTES_START])?;
 
+        // ...read the aggregation scheme value (only 1 byte)
+        let aggregation = read_aggregation(bytes[HDR_AGGREGATION_BYTES_START])?;
+
+        // ...read the number of ids of causing messages (only 1 byte)
+        let caused_by_len = usize::from(bytes[HDR_CAUSED_BY_LEN_BYTES_START]);
+        if caused_by_len > MAX_CAUSED_BY_LEN {
+            return Err(Error::FailedToParse(format!(
+                "too many ids of causing messages in header: {}",
+                caused_by_len
+            )));
+        }
+        let caused_by_bytes_len = caused_by_len * MESSAGE_ID_LEN;
+        if HEADER_MIN_SIZE + caused_by_bytes_len > header_size.into() {
+            return Err(Error::FailedToParse(format!(
+                "header size ({}) too small to read {} ids of causing messages",
+                header_size, caused_by_len
+            )));
+        }
+
+        // ...read the length of the actual payload
+        let mut payload_len_bytes = [0; HDR_PAYLOAD_LEN_BYTES_LEN];
+        payload_len_bytes[0..]
+            .copy_from_slice(&bytes[HDR_PAYLOAD_LEN_BYTES_START..HDR_PAYLOAD_LEN_BYTES_END]);
+        let payload_len = u32::from_be_bytes(payload_len_bytes) as usize;
+        if length - usize::from(header_size) < payload_len {
+            return Err(Error::FailedToParse(format!(
+                "not enough bytes received ({}) to read the message payload of length {}",
+                length, payload_len
+            )));
+        }
+
+        // ...read the checksum of the payload
+        let mut checksum_bytes = [0; HDR_CHECKSUM_BYTES_LEN];
+        checksum_bytes[0..]
+            .copy_from_slice(&bytes[HDR_CHECKSUM_BYTES_START..HDR_CHECKSUM_BYTES_END]);
+        let checksum = u32::from_be_bytes(checksum_bytes);
+
         // ...now let's read the destination bytes
-        let mut dest_bytes = [0; HDR_DEST_BYTES_LEN];
-        dest_bytes[0..].copy_from_slice(&bytes[HDR_DEST_BYTES_START..HDR_DEST_BYTES_END]);
-        let dest = XorName(dest_bytes);
+        let dest = read_dst_location(&bytes[HDR_DEST_BYTES_START..HDR_DEST_BYTES_END])?;
 
         // ...read the destination section pubic key bytes
         let mut dest_pk_bytes = [0; HDR_DEST_PK_BYTES_LEN];
@@ -185,7 +373,8 @@ impl WireMsgHeader {
         // and if the header size has the exact number of bytes to read a PublicKey from.
         // Once we move back to fixed-length header we won't need this check.
         let src_section_pk = if kind == MessageKind::Node
-            && HEADER_MIN_SIZE + HDR_SRC_PK_BYTES_LEN == header_size.into()
+            && HEADER_MIN_SIZE + HDR_SRC_PK_BYTES_LEN + caused_by_bytes_len
+                == usize::from(header_size)
         {
             let mut src_pk_bytes = [0; HDR_SRC_PK_BYTES_LEN];
             src_pk_bytes[0..].copy_from_slice(&bytes[HDR_SRC_PK_BYTES_START..HDR_SRC_PK_BYTES_END]);
@@ -200,23 +389,55 @@ impl WireMsgHeader {
             None
         };
 
+        // ...and the ids of causing messages, which are at the end of the header
+        let caused_by_start = usize::from(header_size) - caused_by_bytes_len;
+        let caused_by = bytes[caused_by_start..usize::from(header_size)]
+            .chunks(MESSAGE_ID_LEN)
+            .map(|chunk| {
+                let mut id_bytes = [0; MESSAGE_ID_LEN];
+                id_bytes.copy_from_slice(chunk);
+                MessageId::with(id_bytes)
+            })
+            .collect();
+
         let header = Self {
             msg_id,
             header_size,
             version,
             kind,
+            aggregation,
             dest,
             dest_section_pk,
             src_section_pk,
+            caused_by,
         };
 
-        // Get a slice for the payload bytes, i.e. the bytes after the header bytes
-        let payload_bytes = bytes.split_off(header_size.into());
+        // Get a slice for the payload bytes, i.e. the bytes after the header bytes,
+        // dropping any padding which follows them
+        let mut payload_bytes = bytes.split_off(header_size.into());
+        payload_bytes.truncate(payload_len);
+
+        let actual = crc32(&payload_bytes);
+        if actual != checksum {
+            return Err(Error::ChecksumMismatch {
+                expected: checksum,
+                actual,
+            });
+        }
 
         Ok((header, payload_bytes))
     }
 
-    pub fn write<'a>(&self, buffer: &'a mut [u8]) -> Result<&'a mut [u8]> {
+    // Writes the header into the buffer, declaring the length and the checksum
+    // of the actual payload which is to follow it (excluding any padding).
+    pub fn write<'a>(&self, buffer: &'a mut [u8], payload: &[u8]) -> Result<&'a mut [u8]> {
+        let payload_len = u32::try_from(payload.len()).map_err(|_| {
+            Error::Serialisation(format!(
+                "payload length ({}) couldn't be serialized in header",
+                payload.len()
+            ))
+        })?;
+
         // Let's write the header size first
         let (buf_at_version, _) = gen(be_u16(self.header_size), buffer).map_err(|err| {
             Error::Serialisation(format!(
@@ -243,15 +464,54 @@ impl WireMsgHeader {
             })?;
 
         // ...now let's write the value signaling the message kind
-        let (buf_at_dest, _) = gen(be_u8(self.kind.into()), buf_at_msg_kind).map_err(|err| {
+        let (buf_at_aggregation, _) =
+            gen(be_u8(self.kind.into()), buf_at_msg_kind).map_err(|err| {
+                Error::Serialisation(format!(
+                    "message kind field couldn't be serialized in header: {}",
+                    err
+                ))
+            })?;
+
+        // ...write the value signaling the aggregation scheme
+        let (buf_at_caused_by_len, _) = gen(
+            be_u8(aggregation_byte(self.aggregation)),
+            buf_at_aggregation,
+        )
+        .map_err(|err| {
+            Error::Serialisation(format!(
+                "aggregation field couldn't be serialized in header: {}",
+                err
+            ))
+        })?;
+
+        // ...write the number of ids of causing messages
+        let (buf_at_payload_len, _) = gen(be_u8(self.caused_by.len() as u8), buf_at_caused_by_len)
+            .map_err(|err| {
+                Error::Serialisation(format!(
+                    "caused-by length field couldn't be serialized in header: {}",
+                    err
+                ))
+            })?;
+
+        // ...write the length of the actual payload
+        let (buf_at_checksum, _) = gen(be_u32(payload_len), buf_at_payload_len).map_err(|err| {
+            Error::Serialisation(format!(
+                "payload length field couldn't be serialized in header: {}",
+                err
+            ))
+        })?;
+
+        // ...write the checksum of the actual payload
+        let (buf_at_dest, _) = gen(be_u32(crc32(payload)), buf_at_checksum).map_err(|err| {
             Error::Serialisation(format!(
-                "message kind field couldn't be serialized in header: {}",
+                "checksum field couldn't be serialized in header: {}",
                 err
             ))
         })?;
 
         // ...write the destination bytes
-        let (buf_at_dest_pk, _) = gen(slice(&self.dest), buf_at_dest).map_err(|err| {
+        let dest_bytes = dst_location_bytes(&self.dest);
+        let (buf_at_dest_pk, _) = gen(slice(&dest_bytes), buf_at_dest).map_err(|err| {
             Error::Serialisation(format!(
                 "destination field couldn't be serialized in header: {}",
                 err
@@ -269,7 +529,7 @@ impl WireMsgHeader {
 
         // ...now write the source section public key if it's a NodeMsg
         // and a source section public key was provided
-        let buf_at_payload = if let Some(src_section_pk) = self.src_section_pk {
+        let buf_at_caused_by = if let Some(src_section_pk) = self.src_section_pk {
             if self.kind != MessageKind::Node {
                 return Err(Error::Serialisation(format!(
                     "source section public key field couldn't be serialized in header since it's not a NodeMsg but a {:?}",
@@ -289,6 +549,18 @@ impl WireMsgHeader {
             buf_at_src_pk
         };
 
+        // ...and finally the ids of causing messages
+        let mut buf_at_payload = buf_at_caused_by;
+        for id in &self.caused_by {
+            let (buf, _) = gen(slice(id.as_ref()), buf_at_payload).map_err(|err| {
+                Error::Serialisation(format!(
+                    "caused-by field couldn't be serialized in header: {}",
+                    err
+                ))
+            })?;
+            buf_at_payload = buf;
+        }
+
         Ok(buf_at_payload)
     }
 
@@ -297,20 +569,144 @@ impl WireMsgHeader {
         self.header_size
     }
 
-    // Size in bytes when serialized if a WireMsgHeader
-    // depending if a source section public key is included.
-    fn bytes_size(with_src_section_pk: bool) -> usize {
+    // Size in bytes when serialized if a WireMsgHeader depending if a source
+    // section public key is included, and on the number of ids of causing messages.
+    fn bytes_size(with_src_section_pk: bool, caused_by_len: usize) -> usize {
         // We don't use 'std::mem::size_of' since, for example, the
         // 'MessageKind' enum it reports 2 bytes mem size,
         // and we want to serialize that field using 1 byte only.
+        let size = HEADER_MIN_SIZE + caused_by_len * MESSAGE_ID_LEN;
         if with_src_section_pk {
-            HEADER_MIN_SIZE + HDR_SRC_PK_BYTES_LEN
+            size + HDR_SRC_PK_BYTES_LEN
         } else {
-            HEADER_MIN_SIZE
+            size
         }
     }
 }
 
+// Table of the CRC-32 (IEEE 802.3) remainders of each byte value.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut crc = byte as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[byte] = crc;
+        byte += 1;
+    }
+    table
+};
+
+// Computes the CRC-32 (IEEE 802.3) checksum of the given bytes, written to the header
+// to detect payloads corrupted in transit.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, byte| {
+        CRC32_TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+// Bytes values used for each of the aggregation schemes
+// when written to the message header
+const AGGREGATION_NONE: u8 = 0x00;
+const AGGREGATION_AT_SOURCE: u8 = 0x01;
+const AGGREGATION_AT_DESTINATION: u8 = 0x02;
+
+fn aggregation_byte(aggregation: Aggregation) -> u8 {
+    match aggregation {
+        Aggregation::None => AGGREGATION_NONE,
+        Aggregation::AtSource => AGGREGATION_AT_SOURCE,
+        Aggregation::AtDestination => AGGREGATION_AT_DESTINATION,
+    }
+}
+
+fn read_aggregation(byte: u8) -> Result<Aggregation> {
+    match byte {
+        AGGREGATION_NONE => Ok(Aggregation::None),
+        AGGREGATION_AT_SOURCE => Ok(Aggregation::AtSource),
+        AGGREGATION_AT_DESTINATION => Ok(Aggregation::AtDestination),
+        other => Err(Error::FailedToParse(format!(
+            "unknown aggregation scheme in header: {}",
+            other
+        ))),
+    }
+}
+
+// Bytes values used for each of the kind of destination locations
+// when written to the message header
+const DST_END_USER_TAG: u8 = 0x00;
+const DST_NODE_TAG: u8 = 0x01;
+const DST_SECTION_TAG: u8 = 0x02;
+const DST_DIRECT_AND_UNROUTED_TAG: u8 = 0x03;
+
+// Encodes the destination location into its fixed length header representation.
+fn dst_location_bytes(dest: &DstLocation) -> [u8; HDR_DEST_BYTES_LEN] {
+    let mut bytes = [0; HDR_DEST_BYTES_LEN];
+    let name_range = HDR_DEST_TAG_BYTES_LEN..HDR_DEST_TAG_BYTES_LEN + XOR_NAME_LEN;
+    match dest {
+        DstLocation::EndUser(user) => {
+            bytes[0] = DST_END_USER_TAG;
+            bytes[name_range.clone()].copy_from_slice(&user.xorname);
+            bytes[name_range.end..].copy_from_slice(&user.socket_id);
+        }
+        DstLocation::Node(name) => {
+            bytes[0] = DST_NODE_TAG;
+            bytes[name_range].copy_from_slice(name);
+        }
+        DstLocation::Section(name) => {
+            bytes[0] = DST_SECTION_TAG;
+            bytes[name_range].copy_from_slice(name);
+        }
+        DstLocation::DirectAndUnrouted => bytes[0] = DST_DIRECT_AND_UNROUTED_TAG,
+    }
+
+    bytes
+}
+
+// Decodes a destination location from its fixed length header representation.
+fn read_dst_location(bytes: &[u8]) -> Result<DstLocation> {
+    let read_name = |start: usize| {
+        let mut name = [0; XOR_NAME_LEN];
+        name.copy_from_slice(&bytes[start..start + XOR_NAME_LEN]);
+        XorName(name)
+    };
+    let name_start = HDR_DEST_TAG_BYTES_LEN;
+    let socket_id_start = name_start + XOR_NAME_LEN;
+
+    match bytes[0] {
+        DST_END_USER_TAG => Ok(DstLocation::EndUser(EndUser {
+            xorname: read_name(name_start),
+            socket_id: read_name(socket_id_start),
+        })),
+        DST_NODE_TAG => Ok(DstLocation::Node(read_name(name_start))),
+        DST_SECTION_TAG => Ok(DstLocation::Section(read_name(name_start))),
+        DST_DIRECT_AND_UNROUTED_TAG => Ok(DstLocation::DirectAndUnrouted),
+        other => Err(Error::FailedToParse(format!(
+            "unknown destination location kind in header: {}",
+            other
+        ))),
+    }
+}
+
+// Reads the section public key of the given role from its bytes in a header.
+fn read_public_key(bytes: &[u8], role: &str) -> Result<PublicKey> {
+    let mut pk_bytes = [0; PK_SIZE];
+    pk_bytes[0..].copy_from_slice(bytes);
+    PublicKey::from_bytes(pk_bytes).map_err(|err| {
+        Error::FailedToParse(format!(
+            "{} section PublicKey couldn't be deserialized from header: {}",
+            role, err
+        ))
+    })
+}
+
 // Type of message being sent over the wire, this type
 // is part of the WireMsgHeader.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -342,6 +738,18 @@ impl TryFrom<u8> for MessageKind {
     }
 }
 
+impl MessageKind {
+    /// Name of the kind, e.g. for labelling metrics.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::SectionInfo => "SectionInfo",
+            Self::Client => "Client",
+            Self::Routing => "Routing",
+            Self::Node => "Node",
+        }
+    }
+}
+
 impl From<MessageKind> for u8 {
     fn from(kind: MessageKind) -> u8 {
         match kind {
@@ -376,4 +784,46 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn aggregation_from_u8() -> Result<()> {
+        for aggregation in &[
+            Aggregation::None,
+            Aggregation::AtSource,
+            Aggregation::AtDestination,
+        ] {
+            assert_eq!(
+                read_aggregation(aggregation_byte(*aggregation))?,
+                *aggregation
+            );
+        }
+
+        for byte in 3..=u8::MAX {
+            assert!(read_aggregation(byte).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn dst_location_header_roundtrip() -> Result<()> {
+        let user = EndUser {
+            xorname: XorName::random(),
+            socket_id: XorName::random(),
+        };
+        for dst in &[
+            DstLocation::EndUser(user),
+            DstLocation::Node(XorName::random()),
+            DstLocation::Section(XorName::random()),
+            DstLocation::DirectAndUnrouted,
+        ] {
+            assert_eq!(read_dst_location(&dst_location_bytes(dst))?, *dst);
+        }
+
+        let mut bytes = dst_location_bytes(&DstLocation::DirectAndUnrouted);
+        bytes[0] = 0xff;
+        assert!(read_dst_location(&bytes).is_err());
+
+        Ok(())
+    }
 }
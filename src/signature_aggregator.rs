@@ -7,7 +7,7 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::signed::{Signed, SignedShare};
+use crate::signed::{Signed, SignedShare};
 use std::{
     collections::HashMap,
     fmt::Debug,
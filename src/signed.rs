@@ -7,10 +7,46 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
+use crate::canonical::{signing_bytes, CanonicalBytes};
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Debug, Formatter};
 use threshold_crypto as bls;
 
+/// Version of the layout of the payloads signed by this crate: the canonical bytes of the
+/// version and the payload, see [`crate::canonical`]. Payloads signed with the bincode
+/// layouts of earlier versions are no longer supported.
+pub const SIGNABLE_VERSION: u16 = 2;
+
+/// Envelope of a payload to sign, carrying the version of its layout, so a signature made
+/// over an older layout is reported as such instead of merely failing to verify.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Signable<T> {
+    /// Version of the layout of the payload.
+    pub version: u16,
+    /// The payload to sign.
+    pub payload: T,
+}
+
+impl<T: CanonicalBytes> Signable<T> {
+    /// Wraps the payload with the current layout version.
+    pub fn new(payload: T) -> Self {
+        Self::with_version(SIGNABLE_VERSION, payload)
+    }
+
+    /// Wraps the payload with the given layout version, e.g. the one it was signed with.
+    pub fn with_version(version: u16, payload: T) -> Self {
+        Self { version, payload }
+    }
+
+    /// Returns the bytes to sign, or to verify a signature against.
+    pub fn bytes(&self) -> crate::Result<Vec<u8>> {
+        if self.version != SIGNABLE_VERSION {
+            return Err(crate::Error::UnsupportedSignableVersion(self.version));
+        }
+        signing_bytes("signable", &(self.version, &self.payload))
+    }
+}
+
 /// Signed that a quorum of the section elders has agreed on something.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub struct Signed {
@@ -89,4 +125,34 @@ mod tests {
         };
         assert!(signed.verify(&data.as_bytes()));
     }
+
+    #[test]
+    fn signable_version() -> anyhow::Result<()> {
+        let sk = SecretKey::random();
+        let bytes = Signable::new("hello").bytes()?;
+        let signed = Signed {
+            public_key: sk.public_key(),
+            signature: sk.sign(&bytes),
+        };
+        assert!(signed.verify(&bytes));
+        assert_eq!(
+            bytes,
+            signing_bytes("signable", &(SIGNABLE_VERSION, "hello"))?
+        );
+
+        // Neither the bincode layouts of earlier versions nor future ones are supported.
+        for version in &[0, 1] {
+            assert!(matches!(
+                Signable::with_version(*version, "hello").bytes(),
+                Err(crate::Error::UnsupportedSignableVersion(unsupported)) if unsupported == *version
+            ));
+        }
+        let future = Signable::with_version(SIGNABLE_VERSION + 1, "hello");
+        assert!(matches!(
+            future.bytes(),
+            Err(crate::Error::UnsupportedSignableVersion(version)) if version == SIGNABLE_VERSION + 1
+        ));
+
+        Ok(())
+    }
 }
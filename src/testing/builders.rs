@@ -0,0 +1,431 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Builders of messages with sensible defaults (random ids, throwaway keys),
+//! to construct messages in tests without spelling them out in full.
+
+#[cfg(not(feature = "client-only"))]
+use crate::{
+    client::Error,
+    node::{NodeCmd, NodeMsg, NodeQuery, RoutingMsg, SignedShare, SrcAuthority, Variant},
+    Aggregation,
+};
+use crate::{
+    client::{
+        AntiSpam, ClientMsg, ClientSigned, Cmd, ProcessMsg, Query, QueryResponse, ResponseOrigin,
+        SessionId,
+    },
+    section_info::SectionInfoMsg,
+    DstLocation, MessageId, Result, WireMsg,
+};
+#[cfg(not(feature = "client-only"))]
+use ed25519_dalek::Signer;
+use serde::Serialize;
+use sn_data_types::{Keypair, PublicKey};
+use threshold_crypto::{PublicKey as BlsPublicKey, SecretKey as BlsSecretKey};
+use xor_name::XorName;
+
+fn serialise<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    bincode::serialize(value).map_err(|err| crate::Error::Serialisation(err.to_string()))
+}
+
+/// Builder of client messages, signed with the client keypair.
+/// Defaults to a random message id and a random Ed25519 keypair.
+pub struct MessageBuilder {
+    id: MessageId,
+    keypair: Keypair,
+    nonce: u64,
+    deadline: Option<u64>,
+    anti_spam: Option<AntiSpam>,
+    session: Option<SessionId>,
+    origin: Option<ResponseOrigin>,
+}
+
+impl Default for MessageBuilder {
+    fn default() -> Self {
+        Self {
+            id: MessageId::new(),
+            keypair: Keypair::new_ed25519(&mut rand::thread_rng()),
+            nonce: 1,
+            deadline: None,
+            anti_spam: None,
+            session: None,
+            origin: None,
+        }
+    }
+}
+
+impl MessageBuilder {
+    /// Create a builder with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the message id.
+    pub fn id(mut self, id: MessageId) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Sets the keypair of the client signing the message.
+    pub fn keypair(mut self, keypair: Keypair) -> Self {
+        self.keypair = keypair;
+        self
+    }
+
+    /// Sets the nonce of a command.
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Sets the deadline of a query, in milliseconds since the Unix epoch.
+    pub fn deadline(mut self, deadline: u64) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the anti-spam proof of a query.
+    pub fn anti_spam(mut self, anti_spam: AntiSpam) -> Self {
+        self.anti_spam = Some(anti_spam);
+        self
+    }
+
+    /// Sets the session a query or command is sent within.
+    pub fn session(mut self, session: SessionId) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Sets the section which handled the request a response responds to.
+    pub fn origin(mut self, origin: ResponseOrigin) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// Public key of the client signing the message.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from(&self.keypair)
+    }
+
+    fn client_signed(&self, payload: &[u8]) -> ClientSigned {
+        ClientSigned {
+            public_key: self.public_key(),
+            signature: self.keypair.sign(payload),
+        }
+    }
+
+    /// Builds a query message, signed over the serialized query.
+    pub fn query(self, query: Query) -> Result<ClientMsg> {
+        let client_signed = self.client_signed(&serialise(&query)?);
+        Ok(ClientMsg::Process(ProcessMsg::Query {
+            id: self.id,
+            query,
+            client_signed,
+            deadline: self.deadline,
+            anti_spam: self.anti_spam,
+            session: self.session,
+        }))
+    }
+
+    /// Builds a command message, signed over the command and nonce.
+    pub fn cmd(self, cmd: Cmd) -> Result<ClientMsg> {
+        let client_signed = self.client_signed(&ProcessMsg::cmd_bytes(&cmd, self.nonce)?);
+        Ok(ClientMsg::Process(ProcessMsg::Cmd {
+            id: self.id,
+            cmd,
+            nonce: self.nonce,
+            client_signed,
+            session: self.session,
+        }))
+    }
+
+    /// Builds the cancellation of the query with the given message id,
+    /// signed over the query id.
+    pub fn cancel_query(self, correlation_id: MessageId) -> Result<ClientMsg> {
+        let client_signed = self.client_signed(&ProcessMsg::cancel_query_bytes(&correlation_id)?);
+        Ok(ClientMsg::Process(ProcessMsg::CancelQuery {
+            id: self.id,
+            correlation_id,
+            client_signed,
+        }))
+    }
+
+    /// Builds the response to the query with the given message id.
+    pub fn query_response(self, response: QueryResponse, correlation_id: MessageId) -> ClientMsg {
+        ClientMsg::Process(ProcessMsg::QueryResponse {
+            id: self.id,
+            response,
+            correlation_id,
+            origin: self.origin,
+        })
+    }
+}
+
+/// Builder of wire messages.
+/// Defaults to a random section destination, a random destination section key,
+/// no source section key and no aggregation.
+pub struct WireMsgBuilder {
+    dest: DstLocation,
+    dest_section_pk: BlsPublicKey,
+    src_section_pk: Option<BlsPublicKey>,
+    #[cfg(not(feature = "client-only"))]
+    aggregation: Aggregation,
+    #[cfg(not(feature = "client-only"))]
+    signed_share: Option<SignedShare>,
+}
+
+impl Default for WireMsgBuilder {
+    fn default() -> Self {
+        Self {
+            dest: DstLocation::Section(XorName::random()),
+            dest_section_pk: BlsSecretKey::random().public_key(),
+            src_section_pk: None,
+            #[cfg(not(feature = "client-only"))]
+            aggregation: Aggregation::None,
+            #[cfg(not(feature = "client-only"))]
+            signed_share: None,
+        }
+    }
+}
+
+impl WireMsgBuilder {
+    /// Create a builder with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the destination.
+    pub fn dest(mut self, dest: DstLocation) -> Self {
+        self.dest = dest;
+        self
+    }
+
+    /// Sets the destination section key.
+    pub fn dest_section_pk(mut self, dest_section_pk: BlsPublicKey) -> Self {
+        self.dest_section_pk = dest_section_pk;
+        self
+    }
+
+    /// Sets the source section key, only included in the header of node messages.
+    pub fn src_section_pk(mut self, src_section_pk: BlsPublicKey) -> Self {
+        self.src_section_pk = Some(src_section_pk);
+        self
+    }
+
+    /// Sets a node message to be aggregated at destination, with the sender's signature share.
+    #[cfg(not(feature = "client-only"))]
+    pub fn aggregate_at_dst(mut self, signed_share: SignedShare) -> Self {
+        self.aggregation = Aggregation::AtDestination;
+        self.signed_share = Some(signed_share);
+        self
+    }
+
+    /// Builds the wire message of a section info message.
+    pub fn section_info_msg(self, msg: &SectionInfoMsg) -> Result<WireMsg> {
+        WireMsg::new_section_info_msg(msg, self.dest, self.dest_section_pk)
+    }
+
+    /// Builds the wire message of a client message.
+    pub fn client_msg(self, msg: &ClientMsg) -> Result<WireMsg> {
+        WireMsg::new_client_msg(msg, self.dest, self.dest_section_pk)
+    }
+
+    /// Builds the wire message of a routing message.
+    /// The aggregation scheme is the one of the routing message.
+    #[cfg(not(feature = "client-only"))]
+    pub fn routing_msg(self, msg: &RoutingMsg) -> Result<WireMsg> {
+        WireMsg::new_routing_msg(msg, self.dest, self.dest_section_pk)
+    }
+
+    /// Builds the wire message of a node message.
+    #[cfg(not(feature = "client-only"))]
+    pub fn node_msg(self, msg: &NodeMsg) -> Result<WireMsg> {
+        WireMsg::new_aggregated_node_msg(
+            msg,
+            self.dest,
+            self.dest_section_pk,
+            self.src_section_pk,
+            self.aggregation,
+            self.signed_share,
+        )
+    }
+}
+
+/// Builder of node messages.
+/// Defaults to a random message id.
+#[cfg(not(feature = "client-only"))]
+#[derive(Default)]
+pub struct NodeMsgBuilder {
+    id: MessageId,
+}
+
+#[cfg(not(feature = "client-only"))]
+impl NodeMsgBuilder {
+    /// Create a builder with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the message id.
+    pub fn id(mut self, id: MessageId) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Builds a node command message.
+    pub fn cmd(self, cmd: NodeCmd) -> NodeMsg {
+        NodeMsg::NodeCmd { cmd, id: self.id }
+    }
+
+    /// Builds a node query message.
+    pub fn query(self, query: NodeQuery) -> NodeMsg {
+        NodeMsg::NodeQuery { query, id: self.id }
+    }
+
+    /// Builds the error returned when handling the message with the given message id.
+    pub fn error(self, error: Error, correlation_id: MessageId) -> NodeMsg {
+        NodeMsg::NodeMsgError {
+            error,
+            id: self.id,
+            correlation_id,
+        }
+    }
+}
+
+/// Builder of routing messages from a single node.
+/// Defaults to a random message id, a random node keypair, a random destination node,
+/// a random section key and no aggregation.
+#[cfg(not(feature = "client-only"))]
+pub struct RoutingMsgBuilder {
+    id: MessageId,
+    keypair: ed25519_dalek::Keypair,
+    dst: DstLocation,
+    aggregation: Aggregation,
+    section_pk: BlsPublicKey,
+}
+
+#[cfg(not(feature = "client-only"))]
+impl Default for RoutingMsgBuilder {
+    fn default() -> Self {
+        Self {
+            id: MessageId::new(),
+            keypair: ed25519_dalek::Keypair::generate(&mut rand::thread_rng()),
+            dst: DstLocation::Node(XorName::random()),
+            aggregation: Aggregation::None,
+            section_pk: BlsSecretKey::random().public_key(),
+        }
+    }
+}
+
+#[cfg(not(feature = "client-only"))]
+impl RoutingMsgBuilder {
+    /// Create a builder with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the message id.
+    pub fn id(mut self, id: MessageId) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Sets the keypair of the node sending the message.
+    pub fn keypair(mut self, keypair: ed25519_dalek::Keypair) -> Self {
+        self.keypair = keypair;
+        self
+    }
+
+    /// Sets the destination.
+    pub fn dst(mut self, dst: DstLocation) -> Self {
+        self.dst = dst;
+        self
+    }
+
+    /// Sets the aggregation scheme.
+    pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Sets the section key of the sender.
+    pub fn section_pk(mut self, section_pk: BlsPublicKey) -> Self {
+        self.section_pk = section_pk;
+        self
+    }
+
+    /// Builds the message with the given variant, signed by the node
+    /// over the serialized destination and variant.
+    pub fn build(self, variant: Variant) -> Result<RoutingMsg> {
+        let signature = self.keypair.sign(&serialise(&(&self.dst, &variant))?);
+        Ok(RoutingMsg {
+            id: self.id,
+            src: SrcAuthority::Node {
+                public_key: self.keypair.public,
+                signature,
+            },
+            dst: self.dst,
+            aggregation: self.aggregation,
+            variant,
+            section_pk: self.section_pk,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::{BlobRead, DataQuery},
+        MessageType,
+    };
+    use anyhow::Result;
+    use sn_data_types::BlobAddress;
+
+    #[test]
+    fn client_msg_from_builders() -> Result<()> {
+        let query = Query::Data(DataQuery::Blob(BlobRead::Get(BlobAddress::Public(
+            XorName::random(),
+        ))));
+        let builder = MessageBuilder::new();
+        let public_key = builder.public_key();
+        let msg = builder.query(query.clone())?;
+
+        if let ClientMsg::Process(ProcessMsg::Query { client_signed, .. }) = &msg {
+            assert_eq!(client_signed.public_key, public_key);
+            assert!(public_key
+                .verify(&client_signed.signature, &serialise(&query)?)
+                .is_ok());
+        } else {
+            panic!("unexpected message: {:?}", msg);
+        }
+
+        let wire_msg = WireMsgBuilder::new().client_msg(&msg)?;
+        match wire_msg.to_message()? {
+            MessageType::Client { msg: parsed, .. } => assert_eq!(parsed, msg),
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "client-only"))]
+    #[test]
+    fn routing_msg_from_builder() -> Result<()> {
+        let dst = DstLocation::Section(XorName::random());
+        let variant = Variant::UserMessage(b"hello".to_vec());
+        let msg = RoutingMsgBuilder::new().dst(dst).build(variant.clone())?;
+
+        assert!(msg.src.verify(&serialise(&(&dst, &variant))?));
+        assert_eq!(msg.dst, dst);
+
+        Ok(())
+    }
+}
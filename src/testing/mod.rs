@@ -0,0 +1,613 @@
+// Copyright 2021 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Proptest strategies for the messages of this crate, available with the `testing` feature.
+//!
+//! Transfer agreements are signed in-process by replicas whose key set is derived from a
+//! generated seed, so the generated proofs verify. Variants carrying section signatures
+//! over section state, e.g. resumed sessions or section splits, are not generated.
+
+mod builders;
+
+pub use self::builders::{MessageBuilder, WireMsgBuilder};
+#[cfg(not(feature = "client-only"))]
+pub use self::builders::{NodeMsgBuilder, RoutingMsgBuilder};
+#[cfg(not(feature = "client-only"))]
+use crate::node::{Forwarded, NodeCmd, NodeMsg, NodeQuery, NodeSystemCmd};
+use crate::{
+    client::{
+        AntiSpam, BlobRead, BlobWrite, Capabilities, ClientMsg, ClientSigned, Cmd, DataCmd,
+        DataCmdKind, DataError, DataQuery, Error, MapRead, ProcessMsg, ProcessingError, Query,
+        QueryResponse, QuoteId, RequiredPermission, ResponseOrigin, SessionId, TransferCmd,
+        TransferProof, TransferQuery,
+    },
+    Aggregation, DstLocation, EndUser, MessageId, Signed, SrcLocation, MESSAGE_ID_LEN,
+};
+use crdts::Dot;
+use ed25519_dalek::{Keypair, PublicKey as Ed25519PublicKey, SecretKey, Signer};
+use proptest::{collection, option, prelude::*};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+use sn_data_types::{
+    Blob, BlobAddress, Credit, DataAddress, Debit, MapAddress, PrivateBlob, PublicBlob, PublicKey,
+    Signature, SignedCredit, SignedDebit, SignedTransfer, Token, TransferAgreementProof,
+};
+use std::net::SocketAddr;
+use threshold_crypto::{
+    IntoFr, PublicKey as BlsPublicKey, SecretKey as BlsSecretKey, SecretKeySet,
+    Signature as BlsSignature,
+};
+use xor_name::{XorName, XOR_NAME_LEN};
+
+macro_rules! arbitrary {
+    ($type:ty, $strategy:expr) => {
+        impl Arbitrary for $type {
+            type Parameters = ();
+            type Strategy = BoxedStrategy<Self>;
+
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                $strategy.boxed()
+            }
+        }
+    };
+}
+
+/// Strategy generating random names.
+pub fn xor_name() -> impl Strategy<Value = XorName> {
+    any::<[u8; XOR_NAME_LEN]>().prop_map(XorName)
+}
+
+/// Strategy generating Ed25519 keypairs.
+pub fn keypair() -> impl Strategy<Value = Keypair> {
+    any::<[u8; 32]>().prop_map(|seed| {
+        // Any 32 bytes make a valid Ed25519 secret key.
+        let secret = SecretKey::from_bytes(&seed).expect("invalid secret key length");
+        let public = Ed25519PublicKey::from(&secret);
+        Keypair { secret, public }
+    })
+}
+
+/// Strategy generating public keys.
+pub fn public_key() -> impl Strategy<Value = PublicKey> {
+    keypair().prop_map(|keypair| PublicKey::from(keypair.public))
+}
+
+/// Strategy generating client signatures over random bytes.
+pub fn client_signed() -> impl Strategy<Value = ClientSigned> {
+    (keypair(), collection::vec(any::<u8>(), 0..64)).prop_map(|(keypair, bytes)| ClientSigned {
+        public_key: PublicKey::from(keypair.public),
+        signature: Signature::Ed25519(keypair.sign(&bytes)),
+    })
+}
+
+/// Strategy generating BLS public keys.
+pub fn bls_public_key() -> impl Strategy<Value = BlsPublicKey> {
+    any::<u64>().prop_map(|seed| BlsSecretKey::from_mut(&mut seed.into_fr()).public_key())
+}
+
+/// Strategy generating transfers signed by their sender.
+pub fn signed_transfer() -> impl Strategy<Value = SignedTransfer> {
+    (keypair(), any::<(u64, u64)>(), public_key(), "[a-z ]{0,16}").prop_map(
+        |(sender, (counter, amount), recipient, msg)| {
+            let sender = sn_data_types::Keypair::from(sender.secret);
+            let debit = Debit {
+                id: Dot::new(sender.public_key(), counter),
+                amount: Token::from_nano(amount),
+            };
+            let credit = Credit {
+                id: debit.credit_id().expect("failed to derive credit id"),
+                amount: debit.amount,
+                recipient,
+                msg,
+            };
+            SignedTransfer {
+                debit: SignedDebit {
+                    actor_signature: sender.sign(&serialize(&debit)),
+                    debit,
+                },
+                credit: SignedCredit {
+                    actor_signature: sender.sign(&serialize(&credit)),
+                    credit,
+                },
+            }
+        },
+    )
+}
+
+/// Strategy generating transfer agreements, signed by replicas of a key set derived
+/// from a generated seed.
+pub fn transfer_agreement_proof() -> impl Strategy<Value = TransferAgreementProof> {
+    (signed_transfer(), any::<u64>()).prop_map(|(transfer, seed)| {
+        let replicas = SecretKeySet::random(1, &mut StdRng::seed_from_u64(seed));
+        let debit_sig = replicas_sign(&replicas, &transfer.debit);
+        let credit_sig = replicas_sign(&replicas, &transfer.credit);
+        TransferAgreementProof {
+            signed_debit: transfer.debit,
+            signed_credit: transfer.credit,
+            debit_sig: Signature::Bls(debit_sig),
+            credit_sig: Signature::Bls(credit_sig),
+            debiting_replicas_keys: replicas.public_keys(),
+        }
+    })
+}
+
+// Actors and replicas sign the bincode serialization of debits and credits.
+fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
+    bincode::serialize(value).expect("failed to serialize transfer")
+}
+
+fn replicas_sign<T: Serialize>(replicas: &SecretKeySet, value: &T) -> BlsSignature {
+    let bytes = serialize(value);
+    let shares: Vec<_> = (0..=replicas.threshold())
+        .map(|index| (index, replicas.secret_key_share(index).sign(&bytes)))
+        .collect();
+    replicas
+        .public_keys()
+        .combine_signatures(shares.iter().map(|(index, share)| (*index, share)))
+        .expect("failed to combine signature shares")
+}
+
+fn transfer_proof() -> impl Strategy<Value = TransferProof> {
+    (transfer_agreement_proof(), any::<u64>()).prop_map(|(proof, seed)| {
+        let section_key = BlsSecretKey::from_mut(&mut seed.into_fr());
+        TransferProof {
+            registered: Signed {
+                public_key: section_key.public_key(),
+                signature: section_key.sign(
+                    TransferProof::bytes_to_sign(&proof).expect("failed to lay out the proof"),
+                ),
+            },
+            proof,
+        }
+    })
+}
+
+fn blob() -> impl Strategy<Value = Blob> {
+    prop_oneof![
+        collection::vec(any::<u8>(), 0..64).prop_map(|value| Blob::Public(PublicBlob::new(value))),
+        (collection::vec(any::<u8>(), 0..64), public_key())
+            .prop_map(|(value, owner)| Blob::Private(PrivateBlob::new(value, owner))),
+    ]
+}
+
+fn data_cmd() -> impl Strategy<Value = DataCmd> {
+    prop_oneof![
+        blob().prop_map(|blob| DataCmd::Blob(BlobWrite::New(blob))),
+        xor_name()
+            .prop_map(|name| DataCmd::Blob(BlobWrite::DeletePrivate(BlobAddress::Private(name)))),
+    ]
+}
+
+fn response_origin() -> impl Strategy<Value = ResponseOrigin> {
+    (bls_public_key(), any::<MessageId>(), any::<u64>()).prop_map(
+        |(section_key, handler_msg_id, epoch)| ResponseOrigin {
+            section_key,
+            handler_msg_id,
+            epoch,
+        },
+    )
+}
+
+fn blob_address() -> impl Strategy<Value = BlobAddress> {
+    prop_oneof![
+        xor_name().prop_map(BlobAddress::Private),
+        xor_name().prop_map(BlobAddress::Public),
+    ]
+}
+
+fn map_address() -> impl Strategy<Value = MapAddress> {
+    prop_oneof![
+        (xor_name(), any::<u64>()).prop_map(|(name, tag)| MapAddress::Unseq { name, tag }),
+        (xor_name(), any::<u64>()).prop_map(|(name, tag)| MapAddress::Seq { name, tag }),
+    ]
+}
+
+fn socket_addr() -> impl Strategy<Value = SocketAddr> {
+    prop_oneof![
+        (any::<[u8; 4]>(), any::<u16>()).prop_map(|(ip, port)| SocketAddr::from((ip, port))),
+        (any::<[u16; 8]>(), any::<u16>()).prop_map(|(ip, port)| SocketAddr::from((ip, port))),
+    ]
+}
+
+arbitrary!(
+    MessageId,
+    any::<[u8; MESSAGE_ID_LEN]>().prop_map(MessageId::with)
+);
+
+arbitrary!(
+    EndUser,
+    (xor_name(), xor_name()).prop_map(|(xorname, socket_id)| EndUser { xorname, socket_id })
+);
+
+arbitrary!(
+    SrcLocation,
+    prop_oneof![
+        any::<EndUser>().prop_map(SrcLocation::EndUser),
+        xor_name().prop_map(SrcLocation::Node),
+        xor_name().prop_map(SrcLocation::Section),
+    ]
+);
+
+arbitrary!(
+    DstLocation,
+    prop_oneof![
+        any::<EndUser>().prop_map(DstLocation::EndUser),
+        xor_name().prop_map(DstLocation::Node),
+        xor_name().prop_map(DstLocation::Section),
+        Just(DstLocation::DirectAndUnrouted),
+    ]
+);
+
+arbitrary!(
+    Aggregation,
+    prop_oneof![
+        Just(Aggregation::None),
+        Just(Aggregation::AtSource),
+        Just(Aggregation::AtDestination),
+    ]
+);
+
+arbitrary!(
+    Error,
+    prop_oneof![
+        public_key().prop_map(|key| Error::Data(DataError::AccessDenied(key))),
+        public_key().prop_map(|key| Error::Data(DataError::AccessDeniedDetailed {
+            key,
+            op: Some(DataCmdKind::Delete),
+            required: Some(RequiredPermission::Delete),
+        })),
+        blob_address()
+            .prop_map(|address| Error::Data(DataError::DataNotFound(DataAddress::Blob(address)))),
+        any::<u64>().prop_map(|version| Error::Data(DataError::InvalidSuccessor(version))),
+        any::<u64>().prop_map(Error::ReplayDetected),
+        Just(Error::Data(DataError::DataExists)),
+        Just(Error::Data(DataError::NoSuchEntry)),
+        Just(Error::InvalidSignature),
+    ]
+);
+
+arbitrary!(
+    Query,
+    prop_oneof![
+        blob_address().prop_map(|address| Query::Data(DataQuery::Blob(BlobRead::Get(address)))),
+        blob_address().prop_map(|address| Query::Data(DataQuery::Blob(BlobRead::Exists(address)))),
+        map_address().prop_map(|address| Query::Data(DataQuery::Map(MapRead::Get(address)))),
+        (map_address(), collection::vec(any::<u8>(), 0..32)).prop_map(|(address, key)| {
+            Query::Data(DataQuery::Map(MapRead::GetValue { address, key }))
+        }),
+        (map_address(), public_key()).prop_map(|(address, user)| {
+            Query::Data(DataQuery::Map(MapRead::ListUserPermissions {
+                address,
+                user,
+            }))
+        }),
+        (public_key(), any::<Option<usize>>()).prop_map(|(at, history_index)| {
+            Query::Transfer(TransferQuery::GetBalance { at, history_index })
+        }),
+        (public_key(), any::<usize>(), any::<Option<usize>>()).prop_map(
+            |(at, since_index, max_items)| Query::Transfer(TransferQuery::GetHistory {
+                at,
+                since_index,
+                max_items,
+            })
+        ),
+        (public_key(), any::<u64>()).prop_map(|(requester, bytes)| {
+            Query::Transfer(TransferQuery::GetStoreCost { requester, bytes })
+        }),
+        xor_name().prop_map(|name| Query::Transfer(TransferQuery::GetSectionWallet(name))),
+        (public_key(), any::<u64>()).prop_map(|(requester, bytes)| {
+            Query::Transfer(TransferQuery::GetStoreCostQuote { requester, bytes })
+        }),
+        (public_key(), any::<u64>()).prop_map(|(actor, counter)| {
+            Query::Transfer(TransferQuery::GetTransferProof(Dot::new(actor, counter)))
+        }),
+    ]
+);
+
+// Resuming sessions carry a section signature over the session, so they aren't generated.
+arbitrary!(
+    Cmd,
+    prop_oneof![
+        (keypair(), socket_addr()).prop_map(|(keypair, socket_addr)| Cmd::RegisterEndUser {
+            socket_addr,
+            socketaddr_sig: Signature::Ed25519(
+                keypair.sign(
+                    &Cmd::register_end_user_bytes(&socket_addr)
+                        .expect("failed to lay out the socket address")
+                )
+            ),
+        }),
+        (
+            public_key(),
+            any::<(u16, bool)>(),
+            option::of(bls_public_key())
+        )
+            .prop_map(|(client, (protocol_version, events), last_seen_key)| {
+                Cmd::CreateSession {
+                    client,
+                    capabilities: Capabilities {
+                        protocol_version,
+                        events,
+                    },
+                    last_seen_key,
+                }
+            }),
+        xor_name().prop_map(|socket_id| Cmd::KeepAlive { socket_id }),
+        (data_cmd(), transfer_agreement_proof())
+            .prop_map(|(cmd, payment)| Cmd::Data { cmd, payment }),
+        signed_transfer()
+            .prop_map(|transfer| Cmd::Transfer(TransferCmd::ValidateTransfer(transfer))),
+        transfer_agreement_proof()
+            .prop_map(|proof| Cmd::Transfer(TransferCmd::RegisterTransfer(proof))),
+        (data_cmd(), any::<[u8; 32]>(), transfer_agreement_proof()).prop_map(
+            |(cmd, quote, payment)| Cmd::QuotedData {
+                cmd,
+                quote: QuoteId(quote),
+                payment,
+            }
+        ),
+    ]
+);
+
+arbitrary!(
+    QueryResponse,
+    prop_oneof![
+        any::<Error>().prop_map(|error| QueryResponse::GetBlob(Err(error))),
+        any::<Error>().prop_map(|error| QueryResponse::GetRegisterEntriesSince(Err(error))),
+        any::<Error>().prop_map(|error| QueryResponse::GetTransferProof(Err(error))),
+        transfer_proof().prop_map(|proof| QueryResponse::GetTransferProof(Ok(proof))),
+        any::<Error>().prop_map(|error| QueryResponse::GetStoreCostQuote(Err(error))),
+        (
+            any::<Result<Vec<u8>, Error>>(),
+            any::<(u32, u32)>(),
+            any::<MessageId>()
+        )
+            .prop_map(|(part, (index, total), correlation)| {
+                QueryResponse::GetChunkStream {
+                    part,
+                    index,
+                    total,
+                    correlation,
+                }
+            }),
+        any::<Result<u64, Error>>().prop_map(QueryResponse::GetMapVersion),
+        collection::btree_set(collection::vec(any::<u8>(), 0..32), 0..8)
+            .prop_map(|keys| QueryResponse::ListMapKeys(Ok(keys))),
+        any::<Result<(u64, usize), Error>>().prop_map(|result| QueryResponse::GetBalance(
+            result.map(|(balance, index)| (Token::from_nano(balance), index))
+        )),
+        (any::<u64>(), any::<u64>(), public_key()).prop_map(|(bytes, cost, key)| {
+            QueryResponse::GetStoreCost(Ok((bytes, Token::from_nano(cost), key)))
+        }),
+        (public_key(), any::<u64>()).prop_map(|(key, balance)| {
+            QueryResponse::GetSectionWallet(Ok((key, Token::from_nano(balance))))
+        }),
+    ]
+);
+
+arbitrary!(
+    ProcessMsg,
+    prop_oneof![
+        (
+            any::<MessageId>(),
+            any::<Cmd>(),
+            any::<u64>(),
+            client_signed(),
+            option::of(any::<[u8; 32]>().prop_map(SessionId))
+        )
+            .prop_map(|(id, cmd, nonce, client_signed, session)| {
+                ProcessMsg::Cmd {
+                    id,
+                    cmd,
+                    nonce,
+                    client_signed,
+                    session,
+                }
+            }),
+        (
+            any::<MessageId>(),
+            any::<Query>(),
+            client_signed(),
+            any::<Option<u64>>(),
+            option::of(any::<u64>().prop_map(AntiSpam::ProofOfWork)),
+            option::of(any::<[u8; 32]>().prop_map(SessionId))
+        )
+            .prop_map(|(id, query, client_signed, deadline, anti_spam, session)| {
+                ProcessMsg::Query {
+                    id,
+                    query,
+                    client_signed,
+                    deadline,
+                    anti_spam,
+                    session,
+                }
+            }),
+        (
+            any::<MessageId>(),
+            any::<QueryResponse>(),
+            any::<MessageId>(),
+            option::of(response_origin())
+        )
+            .prop_map(
+                |(id, response, correlation_id, origin)| ProcessMsg::QueryResponse {
+                    id,
+                    response,
+                    correlation_id,
+                    origin,
+                }
+            ),
+        (any::<MessageId>(), any::<MessageId>(), client_signed()).prop_map(
+            |(id, correlation_id, client_signed)| ProcessMsg::CancelQuery {
+                id,
+                correlation_id,
+                client_signed,
+            }
+        ),
+        (
+            any::<MessageId>(),
+            bls_public_key(),
+            option::of(xor_name()),
+            collection::vec(any::<u8>(), 0..64)
+        )
+            .prop_map(
+                |(id, recipient, dst_address, bytes)| ProcessMsg::Encrypted {
+                    id,
+                    recipient_pk_hint: ProcessMsg::recipient_pk_hint(&recipient),
+                    dst_address,
+                    ciphertext: recipient.encrypt(bytes),
+                }
+            ),
+    ]
+);
+
+arbitrary!(
+    ClientMsg,
+    prop_oneof![
+        any::<ProcessMsg>().prop_map(ClientMsg::Process),
+        (
+            option::of(any::<Error>()),
+            option::of(any::<ProcessMsg>()),
+            any::<MessageId>()
+        )
+            .prop_map(|(reason, source_message, id)| ClientMsg::ProcessingError(
+                ProcessingError::new(reason, source_message, id)
+            )),
+    ]
+);
+
+#[cfg(not(feature = "client-only"))]
+arbitrary!(
+    NodeCmd,
+    prop_oneof![
+        (blob_address(), client_signed(), any::<EndUser>()).prop_map(
+            |(address, client_signed, origin)| NodeCmd::Chunks {
+                cmd: BlobWrite::DeletePrivate(address),
+                client_signed,
+                origin,
+            }
+        ),
+        (public_key(), any::<u64>(), keypair()).prop_map(|(wallet, nonce, node)| {
+            let node = sn_data_types::Keypair::from(node.secret);
+            NodeCmd::System(
+                NodeSystemCmd::register_wallet(wallet, nonce, &node)
+                    .expect("failed to sign the wallet registration"),
+            )
+        }),
+        (public_key(), xor_name()).prop_map(|(node_id, section)| NodeCmd::System(
+            NodeSystemCmd::StorageFull { node_id, section }
+        )),
+        (xor_name(), collection::vec(blob_address(), 0..8)).prop_map(|(holder, addresses)| {
+            NodeCmd::System(NodeSystemCmd::ReplicateChunksFrom { holder, addresses })
+        }),
+        xor_name().prop_map(|node| NodeCmd::System(NodeSystemCmd::BeginDecommission { node })),
+        any::<MessageId>()
+            .prop_map(|query_id| NodeCmd::System(NodeSystemCmd::AbandonQuery { query_id })),
+    ]
+);
+
+#[cfg(not(feature = "client-only"))]
+arbitrary!(
+    NodeQuery,
+    (blob_address(), any::<EndUser>()).prop_map(|(address, origin)| NodeQuery::Chunks {
+        query: BlobRead::Get(address),
+        origin,
+    })
+);
+
+#[cfg(not(feature = "client-only"))]
+arbitrary!(
+    NodeMsg,
+    prop_oneof![
+        (any::<NodeCmd>(), any::<MessageId>()).prop_map(|(cmd, id)| NodeMsg::NodeCmd { cmd, id }),
+        (any::<NodeQuery>(), any::<MessageId>())
+            .prop_map(|(query, id)| NodeMsg::NodeQuery { query, id }),
+        (any::<Error>(), any::<MessageId>(), any::<MessageId>()).prop_map(
+            |(error, id, correlation_id)| NodeMsg::NodeMsgError {
+                error,
+                id,
+                correlation_id,
+            }
+        ),
+        (
+            collection::vec(any::<u8>(), 0..64),
+            keypair(),
+            bls_public_key(),
+            any::<MessageId>()
+        )
+            .prop_map(|(original, keypair, section_pk, id)| NodeMsg::Forwarded {
+                forwarded: Forwarded::new(original.into(), &keypair, section_pk)
+                    .expect("failed to sign the forwarded message"),
+                id,
+            }),
+        (
+            collection::vec(any::<u8>(), 0..64),
+            bls_public_key(),
+            any::<MessageId>(),
+            any::<MessageId>()
+        )
+            .prop_map(
+                |(msg, known_key, id, correlation_id)| NodeMsg::BouncedUntrusted {
+                    msg: msg.into(),
+                    known_key,
+                    id,
+                    correlation_id,
+                }
+            ),
+    ]
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DestInfo, MessageType, WireMsg};
+    use threshold_crypto::SecretKey;
+
+    proptest! {
+        // Verifying BLS signatures is slow, the few cases are enough to check the signing.
+        #![proptest_config(ProptestConfig::with_cases(8))]
+        #[test]
+        fn transfer_proofs_verify(proof in transfer_proof()) {
+            prop_assert!(proof.verify());
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn client_msg_roundtrip(msg in any::<ClientMsg>(), dest in any::<DstLocation>()) {
+            let dest_section_pk = SecretKey::random().public_key();
+            let serialized = msg.serialize(dest, dest_section_pk).unwrap();
+
+            prop_assert_eq!(
+                WireMsg::deserialize(serialized).unwrap(),
+                MessageType::Client {
+                    msg,
+                    dest_info: DestInfo { dest, dest_section_pk },
+                }
+            );
+        }
+
+        #[cfg(not(feature = "client-only"))]
+        #[test]
+        fn node_msg_roundtrip(msg in any::<NodeMsg>(), dest in any::<DstLocation>()) {
+            let dest_section_pk = SecretKey::random().public_key();
+            let serialized = msg.serialize(dest, dest_section_pk, None).unwrap();
+
+            prop_assert_eq!(
+                WireMsg::deserialize(serialized).unwrap(),
+                MessageType::Node {
+                    msg,
+                    dest_info: DestInfo { dest, dest_section_pk },
+                    src_section_pk: None,
+                    aggregation: Aggregation::None,
+                    signed_share: None,
+                }
+            );
+        }
+    }
+}